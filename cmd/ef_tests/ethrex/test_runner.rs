@@ -1,7 +1,7 @@
 use std::{collections::HashMap, path::Path};
 
 use crate::types::{BlockWithRLP, TestUnit};
-use ethrex_blockchain::{add_block, fork_choice::apply_fork_choice};
+use ethrex_blockchain::Blockchain;
 use ethrex_core::types::{
     Account as CoreAccount, Block as CoreBlock, BlockHeader as CoreBlockHeader,
 };
@@ -16,6 +16,7 @@ pub fn run_ef_test(test_key: &str, test: &TestUnit) {
     assert_eq!(decoded_block.header, genesis_block_header);
 
     let store = build_store_for_test(test);
+    let blockchain = Blockchain::new(store.clone());
 
     // Check world_state
     check_prestate_against_db(test_key, test, &store);
@@ -33,7 +34,7 @@ pub fn run_ef_test(test_key: &str, test: &TestUnit) {
         let hash = block.hash();
 
         // Attempt to add the block as the head of the chain
-        let chain_result = add_block(block, &store);
+        let chain_result = blockchain.add_block(block);
         match chain_result {
             Err(error) => {
                 assert!(
@@ -50,7 +51,7 @@ pub fn run_ef_test(test_key: &str, test: &TestUnit) {
                     test_key,
                     block_fixture.expect_exception.clone().unwrap()
                 );
-                apply_fork_choice(&store, hash, hash, hash).unwrap();
+                blockchain.apply_fork_choice(hash, hash, hash).unwrap();
             }
         }
     }
@@ -3,7 +3,7 @@ use ethrex_net::bootnode::BootNode;
 use tracing::Level;
 
 pub fn cli() -> Command {
-    Command::new("ethrex")
+    let cli = Command::new("ethrex")
         .about("ethrex Execution client")
         .author("Lambdaclass")
         .arg(
@@ -110,6 +110,130 @@ pub fn cli() -> Command {
                 .required(false)
                 .value_name("SYNC_MODE"),
         )
+        .arg(
+            Arg::new("gcmode")
+                .long("gcmode")
+                .required(false)
+                .value_name("GC_MODE")
+                .value_parser(["archive", "full"]),
+        )
+        .arg(
+            Arg::new("gcmode.retain")
+                .long("gcmode.retain")
+                .required(false)
+                .value_name("BLOCKS")
+                .value_parser(clap::value_parser!(u64).range(1..)),
+        )
+        .arg(
+            Arg::new("history.mode")
+                .long("history.mode")
+                .required(false)
+                .value_name("HISTORY_MODE")
+                .value_parser(["all", "recent", "postmerge"]),
+        )
+        .arg(
+            Arg::new("history.retain")
+                .long("history.retain")
+                .required(false)
+                .value_name("BLOCKS")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("freezer.threshold")
+                .long("freezer.threshold")
+                .required(false)
+                .value_name("BLOCKS")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("pending.maxdepth")
+                .long("pending.maxdepth")
+                .required(false)
+                .value_name("BLOCKS")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("pending.maxage")
+                .long("pending.maxage")
+                .required(false)
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("mempool.pricebump")
+                .long("mempool.pricebump")
+                .required(false)
+                .value_name("PERCENT")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("mempool.maxtxs")
+                .long("mempool.maxtxs")
+                .required(false)
+                .value_name("COUNT")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("mempool.maxsize")
+                .long("mempool.maxsize")
+                .required(false)
+                .value_name("BYTES")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("mempool.maxblobtxs")
+                .long("mempool.maxblobtxs")
+                .required(false)
+                .value_name("COUNT")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("mempool.maxblobsize")
+                .long("mempool.maxblobsize")
+                .required(false)
+                .value_name("BYTES")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("mempool.accountslots")
+                .long("mempool.accountslots")
+                .required(false)
+                .value_name("COUNT")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("mempool.accountqueue")
+                .long("mempool.accountqueue")
+                .required(false)
+                .value_name("COUNT")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("mempool.lifetime")
+                .long("mempool.lifetime")
+                .required(false)
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("mempool.journal")
+                .long("mempool.journal")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("mempool.rejournal")
+                .long("mempool.rejournal")
+                .required(false)
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("logs.index")
+                .long("logs.index")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("import_dir")
                 .long("import_dir")
@@ -122,6 +246,40 @@ pub fn cli() -> Command {
                 .required(false)
                 .value_name("PROMETHEUS_METRICS_PORT"),
         )
+        .arg(
+            Arg::new("builder.gaslimit")
+                .long("builder.gaslimit")
+                .required(false)
+                .value_name("GAS_LIMIT")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("builder.extradata")
+                .long("builder.extradata")
+                .required(false)
+                .value_name("EXTRA_DATA"),
+        )
+        .arg(
+            Arg::new("builder.mintip")
+                .long("builder.mintip")
+                .required(false)
+                .value_name("MIN_TIP")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("builder.maxblobs")
+                .long("builder.maxblobs")
+                .required(false)
+                .value_name("MAX_BLOBS")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("builder.timeout")
+                .long("builder.timeout")
+                .required(false)
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64)),
+        )
         .subcommand(
             Command::new("removedb").about("Remove the database").arg(
                 Arg::new("datadir")
@@ -130,4 +288,152 @@ pub fn cli() -> Command {
                     .action(ArgAction::Set),
             ),
         )
+        .subcommand(
+            Command::new("export")
+                .about("Export blocks from the canonical chain to an RLP file or era1 archives")
+                .arg(Arg::new("path").required(true).value_name("OUTPUT_PATH"))
+                .arg(
+                    Arg::new("datadir")
+                        .long("datadir")
+                        .value_name("DATABASE_DIRECTORY")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("first")
+                        .long("first")
+                        .value_name("BLOCK_NUMBER")
+                        .default_value("0")
+                        .value_parser(clap::value_parser!(u64)),
+                )
+                .arg(
+                    Arg::new("last")
+                        .long("last")
+                        .required(false)
+                        .value_name("BLOCK_NUMBER")
+                        .value_parser(clap::value_parser!(u64)),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .default_value("rlp")
+                        .value_parser(["rlp", "era1"]),
+                )
+                .arg(
+                    Arg::new("network")
+                        .long("network")
+                        .value_name("NETWORK_NAME")
+                        .default_value("mainnet"),
+                ),
+        )
+        .subcommand(
+            Command::new("db")
+                .about("Manage the database")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("migrate")
+                        .about("Upgrade the database to the schema of this release")
+                        .arg(datadir_arg()),
+                )
+                .subcommand(
+                    Command::new("stats")
+                        .about("Show the amount of entries of each table and their size")
+                        .arg(datadir_arg()),
+                )
+                .subcommand(
+                    Command::new("get")
+                        .about("Show the entries of a table stored under a key")
+                        .arg(Arg::new("table").required(true).value_name("TABLE"))
+                        .arg(
+                            Arg::new("key")
+                                .required(true)
+                                .value_name("HEX_KEY")
+                                .value_parser(parse_hex_bytes),
+                        )
+                        .arg(datadir_arg()),
+                )
+                .subcommand(
+                    Command::new("iter")
+                        .about("Show the entries of a table in key order")
+                        .arg(Arg::new("table").required(true).value_name("TABLE"))
+                        .arg(
+                            Arg::new("start")
+                                .long("start")
+                                .value_name("HEX_KEY")
+                                .value_parser(parse_hex_bytes),
+                        )
+                        .arg(
+                            Arg::new("limit")
+                                .long("limit")
+                                .value_name("COUNT")
+                                .default_value("100")
+                                .value_parser(clap::value_parser!(usize)),
+                        )
+                        .arg(datadir_arg()),
+                )
+                .subcommand(
+                    Command::new("verify-state")
+                        .about("Walk the state of a block looking for missing trie nodes")
+                        .arg(
+                            Arg::new("block")
+                                .required(true)
+                                .value_name("BLOCK_NUMBER")
+                                .value_parser(clap::value_parser!(u64)),
+                        )
+                        .arg(datadir_arg()),
+                )
+                .subcommand(
+                    Command::new("set-head")
+                        .about("Rewind the canonical chain to one of its blocks")
+                        .arg(
+                            Arg::new("block")
+                                .required(true)
+                                .value_name("BLOCK_NUMBER")
+                                .value_parser(clap::value_parser!(u64)),
+                        )
+                        .arg(datadir_arg()),
+                ),
+        );
+    #[cfg(feature = "dev")]
+    let cli = cli
+        .arg(
+            Arg::new("dev.mode")
+                .long("dev.mode")
+                .value_name("MODE")
+                .default_value("interval")
+                .value_parser(["automine", "interval", "manual"]),
+        )
+        .arg(
+            Arg::new("dev.blocktime")
+                .long("dev.blocktime")
+                .value_name("MILLISECONDS")
+                .default_value("1000")
+                .value_parser(clap::value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("dev.mnemonic")
+                .long("dev.mnemonic")
+                .value_name("MNEMONIC")
+                .default_value(ethrex_dev::accounts::DEFAULT_MNEMONIC),
+        )
+        .arg(
+            Arg::new("dev.accounts")
+                .long("dev.accounts")
+                .value_name("COUNT")
+                .default_value("10")
+                .value_parser(clap::value_parser!(u32)),
+        );
+    cli
+}
+
+fn datadir_arg() -> Arg {
+    Arg::new("datadir")
+        .long("datadir")
+        .value_name("DATABASE_DIRECTORY")
+        .action(ArgAction::Set)
+}
+
+/// Parses hex encoded bytes, with or without a `0x` prefix
+fn parse_hex_bytes(value: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    hex::decode(value.strip_prefix("0x").unwrap_or(value))
 }
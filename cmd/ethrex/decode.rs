@@ -1,3 +1,4 @@
+use crate::era1;
 use anyhow::Error;
 use bytes::Bytes;
 use ethrex_core::types::{Block, Genesis};
@@ -30,6 +31,12 @@ pub fn chain_file(file: File) -> Result<Vec<Block>, Error> {
     Ok(blocks)
 }
 
+pub fn era1_file(file: File) -> Result<Vec<Block>, Error> {
+    let mut archive = vec![];
+    BufReader::new(file).read_to_end(&mut archive)?;
+    era1::decode(&archive)
+}
+
 pub fn genesis_file(file: File) -> Result<Genesis, serde_json::Error> {
     let genesis_reader = BufReader::new(file);
     serde_json::from_reader(genesis_reader)
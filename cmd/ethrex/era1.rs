@@ -0,0 +1,328 @@
+//! Era1 archives store up to [`MAX_EPOCH_SIZE`] consecutive blocks, with their receipts and total
+//! difficulty, as snappy compressed entries of an e2store file:
+//!
+//! ```text
+//! era1 := Version | (CompressedHeader | CompressedBody | CompressedReceipts | TotalDifficulty)*
+//!         | Accumulator | BlockIndex
+//! ```
+//!
+//! Every entry is preceded by its type, the length of its data and two reserved bytes. The block
+//! index lists where each block's entries start, relative to the index itself.
+use anyhow::{anyhow, bail, ensure, Error};
+use ethrex_core::{
+    types::{Block, BlockBody, BlockHash, BlockHeader, BlockNumber, Receipt},
+    H256, U256,
+};
+use ethrex_rlp::{decode::RLPDecode as _, encode::RLPEncode as _};
+use sha2::{Digest as _, Sha256};
+use snap::{read::FrameDecoder, write::FrameEncoder};
+use std::io::{Read as _, Write as _};
+
+/// Maximum amount of blocks in an era1 archive
+pub const MAX_EPOCH_SIZE: u64 = 8192;
+
+const VERSION: u16 = 0x3265;
+const COMPRESSED_HEADER: u16 = 0x03;
+const COMPRESSED_BODY: u16 = 0x04;
+const COMPRESSED_RECEIPTS: u16 = 0x05;
+const TOTAL_DIFFICULTY: u16 = 0x06;
+const ACCUMULATOR: u16 = 0x07;
+const BLOCK_INDEX: u16 = 0x3266;
+
+/// Size of the type, length and reserved bytes preceding each entry's data
+const ENTRY_HEADER_SIZE: usize = 8;
+
+/// A block along with the data stored next to it in an era1 archive
+pub struct Era1Block {
+    pub block: Block,
+    pub receipts: Vec<Receipt>,
+    /// Total difficulty of the chain up to and including this block
+    pub total_difficulty: U256,
+}
+
+/// Encodes consecutive blocks as an era1 archive, returning it along with its accumulator root
+pub fn encode(blocks: &[Era1Block]) -> Result<(Vec<u8>, H256), Error> {
+    let Some(first) = blocks.first() else {
+        bail!("An era1 archive needs at least one block");
+    };
+    ensure!(
+        blocks.len() as u64 <= MAX_EPOCH_SIZE,
+        "An era1 archive can't hold more than {MAX_EPOCH_SIZE} blocks"
+    );
+    ensure!(
+        blocks
+            .windows(2)
+            .all(|pair| pair[1].block.header.number == pair[0].block.header.number + 1),
+        "The blocks of an era1 archive must be consecutive"
+    );
+
+    let mut archive = Vec::new();
+    write_entry(&mut archive, VERSION, &[]);
+    let mut offsets = Vec::with_capacity(blocks.len());
+    for era1_block in blocks {
+        offsets.push(archive.len() as i64);
+        let block = &era1_block.block;
+        write_entry(
+            &mut archive,
+            COMPRESSED_HEADER,
+            &compress(&block.header.encode_to_vec())?,
+        );
+        write_entry(
+            &mut archive,
+            COMPRESSED_BODY,
+            &compress(&block.body.encode_to_vec())?,
+        );
+        write_entry(
+            &mut archive,
+            COMPRESSED_RECEIPTS,
+            &compress(&era1_block.receipts.encode_to_vec())?,
+        );
+        write_entry(
+            &mut archive,
+            TOTAL_DIFFICULTY,
+            &era1_block.total_difficulty.to_little_endian(),
+        );
+    }
+
+    let accumulator_root = accumulator_root(
+        blocks
+            .iter()
+            .map(|era1_block| (era1_block.block.hash(), era1_block.total_difficulty)),
+    );
+    write_entry(&mut archive, ACCUMULATOR, accumulator_root.as_bytes());
+
+    let index_offset = archive.len() as i64;
+    let mut index = first.block.header.number.to_le_bytes().to_vec();
+    for offset in offsets {
+        index.extend((offset - index_offset).to_le_bytes());
+    }
+    index.extend((blocks.len() as u64).to_le_bytes());
+    write_entry(&mut archive, BLOCK_INDEX, &index);
+
+    Ok((archive, accumulator_root))
+}
+
+/// Decodes the blocks of an era1 archive, checking them against its accumulator root. Receipts
+/// are skipped, as importing the blocks computes them again.
+pub fn decode(archive: &[u8]) -> Result<Vec<Block>, Error> {
+    let (entry_type, _, mut rest) = read_entry(archive)?;
+    ensure!(entry_type == VERSION, "Missing era1 version entry");
+
+    let mut blocks = Vec::new();
+    let mut total_difficulties = Vec::new();
+    let mut expected_root = None;
+    let mut header = None;
+    while !rest.is_empty() {
+        let (entry_type, data, next) = read_entry(rest)?;
+        rest = next;
+        match entry_type {
+            COMPRESSED_HEADER => header = Some(BlockHeader::decode(&decompress(data)?)?),
+            COMPRESSED_BODY => {
+                let header = header
+                    .take()
+                    .ok_or(anyhow!("Found an era1 block body without its header"))?;
+                blocks.push(Block::new(header, BlockBody::decode(&decompress(data)?)?));
+            }
+            TOTAL_DIFFICULTY => total_difficulties.push(U256::from_little_endian(data)),
+            ACCUMULATOR => {
+                ensure!(data.len() == 32, "Invalid era1 accumulator entry");
+                expected_root = Some(H256::from_slice(data));
+            }
+            _ => {}
+        }
+    }
+
+    let expected_root = expected_root.ok_or(anyhow!("Missing era1 accumulator entry"))?;
+    ensure!(
+        total_difficulties.len() == blocks.len(),
+        "Expected {} era1 total difficulty entries, found {}",
+        blocks.len(),
+        total_difficulties.len()
+    );
+    let root = accumulator_root(
+        blocks
+            .iter()
+            .map(Block::hash)
+            .zip(total_difficulties.iter().copied()),
+    );
+    ensure!(
+        root == expected_root,
+        "Era1 accumulator root mismatch: expected {expected_root:#x}, computed {root:#x}"
+    );
+    Ok(blocks)
+}
+
+/// Name of an era1 archive, as expected by other clients. Archives are named after their epoch,
+/// so they must start at an epoch boundary.
+pub fn file_name(
+    network: &str,
+    first_block: BlockNumber,
+    accumulator_root: H256,
+) -> Result<String, Error> {
+    ensure!(
+        first_block % MAX_EPOCH_SIZE == 0,
+        "Era1 archives must start at a multiple of {MAX_EPOCH_SIZE}, got block {first_block}"
+    );
+    Ok(format!(
+        "{network}-{:05}-{}.era1",
+        first_block / MAX_EPOCH_SIZE,
+        hex::encode(&accumulator_root.as_bytes()[..4])
+    ))
+}
+
+/// SSZ hash tree root of the list of (block hash, total difficulty) records of an epoch
+fn accumulator_root(records: impl Iterator<Item = (BlockHash, U256)>) -> H256 {
+    let mut layer: Vec<[u8; 32]> = records
+        .map(|(block_hash, total_difficulty)| {
+            hash_pair(block_hash.as_bytes(), &total_difficulty.to_little_endian())
+        })
+        .collect();
+    let length = layer.len() as u64;
+
+    // Merkleize the records as if the list was full, with zeroed subtrees as padding
+    let mut zero_hash = [0; 32];
+    for _ in 0..MAX_EPOCH_SIZE.ilog2() {
+        if layer.len() % 2 == 1 {
+            layer.push(zero_hash);
+        }
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+        zero_hash = hash_pair(&zero_hash, &zero_hash);
+    }
+    let root = layer.first().copied().unwrap_or(zero_hash);
+
+    // Mix in the list's length
+    let mut length_bytes = [0; 32];
+    length_bytes[..8].copy_from_slice(&length.to_le_bytes());
+    H256(hash_pair(&root, &length_bytes))
+}
+
+fn hash_pair(left: &[u8], right: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+fn write_entry(archive: &mut Vec<u8>, entry_type: u16, data: &[u8]) {
+    archive.extend(entry_type.to_le_bytes());
+    archive.extend((data.len() as u32).to_le_bytes());
+    archive.extend([0; 2]);
+    archive.extend(data);
+}
+
+/// Returns the type and data of the entry at the start of the archive, and the rest of it
+fn read_entry(archive: &[u8]) -> Result<(u16, &[u8], &[u8]), Error> {
+    ensure!(
+        archive.len() >= ENTRY_HEADER_SIZE,
+        "Truncated e2store entry header"
+    );
+    let entry_type = u16::from_le_bytes([archive[0], archive[1]]);
+    let length = u32::from_le_bytes([archive[2], archive[3], archive[4], archive[5]]) as usize;
+    let end = ENTRY_HEADER_SIZE + length;
+    let data = archive
+        .get(ENTRY_HEADER_SIZE..end)
+        .ok_or(anyhow!("Truncated e2store entry"))?;
+    Ok((entry_type, data, &archive[end..]))
+}
+
+fn compress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut encoder = FrameEncoder::new(Vec::new());
+    encoder.write_all(data)?;
+    encoder
+        .into_inner()
+        .map_err(|error| anyhow!("Failed to compress era1 entry: {}", error.error()))
+}
+
+fn decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut decompressed = Vec::new();
+    FrameDecoder::new(data).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::chain_file;
+    use std::fs::File;
+
+    fn era1_blocks() -> Vec<Era1Block> {
+        let file = File::open("../../test_data/chain.rlp").expect("Failed to open chain file");
+        chain_file(file)
+            .expect("Failed to decode chain file")
+            .into_iter()
+            .map(|block| Era1Block {
+                total_difficulty: block.header.difficulty,
+                block,
+                receipts: Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn era1_archive_roundtrip() {
+        let blocks = era1_blocks();
+        let (archive, accumulator_root) = encode(&blocks).unwrap();
+        let decoded = decode(&archive).unwrap();
+        assert_eq!(decoded.len(), blocks.len());
+        for (decoded, era1_block) in decoded.iter().zip(&blocks) {
+            assert_eq!(decoded.hash(), era1_block.block.hash());
+        }
+
+        // The index points at each block's header entry
+        let index_offset = archive.len() - ENTRY_HEADER_SIZE - 8 * (blocks.len() + 2);
+        let (entry_type, index, rest) = read_entry(&archive[index_offset..]).unwrap();
+        assert_eq!(entry_type, BLOCK_INDEX);
+        assert!(rest.is_empty());
+        let first_offset = i64::from_le_bytes(index[8..16].try_into().unwrap());
+        let (entry_type, _, _) =
+            read_entry(&archive[(index_offset as i64 + first_offset) as usize..]).unwrap();
+        assert_eq!(entry_type, COMPRESSED_HEADER);
+
+        assert!(file_name("mainnet", 0, accumulator_root)
+            .unwrap()
+            .starts_with("mainnet-00000-"));
+        assert!(file_name("mainnet", 1, accumulator_root).is_err());
+    }
+
+    #[test]
+    fn era1_archive_with_a_wrong_accumulator_root_is_rejected() {
+        let (mut archive, accumulator_root) = encode(&era1_blocks()).unwrap();
+        let position = archive
+            .windows(32)
+            .position(|window| window == accumulator_root.as_bytes())
+            .unwrap();
+        archive[position] ^= 1;
+        assert!(decode(&archive).is_err());
+    }
+
+    // The archive isn't checked in, it can be fetched from any era1 mirror into `test_data`
+    #[test]
+    #[ignore = "needs test_data/mainnet-00000-5ec1ffb8.era1"]
+    fn mainnet_era1_accumulator_root() {
+        let archive = std::fs::read("../../test_data/mainnet-00000-5ec1ffb8.era1").unwrap();
+        // Decoding checks the archive's accumulator root against its blocks
+        let blocks = decode(&archive).unwrap();
+        assert_eq!(blocks.len() as u64, MAX_EPOCH_SIZE);
+        let (entry_type, accumulator, _) = read_entry(
+            &archive[archive.len() - 2 * ENTRY_HEADER_SIZE - 8 * (blocks.len() + 2) - 32..],
+        )
+        .unwrap();
+        assert_eq!(entry_type, ACCUMULATOR);
+        assert_eq!(
+            file_name("mainnet", 0, H256::from_slice(accumulator)).unwrap(),
+            "mainnet-00000-5ec1ffb8.era1"
+        );
+    }
+
+    #[test]
+    fn era1_blocks_must_be_consecutive() {
+        let mut blocks = era1_blocks();
+        blocks.remove(1);
+        assert!(encode(&blocks).is_err());
+        assert!(encode(&[]).is_err());
+    }
+}
@@ -1,25 +1,35 @@
 use bytes::Bytes;
 use directories::ProjectDirs;
-use ethrex_blockchain::{add_block, fork_choice::apply_fork_choice};
-use ethrex_core::types::{Block, Genesis};
+use ethrex_blockchain::{
+    mempool, payload::BuilderConfig, Blockchain, GcMode, HistoryMode, PendingBlocksConfig,
+    DEFAULT_FREEZER_THRESHOLD, IMPORT_BATCH_SIZE,
+};
+use ethrex_core::{
+    types::{Block, BlockNumber, Genesis},
+    U256,
+};
 use ethrex_net::{
     bootnode::BootNode,
     node_id_from_signing_key, peer_table,
     sync::{SyncManager, SyncMode},
     types::Node,
 };
-use ethrex_rlp::decode::RLPDecode;
-use ethrex_storage::{EngineType, Store};
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
+use ethrex_storage::{
+    error::StoreError, EngineType, MempoolConfig, Store, TableEntry, MEMPOOL_JOURNAL_FILE,
+    SCHEMA_VERSION,
+};
 use k256::ecdsa::SigningKey;
 use local_ip_address::local_ip;
 use rand::rngs::OsRng;
 use std::{
     fs::{self, File},
     future::IntoFuture,
-    io,
+    io::{self, BufWriter, Write},
     net::{Ipv4Addr, SocketAddr, ToSocketAddrs},
     path::Path,
     str::FromStr as _,
+    sync::Arc,
     time::Duration,
 };
 use tokio_util::task::TaskTracker;
@@ -27,9 +37,13 @@ use tracing::{error, info, warn};
 use tracing_subscriber::{filter::Directive, EnvFilter, FmtSubscriber};
 mod cli;
 mod decode;
+mod era1;
 mod networks;
 
 const DEFAULT_DATADIR: &str = "ethrex";
+/// Seconds between rewrites of the mempool journal
+const DEFAULT_MEMPOOL_REJOURNAL: u64 = 3600;
+
 #[tokio::main]
 async fn main() {
     let matches = cli::cli().get_matches();
@@ -60,6 +74,91 @@ async fn main() {
         .finish();
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
 
+    if let Some(matches) = matches.subcommand_matches("export") {
+        let data_dir = matches
+            .get_one::<String>("datadir")
+            .map_or(set_datadir(DEFAULT_DATADIR), |datadir| set_datadir(datadir));
+        let store = open_store(&data_dir);
+        let path = matches.get_one::<String>("path").expect("path is required");
+        let first = *matches
+            .get_one::<u64>("first")
+            .expect("first has a default value");
+        let last = match matches.get_one::<u64>("last") {
+            Some(last) => *last,
+            None => store
+                .get_latest_block_number()
+                .expect("Failed to get the latest block number"),
+        };
+        let result = match matches.get_one::<String>("format").map(String::as_str) {
+            Some("era1") => {
+                let network = matches
+                    .get_one::<String>("network")
+                    .expect("network has a default value");
+                export_era1(&store, path, first, last, network)
+            }
+            _ => export_chain_file(&store, path, first, last),
+        };
+        if let Err(error) = result {
+            error!("Failed to export blocks: {error}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("db") {
+        let (subcommand, matches) = matches.subcommand().expect("db requires a subcommand");
+        let data_dir = matches
+            .get_one::<String>("datadir")
+            .map_or(set_datadir(DEFAULT_DATADIR), |datadir| set_datadir(datadir));
+        if subcommand == "migrate" {
+            migrate_store(&data_dir);
+            return;
+        }
+        let store = open_store(&data_dir);
+        match subcommand {
+            "stats" => print_table_stats(&store),
+            "get" => {
+                let table = matches
+                    .get_one::<String>("table")
+                    .expect("table is required");
+                let key = matches.get_one::<Vec<u8>>("key").expect("key is required");
+                let entries = db_result(
+                    store.get_table_entries(table, key),
+                    "Failed to read the table",
+                );
+                if entries.is_empty() {
+                    warn!("No entries found for key 0x{}", hex::encode(key));
+                }
+                print_table_entries(&entries);
+            }
+            "iter" => {
+                let table = matches
+                    .get_one::<String>("table")
+                    .expect("table is required");
+                let start = matches.get_one::<Vec<u8>>("start");
+                let limit = *matches
+                    .get_one::<usize>("limit")
+                    .expect("limit has a default value");
+                let entries = db_result(
+                    store.iter_table(table, start.map(Vec::as_slice), limit),
+                    "Failed to read the table",
+                );
+                print_table_entries(&entries);
+            }
+            "verify-state" => {
+                let block = *matches.get_one::<u64>("block").expect("block is required");
+                verify_state(&store, block);
+            }
+            "set-head" => {
+                let block = *matches.get_one::<u64>("block").expect("block is required");
+                let hash = db_result(store.set_head(block), "Failed to set the head");
+                info!("Block {block} ({hash:#x}) is now the head of the chain");
+            }
+            _ => unreachable!("unknown db subcommand: {subcommand}"),
+        }
+        return;
+    }
+
     let http_addr = matches
         .get_one::<String>("http.addr")
         .expect("http.addr is required");
@@ -89,10 +188,7 @@ async fn main() {
         .get_one::<String>("discovery.port")
         .expect("discovery.port is required");
 
-    let mut network = matches
-        .get_one::<String>("network")
-        .expect("network is required")
-        .clone();
+    let mut network = matches.get_one::<String>("network").cloned();
 
     let mut bootnodes: Vec<BootNode> = matches
         .get_many("bootnodes")
@@ -100,17 +196,17 @@ async fn main() {
         .map(Iterator::collect)
         .unwrap_or_default();
 
-    if network == "holesky" {
+    if network.as_deref() == Some("holesky") {
         warn!("Using holesky presets, bootnodes field will be ignored");
         // Set holesky presets
-        network = String::from(networks::HOLESKY_GENESIS_PATH);
+        network = Some(String::from(networks::HOLESKY_GENESIS_PATH));
         bootnodes = networks::HOLESKY_BOOTNODES.to_vec();
     }
 
-    if network == "sepolia" {
+    if network.as_deref() == Some("sepolia") {
         warn!("Using sepolia presets, bootnodes field will be ignored");
         // Set sepolia presets
-        network = String::from(networks::SEPOLIA_GENESIS_PATH);
+        network = Some(String::from(networks::SEPOLIA_GENESIS_PATH));
         bootnodes = networks::SEPOLIA_BOOTNODES.to_vec();
     }
 
@@ -134,25 +230,66 @@ async fn main() {
 
     let sync_mode = sync_mode(&matches);
 
-    cfg_if::cfg_if! {
-        if #[cfg(feature = "redb")] {
-            let store = Store::new(&data_dir, EngineType::RedB).expect("Failed to create Store");
-        } else if #[cfg(feature = "libmdbx")] {
-            let store = Store::new(&data_dir, EngineType::Libmdbx).expect("Failed to create Store");
-        } else {
-            let store = Store::new(&data_dir, EngineType::InMemory).expect("Failed to create Store");
-        }
-    }
-
-    let genesis = read_genesis_file(&network);
+    let store = open_store(&data_dir)
+        .with_mempool_config(mempool_config(&matches))
+        .with_log_index(matches.get_flag("logs.index"))
+        .expect("Failed to set up the log index");
+
+    #[cfg(all(feature = "dev", not(feature = "l2")))]
+    let dev_accounts = dev_accounts(&matches);
+
+    let genesis = match &network {
+        Some(network) => read_genesis_file(network),
+        // Without a network, dev nodes start their own chain funding the accounts they sign for
+        #[cfg(all(feature = "dev", not(feature = "l2")))]
+        None => ethrex_dev::genesis::dev_genesis(
+            &dev_accounts
+                .iter()
+                .map(|account| account.address)
+                .collect::<Vec<_>>(),
+        ),
+        #[cfg(not(all(feature = "dev", not(feature = "l2"))))]
+        None => panic!("network is required"),
+    };
     store
         .add_initial_state(genesis.clone())
         .expect("Failed to create genesis block");
 
+    let builder_config = builder_config(&matches);
+    // On L2 the proposer's builder settings take precedence over the CLI ones
+    #[cfg(feature = "l2")]
+    let builder_config = match ethrex_l2::utils::config::read_env_file()
+        .and_then(|_| ethrex_l2::utils::config::proposer::ProposerConfig::from_env())
+    {
+        Ok(proposer_config) => proposer_config.builder_config(builder_config),
+        Err(error) => {
+            warn!("Failed to read the proposer config, using the CLI builder settings: {error}");
+            builder_config
+        }
+    };
+    let blockchain = Arc::new(
+        Blockchain::new(store.clone())
+            .with_builder_config(builder_config)
+            .expect("Invalid block builder settings")
+            .with_pending_blocks_config(pending_blocks_config(&matches))
+            .with_gc_mode(gc_mode(&matches))
+            .with_history_mode(history_mode(&matches))
+            .with_freezer_threshold(
+                matches
+                    .get_one::<u64>("freezer.threshold")
+                    .copied()
+                    .unwrap_or(DEFAULT_FREEZER_THRESHOLD),
+            ),
+    );
+
     if let Some(chain_rlp_path) = matches.get_one::<String>("import") {
         info!("Importing blocks from chain file: {}", chain_rlp_path);
-        let blocks = read_chain_file(chain_rlp_path);
-        import_blocks(&store, &blocks);
+        let blocks = if chain_rlp_path.ends_with(".era1") {
+            read_era1_file(chain_rlp_path)
+        } else {
+            read_chain_file(chain_rlp_path)
+        };
+        import_blocks(&blockchain, &blocks);
     }
 
     if let Some(blocks_path) = matches.get_one::<String>("import_dir") {
@@ -168,10 +305,16 @@ async fn main() {
             let s = path
                 .to_str()
                 .expect("Path could not be converted into string");
-            blocks.push(read_block_file(s));
+            if s.ends_with(".era1") {
+                blocks.extend(read_era1_file(s));
+            } else {
+                blocks.push(read_block_file(s));
+            }
         }
+        // Directory entries come in no particular order
+        blocks.sort_by_key(|block| block.header.number);
 
-        import_blocks(&store, &blocks);
+        import_blocks(&blockchain, &blocks);
     }
 
     let jwt_secret = read_jwtsecret_file(authrpc_jwtsecret);
@@ -216,15 +359,50 @@ async fn main() {
     // Create SyncManager
     let syncer = SyncManager::new(peer_table.clone(), sync_mode);
 
+    // Dev chains seal their own blocks, on their own or through the evm namespace
+    cfg_if::cfg_if! {
+        if #[cfg(all(feature = "dev", not(feature = "l2")))] {
+            let dev_chain = Some(Arc::new(
+                ethrex_rpc::evm::DevChain::new(blockchain.clone(), ethrex_core::Address::default())
+                    .with_accounts(dev_accounts),
+            ));
+        } else {
+            let dev_chain: Option<Arc<ethrex_rpc::evm::DevChain>> = None;
+        }
+    }
+
     // TODO: Check every module starts properly.
     let tracker = TaskTracker::new();
+
+    // Blocks stored before the log index was enabled are indexed while the node runs
+    store.backfill_log_index_in_background();
+
+    if matches.get_flag("mempool.journal") {
+        let journal_path = Path::new(&data_dir).join(MEMPOOL_JOURNAL_FILE);
+        match mempool::load_journal(&journal_path, &store, blockchain.events()) {
+            Ok(loaded) => info!("Loaded {loaded} transactions from the mempool journal"),
+            Err(error) => error!("Failed to load the mempool journal: {error}"),
+        }
+        let rejournal = matches
+            .get_one::<u64>("mempool.rejournal")
+            .copied()
+            .unwrap_or(DEFAULT_MEMPOOL_REJOURNAL);
+        tracker.spawn(rejournal_mempool(
+            store.clone(),
+            Duration::from_secs(rejournal),
+        ));
+    }
+    // Kept to flush the buffered writes of the mempool journal on shutdown
+    let journal_store = store.clone();
     let rpc_api = ethrex_rpc::start_api(
         http_socket_addr,
         authrpc_socket_addr,
         store.clone(),
+        blockchain.clone(),
         jwt_secret,
         local_p2p_node,
         syncer,
+        dev_chain.clone(),
     )
     .into_future();
 
@@ -249,19 +427,22 @@ async fn main() {
     // We do not want to start the networking module if the l2 feature is enabled.
     cfg_if::cfg_if! {
         if #[cfg(feature = "l2")] {
-            let l2_proposer = ethrex_l2::start_proposer(store).into_future();
+            let l2_proposer = ethrex_l2::start_proposer(store, blockchain.events().clone()).into_future();
             tracker.spawn(l2_proposer);
         } else if #[cfg(feature = "dev")] {
-            use ethrex_dev;
-
-            let authrpc_jwtsecret = std::fs::read(authrpc_jwtsecret).expect("Failed to read JWT secret");
-            let head_block_hash = {
-                let current_block_number = store.get_latest_block_number().unwrap();
-                store.get_canonical_block_hash(current_block_number).unwrap().unwrap()
+            use ethrex_dev::block_producer::BlockProductionMode;
+
+            let mode = match matches.get_one::<String>("dev.mode").map(String::as_str) {
+                Some("automine") => BlockProductionMode::Automine,
+                Some("manual") => BlockProductionMode::Manual,
+                _ => {
+                    let block_time = matches.get_one::<u64>("dev.blocktime").expect("dev.blocktime has a default value");
+                    BlockProductionMode::Interval(Duration::from_millis(*block_time))
+                }
             };
+            let dev_chain = dev_chain.expect("The dev chain is set with the dev feature");
             let max_tries = 3;
-            let url = format!("http://{authrpc_socket_addr}");
-            let block_producer_engine = ethrex_dev::block_producer::start_block_producer(url, authrpc_jwtsecret.into(), head_block_hash, max_tries, 1000, ethrex_core::Address::default());
+            let block_producer_engine = ethrex_dev::block_producer::start_block_producer(dev_chain, mode, max_tries);
             tracker.spawn(block_producer_engine);
         } else {
             ethrex_net::start_network(
@@ -271,6 +452,7 @@ async fn main() {
                 signer,
                 peer_table.clone(),
                 store,
+                blockchain.events().clone(),
             )
             .await.expect("Network starts");
             tracker.spawn(ethrex_net::periodically_show_peer_stats(peer_table));
@@ -280,6 +462,9 @@ async fn main() {
     tokio::select! {
         _ = tokio::signal::ctrl_c() => {
             info!("Server shut down started...");
+            if let Err(error) = journal_store.flush_mempool_journal() {
+                warn!("Failed to flush the mempool journal: {error}");
+            }
             tokio::time::sleep(Duration::from_secs(1)).await;
             info!("Server shutting down!");
             return;
@@ -287,6 +472,20 @@ async fn main() {
     }
 }
 
+/// Rewrites the mempool journal on every interval, so the transactions that left the pool don't
+/// pile up in it
+async fn rejournal_mempool(store: Store, interval: Duration) {
+    let mut interval = tokio::time::interval(interval);
+    // The first tick completes right away, and the journal was just rewritten when opened
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        if let Err(error) = store.rewrite_mempool_journal() {
+            warn!("Failed to rewrite the mempool journal: {error}");
+        }
+    }
+}
+
 fn read_jwtsecret_file(jwt_secret_path: &str) -> Bytes {
     match File::open(jwt_secret_path) {
         Ok(mut file) => decode::jwtsecret_file(&mut file),
@@ -314,6 +513,11 @@ fn read_chain_file(chain_rlp_path: &str) -> Vec<Block> {
     decode::chain_file(chain_file).expect("Failed to decode chain rlp file")
 }
 
+fn read_era1_file(era1_path: &str) -> Vec<Block> {
+    let era1_file = std::fs::File::open(era1_path).expect("Failed to open era1 file");
+    decode::era1_file(era1_file).expect("Failed to decode era1 file")
+}
+
 fn read_block_file(block_file_path: &str) -> Block {
     let encoded_block = std::fs::read(block_file_path)
         .unwrap_or_else(|_| panic!("Failed to read block file with path {}", block_file_path));
@@ -326,6 +530,27 @@ fn read_genesis_file(genesis_file_path: &str) -> Genesis {
     decode::genesis_file(genesis_file).expect("Failed to decode genesis file")
 }
 
+#[cfg(all(feature = "dev", not(feature = "l2")))]
+fn dev_accounts(matches: &clap::ArgMatches) -> Vec<ethrex_rpc::evm::DevAccount> {
+    let mnemonic = matches
+        .get_one::<String>("dev.mnemonic")
+        .expect("dev.mnemonic has a default value");
+    let count = *matches
+        .get_one::<u32>("dev.accounts")
+        .expect("dev.accounts has a default value");
+    let accounts = ethrex_dev::accounts::derive_accounts(mnemonic, count)
+        .expect("Failed to derive dev accounts");
+    info!("Dev accounts:");
+    for (index, account) in accounts.iter().enumerate() {
+        info!(
+            "({index}) {:#x} (private key: 0x{})",
+            account.address,
+            hex::encode(account.secret_key.secret_bytes())
+        );
+    }
+    accounts
+}
+
 fn parse_socket_addr(addr: &str, port: &str) -> io::Result<SocketAddr> {
     // NOTE: this blocks until hostname can be resolved
     format!("{addr}:{port}")
@@ -350,6 +575,185 @@ fn sync_mode(matches: &clap::ArgMatches) -> SyncMode {
     }
 }
 
+fn pending_blocks_config(matches: &clap::ArgMatches) -> PendingBlocksConfig {
+    let mut config = PendingBlocksConfig::default();
+    if let Some(max_depth) = matches.get_one::<u64>("pending.maxdepth") {
+        config.max_depth = *max_depth;
+    }
+    if let Some(max_age) = matches.get_one::<u64>("pending.maxage") {
+        config.max_age = Duration::from_secs(*max_age);
+    }
+    config
+}
+
+fn mempool_config(matches: &clap::ArgMatches) -> MempoolConfig {
+    let mut config = MempoolConfig::default();
+    if let Some(price_bump) = matches.get_one::<u64>("mempool.pricebump") {
+        config.price_bump = *price_bump;
+    }
+    if let Some(max_transactions) = matches.get_one::<usize>("mempool.maxtxs") {
+        config.max_transactions = *max_transactions;
+    }
+    if let Some(max_size) = matches.get_one::<usize>("mempool.maxsize") {
+        config.max_size = *max_size;
+    }
+    if let Some(max_blob_transactions) = matches.get_one::<usize>("mempool.maxblobtxs") {
+        config.max_blob_transactions = *max_blob_transactions;
+    }
+    if let Some(max_blobs_size) = matches.get_one::<usize>("mempool.maxblobsize") {
+        config.max_blobs_size = *max_blobs_size;
+    }
+    if let Some(max_account_slots) = matches.get_one::<usize>("mempool.accountslots") {
+        config.max_account_slots = *max_account_slots;
+    }
+    if let Some(max_account_queue) = matches.get_one::<usize>("mempool.accountqueue") {
+        config.max_account_queue = *max_account_queue;
+    }
+    if let Some(lifetime) = matches.get_one::<u64>("mempool.lifetime") {
+        config.lifetime = Duration::from_secs(*lifetime);
+    }
+    config
+}
+
+fn gc_mode(matches: &clap::ArgMatches) -> GcMode {
+    let retained_blocks = matches
+        .get_one::<u64>("gcmode.retain")
+        .copied()
+        .unwrap_or(GcMode::DEFAULT_RETAINED_BLOCKS);
+    match matches.get_one::<String>("gcmode").map(String::as_str) {
+        Some("full") => GcMode::Full { retained_blocks },
+        _ => GcMode::Archive,
+    }
+}
+
+fn history_mode(matches: &clap::ArgMatches) -> HistoryMode {
+    let retained_blocks = matches
+        .get_one::<u64>("history.retain")
+        .copied()
+        .unwrap_or(HistoryMode::DEFAULT_RETAINED_BLOCKS);
+    match matches
+        .get_one::<String>("history.mode")
+        .map(String::as_str)
+    {
+        Some("recent") => HistoryMode::Recent { retained_blocks },
+        Some("postmerge") => HistoryMode::PostMerge,
+        _ => HistoryMode::All,
+    }
+}
+
+fn builder_config(matches: &clap::ArgMatches) -> BuilderConfig {
+    let mut config = BuilderConfig::default();
+    if let Some(gas_ceil) = matches.get_one::<u64>("builder.gaslimit") {
+        config.gas_ceil = *gas_ceil;
+    }
+    if let Some(extra_data) = matches.get_one::<String>("builder.extradata") {
+        config.extra_data = Bytes::from(extra_data.clone().into_bytes());
+    }
+    if let Some(min_tip) = matches.get_one::<u64>("builder.mintip") {
+        config.min_tip = *min_tip;
+    }
+    if let Some(max_blobs_per_block) = matches.get_one::<u64>("builder.maxblobs") {
+        config.max_blobs_per_block = *max_blobs_per_block;
+    }
+    if let Some(build_timeout) = matches.get_one::<u64>("builder.timeout") {
+        config.build_timeout = Duration::from_secs(*build_timeout);
+    }
+    config
+}
+
+fn engine_type() -> EngineType {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "redb")] {
+            EngineType::RedB
+        } else if #[cfg(feature = "libmdbx")] {
+            EngineType::Libmdbx
+        } else {
+            EngineType::InMemory
+        }
+    }
+}
+
+fn open_store(data_dir: &str) -> Store {
+    match Store::new(data_dir, engine_type()) {
+        Ok(store) => store,
+        Err(error) => {
+            // Outdated databases need to be migrated, the error explains how
+            error!("Failed to open the database: {error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn migrate_store(data_dir: &str) {
+    let version = Store::migrate(data_dir, engine_type()).expect("Failed to migrate the database");
+    if version == SCHEMA_VERSION {
+        info!("Database schema is already up to date (version {SCHEMA_VERSION})");
+    } else {
+        info!("Migrated database schema from version {version} to {SCHEMA_VERSION}");
+    }
+}
+
+// Unwraps the outcome of a database command, exiting with its error if it failed
+fn db_result<T>(result: Result<T, StoreError>, context: &str) -> T {
+    result.unwrap_or_else(|error| {
+        error!("{context}: {error}");
+        std::process::exit(1);
+    })
+}
+
+fn print_table_stats(store: &Store) {
+    let stats = db_result(store.table_stats(), "Failed to read the table stats");
+    println!("{:<24} {:>14} {:>16}", "TABLE", "ENTRIES", "SIZE (BYTES)");
+    for table in &stats {
+        println!(
+            "{:<24} {:>14} {:>16}",
+            table.name, table.entries, table.size
+        );
+    }
+    println!(
+        "{:<24} {:>14} {:>16}",
+        "TOTAL",
+        stats.iter().map(|table| table.entries).sum::<u64>(),
+        stats.iter().map(|table| table.size).sum::<u64>()
+    );
+}
+
+/// Prints each entry's key as stored, which is what `db get` and `db iter --start` take, along
+/// with its decoded key and value
+fn print_table_entries(entries: &[TableEntry]) {
+    for entry in entries {
+        println!("0x{}", hex::encode(&entry.key));
+        println!("  key: {}", entry.decoded_key);
+        println!("  value: {}", entry.decoded_value);
+    }
+}
+
+fn verify_state(store: &Store, block_number: BlockNumber) {
+    info!("Verifying the state of block {block_number}");
+    let verification = db_result(
+        store.verify_state(block_number),
+        "Failed to verify the state",
+    );
+    for node_hash in &verification.missing_state_nodes {
+        warn!("Missing state trie node {node_hash:#x}");
+    }
+    for (hashed_address, node_hash) in &verification.missing_storage_nodes {
+        warn!("Missing storage trie node {node_hash:#x} of account {hashed_address:#x}");
+    }
+    if !verification.is_complete() {
+        error!(
+            "State of block {block_number} is missing {} state trie nodes and {} storage trie nodes",
+            verification.missing_state_nodes.len(),
+            verification.missing_storage_nodes.len()
+        );
+        std::process::exit(1);
+    }
+    info!(
+        "State of block {block_number} is complete, with {} accounts",
+        verification.accounts
+    );
+}
+
 fn set_datadir(datadir: &str) -> String {
     let project_dir = ProjectDirs::from("", "", datadir).expect("Couldn't find home directory");
     project_dir
@@ -359,50 +763,161 @@ fn set_datadir(datadir: &str) -> String {
         .to_owned()
 }
 
-fn import_blocks(store: &Store, blocks: &Vec<Block>) {
+fn import_blocks(blockchain: &Blockchain, blocks: &Vec<Block>) {
+    let store = &blockchain.storage;
     let size = blocks.len();
-    for block in blocks {
-        let hash = block.hash();
+    'import: for batch in blocks.chunks(IMPORT_BATCH_SIZE) {
+        let (Some(first), Some(last)) = (batch.first(), batch.last()) else {
+            continue;
+        };
         info!(
-            "Adding block {} with hash {:#x}.",
-            block.header.number, hash
+            "Adding blocks {} to {}.",
+            first.header.number, last.header.number
         );
-        let result = add_block(block, store);
-        if let Some(error) = result.err() {
-            warn!(
-                "Failed to add block {} with hash {:#x}: {}.",
-                block.header.number, hash, error
-            );
-        }
-        if store
-            .update_latest_block_number(block.header.number)
-            .is_err()
-        {
-            error!("Fatal: added block {} but could not update the block number -- aborting block import", block.header.number);
-            break;
-        };
-        if store
-            .set_canonical_block(block.header.number, hash)
-            .is_err()
-        {
-            error!(
-                "Fatal: added block {} but could not set it as canonical -- aborting block import",
-                block.header.number
-            );
-            break;
+        // A successful batch is already canonical. Add the blocks one by one if it fails, to
+        // find out which ones are at fault
+        let Err(error) = blockchain.add_blocks_in_batch(batch) else {
+            continue;
         };
+        warn!(
+            "Failed to add blocks {} to {} at once, adding them one by one: {}.",
+            first.header.number, last.header.number, error
+        );
+        for block in batch {
+            let hash = block.hash();
+            if let Err(error) = blockchain.add_block(block) {
+                warn!(
+                    "Failed to add block {} with hash {:#x}: {}.",
+                    block.header.number, hash, error
+                );
+            }
+            if store
+                .update_latest_block_number(block.header.number)
+                .is_err()
+            {
+                error!("Fatal: added block {} but could not update the block number -- aborting block import", block.header.number);
+                break 'import;
+            };
+            if store
+                .set_canonical_block(block.header.number, hash)
+                .is_err()
+            {
+                error!(
+                    "Fatal: added block {} but could not set it as canonical -- aborting block import",
+                    block.header.number
+                );
+                break 'import;
+            };
+        }
     }
     if let Some(last_block) = blocks.last() {
         let hash = last_block.hash();
         cfg_if::cfg_if! {
             if #[cfg(feature = "levm")] {
                 // We are allowing this not to unwrap so that tests can run even if block execution results in the wrong root hash with LEVM.
-                let _ = apply_fork_choice(store, hash, hash, hash);
+                let _ = blockchain.apply_fork_choice(hash, hash, hash);
             }
             else {
-                apply_fork_choice(store, hash, hash, hash).unwrap();
+                blockchain.apply_fork_choice(hash, hash, hash).unwrap();
             }
         }
     }
     info!("Added {} blocks to blockchain", size);
 }
+
+fn canonical_block(store: &Store, number: BlockNumber) -> Result<Block, anyhow::Error> {
+    let header = store
+        .get_block_header(number)?
+        .ok_or_else(|| anyhow::anyhow!("Block {number} is not in the canonical chain"))?;
+    let body = store
+        .get_block_body(number)?
+        .ok_or_else(|| anyhow::anyhow!("Missing body for block {number}"))?;
+    Ok(Block::new(header, body))
+}
+
+/// Writes the canonical blocks in the given range as a chain file, as read by `--import`
+fn export_chain_file(
+    store: &Store,
+    path: &str,
+    first: BlockNumber,
+    last: BlockNumber,
+) -> Result<(), anyhow::Error> {
+    info!("Exporting blocks {first} to {last} to chain file: {path}");
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    for number in first..=last {
+        let block = canonical_block(store, number)?;
+        writer.write_all(&block.encode_to_vec())?;
+    }
+    writer.flush()?;
+    info!("Exported {} blocks", (last + 1).saturating_sub(first));
+    Ok(())
+}
+
+/// Total difficulty of the canonical chain up to and including the given block
+fn canonical_total_difficulty(store: &Store, number: BlockNumber) -> Result<U256, anyhow::Error> {
+    let header = store
+        .get_block_header(number)?
+        .ok_or_else(|| anyhow::anyhow!("Block {number} is not in the canonical chain"))?;
+    if let Some(total_difficulty) = store.get_block_total_difficulty(header.compute_block_hash())? {
+        return Ok(total_difficulty);
+    }
+    // Fall back to adding up the difficulty of every header
+    (0..=number).try_fold(U256::zero(), |total, number| {
+        let header = store
+            .get_block_header(number)?
+            .ok_or_else(|| anyhow::anyhow!("Block {number} is not in the canonical chain"))?;
+        Ok(total + header.difficulty)
+    })
+}
+
+/// Writes the canonical blocks in the given range as era1 archives in the given directory, one per
+/// epoch of [`era1::MAX_EPOCH_SIZE`] blocks. The range must start at an epoch boundary.
+fn export_era1(
+    store: &Store,
+    dir: &str,
+    first: BlockNumber,
+    last: BlockNumber,
+    network: &str,
+) -> Result<(), anyhow::Error> {
+    anyhow::ensure!(
+        first % era1::MAX_EPOCH_SIZE == 0,
+        "Era1 exports must start at a multiple of {}, got block {first}",
+        era1::MAX_EPOCH_SIZE
+    );
+    fs::create_dir_all(dir)?;
+    let mut total_difficulty = match first.checked_sub(1) {
+        Some(parent) => canonical_total_difficulty(store, parent)?,
+        None => U256::zero(),
+    };
+    let mut start = first;
+    while start <= last {
+        let end = last.min(start + era1::MAX_EPOCH_SIZE - 1);
+        let blocks = (start..=end)
+            .map(|number| -> Result<era1::Era1Block, anyhow::Error> {
+                let block = canonical_block(store, number)?;
+                let receipts = store.get_receipts_for_block(&block.hash())?;
+                // Missing receipts would be written as an archive not matching the receipts root
+                anyhow::ensure!(
+                    receipts.len() == block.body.transactions.len(),
+                    "Missing receipts for block {number}"
+                );
+                total_difficulty += block.header.difficulty;
+                Ok(era1::Era1Block {
+                    block,
+                    receipts,
+                    total_difficulty,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let (archive, accumulator_root) = era1::encode(&blocks)?;
+        let path = Path::new(dir).join(era1::file_name(network, start, accumulator_root)?);
+        fs::write(&path, archive)?;
+        info!(
+            "Exported blocks {start} to {end} to era1 archive: {}",
+            path.display()
+        );
+        start = end + 1;
+    }
+    Ok(())
+}
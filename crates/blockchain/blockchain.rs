@@ -1,77 +1,996 @@
 pub mod constants;
 pub mod error;
+pub mod events;
 pub mod fork_choice;
 pub mod mempool;
 pub mod payload;
 mod smoke_test;
 
 use constants::{GAS_PER_BLOB, MAX_BLOB_GAS_PER_BLOCK, MAX_BLOB_NUMBER_PER_BLOCK};
-use error::{ChainError, InvalidBlockError};
+use error::{ChainError, InvalidBlockError, InvalidBuilderConfig, InvalidForkChoice};
 use ethrex_core::types::{
     compute_receipts_root, validate_block_header, validate_cancun_header_fields,
-    validate_no_cancun_header_fields, Block, BlockHash, BlockHeader, BlockNumber,
+    validate_no_cancun_header_fields, Block, BlockHash, BlockHeader, BlockNumber, ChainConfig,
     EIP4844Transaction, Receipt, Transaction,
 };
 use ethrex_core::H256;
+#[cfg(not(feature = "levm"))]
+use ethrex_core::{types::TxKind, Address};
+use events::{ChainEvent, EventBus, EventSubscriber};
+use payload::{BuilderConfig, PayloadBuildTask};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, RwLock, RwLockReadGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
 
 use ethrex_storage::error::StoreError;
-use ethrex_storage::{AccountUpdate, Store};
+use ethrex_storage::{
+    AccountUpdate, PruneCursor, ReachableNodes, Store, WriteBatch, STATE_PRUNING_BATCH_SIZE,
+};
 use ethrex_vm::{evm_state, execute_block, spec_id, EvmState, SpecId};
+#[cfg(not(feature = "levm"))]
+use ethrex_vm::{execute_block_with_senders, recover_sender};
 
-//TODO: Implement a struct Chain or BlockChain to encapsulate
-//functionality and canonical chain state and config
-
-/// Adds a new block to the store. It may or may not be canonical, as long as its ancestry links
-/// with the canonical chain and its parent's post-state is calculated. It doesn't modify the
-/// canonical chain/head. Fork choice needs to be updated for that in a separate step.
-///
-/// Performs pre and post execution validation, and updates the database with the post state.
-pub fn add_block(block: &Block, storage: &Store) -> Result<(), ChainError> {
-    let block_hash = block.header.compute_block_hash();
-
-    // Validate if it can be the new head and find the parent
-    let Ok(parent_header) = find_parent_header(&block.header, storage) else {
-        // If the parent is not present, we store it as pending.
-        storage.add_pending_block(block.clone())?;
-        return Err(ChainError::ParentNotFound);
-    };
-    let mut state = evm_state(storage.clone(), block.header.parent_hash);
+/// Amount of blocks meant to be imported at once with [`Blockchain::add_blocks_in_batch`]
+pub const IMPORT_BATCH_SIZE: usize = 1024;
+
+/// Maximum amount of invalid blocks remembered by the [`Blockchain`]
+const MAX_INVALID_BLOCKS: usize = 512;
+
+/// Maximum amount of blocks leaving or joining the canonical chain for the mempool to be updated
+/// from their transactions when the head changes. Longer head changes, as the ones made by sync,
+/// only rely on the nonce and balance checks to drop included transactions
+const MAX_MEMPOOL_UPDATE_DEPTH: usize = 64;
+
+/// Finalized blocks further than this behind the head are moved to the store's freezer by default
+pub const DEFAULT_FREEZER_THRESHOLD: u64 = 90_000;
+
+/// Owns the [`Store`] and keeps the state that block import and fork choice keep asking for:
+/// the chain config, the head, safe and finalized headers, the blocks known to be invalid and
+/// the payloads being built. Changes to the chain and the mempool are published on its
+/// [`EventBus`]. With [`GcMode::Full`], it also prunes the state of old blocks in the background
+/// as the head moves.
+/// Old finalized blocks are moved to the store's freezer in the background, and with a
+/// [`HistoryMode`] other than `All` their bodies and receipts are eventually deleted.
+#[derive(Debug)]
+pub struct Blockchain {
+    pub storage: Store,
+    chain_config: RwLock<Option<ChainConfig>>,
+    fork_choice: RwLock<Option<ForkChoiceHeaders>>,
+    invalid_blocks: Mutex<InvalidBlocks>,
+    pending_blocks: PendingBlocksConfig,
+    builder_config: BuilderConfig,
+    payload_build_tasks: Mutex<HashMap<u64, PayloadBuildTask>>,
+    events: EventBus,
+    gc_mode: GcMode,
+    /// Finalized blocks further than this behind the head are moved to the freezer
+    freezer_threshold: u64,
+    history_mode: HistoryMode,
+    /// Number of the first post-merge block, once found
+    merge_block: OnceLock<BlockNumber>,
+    /// Held for reading while a block's state is being stored and for writing while pruning
+    /// deletes nodes, as the nodes of a block that wasn't stored yet aren't reachable from any
+    /// retained root
+    state_lock: Arc<RwLock<()>>,
+    /// Number and state root of the blocks imported since the last pruning, so the state of
+    /// recent non-canonical blocks is kept as well
+    recent_imports: Arc<Mutex<Vec<(BlockNumber, H256)>>>,
+    /// State pruning running in the background, if any
+    state_pruning: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Limits on how long blocks with an unknown parent are kept around waiting for it
+#[derive(Debug, Clone, Copy)]
+pub struct PendingBlocksConfig {
+    /// Pending blocks more than this many blocks behind the head are dropped
+    pub max_depth: u64,
+    /// Pending blocks received longer ago than this are dropped, whatever their timestamp
+    pub max_age: Duration,
+}
+
+impl Default for PendingBlocksConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 128,
+            max_age: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// How the state of old blocks is handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GcMode {
+    /// The state of every block is kept
+    #[default]
+    Archive,
+    /// Only the state of the latest `retained_blocks` blocks, the finalized block and the blocks
+    /// imported on top of them is kept. Pruning runs in the background once every
+    /// `retained_blocks` blocks.
+    Full { retained_blocks: u64 },
+}
+
+impl GcMode {
+    /// Amount of blocks whose state is kept by default on full mode
+    pub const DEFAULT_RETAINED_BLOCKS: u64 = 128;
+}
+
+/// Which block bodies and receipts are kept, as in EIP-4444. Headers are always kept, and the
+/// history of non-finalized blocks is never deleted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HistoryMode {
+    /// The history of every block is kept
+    #[default]
+    All,
+    /// Only the history of the latest `retained_blocks` blocks is kept
+    Recent { retained_blocks: u64 },
+    /// Only the history from the merge onwards is kept
+    PostMerge,
+}
+
+impl HistoryMode {
+    /// Amount of blocks whose history is kept by default on recent mode, about a year's worth
+    pub const DEFAULT_RETAINED_BLOCKS: u64 = 2_628_000;
+}
+
+/// Headers of the blocks labeled by the last fork choice update
+#[derive(Debug, Clone)]
+pub struct ForkChoiceHeaders {
+    pub head: BlockHeader,
+    pub safe: Option<BlockHeader>,
+    pub finalized: Option<BlockHeader>,
+}
+
+/// Bounded map of invalid block hashes to their latest valid ancestor.
+/// Once full, the least recently used entry is evicted.
+#[derive(Debug, Default)]
+struct InvalidBlocks {
+    latest_valid: HashMap<BlockHash, BlockHash>,
+    usage: VecDeque<BlockHash>,
+}
+
+impl InvalidBlocks {
+    fn get(&mut self, block_hash: &BlockHash) -> Option<BlockHash> {
+        let latest_valid_hash = *self.latest_valid.get(block_hash)?;
+        self.touch(block_hash);
+        Some(latest_valid_hash)
+    }
 
-    // Validate the block pre-execution
-    validate_block(block, &parent_header, &state)?;
-    let (receipts, account_updates): (Vec<Receipt>, Vec<AccountUpdate>) = {
-        // TODO: Consider refactoring both implementations so that they have the same signature
-        #[cfg(feature = "levm")]
+    fn insert(&mut self, block_hash: BlockHash, latest_valid_hash: BlockHash) {
+        if self
+            .latest_valid
+            .insert(block_hash, latest_valid_hash)
+            .is_some()
         {
-            execute_block(block, &mut state)?
+            self.touch(&block_hash);
+            return;
+        }
+        self.usage.push_back(block_hash);
+        if self.usage.len() > MAX_INVALID_BLOCKS {
+            if let Some(evicted) = self.usage.pop_front() {
+                self.latest_valid.remove(&evicted);
+            }
+        }
+    }
+
+    fn touch(&mut self, block_hash: &BlockHash) {
+        if let Some(position) = self.usage.iter().position(|hash| hash == block_hash) {
+            self.usage.remove(position);
+        }
+        self.usage.push_back(*block_hash);
+    }
+}
+
+impl Blockchain {
+    pub fn new(storage: Store) -> Self {
+        Self {
+            storage,
+            chain_config: RwLock::new(None),
+            fork_choice: RwLock::new(None),
+            invalid_blocks: Mutex::new(InvalidBlocks::default()),
+            pending_blocks: PendingBlocksConfig::default(),
+            builder_config: BuilderConfig::default(),
+            payload_build_tasks: Mutex::new(HashMap::new()),
+            events: EventBus::default(),
+            gc_mode: GcMode::default(),
+            freezer_threshold: DEFAULT_FREEZER_THRESHOLD,
+            history_mode: HistoryMode::default(),
+            merge_block: OnceLock::new(),
+            state_lock: Arc::new(RwLock::new(())),
+            recent_imports: Arc::new(Mutex::new(Vec::new())),
+            state_pruning: Mutex::new(None),
+        }
+    }
+
+    /// Sets the settings used to build payloads, failing if they are not valid
+    pub fn with_builder_config(
+        mut self,
+        config: BuilderConfig,
+    ) -> Result<Self, InvalidBuilderConfig> {
+        config.validate()?;
+        self.builder_config = config;
+        Ok(self)
+    }
+
+    pub fn with_pending_blocks_config(mut self, config: PendingBlocksConfig) -> Self {
+        self.pending_blocks = config;
+        self
+    }
+
+    pub fn with_gc_mode(mut self, gc_mode: GcMode) -> Self {
+        self.gc_mode = gc_mode;
+        self
+    }
+
+    pub fn with_freezer_threshold(mut self, freezer_threshold: u64) -> Self {
+        self.freezer_threshold = freezer_threshold;
+        self
+    }
+
+    pub fn with_history_mode(mut self, history_mode: HistoryMode) -> Self {
+        self.history_mode = history_mode;
+        self
+    }
+
+    /// Bus the chain and mempool events are published on. Needed by the mempool functions
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Returns a subscriber receiving the chain and mempool events published from now on
+    pub fn subscribe(&self) -> EventSubscriber {
+        self.events.subscribe()
+    }
+
+    /// Adds a new block to the store. It may or may not be canonical, as long as its ancestry links
+    /// with the canonical chain and its parent's post-state is calculated. It doesn't modify the
+    /// canonical chain/head. Fork choice needs to be updated for that in a separate step.
+    ///
+    /// Performs pre and post execution validation, and updates the database with the post state.
+    /// Blocks that fail validation, and any of their descendants, are remembered as invalid so
+    /// they are rejected without being executed again.
+    ///
+    /// Once the block is added, any pending blocks waiting for it are imported as well.
+    pub fn add_block(&self, block: &Block) -> Result<(), ChainError> {
+        let block_hash = block.hash();
+        self.import_block(block, block_hash)?;
+        self.import_pending_descendants(block_hash)
+    }
+
+    fn import_block(&self, block: &Block, block_hash: BlockHash) -> Result<(), ChainError> {
+        if let Some(latest_valid_hash) = self.latest_valid_ancestor(block_hash)? {
+            return Err(InvalidBlockError::InvalidAncestor(latest_valid_hash).into());
+        }
+        if let Some(latest_valid_hash) = self.latest_valid_ancestor(block.header.parent_hash)? {
+            self.mark_invalid(block_hash, latest_valid_hash)?;
+            return Err(InvalidBlockError::InvalidAncestor(latest_valid_hash).into());
+        }
+
+        let result = self.execute_and_store_block(block, block_hash);
+        match &result {
+            Ok(()) => self.events.publish(ChainEvent::BlockImported {
+                number: block.header.number,
+                hash: block_hash,
+            }),
+            Err(error) if error.is_invalid_block() => {
+                // The parent was found and has a computed state, so it is the latest valid one
+                self.mark_invalid(block_hash, block.header.parent_hash)?;
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    /// Imports the pending blocks descending from the given block, which was just added.
+    /// Children that fail to import are dropped along with their own pending descendants, which
+    /// will eventually be pruned.
+    fn import_pending_descendants(&self, block_hash: BlockHash) -> Result<(), ChainError> {
+        let mut parents = vec![block_hash];
+        while let Some(parent_hash) = parents.pop() {
+            for child in self.storage.get_pending_blocks_by_parent(parent_hash)? {
+                let child_hash = child.hash();
+                self.storage.remove_pending_block(child_hash)?;
+                match self.import_block(&child, child_hash) {
+                    Ok(()) => {
+                        debug!("Imported pending block {child_hash:#x}");
+                        parents.push(child_hash);
+                    }
+                    Err(error) => warn!("Failed to import pending block {child_hash:#x}: {error}"),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops the pending blocks that are too far behind the given head or have waited too long
+    /// for their parent, as configured by [`PendingBlocksConfig`]
+    pub fn prune_pending_blocks(&self, head: &BlockHeader) -> Result<(), StoreError> {
+        let now = Instant::now();
+        let min_number = head.number.saturating_sub(self.pending_blocks.max_depth);
+        for pending in self.storage.get_pending_block_index()? {
+            if pending.number < min_number
+                || now.saturating_duration_since(pending.received_at) > self.pending_blocks.max_age
+            {
+                self.storage.remove_pending_block(pending.hash)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn execute_and_store_block(
+        &self,
+        block: &Block,
+        block_hash: BlockHash,
+    ) -> Result<(), ChainError> {
+        let storage = &self.storage;
+        let _state_guard = self.state_read_guard()?;
+
+        // Validate if it can be the new head and find the parent
+        let Ok(parent_header) = find_parent_header(&block.header, storage) else {
+            // If the parent is not present, we store it as pending.
+            storage.add_pending_block(block.clone())?;
+            return Err(ChainError::ParentNotFound);
+        };
+        let mut state = evm_state(storage.clone(), block.header.parent_hash);
+
+        // Validate the block pre-execution
+        validate_block(block, &parent_header, &state)?;
+        let (receipts, account_updates): (Vec<Receipt>, Vec<AccountUpdate>) = {
+            // TODO: Consider refactoring both implementations so that they have the same signature
+            #[cfg(feature = "levm")]
+            {
+                execute_block(block, &mut state)?
+            }
+            #[cfg(not(feature = "levm"))]
+            {
+                let receipts = execute_block(block, &mut state)?;
+                let account_updates = ethrex_vm::get_state_transitions(&mut state);
+                (receipts, account_updates)
+            }
+        };
+
+        validate_gas_used(&receipts, &block.header)?;
+
+        // Apply the account updates over the last block's state and compute the new state root
+        let mut batch = WriteBatch::new();
+        let new_state_root = state
+            .database()
+            .ok_or(ChainError::StoreError(StoreError::MissingStore))?
+            .stage_account_updates(block.header.parent_hash, &account_updates, &mut batch)?
+            .ok_or(ChainError::ParentStateNotFound)?;
+
+        // Check state root matches the one in block header after execution
+        validate_state_root(&block.header, new_state_root)?;
+
+        // Check receipts root matches the one in block header after execution
+        validate_receipts_root(&block.header, &receipts)?;
+
+        // The block, its receipts and its state are committed at once
+        storage.stage_block(&mut batch, block.clone())?;
+        batch.add_receipts(block_hash, receipts);
+        storage.write_batch(batch)?;
+        self.record_import(&block.header)?;
+
+        Ok(())
+    }
+
+    /// Imports a segment of blocks, each one the child of the previous one, on top of a block
+    /// whose state is already computed. Meant for chain import and full sync, where blocks come
+    /// in long runs:
+    /// - the senders of the segment's transactions are recovered in parallel up front
+    /// - blocks are executed one after another over the same state, while the accounts and
+    ///   storage touched by the next block are prefetched
+    /// - each block's state changes are staged on top of the previous block's, and the whole
+    ///   segment is committed in a single write
+    ///
+    /// Every block is fully checked, including its state root. Nothing is stored if any check
+    /// fails, and no block is marked as invalid: callers should fall back to
+    /// [`Blockchain::add_block`] for the segment.
+    /// The segment extends the canonical chain, so it is made canonical along with the blocks and
+    /// its last block is published as the new head.
+    pub fn add_blocks_in_batch(&self, blocks: &[Block]) -> Result<(), ChainError> {
+        if blocks.is_empty() {
+            return Ok(());
+        }
+        // Make sure the current head is cached, so the mempool can be updated from it
+        self.fork_choice_headers()?;
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "levm")] {
+                // LEVM doesn't keep a block's changes around to execute the next one over them yet
+                for block in blocks {
+                    self.add_block(block)?;
+                }
+                let mut batch = WriteBatch::new();
+                stage_canonical_segment(&mut batch, blocks);
+                self.storage.write_batch(batch)?;
+            } else {
+                self.execute_and_store_blocks(blocks)?;
+            }
+        }
+        self.refresh_fork_choice()?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "levm"))]
+    fn execute_and_store_blocks(&self, blocks: &[Block]) -> Result<(), ChainError> {
+        let Some(first) = blocks.first() else {
+            return Ok(());
+        };
+        let storage = &self.storage;
+        let _state_guard = self.state_read_guard()?;
+        let state_block_hash = first.header.parent_hash;
+        let mut parent_header = find_parent_header(&first.header, storage)?;
+        let mut state = evm_state(storage.clone(), state_block_hash);
+        let senders = recover_senders(blocks);
+        let mut batch = WriteBatch::new();
+
+        thread::scope(|scope| {
+            for (index, (block, block_senders)) in blocks.iter().zip(&senders).enumerate() {
+                // Warm up the database with the next block's state while this one runs
+                let prefetch = blocks.get(index + 1).zip(senders.get(index + 1)).map(
+                    |(next_block, next_senders)| {
+                        scope.spawn(move || {
+                            prefetch_block_state(
+                                storage,
+                                state_block_hash,
+                                next_block,
+                                next_senders,
+                            )
+                        })
+                    },
+                );
+
+                if block.header.parent_hash != parent_header.compute_block_hash() {
+                    return Err(ChainError::ParentNotFound);
+                }
+                validate_block(block, &parent_header, &state)?;
+                let block_receipts = execute_block_with_senders(block, block_senders, &mut state)?;
+                validate_gas_used(&block_receipts, &block.header)?;
+                validate_receipts_root(&block.header, &block_receipts)?;
+
+                // Stage the block's state over its parent's, which may itself be staged
+                let account_updates = ethrex_vm::get_state_transitions(&mut state);
+                let new_state_root = storage
+                    .stage_account_updates(block.header.parent_hash, &account_updates, &mut batch)?
+                    .ok_or(ChainError::ParentStateNotFound)?;
+                validate_state_root(&block.header, new_state_root)?;
+                storage.stage_block(&mut batch, block.clone())?;
+                batch.add_receipts(block.hash(), block_receipts);
+
+                // Later blocks in the segment may ask for this block's hash
+                state.cache_block_hash(block.header.number, block.hash());
+                parent_header = block.header.clone();
+
+                if let Some(prefetch) = prefetch {
+                    // Prefetching is only an optimization, a failed one is ignored
+                    let _ = prefetch.join();
+                }
+            }
+            Ok::<_, ChainError>(())
+        })?;
+
+        // The whole segment is committed at once, along with its place in the canonical chain
+        stage_canonical_segment(&mut batch, blocks);
+        storage.write_batch(batch)?;
+        for block in blocks {
+            self.events.publish(ChainEvent::BlockImported {
+                number: block.header.number,
+                hash: block.hash(),
+            });
+            self.record_import(&block.header)?;
+        }
+        Ok(())
+    }
+
+    /// Applies new fork choice data to the canonical chain and refreshes the cached head, safe
+    /// and finalized headers, updating the mempool and the bundle pool for the new head right
+    /// away. A head known to be invalid is rejected right away.
+    pub fn apply_fork_choice(
+        &self,
+        head_hash: H256,
+        safe_hash: H256,
+        finalized_hash: H256,
+    ) -> Result<BlockHeader, InvalidForkChoice> {
+        if let Some(latest_valid_hash) = self.latest_valid_ancestor(head_hash)? {
+            return Err(InvalidForkChoice::InvalidAncestor(latest_valid_hash));
+        }
+        // Make sure the current head is cached, so the mempool can be updated from it
+        self.fork_choice_headers()?;
+        let head =
+            fork_choice::apply_fork_choice(&self.storage, head_hash, safe_hash, finalized_hash)?;
+        let headers = self.refresh_fork_choice()?;
+        // The fork choice was already applied, a failed pruning is retried on the next one
+        if let Err(error) = self.prune_pending_blocks(&head) {
+            warn!("Failed to prune pending blocks: {error}");
         }
-        #[cfg(not(feature = "levm"))]
+        if let Err(error) = self.prune_state(&headers) {
+            warn!("Failed to prune state: {error}");
+        }
+        // Both run in the background, one at a time
+        if let Err(error) = self.expire_history(&headers) {
+            warn!("Failed to expire history: {error}");
+        }
+        self.freeze_blocks(&headers);
+        Ok(head)
+    }
+
+    /// Deletes the history of the finalized blocks outside of the kept range, as configured by
+    /// [`HistoryMode`], in the background
+    fn expire_history(&self, headers: &ForkChoiceHeaders) -> Result<(), StoreError> {
+        let Some(finalized) = &headers.finalized else {
+            return Ok(());
+        };
+        let cutoff = match self.history_mode {
+            HistoryMode::All => return Ok(()),
+            HistoryMode::Recent { retained_blocks } => {
+                headers.head.number.saturating_sub(retained_blocks)
+            }
+            HistoryMode::PostMerge => match self.merge_block_number(finalized)? {
+                Some(merge_block) => merge_block,
+                None => return Ok(()),
+            },
+        }
+        .min(finalized.number);
+        if cutoff > self.storage.get_earliest_block_number()? {
+            self.storage.expire_history_in_background(cutoff);
+        }
+        Ok(())
+    }
+
+    /// Returns the number of the first post-merge block, if it's not after the given one.
+    /// Found by binary search, as post-merge blocks are the ones with zero difficulty
+    fn merge_block_number(&self, latest: &BlockHeader) -> Result<Option<BlockNumber>, StoreError> {
+        if let Some(merge_block) = self.merge_block.get() {
+            return Ok(Some(*merge_block));
+        }
+        if !latest.difficulty.is_zero() {
+            return Ok(None);
+        }
+        let (mut low, mut high) = (0, latest.number);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let header = self.storage.get_block_header(middle)?.ok_or_else(|| {
+                StoreError::Custom(format!("Missing header of canonical block {middle}"))
+            })?;
+            if header.difficulty.is_zero() {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+        Ok(Some(*self.merge_block.get_or_init(|| low)))
+    }
+
+    /// Moves the finalized blocks further than the freezer threshold behind the head to the
+    /// store's freezer, in the background
+    fn freeze_blocks(&self, headers: &ForkChoiceHeaders) {
+        let Some(finalized) = &headers.finalized else {
+            return;
+        };
+        let limit = headers
+            .head
+            .number
+            .saturating_sub(self.freezer_threshold)
+            .min(finalized.number + 1);
+        self.storage.freeze_blocks_in_background(limit);
+    }
+
+    /// Deletes the state of the blocks that fell out of the retained window, as configured by
+    /// [`GcMode`], in the background. Does nothing on archive mode, if the window didn't move far
+    /// enough since the last pruning or if the last pruning is still running.
+    /// The state of the canonical blocks in the window, the finalized block and the blocks
+    /// imported on top of the window's first block is kept.
+    pub fn prune_state(&self, headers: &ForkChoiceHeaders) -> Result<(), StoreError> {
+        let GcMode::Full { retained_blocks } = self.gc_mode else {
+            return Ok(());
+        };
+        let cutoff = headers.head.number.saturating_sub(retained_blocks);
+        let earliest_state_block = self.storage.get_earliest_state_block_number()?;
+        if cutoff < earliest_state_block.unwrap_or_default() + retained_blocks {
+            return Ok(());
+        }
+        let mut state_pruning = self
+            .state_pruning
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))?;
+        if state_pruning
+            .as_ref()
+            .is_some_and(|pruning| !pruning.is_finished())
         {
-            let receipts = execute_block(block, &mut state)?;
-            let account_updates = ethrex_vm::get_state_transitions(&mut state);
-            (receipts, account_updates)
+            return Ok(());
         }
-    };
 
-    validate_gas_used(&receipts, &block.header)?;
+        let mut retained_roots = HashSet::new();
+        for number in cutoff..=headers.head.number {
+            if let Some(header) = self.storage.get_block_header(number)? {
+                retained_roots.insert(header.state_root);
+            }
+        }
+        if let Some(finalized) = &headers.finalized {
+            retained_roots.insert(finalized.state_root);
+        }
+        let mut recent_imports = self.recent_imports()?;
+        recent_imports.retain(|(number, _)| *number >= cutoff);
+        retained_roots.extend(recent_imports.iter().map(|(_, state_root)| *state_root));
+        drop(recent_imports);
 
-    // Apply the account updates over the last block's state and compute the new state root
-    let new_state_root = state
-        .database()
-        .ok_or(ChainError::StoreError(StoreError::MissingStore))?
-        .apply_account_updates(block.header.parent_hash, &account_updates)?
-        .ok_or(ChainError::ParentStateNotFound)?;
+        let storage = self.storage.clone();
+        let state_lock = self.state_lock.clone();
+        let recent_imports = self.recent_imports.clone();
+        *state_pruning = Some(thread::spawn(move || {
+            match prune_state_in_batches(
+                &storage,
+                &state_lock,
+                &recent_imports,
+                cutoff,
+                retained_roots,
+            ) {
+                Ok(deleted) => {
+                    info!(
+                        "Pruned {deleted} state trie nodes, keeping the state from block {cutoff}"
+                    )
+                }
+                Err(error) => warn!("Failed to prune state: {error}"),
+            }
+        }));
+        Ok(())
+    }
 
-    // Check state root matches the one in block header after execution
-    validate_state_root(&block.header, new_state_root)?;
+    /// Waits for the state pruning running in the background to finish, if any
+    pub fn wait_for_state_pruning(&self) -> Result<(), StoreError> {
+        let state_pruning = self
+            .state_pruning
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .take();
+        if let Some(state_pruning) = state_pruning {
+            state_pruning
+                .join()
+                .map_err(|_| StoreError::Custom("State pruning panicked".to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Moves the head back to one of its canonical ancestors and refreshes the cached head, safe
+    /// and finalized headers. The transactions of the blocks removed from the canonical chain
+    /// go back to the mempool, as in any reorg.
+    pub fn rewind_head(&self, head_hash: BlockHash) -> Result<BlockHeader, InvalidForkChoice> {
+        self.rewind(head_hash, true)
+    }
 
-    // Check receipts root matches the one in block header after execution
-    validate_receipts_root(&block.header, &receipts)?;
+    /// Moves the head back as [`Blockchain::rewind_head`] does, but the transactions of the
+    /// removed blocks are dropped instead of going back to the mempool, as when reverting a dev
+    /// chain to a snapshot
+    pub fn rewind_head_dropping_transactions(
+        &self,
+        head_hash: BlockHash,
+    ) -> Result<BlockHeader, InvalidForkChoice> {
+        self.rewind(head_hash, false)
+    }
 
-    store_block(storage, block.clone())?;
-    store_receipts(storage, receipts, block_hash)?;
+    fn rewind(
+        &self,
+        head_hash: BlockHash,
+        restore_transactions: bool,
+    ) -> Result<BlockHeader, InvalidForkChoice> {
+        // Make sure the current head is cached, so the mempool can be updated from it
+        self.fork_choice_headers()?;
+        let head = fork_choice::rewind_canonical_chain(&self.storage, head_hash)?;
+        self.reload_fork_choice(restore_transactions)?;
+        Ok(head)
+    }
 
-    Ok(())
+    /// Returns the hash of the latest valid ancestor of the given block if it is known to be invalid
+    pub fn latest_valid_ancestor(
+        &self,
+        block_hash: BlockHash,
+    ) -> Result<Option<BlockHash>, StoreError> {
+        Ok(self.invalid_blocks()?.get(&block_hash))
+    }
+
+    fn mark_invalid(
+        &self,
+        block_hash: BlockHash,
+        latest_valid_hash: BlockHash,
+    ) -> Result<(), StoreError> {
+        self.invalid_blocks()?.insert(block_hash, latest_valid_hash);
+        Ok(())
+    }
+
+    fn invalid_blocks(&self) -> Result<MutexGuard<'_, InvalidBlocks>, StoreError> {
+        self.invalid_blocks
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))
+    }
+
+    fn state_read_guard(&self) -> Result<RwLockReadGuard<'_, ()>, StoreError> {
+        self.state_lock
+            .read()
+            .map_err(|error| StoreError::Custom(error.to_string()))
+    }
+
+    fn recent_imports(&self) -> Result<MutexGuard<'_, Vec<(BlockNumber, H256)>>, StoreError> {
+        self.recent_imports
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))
+    }
+
+    /// Remembers the state root of an imported block, so it isn't pruned before falling out of
+    /// the retained window. Called while still holding the state lock the block was stored with
+    fn record_import(&self, header: &BlockHeader) -> Result<(), StoreError> {
+        if matches!(self.gc_mode, GcMode::Full { .. }) {
+            self.recent_imports()?
+                .push((header.number, header.state_root));
+        }
+        Ok(())
+    }
+
+    fn payload_build_tasks(
+        &self,
+    ) -> Result<MutexGuard<'_, HashMap<u64, PayloadBuildTask>>, StoreError> {
+        self.payload_build_tasks
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))
+    }
+
+    /// Returns the chain config, only reading it from the store the first time
+    pub fn chain_config(&self) -> Result<ChainConfig, StoreError> {
+        if let Some(chain_config) = *self
+            .chain_config
+            .read()
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+        {
+            return Ok(chain_config);
+        }
+        let chain_config = self.storage.get_chain_config()?;
+        *self
+            .chain_config
+            .write()
+            .map_err(|error| StoreError::Custom(error.to_string()))? = Some(chain_config);
+        Ok(chain_config)
+    }
+
+    /// Returns the head, safe and finalized headers, reading them from the store if they were not
+    /// cached yet. Only reads: the mempool and the bundle pool are updated when the head changes,
+    /// see [`Blockchain::refresh_fork_choice`]
+    pub fn fork_choice_headers(&self) -> Result<ForkChoiceHeaders, StoreError> {
+        if let Some(headers) = self
+            .fork_choice
+            .read()
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .as_ref()
+        {
+            return Ok(headers.clone());
+        }
+        let headers = self.read_fork_choice()?;
+        Ok(self
+            .fork_choice
+            .write()
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .get_or_insert(headers)
+            .clone())
+    }
+
+    /// Returns the header of the head of the canonical chain
+    pub fn latest_block_header(&self) -> Result<BlockHeader, StoreError> {
+        Ok(self.fork_choice_headers()?.head)
+    }
+
+    /// Reloads the cached head, safe and finalized headers from the store. If the head changed,
+    /// the mempool is updated and the new head, along with the reorg leading to it if any, is
+    /// published. Bundles that can no longer be included are dropped.
+    /// Called right after every change to the canonical chain, and must be called after updating
+    /// it without going through [`Blockchain::apply_fork_choice`], as it happens during sync.
+    pub fn refresh_fork_choice(&self) -> Result<ForkChoiceHeaders, StoreError> {
+        self.reload_fork_choice(true)
+    }
+
+    /// Reloads the fork choice as [`Blockchain::refresh_fork_choice`] does. Unless
+    /// `restore_transactions` is set, the transactions of the blocks that left the canonical
+    /// chain are not added back to the mempool
+    fn reload_fork_choice(
+        &self,
+        restore_transactions: bool,
+    ) -> Result<ForkChoiceHeaders, StoreError> {
+        let headers = self.read_fork_choice()?;
+        let previous = self
+            .fork_choice
+            .write()
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .replace(headers.clone());
+        match previous {
+            Some(previous) if previous.head != headers.head => {
+                let branches = self.head_change_branches(&previous.head, &headers.head)?;
+                if let Some((removed, added)) = &branches {
+                    if !removed.is_empty() {
+                        self.events.publish(ChainEvent::Reorg {
+                            removed: removed.clone(),
+                            added: added.clone(),
+                        });
+                    }
+                }
+                // Reorgs of any depth are published, but only shallow ones update the mempool
+                let branches = branches.filter(|(removed, added)| {
+                    removed.len() <= MAX_MEMPOOL_UPDATE_DEPTH
+                        && added.len() <= MAX_MEMPOOL_UPDATE_DEPTH
+                });
+                self.update_mempool(branches, restore_transactions)?;
+                self.events
+                    .publish(ChainEvent::NewHead(Box::new(headers.head.clone())));
+            }
+            Some(_) => {}
+            // The previous head is unknown, so only the checks against the new state are run
+            None => mempool::reorganize_pool(&self.storage, &self.events)?,
+        }
+        // Bundles targeting the head or its ancestors can no longer be included
+        self.storage.prune_bundle_pool(headers.head.number)?;
+        Ok(headers)
+    }
+
+    /// Reads the head, safe and finalized headers from the store
+    fn read_fork_choice(&self) -> Result<ForkChoiceHeaders, StoreError> {
+        let latest_block_number = self.storage.get_latest_block_number()?;
+        let head =
+            self.storage
+                .get_block_header(latest_block_number)?
+                .ok_or(StoreError::Custom(
+                    "Could not find latest block header".to_string(),
+                ))?;
+        let safe = match self.storage.get_safe_block_number()? {
+            Some(number) => self.storage.get_block_header(number)?,
+            None => None,
+        };
+        let finalized = match self.storage.get_finalized_block_number()? {
+            Some(number) => self.storage.get_block_header(number)?,
+            None => None,
+        };
+        Ok(ForkChoiceHeaders {
+            head,
+            safe,
+            finalized,
+        })
+    }
+
+    /// Updates the mempool after the canonical head moved, given the branches found by
+    /// [`Blockchain::head_change_branches`].
+    /// Transactions included in the new canonical blocks are removed and, if
+    /// `restore_transactions` is set, the ones from the blocks that are no longer canonical are
+    /// added back, then the rest are checked against the new state.
+    fn update_mempool(
+        &self,
+        branches: Option<(Vec<BlockHash>, Vec<BlockHash>)>,
+        restore_transactions: bool,
+    ) -> Result<(), StoreError> {
+        if let Some((old_branch, new_branch)) = branches {
+            let mut included = HashSet::new();
+            for block_hash in new_branch {
+                let Some(body) = self.storage.get_block_body_by_hash(block_hash)? else {
+                    continue;
+                };
+                for tx in body.transactions {
+                    let tx_hash = tx.compute_hash();
+                    mempool::remove_transaction(&tx_hash, &self.storage, &self.events)?;
+                    included.insert(tx_hash);
+                }
+            }
+            let restored = if restore_transactions {
+                old_branch
+            } else {
+                Vec::new()
+            };
+            for block_hash in restored {
+                let Some(body) = self.storage.get_block_body_by_hash(block_hash)? else {
+                    continue;
+                };
+                for tx in body.transactions {
+                    // Blob transactions can't be added back, as their blobs bundles are gone
+                    if included.contains(&tx.compute_hash())
+                        || matches!(tx, Transaction::EIP4844Transaction(_))
+                    {
+                        continue;
+                    }
+                    if let Err(error) = mempool::add_transaction(tx, &self.storage, &self.events) {
+                        debug!("Dropped transaction from reorged out block: {error}");
+                    }
+                }
+            }
+        }
+        mempool::reorganize_pool(&self.storage, &self.events)
+    }
+
+    /// Finds how the canonical chain changed when its head moved. Returns the hashes of the blocks
+    /// that left the canonical chain and of the ones that joined it, newest first.
+    /// Only the old branch is walked back, as the new one is canonical by now. Returns `None` if
+    /// the old branch can't be walked back, or if no block left the canonical chain and the head
+    /// moved further than [`MAX_MEMPOOL_UPDATE_DEPTH`] blocks ahead, as it happens during sync.
+    #[allow(clippy::type_complexity)]
+    fn head_change_branches(
+        &self,
+        old_head: &BlockHeader,
+        new_head: &BlockHeader,
+    ) -> Result<Option<(Vec<BlockHash>, Vec<BlockHash>)>, StoreError> {
+        let mut old_branch = Vec::new();
+        let mut old = old_head.clone();
+        let mut old_hash = old.compute_block_hash();
+        while old.number > new_head.number || !is_canonical(&self.storage, old.number, old_hash)? {
+            old_branch.push(old_hash);
+            old_hash = old.parent_hash;
+            let Some(parent) = self.storage.get_block_header_by_hash(old_hash)? else {
+                return Ok(None);
+            };
+            old = parent;
+        }
+
+        let ancestor_number = old.number;
+        if old_branch.is_empty()
+            && new_head.number.saturating_sub(ancestor_number) > MAX_MEMPOOL_UPDATE_DEPTH as u64
+        {
+            return Ok(None);
+        }
+        let mut new_branch = Vec::new();
+        for number in (ancestor_number + 1..=new_head.number).rev() {
+            let Some(hash) = self.storage.get_canonical_block_hash(number)? else {
+                return Ok(None);
+            };
+            new_branch.push(hash);
+        }
+        Ok(Some((old_branch, new_branch)))
+    }
+}
+
+/// Deletes the state below `cutoff` that isn't reachable from the retained state roots, a batch of
+/// nodes at a time. The state lock is only held while deleting each batch, and the state of the
+/// blocks imported in the meantime is marked as reachable before it, as they may have stored
+/// again nodes that weren't reachable anymore. Returns the amount of deleted nodes
+fn prune_state_in_batches(
+    storage: &Store,
+    state_lock: &RwLock<()>,
+    recent_imports: &Mutex<Vec<(BlockNumber, H256)>>,
+    cutoff: BlockNumber,
+    retained_roots: HashSet<H256>,
+) -> Result<usize, StoreError> {
+    // Only pruning deletes nodes, so the retained state can be marked without the lock
+    let mut reachable = ReachableNodes::default();
+    for state_root in &retained_roots {
+        storage.mark_reachable_state(&mut reachable, *state_root)?;
+    }
+    let mut marked_roots = retained_roots;
+    storage.update_earliest_state_block_number(cutoff)?;
+
+    let mut deleted = 0;
+    let mut cursor = Some(PruneCursor::default());
+    while let Some(from) = cursor {
+        let _state_guard = state_lock
+            .write()
+            .map_err(|error| StoreError::Custom(error.to_string()))?;
+        let imported: Vec<H256> = recent_imports
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .iter()
+            .filter(|(number, _)| *number >= cutoff)
+            .map(|(_, state_root)| *state_root)
+            .collect();
+        for state_root in imported {
+            if marked_roots.insert(state_root) {
+                storage.mark_reachable_state(&mut reachable, state_root)?;
+            }
+        }
+        let (batch_deleted, next) =
+            storage.prune_unreachable_state(&reachable, from, STATE_PRUNING_BATCH_SIZE)?;
+        deleted += batch_deleted;
+        cursor = next;
+    }
+    Ok(deleted)
+}
+
+/// Makes a segment of consecutive blocks canonical, with its last block as the head
+fn stage_canonical_segment(batch: &mut WriteBatch, blocks: &[Block]) {
+    for block in blocks {
+        batch.set_canonical_block(block.header.number, block.hash());
+    }
+    if let Some(last) = blocks.last() {
+        batch.update_latest_block_number(last.header.number);
+    }
 }
 
 /// Stores block and header in the database
@@ -143,6 +1062,60 @@ pub fn find_parent_header(
     }
 }
 
+/// Recovers the transaction senders of each block, splitting the blocks among the available
+/// threads
+#[cfg(not(feature = "levm"))]
+fn recover_senders(blocks: &[Block]) -> Vec<Vec<Address>> {
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    let chunk_size = blocks.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = blocks
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .map(|block| block.body.transactions.iter().map(recover_sender).collect())
+                        .collect::<Vec<Vec<Address>>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    })
+}
+
+/// Reads the accounts and storage slots the block is known to touch from the given block's
+/// state, so their trie nodes are already in the database's page cache by the time the block is
+/// executed
+#[cfg(not(feature = "levm"))]
+fn prefetch_block_state(
+    storage: &Store,
+    state_block_hash: BlockHash,
+    block: &Block,
+    senders: &[Address],
+) -> Result<(), StoreError> {
+    for (transaction, sender) in block.body.transactions.iter().zip(senders) {
+        storage.get_account_info_by_hash(state_block_hash, *sender)?;
+        if let TxKind::Call(to) = transaction.to() {
+            storage.get_account_info_by_hash(state_block_hash, to)?;
+        }
+        for (address, keys) in transaction.access_list() {
+            storage.get_account_info_by_hash(state_block_hash, address)?;
+            for key in keys {
+                storage.get_storage_at_hash(state_block_hash, address, key)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Performs pre-execution validation of the block's header values in reference to the parent_header
 /// Verifies that blob gas fields in the header are correct in reference to the block's body.
 /// If a block passes this check, execution will still fail with execute_block when a transaction runs out of gas
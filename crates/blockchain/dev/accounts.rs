@@ -0,0 +1,62 @@
+use coins_bip39::{English, Mnemonic, MnemonicError};
+use ethrex_rpc::evm::DevAccount;
+use secp256k1::SecretKey;
+
+/// Mnemonic used when none is given, shared with other development tools so the same accounts
+/// are funded everywhere
+pub const DEFAULT_MNEMONIC: &str = "test test test test test test test test test test test junk";
+
+#[derive(Debug, thiserror::Error)]
+pub enum DevAccountsError {
+    #[error("Invalid mnemonic: {0}")]
+    Mnemonic(#[from] MnemonicError),
+    #[error("Invalid derived key: {0}")]
+    Key(#[from] secp256k1::Error),
+}
+
+/// Derives the first `count` accounts of the mnemonic along the standard Ethereum path,
+/// `m/44'/60'/0'/0/{index}`
+pub fn derive_accounts(mnemonic: &str, count: u32) -> Result<Vec<DevAccount>, DevAccountsError> {
+    let mnemonic = Mnemonic::<English>::new_from_phrase(mnemonic)?;
+    (0..count)
+        .map(|index| {
+            let key = mnemonic.derive_key(format!("m/44'/60'/0'/0/{index}").as_str(), None)?;
+            let signing_key: &k256::ecdsa::SigningKey = key.as_ref();
+            let secret_key = SecretKey::from_slice(&signing_key.to_bytes())?;
+            Ok(DevAccount::new(secret_key))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_types::Address;
+    use std::str::FromStr;
+
+    #[test]
+    fn default_mnemonic_derives_the_well_known_accounts() {
+        let accounts = derive_accounts(DEFAULT_MNEMONIC, 2).unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(
+            accounts[0].address,
+            Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap()
+        );
+        assert_eq!(
+            hex::encode(accounts[0].secret_key.secret_bytes()),
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        );
+        assert_eq!(
+            accounts[1].address,
+            Address::from_str("0x70997970C51812dc3A010C7d01b50e0d17dc79C8").unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_mnemonic_is_rejected() {
+        assert!(matches!(
+            derive_accounts("not a mnemonic", 1),
+            Err(DevAccountsError::Mnemonic(_))
+        ));
+    }
+}
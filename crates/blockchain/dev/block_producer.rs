@@ -1,121 +1,160 @@
-use crate::utils::engine_client::{errors::EngineClientError, EngineClient};
-use bytes::Bytes;
-use ethereum_types::{Address, H256};
-use ethrex_rpc::types::fork_choice::{ForkChoiceState, PayloadAttributesV3};
-use sha2::{Digest, Sha256};
-use std::time::{SystemTime, UNIX_EPOCH};
+use ethrex_blockchain::events::ChainEvent;
+use ethrex_rpc::evm::DevChain;
+use std::{sync::Arc, time::Duration};
 
+/// When the dev block producer seals blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockProductionMode {
+    /// Seal a block as soon as a transaction enters the mempool
+    Automine,
+    /// Seal a block every given interval, whether there are transactions or not
+    Interval(Duration),
+    /// Only seal blocks when requested through `evm_mine`
+    Manual,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BlockProducerError {
+    #[error("Failed to produce a block {0} times in a row")]
+    TooManyFailures(u32),
+    #[error("The chain event bus was closed")]
+    EventBusClosed,
+}
+
+/// Seals blocks on the dev chain according to the given mode. Gives up after failing to produce
+/// a block `max_tries` times in a row.
 pub async fn start_block_producer(
-    execution_client_auth_url: String,
-    jwt_secret: Bytes,
-    head_block_hash: H256,
+    dev_chain: Arc<DevChain>,
+    mode: BlockProductionMode,
     max_tries: u32,
-    block_production_interval_ms: u64,
-    coinbase_address: Address,
-) -> Result<(), EngineClientError> {
-    let engine_client = EngineClient::new(&execution_client_auth_url, jwt_secret);
+) -> Result<(), BlockProducerError> {
+    match mode {
+        BlockProductionMode::Automine => automine(dev_chain, max_tries).await,
+        BlockProductionMode::Interval(interval) => {
+            produce_every(dev_chain, interval, max_tries).await
+        }
+        BlockProductionMode::Manual => {
+            tracing::info!("Blocks will only be produced through evm_mine");
+            Ok(())
+        }
+    }
+}
 
-    let mut head_block_hash: H256 = head_block_hash;
-    let parent_beacon_block_root = H256::zero();
+async fn produce_every(
+    dev_chain: Arc<DevChain>,
+    interval: Duration,
+    max_tries: u32,
+) -> Result<(), BlockProducerError> {
     let mut tries = 0;
     while tries < max_tries {
+        tokio::time::sleep(interval).await;
         tracing::info!("Producing block");
-        tracing::debug!("Head block hash: {head_block_hash:#x}");
-        let fork_choice_state = ForkChoiceState {
-            head_block_hash,
-            safe_block_hash: head_block_hash,
-            finalized_block_hash: head_block_hash,
-        };
-
-        let payload_attributes = PayloadAttributesV3 {
-            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
-            prev_randao: H256::zero(),
-            suggested_fee_recipient: coinbase_address,
-            parent_beacon_block_root: Some(parent_beacon_block_root),
-            withdrawals: Some(Vec::new()),
-        };
-        let fork_choice_response = match engine_client
-            .engine_forkchoice_updated_v3(fork_choice_state, Some(payload_attributes))
-            .await
-        {
-            Ok(response) => {
-                tracing::debug!("ForkChoiceV3 response: {response:?}");
-                response
-            }
-            Err(error) => {
-                tracing::error!(
-                    "Failed to produce block: error sending engine_forkchoiceUpdateV3 with PayloadAttributes: {error}"
-                );
-                tries += 1;
-                continue;
-            }
-        };
-        let payload_id = fork_choice_response
-            .payload_id
-            .expect("Failed to produce block: payload_id is None in ForkChoiceResponse");
-        let execution_payload_response = match engine_client.engine_get_payload_v3(payload_id).await
-        {
-            Ok(response) => {
-                tracing::debug!("GetPayloadV3 response: {response:?}");
-                response
+        match dev_chain.mine(None) {
+            Ok(header) => {
+                tracing::info!("Produced block {:#x}", header.compute_block_hash());
+                tries = 0;
             }
             Err(error) => {
-                tracing::error!(
-                    "Failed to produce block: error sending engine_getPayloadV3: {error}"
-                );
+                tracing::error!("Failed to produce block: {error:?}");
                 tries += 1;
-                continue;
-            }
-        };
-        let payload_status = match engine_client
-            .engine_new_payload_v3(
-                execution_payload_response.execution_payload,
-                execution_payload_response
-                    .blobs_bundle
-                    .unwrap_or_default()
-                    .commitments
-                    .iter()
-                    .map(|commitment| {
-                        let mut hasher = Sha256::new();
-                        hasher.update(commitment);
-                        let mut hash = hasher.finalize();
-                        // https://eips.ethereum.org/EIPS/eip-4844 -> kzg_to_versioned_hash
-                        hash[0] = 0x01;
-                        H256::from_slice(&hash)
-                    })
-                    .collect(),
-                parent_beacon_block_root,
-            )
-            .await
-        {
-            Ok(response) => {
-                tracing::debug!("NewPayloadV3 response: {response:?}");
-                response
             }
-            Err(error) => {
-                tracing::error!(
-                    "Failed to produce block: error sending engine_newPayloadV3: {error}"
-                );
-                tries += 1;
-                continue;
+        }
+    }
+    Err(BlockProducerError::TooManyFailures(max_tries))
+}
+
+async fn automine(dev_chain: Arc<DevChain>, max_tries: u32) -> Result<(), BlockProducerError> {
+    let mut events = dev_chain.blockchain().subscribe();
+    let mut tries = 0;
+    while tries < max_tries {
+        match events.recv().await {
+            Some(ChainEvent::TxAdded(_)) => {
+                // Transactions that arrived together are sealed in the same block
+                while events.try_recv().is_some() {}
+                match dev_chain.mine_pending() {
+                    Ok(Some(header)) => {
+                        tracing::info!("Produced block {:#x}", header.compute_block_hash());
+                        tries = 0;
+                    }
+                    Ok(None) => {}
+                    Err(error) => {
+                        tracing::error!("Failed to produce block: {error:?}");
+                        tries += 1;
+                    }
+                }
             }
-        };
-        let produced_block_hash = if let Some(latest_valid_hash) = payload_status.latest_valid_hash
-        {
-            latest_valid_hash
-        } else {
-            tracing::error!("Failed to produce block: latest_valid_hash is None in PayloadStatus: {payload_status:?}");
-            tries += 1;
-            continue;
-        };
-        tracing::info!("Produced block {produced_block_hash:#x}");
+            Some(_) => {}
+            None => return Err(BlockProducerError::EventBusClosed),
+        }
+    }
+    Err(BlockProducerError::TooManyFailures(max_tries))
+}
 
-        head_block_hash = produced_block_hash;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        accounts::{derive_accounts, DEFAULT_MNEMONIC},
+        genesis::dev_genesis,
+    };
+    use ethrex_blockchain::{mempool, Blockchain};
+    use ethrex_core::{
+        types::{EIP1559Transaction, Signable, Transaction, TxKind},
+        Address, U256,
+    };
+    use ethrex_storage::{EngineType, Store};
+
+    #[tokio::test]
+    async fn revert_with_automine_drops_the_transactions() {
+        let account = derive_accounts(DEFAULT_MNEMONIC, 1).unwrap().remove(0);
+        let storage = Store::new("", EngineType::InMemory).unwrap();
+        storage
+            .add_initial_state(dev_genesis(&[account.address]))
+            .unwrap();
+        let blockchain = Arc::new(Blockchain::new(storage.clone()));
+        let dev_chain = Arc::new(
+            DevChain::new(blockchain.clone(), Address::zero()).with_accounts(vec![account.clone()]),
+        );
+        let producer = tokio::spawn(start_block_producer(
+            dev_chain.clone(),
+            BlockProductionMode::Automine,
+            3,
+        ));
+        // Let the producer subscribe to the chain events
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let snapshot_head = blockchain.latest_block_header().unwrap();
+        let id = dev_chain.snapshot().unwrap();
+        let transaction = EIP1559Transaction {
+            chain_id: blockchain.chain_config().unwrap().chain_id,
+            nonce: 0,
+            max_priority_fee_per_gas: 1,
+            max_fee_per_gas: snapshot_head.base_fee_per_gas.unwrap_or_default() * 2,
+            gas_limit: 21_000,
+            to: TxKind::Call(Address::repeat_byte(0x22)),
+            value: U256::one(),
+            ..Default::default()
+        }
+        .sign(&account.secret_key);
+        mempool::add_local_transaction(
+            Transaction::EIP1559Transaction(transaction),
+            &storage,
+            blockchain.events(),
+        )
+        .unwrap();
+        for _ in 0..100 {
+            if blockchain.latest_block_header().unwrap().number == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(blockchain.latest_block_header().unwrap().number, 1);
 
-        tokio::time::sleep(tokio::time::Duration::from_millis(
-            block_production_interval_ms,
-        ))
-        .await;
+        assert!(dev_chain.revert(id).unwrap());
+        // Give the producer the chance to seal the reverted transaction again
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(blockchain.latest_block_header().unwrap(), snapshot_head);
+        assert_eq!(storage.get_pool_stats().unwrap().transactions, 0);
+        producer.abort();
     }
-    Err(EngineClientError::SystemFailed(format!("{}", max_tries)))
 }
@@ -1,2 +1,4 @@
+pub mod accounts;
 pub mod block_producer;
+pub mod genesis;
 pub mod utils;
@@ -0,0 +1,45 @@
+use ethereum_types::{Address, U256};
+use ethrex_core::types::{Genesis, GenesisAccount};
+
+const DEV_GENESIS: &str = include_str!("genesis.json");
+
+/// Balance given to each dev account, 10000 ETH
+const DEV_ACCOUNT_BALANCE: u128 = 10_000_000_000_000_000_000_000;
+
+/// Genesis of the dev chain, with every fork active from the first block and the given accounts
+/// funded
+pub fn dev_genesis(accounts: &[Address]) -> Genesis {
+    let mut genesis: Genesis =
+        serde_json::from_str(DEV_GENESIS).expect("Dev genesis file is valid");
+    for address in accounts {
+        genesis.alloc.insert(
+            *address,
+            GenesisAccount {
+                code: Default::default(),
+                storage: Default::default(),
+                balance: U256::from(DEV_ACCOUNT_BALANCE),
+                nonce: 0,
+            },
+        );
+    }
+    genesis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dev_genesis_funds_the_accounts() {
+        let accounts = [Address::repeat_byte(1), Address::repeat_byte(2)];
+        let genesis = dev_genesis(&accounts);
+        assert_eq!(genesis.config.chain_id, 1337);
+        assert!(genesis.config.is_cancun_activated(0));
+        for address in accounts {
+            assert_eq!(
+                genesis.alloc[&address].balance,
+                U256::from(10_000) * U256::exp10(18)
+            );
+        }
+    }
+}
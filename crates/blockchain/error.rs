@@ -1,4 +1,4 @@
-use ethrex_core::types::{BlobsBundleError, InvalidBlockHeaderError};
+use ethrex_core::types::{BlobsBundleError, BlockHash, InvalidBlockHeaderError};
 use ethrex_storage::error::StoreError;
 use ethrex_vm::EvmError;
 
@@ -18,6 +18,21 @@ pub enum ChainError {
     EvmError(#[from] EvmError),
 }
 
+impl ChainError {
+    /// Returns true if the error was caused by the block itself rather than by the node,
+    /// meaning the block will never be valid
+    pub fn is_invalid_block(&self) -> bool {
+        match self {
+            ChainError::InvalidBlock(_) => true,
+            // Only consensus checks, other errors may come from the node and go away on retry
+            ChainError::EvmError(error) => {
+                matches!(error, EvmError::Transaction(_) | EvmError::Header(_))
+            }
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum InvalidBlockError {
     #[error("World State Root does not match the one in the header after executing")]
@@ -36,6 +51,16 @@ pub enum InvalidBlockError {
     BlobGasUsedMismatch,
     #[error("Invalid transaction: {0}")]
     InvalidTransaction(String),
+    #[error("Block has an invalid ancestor, latest valid block is {0:#x}")]
+    InvalidAncestor(BlockHash),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InvalidBuilderConfig {
+    #[error("Extra data is {0} bytes long, the maximum is {1}")]
+    ExtraDataTooLong(usize, usize),
+    #[error("Gas ceil {0} is below the minimum gas limit of {1}")]
+    GasCeilTooLow(u64, u64),
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -68,6 +93,20 @@ pub enum MempoolError {
     NotEnoughBalance,
     #[error("Transaction gas fields are invalid")]
     InvalidTxGasvalues,
+    #[error("Replacement transaction underpriced, fees must be bumped by at least {0}%")]
+    ReplacementUnderpriced(u64),
+    #[error("Blob transactions can only replace or be replaced by other blob transactions")]
+    ReplacementTypeMismatch,
+    #[error("Mempool is full and the transaction doesn't pay more than the pooled ones")]
+    PoolFull,
+    #[error("Sender already has the maximum of {0} pooled transactions")]
+    AccountSlotsExceeded(usize),
+    #[error("Sender already has the maximum of {0} queued transactions")]
+    AccountQueueExceeded(usize),
+    #[error("Invalid bundle: {0}")]
+    InvalidBundle(String),
+    #[error("Target block already has the maximum of {0} pooled bundles")]
+    BundlePoolFull(usize),
 }
 
 #[derive(Debug)]
@@ -97,4 +136,6 @@ pub enum InvalidForkChoice {
     Disconnected(ForkChoiceElement, ForkChoiceElement),
     #[error("Requested head is an invalid block.")]
     InvalidHead,
+    #[error("Requested head is known to be invalid, latest valid block is {0:#x}")]
+    InvalidAncestor(BlockHash),
 }
@@ -0,0 +1,145 @@
+use ethrex_core::{
+    types::{BlockHash, BlockHeader, BlockNumber},
+    H256,
+};
+use tokio::sync::broadcast::{
+    self,
+    error::{RecvError, TryRecvError},
+};
+use tracing::warn;
+
+/// Amount of events buffered for each subscriber. Subscribers that fall further behind miss the
+/// oldest ones
+pub const EVENT_BUFFER_SIZE: usize = 1024;
+
+/// Changes to the chain and the mempool that other subsystems may want to react to
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainEvent {
+    /// A block was executed and stored. It isn't canonical until a fork choice makes it so
+    BlockImported {
+        number: BlockNumber,
+        hash: BlockHash,
+    },
+    /// The head of the canonical chain changed
+    NewHead(Box<BlockHeader>),
+    /// The canonical chain switched branches. Holds the hashes of the blocks that left and joined
+    /// it, newest first. Published before the [`ChainEvent::NewHead`] of the new branch
+    Reorg {
+        removed: Vec<BlockHash>,
+        added: Vec<BlockHash>,
+    },
+    /// A transaction was added to the mempool
+    TxAdded(H256),
+    /// A transaction left the mempool, either because it was included, replaced, evicted or can
+    /// no longer be included
+    TxRemoved(H256),
+}
+
+/// Broadcasts [`ChainEvent`]s to every subscriber.
+/// Publishing never blocks: each subscriber has a buffer of bounded size and, once it is full,
+/// the oldest events are dropped for that subscriber alone
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<ChainEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(EVENT_BUFFER_SIZE)
+    }
+}
+
+impl EventBus {
+    pub fn new(buffer_size: usize) -> Self {
+        let (sender, _) = broadcast::channel(buffer_size);
+        Self { sender }
+    }
+
+    /// Returns a subscriber receiving the events published from now on
+    pub fn subscribe(&self) -> EventSubscriber {
+        EventSubscriber {
+            receiver: self.sender.subscribe(),
+            missed: 0,
+        }
+    }
+
+    pub fn publish(&self, event: ChainEvent) {
+        // Sending only fails if there are no subscribers, in which case the event is not needed
+        let _ = self.sender.send(event);
+    }
+}
+
+/// Receiving end of an [`EventBus`].
+/// A subscriber that falls behind skips the events dropped from its buffer and goes on from the
+/// oldest one left, keeping count of the ones it missed
+#[derive(Debug)]
+pub struct EventSubscriber {
+    receiver: broadcast::Receiver<ChainEvent>,
+    missed: u64,
+}
+
+impl EventSubscriber {
+    /// Waits for the next event. Returns `None` once every [`EventBus`] handle is dropped
+    pub async fn recv(&mut self) -> Option<ChainEvent> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(RecvError::Lagged(skipped)) => self.lagged(skipped),
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Returns the next event if there is one already published
+    pub fn try_recv(&mut self) -> Option<ChainEvent> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) => return Some(event),
+                Err(TryRecvError::Lagged(skipped)) => self.lagged(skipped),
+                Err(TryRecvError::Empty | TryRecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Amount of events this subscriber missed for falling behind
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+
+    fn lagged(&mut self, skipped: u64) {
+        warn!("Chain event subscriber fell behind and missed {skipped} events");
+        self.missed += skipped;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn publishing_without_subscribers_is_a_noop() {
+        let events = EventBus::default();
+        events.publish(ChainEvent::TxAdded(H256::zero()));
+        let mut subscriber = events.subscribe();
+        assert_eq!(subscriber.try_recv(), None);
+    }
+
+    #[test]
+    fn lagging_subscriber_skips_the_oldest_events() {
+        let events = EventBus::new(2);
+        let mut subscriber = events.subscribe();
+        for byte in 1..=3 {
+            events.publish(ChainEvent::TxAdded(H256::repeat_byte(byte)));
+        }
+        assert_eq!(
+            subscriber.try_recv(),
+            Some(ChainEvent::TxAdded(H256::repeat_byte(2)))
+        );
+        assert_eq!(subscriber.missed(), 1);
+        assert_eq!(
+            subscriber.try_recv(),
+            Some(ChainEvent::TxAdded(H256::repeat_byte(3)))
+        );
+        assert_eq!(subscriber.try_recv(), None);
+    }
+}
@@ -2,7 +2,7 @@ use ethrex_core::{
     types::{Block, BlockHash, BlockHeader, BlockNumber},
     H256,
 };
-use ethrex_storage::{error::StoreError, Store};
+use ethrex_storage::{error::StoreError, Store, WriteBatch};
 
 use crate::{
     error::{self, InvalidForkChoice},
@@ -18,7 +18,7 @@ use crate::{
 /// and itself are made canonical.
 ///
 /// If the fork choice state is applied correctly, the head block header is returned.
-pub fn apply_fork_choice(
+pub(crate) fn apply_fork_choice(
     store: &Store,
     head_hash: H256,
     safe_hash: H256,
@@ -111,30 +111,79 @@ pub fn apply_fork_choice(
 
     // Finished all validations.
 
+    // The canonical chain and its head are updated at once
+    let mut batch = WriteBatch::new();
+
     // Make all ancestors to head canonical.
     for (number, hash) in new_canonical_blocks {
-        store.set_canonical_block(number, hash)?;
+        batch.set_canonical_block(number, hash);
     }
 
     // Remove anything after the head from the canonical chain.
     for number in (head.number + 1)..(latest + 1) {
-        store.unset_canonical_block(number)?;
+        batch.unset_canonical_block(number);
     }
 
     // Make head canonical and label all special blocks correctly.
-    store.set_canonical_block(head.number, head_hash)?;
+    batch.set_canonical_block(head.number, head_hash);
+    batch.update_latest_block_number(head.number);
+    store.write_batch(batch)?;
     if let Some(finalized) = finalized_res {
         store.update_finalized_block_number(finalized.header.number)?;
     }
     if let Some(safe) = safe_res {
         store.update_safe_block_number(safe.header.number)?;
     }
-    store.update_latest_block_number(head.number)?;
     store.update_sync_status(true)?;
 
     Ok(head)
 }
 
+/// Makes a canonical ancestor of the current head the new head, removing the blocks after it
+/// from the canonical chain. Safe and finalized blocks past the new head are moved back to it.
+///
+/// Unlike fork choice updates, which never move the head back along the canonical chain, this
+/// is meant for chains sealed locally, as the ones used for development.
+pub(crate) fn rewind_canonical_chain(
+    store: &Store,
+    head_hash: BlockHash,
+) -> Result<BlockHeader, InvalidForkChoice> {
+    let head =
+        store
+            .get_block_header_by_hash(head_hash)?
+            .ok_or(InvalidForkChoice::ElementNotFound(
+                error::ForkChoiceElement::Head,
+            ))?;
+    if !is_canonical(store, head.number, head_hash)? {
+        return Err(InvalidForkChoice::Disconnected(
+            error::ForkChoiceElement::Head,
+            error::ForkChoiceElement::Head,
+        ));
+    }
+
+    let latest = store.get_latest_block_number()?;
+    let mut batch = WriteBatch::new();
+    for number in (head.number + 1)..(latest + 1) {
+        batch.unset_canonical_block(number);
+    }
+    batch.update_latest_block_number(head.number);
+    store.write_batch(batch)?;
+    if store
+        .get_finalized_block_number()?
+        .is_some_and(|number| number > head.number)
+    {
+        store.update_finalized_block_number(head.number)?;
+    }
+    if store
+        .get_safe_block_number()?
+        .is_some_and(|number| number > head.number)
+    {
+        store.update_safe_block_number(head.number)?;
+    }
+
+    Ok(head)
+}
+
 // Checks that block 1 is prior to block 2 and that if the second is present, the first one is too.
 fn check_order(block_1: &Option<Block>, block_2: &Option<Block>) -> Result<(), InvalidForkChoice> {
     // We don't need to perform the check if the hashes are null
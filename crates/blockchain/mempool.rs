@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use crate::{
     constants::{
@@ -8,6 +12,7 @@ use crate::{
         TX_INIT_CODE_WORD_GAS_COST,
     },
     error::MempoolError,
+    events::{ChainEvent, EventBus},
 };
 use ethrex_core::{
     types::{
@@ -15,7 +20,14 @@ use ethrex_core::{
     },
     Address, H256, U256,
 };
-use ethrex_storage::{error::StoreError, Store};
+use ethrex_rlp::encode::RLPEncode;
+use ethrex_storage::{error::StoreError, Bundle, Mempool, MempoolConfig, MempoolStats, Store};
+use tracing::{debug, warn};
+
+use ethrex_metrics::metrics;
+
+#[cfg(feature = "metrics")]
+use ethrex_metrics::metrics_transactions::{MetricsMempoolDropReason, METRICS_TX};
 
 /// Add a blob transaction and its blobs bundle to the mempool
 #[cfg(feature = "c-kzg")]
@@ -23,6 +35,7 @@ pub fn add_blob_transaction(
     transaction: EIP4844Transaction,
     blobs_bundle: BlobsBundle,
     store: &Store,
+    events: &EventBus,
 ) -> Result<H256, MempoolError> {
     // Validate blobs bundle
     blobs_bundle.validate(&transaction)?;
@@ -33,15 +46,71 @@ pub fn add_blob_transaction(
     // Validate transaction
     validate_transaction(&transaction, sender, store.clone())?;
 
-    // Add transaction and blobs bundle to storage
-    let hash = transaction.compute_hash();
-    store.add_transaction_to_pool(hash, MempoolTransaction::new(transaction, sender))?;
-    store.add_blobs_bundle_to_pool(hash, blobs_bundle)?;
+    pool_transaction(transaction, sender, Some(blobs_bundle), store, events)
+}
+
+/// Add a locally submitted blob transaction and its blobs bundle to the mempool, journaling
+/// them if the mempool journal is open
+#[cfg(feature = "c-kzg")]
+pub fn add_local_blob_transaction(
+    transaction: EIP4844Transaction,
+    blobs_bundle: BlobsBundle,
+    store: &Store,
+    events: &EventBus,
+) -> Result<H256, MempoolError> {
+    let hash = add_blob_transaction(transaction, blobs_bundle, store, events)?;
+    journal_transaction(hash, store);
     Ok(hash)
 }
 
+/// Maximum amount of bundles pooled for the same block
+pub const MAX_BUNDLES_PER_BLOCK: usize = 256;
+
+/// Add a bundle to the bundle pool. Bundles are only checked for consistency here, they are
+/// simulated by the payload builder when building the block they target
+pub fn add_bundle(bundle: Bundle, store: &Store) -> Result<H256, MempoolError> {
+    if bundle.transactions.is_empty() {
+        return Err(MempoolError::InvalidBundle("no transactions".to_string()));
+    }
+    if bundle
+        .transactions
+        .iter()
+        .any(|tx| matches!(tx, Transaction::EIP4844Transaction(_)))
+    {
+        return Err(MempoolError::InvalidBundle(
+            "blob transactions are not supported".to_string(),
+        ));
+    }
+    let chain_id = store.get_chain_config()?.chain_id;
+    if bundle.transactions.iter().any(|tx| {
+        tx.chain_id()
+            .is_some_and(|tx_chain_id| tx_chain_id != chain_id)
+    }) {
+        return Err(MempoolError::InvalidChainId(chain_id));
+    }
+    if bundle.block_number <= store.get_latest_block_number()? {
+        return Err(MempoolError::InvalidBundle(format!(
+            "block {} is already known",
+            bundle.block_number
+        )));
+    }
+    if bundle.min_timestamp.unwrap_or(0) > bundle.max_timestamp.unwrap_or(u64::MAX) {
+        return Err(MempoolError::InvalidBundle(
+            "min timestamp is above the max timestamp".to_string(),
+        ));
+    }
+    if store.get_pool_block_bundles(bundle.block_number)? >= MAX_BUNDLES_PER_BLOCK {
+        return Err(MempoolError::BundlePoolFull(MAX_BUNDLES_PER_BLOCK));
+    }
+    Ok(store.add_bundle_to_pool(bundle)?)
+}
+
 /// Add a transaction to the mempool
-pub fn add_transaction(transaction: Transaction, store: &Store) -> Result<H256, MempoolError> {
+pub fn add_transaction(
+    transaction: Transaction,
+    store: &Store,
+    events: &EventBus,
+) -> Result<H256, MempoolError> {
     // Blob transactions should be submitted via add_blob_transaction along with the corresponding blobs bundle
     if matches!(transaction, Transaction::EIP4844Transaction(_)) {
         return Err(MempoolError::BlobTxNoBlobsBundle);
@@ -50,14 +119,332 @@ pub fn add_transaction(transaction: Transaction, store: &Store) -> Result<H256,
     // Validate transaction
     validate_transaction(&transaction, sender, store.clone())?;
 
-    let hash = transaction.compute_hash();
+    pool_transaction(transaction, sender, None, store, events)
+}
+
+/// Add a locally submitted transaction to the mempool, journaling it if the mempool journal is
+/// open
+pub fn add_local_transaction(
+    transaction: Transaction,
+    store: &Store,
+    events: &EventBus,
+) -> Result<H256, MempoolError> {
+    let hash = add_transaction(transaction, store, events)?;
+    journal_transaction(hash, store);
+    Ok(hash)
+}
+
+// The transaction is already pooled, a failure to journal it is not the submitter's concern
+fn journal_transaction(hash: H256, store: &Store) {
+    if let Err(error) = store.journal_transaction(hash) {
+        warn!("Failed to journal transaction {hash:#x}: {error}");
+    }
+}
 
-    // Add transaction to storage
-    store.add_transaction_to_pool(hash, MempoolTransaction::new(transaction, sender))?;
+/// Adds a validated transaction to the pool, along with its blobs bundle if it's a blob
+/// transaction, taking the place of the pooled one with the same sender and nonce, if any.
+/// The pool is kept locked from the first check against it until the transaction is added, so
+/// nothing changes in between, and no pooled transaction is replaced or evicted unless the
+/// transaction gets in
+fn pool_transaction(
+    transaction: Transaction,
+    sender: Address,
+    blobs_bundle: Option<BlobsBundle>,
+    store: &Store,
+    events: &EventBus,
+) -> Result<H256, MempoolError> {
+    let hash = transaction.compute_hash();
+    let config = store.mempool_config();
+    let account_nonce = account_nonce(sender, store)?;
+    let base_fee = store
+        .get_block_header(store.get_latest_block_number()?)?
+        .and_then(|header| header.base_fee_per_gas);
+    drop_expired_transactions(&config, store, events)?;
+
+    let changes = store.update_mempool(|mempool| -> Result<_, MempoolError> {
+        if mempool.contains(&hash) {
+            // Resubmission of an already pooled transaction
+            return Ok(None);
+        }
+        check_account_queue(&transaction, sender, account_nonce, &config, mempool)?;
+        let replaced = replaced_transaction(&transaction, sender, &config, mempool)?;
+        let evicted = make_room(
+            &transaction,
+            sender,
+            replaced,
+            blobs_bundle.as_ref(),
+            base_fee,
+            &config,
+            mempool,
+        )?;
+
+        // Every check passed, so the transaction can take its room
+        for removed_hash in replaced.iter().chain(&evicted) {
+            mempool.remove(removed_hash);
+        }
+        mempool.insert(hash, MempoolTransaction::new(transaction, sender));
+        if let Some(blobs_bundle) = blobs_bundle {
+            mempool.insert_blobs_bundle(hash, blobs_bundle);
+        }
+        let stale = mempool.reorganize(sender, account_nonce);
+        Ok(Some((replaced, evicted, stale)))
+    })??;
+    let Some((replaced, evicted, stale)) = changes else {
+        return Ok(hash);
+    };
 
+    for removed_hash in replaced.iter().chain(&evicted) {
+        events.publish(ChainEvent::TxRemoved(*removed_hash));
+    }
+    events.publish(ChainEvent::TxAdded(hash));
+    for stale_hash in stale {
+        events.publish(ChainEvent::TxRemoved(stale_hash));
+    }
+    metrics!({
+        for _ in &evicted {
+            METRICS_TX.inc_mempool_dropped(MetricsMempoolDropReason::Evicted);
+        }
+        update_mempool_metrics(store);
+    });
     Ok(hash)
 }
 
+/// Drops the pooled transactions that stayed in the pool for longer than its configured lifetime
+fn drop_expired_transactions(
+    config: &MempoolConfig,
+    store: &Store,
+    events: &EventBus,
+) -> Result<(), StoreError> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros();
+    let expiry = now.saturating_sub(config.lifetime.as_micros());
+    let expired = store.update_mempool(|mempool| {
+        let expired = mempool.received_before(expiry);
+        for hash in &expired {
+            mempool.remove(hash);
+        }
+        expired
+    })?;
+    for hash in expired {
+        events.publish(ChainEvent::TxRemoved(hash));
+        metrics!(METRICS_TX.inc_mempool_dropped(MetricsMempoolDropReason::Expired));
+    }
+    Ok(())
+}
+
+/// Adds the transactions journaled at `path` back to the mempool, revalidating them against the
+/// current head, and then keeps journaling the locally submitted transactions there. Returns the
+/// amount of transactions that made it back into the pool
+pub fn load_journal(path: &Path, store: &Store, events: &EventBus) -> Result<usize, MempoolError> {
+    let mut loaded = Vec::new();
+    for (transaction, blobs_bundle) in Store::read_mempool_journal(path)? {
+        let result = match (transaction, blobs_bundle) {
+            #[cfg(feature = "c-kzg")]
+            (Transaction::EIP4844Transaction(transaction), Some(blobs_bundle)) => {
+                add_blob_transaction(transaction, blobs_bundle, store, events)
+            }
+            (transaction, _) => add_transaction(transaction, store, events),
+        };
+        match result {
+            Ok(hash) => loaded.push(hash),
+            // Transactions included or invalidated while the node was down are expected here
+            Err(error) => debug!("Dropped journaled transaction: {error}"),
+        }
+    }
+    let count = loaded.len();
+    store.open_mempool_journal(path, loaded)?;
+    Ok(count)
+}
+
+/// Reclassifies every pooled transaction as pending or queued against the sender accounts at the
+/// current head, dropping the ones that can no longer be included, either because their nonce
+/// was already used or because the sender can't afford them anymore.
+/// Must be called whenever the canonical head changes
+pub fn reorganize_pool(store: &Store, events: &EventBus) -> Result<(), StoreError> {
+    let latest_block_number = store.get_latest_block_number()?;
+    for sender in store.get_pool_senders()? {
+        let account_info = store
+            .get_account_info(latest_block_number, sender)?
+            .unwrap_or_default();
+        for (hash, tx) in store.get_pool_transactions_by_sender(sender)? {
+            if !tx
+                .cost_without_base_fee()
+                .is_some_and(|cost| cost <= account_info.balance)
+            {
+                remove_pooled_transaction(&hash, store, events)?;
+            }
+        }
+        reorganize_sender(sender, account_info.nonce, store, events)?;
+    }
+    metrics!(update_mempool_metrics(store));
+    Ok(())
+}
+
+/// Reclassifies the pooled transactions of `sender` as pending or queued, dropping the ones
+/// whose nonce was already used
+fn reorganize_sender(
+    sender: Address,
+    account_nonce: u64,
+    store: &Store,
+    events: &EventBus,
+) -> Result<(), StoreError> {
+    for hash in store.reorganize_pool_transactions(sender, account_nonce)? {
+        events.publish(ChainEvent::TxRemoved(hash));
+    }
+    Ok(())
+}
+
+/// Removes a transaction from the pool, publishing its removal if it was pooled
+fn remove_pooled_transaction(
+    hash: &H256,
+    store: &Store,
+    events: &EventBus,
+) -> Result<(), StoreError> {
+    if store.remove_transaction_from_pool(hash)? {
+        events.publish(ChainEvent::TxRemoved(*hash));
+    }
+    Ok(())
+}
+
+/// Returns the nonce of `sender` at the current head
+fn account_nonce(sender: Address, store: &Store) -> Result<u64, StoreError> {
+    let latest_block_number = store.get_latest_block_number()?;
+    Ok(store
+        .get_account_info(latest_block_number, sender)?
+        .map(|info| info.nonce)
+        .unwrap_or_default())
+}
+
+/// Fails if `tx` would be queued behind a nonce gap while its sender already has the maximum
+/// amount of queued transactions
+fn check_account_queue(
+    tx: &Transaction,
+    sender: Address,
+    account_nonce: u64,
+    config: &MempoolConfig,
+    mempool: &Mempool,
+) -> Result<(), MempoolError> {
+    if mempool.get_by_nonce(sender, tx.nonce()).is_none()
+        && !mempool.is_executable(sender, tx.nonce(), account_nonce)
+        && mempool.queued_slots(sender) >= config.max_account_queue
+    {
+        return Err(MempoolError::AccountQueueExceeded(config.max_account_queue));
+    }
+    Ok(())
+}
+
+/// Returns the hash of the pooled transaction with the same sender and nonce as `tx`, which
+/// `tx` would replace.
+/// Fails if only one of them is a blob transaction, or if `tx` doesn't raise all of the pooled
+/// transaction's fees by the configured price bump
+fn replaced_transaction(
+    tx: &Transaction,
+    sender: Address,
+    config: &MempoolConfig,
+    mempool: &Mempool,
+) -> Result<Option<H256>, MempoolError> {
+    let Some((pooled_hash, pooled_tx)) = mempool.get_by_nonce(sender, tx.nonce()) else {
+        return Ok(None);
+    };
+    let is_blob_tx = |tx: &Transaction| matches!(tx, Transaction::EIP4844Transaction(_));
+    if is_blob_tx(tx) != is_blob_tx(pooled_tx) {
+        return Err(MempoolError::ReplacementTypeMismatch);
+    }
+    let price_bump = config.price_bump;
+    let bumped = |new: U256, old: U256| new * 100 >= old * (100 + price_bump);
+    let fees_bumped = bumped(tx.gas_fee_cap().into(), pooled_tx.gas_fee_cap().into())
+        && bumped(tx.gas_tip_cap().into(), pooled_tx.gas_tip_cap().into());
+    let blob_fee_bumped = match (tx.max_fee_per_blob_gas(), pooled_tx.max_fee_per_blob_gas()) {
+        (Some(new), Some(old)) => bumped(new, old),
+        _ => true,
+    };
+    if !(fees_bumped && blob_fee_bumped) {
+        return Err(MempoolError::ReplacementUnderpriced(price_bump));
+    }
+    Ok(Some(pooled_hash))
+}
+
+/// Returns the pooled transactions to evict so that `tx` fits in the mempool within the limits
+/// set by its config, once the transaction it replaces, if any, is removed.
+/// The ones paying the lowest effective tip are evicted first, as long as they pay less than
+/// `tx`. Fails if that isn't enough, without changing the pool
+fn make_room(
+    tx: &Transaction,
+    sender: Address,
+    replaced: Option<H256>,
+    blobs_bundle: Option<&BlobsBundle>,
+    base_fee: Option<u64>,
+    config: &MempoolConfig,
+    mempool: &Mempool,
+) -> Result<Vec<H256>, MempoolError> {
+    if replaced.is_none() && mempool.account_slots(sender) >= config.max_account_slots {
+        return Err(MempoolError::AccountSlotsExceeded(config.max_account_slots));
+    }
+
+    let size = tx.encode_canonical_to_vec().len();
+    let blobs_size = blobs_bundle.map(BlobsBundle::length);
+    let mut stats = mempool.stats();
+    let release = |hash: &H256, stats: &mut MempoolStats| {
+        if let Some(pooled) = mempool.get(hash) {
+            stats.transactions -= 1;
+            stats.size -= pooled.encode_canonical_to_vec().len();
+        }
+        if let Some(blobs_bundle) = mempool.get_blobs_bundle(hash) {
+            stats.blob_transactions -= 1;
+            stats.blobs_size -= blobs_bundle.length();
+        }
+    };
+    if let Some(replaced) = &replaced {
+        release(replaced, &mut stats);
+    }
+
+    let tip = tx.effective_gas_tip(base_fee).unwrap_or_default();
+    let mut candidates = mempool
+        .by_tip(base_fee)
+        .into_iter()
+        .filter(|(hash, _)| Some(*hash) != replaced);
+    let mut evicted = Vec::new();
+    loop {
+        let fits =
+            stats.transactions < config.max_transactions && stats.size + size <= config.max_size;
+        let blobs_fit = match blobs_size {
+            Some(blobs_size) => {
+                stats.blob_transactions < config.max_blob_transactions
+                    && stats.blobs_size + blobs_size <= config.max_blobs_size
+            }
+            None => true,
+        };
+        if fits && blobs_fit {
+            return Ok(evicted);
+        }
+        // Once the transaction fits, only evicting blob transactions makes room for its blobs
+        let Some((hash, candidate_tip)) =
+            candidates.find(|(hash, _)| !fits || mempool.get_blobs_bundle(hash).is_some())
+        else {
+            return Err(MempoolError::PoolFull);
+        };
+        if candidate_tip >= tip {
+            return Err(MempoolError::PoolFull);
+        }
+        release(&hash, &mut stats);
+        evicted.push(hash);
+    }
+}
+
+#[cfg(feature = "metrics")]
+fn update_mempool_metrics(store: &Store) {
+    if let Ok(stats) = store.get_pool_stats() {
+        METRICS_TX.set_mempool_stats(
+            stats.transactions,
+            stats.size,
+            stats.blob_transactions,
+            stats.blobs_size,
+        );
+    }
+}
+
 /// Fetch a blobs bundle from the mempool given its blob transaction hash
 pub fn get_blobs_bundle(tx_hash: H256, store: Store) -> Result<Option<BlobsBundle>, MempoolError> {
     Ok(store.get_blobs_bundle_from_pool(tx_hash)?)
@@ -103,8 +490,10 @@ pub fn filter_transactions(
 }
 
 /// Remove a transaction from the mempool
-pub fn remove_transaction(hash: &H256, store: &Store) -> Result<(), StoreError> {
-    store.remove_transaction_from_pool(hash)
+pub fn remove_transaction(hash: &H256, store: &Store, events: &EventBus) -> Result<(), StoreError> {
+    remove_pooled_transaction(hash, store, events)?;
+    metrics!(update_mempool_metrics(store));
+    Ok(())
 }
 
 pub fn get_nonce(address: &Address, store: &Store) -> Result<Option<u64>, MempoolError> {
@@ -303,19 +692,24 @@ fn transaction_intrinsic_gas(
 #[cfg(test)]
 mod tests {
     use crate::error::MempoolError;
+    use crate::events::{ChainEvent, EventBus};
     use crate::mempool::{
         MAX_INITCODE_SIZE, TX_ACCESS_LIST_ADDRESS_GAS, TX_ACCESS_LIST_STORAGE_KEY_GAS,
         TX_CREATE_GAS_COST, TX_DATA_NON_ZERO_GAS, TX_DATA_NON_ZERO_GAS_EIP2028,
         TX_DATA_ZERO_GAS_COST, TX_GAS_COST, TX_INIT_CODE_WORD_GAS_COST,
     };
 
-    use super::{transaction_intrinsic_gas, validate_transaction};
+    use super::{
+        add_bundle, check_account_queue, make_room, pool_transaction, replaced_transaction,
+        transaction_intrinsic_gas, validate_transaction,
+    };
     use ethrex_core::types::{
-        BlockHeader, ChainConfig, EIP1559Transaction, EIP4844Transaction, Transaction, TxKind,
+        BlockHeader, ChainConfig, EIP1559Transaction, EIP4844Transaction, MempoolTransaction,
+        Transaction, TxKind,
     };
     use ethrex_core::{Address, Bytes, H256, U256};
-    use ethrex_storage::EngineType;
     use ethrex_storage::{error::StoreError, Store};
+    use ethrex_storage::{Bundle, EngineType, MempoolConfig};
 
     fn setup_storage(config: ChainConfig, header: BlockHeader) -> Result<Store, StoreError> {
         let store = Store::new("test", EngineType::InMemory)?;
@@ -657,4 +1051,335 @@ mod tests {
             Err(MempoolError::TxBlobBaseFeeTooLowError)
         ));
     }
+
+    fn eip1559_tx(max_priority_fee_per_gas: u64, max_fee_per_gas: u64) -> Transaction {
+        Transaction::EIP1559Transaction(EIP1559Transaction {
+            nonce: 3,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit: 100_000,
+            to: TxKind::Call(Address::from_low_u64_be(1)),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn underpriced_replacement_should_fail() {
+        let (config, header) = build_basic_config_and_header(false, false);
+        let store = setup_storage(config, header).expect("Storage setup");
+        let sender = Address::random();
+
+        let pooled_tx = eip1559_tx(10, 100);
+        let pooled_hash = pooled_tx.compute_hash();
+        store
+            .add_transaction_to_pool(pooled_hash, MempoolTransaction::new(pooled_tx, sender))
+            .unwrap();
+
+        // Only the max fee is bumped
+        let tx = eip1559_tx(10, 110);
+        let replacement = store
+            .update_mempool(|mempool| {
+                replaced_transaction(&tx, sender, &store.mempool_config(), mempool)
+            })
+            .unwrap();
+        assert!(matches!(
+            replacement,
+            Err(MempoolError::ReplacementUnderpriced(10))
+        ));
+
+        // Bumping both fees replaces it
+        let tx = eip1559_tx(11, 110);
+        let replacement = store
+            .update_mempool(|mempool| {
+                replaced_transaction(&tx, sender, &store.mempool_config(), mempool)
+            })
+            .unwrap();
+        assert_eq!(replacement.unwrap(), Some(pooled_hash));
+    }
+
+    #[test]
+    fn replacement_with_bumped_fees_evicts_pooled_transaction() {
+        let (config, header) = build_basic_config_and_header(false, false);
+        let store = setup_storage(config, header).expect("Storage setup");
+        let sender = Address::random();
+
+        let pooled_tx = eip1559_tx(10, 100);
+        let pooled_hash = pooled_tx.compute_hash();
+        store
+            .add_transaction_to_pool(pooled_hash, MempoolTransaction::new(pooled_tx, sender))
+            .unwrap();
+
+        let events = EventBus::default();
+        let mut subscriber = events.subscribe();
+        let tx = eip1559_tx(11, 110);
+        let hash = pool_transaction(tx, sender, None, &store, &events).unwrap();
+        let (pooled, _) = store
+            .get_pool_transaction_by_nonce(sender, 3)
+            .unwrap()
+            .unwrap();
+        assert_eq!(pooled, hash);
+        assert_eq!(
+            subscriber.try_recv(),
+            Some(ChainEvent::TxRemoved(pooled_hash))
+        );
+        assert_eq!(subscriber.try_recv(), Some(ChainEvent::TxAdded(hash)));
+    }
+
+    #[test]
+    fn replacement_across_blob_and_non_blob_transactions_should_fail() {
+        let (config, header) = build_basic_config_and_header(false, false);
+        let store = setup_storage(config, header).expect("Storage setup");
+        let sender = Address::random();
+
+        let pooled_tx = eip1559_tx(10, 100);
+        store
+            .add_transaction_to_pool(
+                pooled_tx.compute_hash(),
+                MempoolTransaction::new(pooled_tx, sender),
+            )
+            .unwrap();
+
+        let blob_tx = Transaction::EIP4844Transaction(EIP4844Transaction {
+            nonce: 3,
+            max_priority_fee_per_gas: 20,
+            max_fee_per_gas: 200,
+            max_fee_per_blob_gas: 200.into(),
+            gas: 100_000,
+            to: Address::from_low_u64_be(1),
+            ..Default::default()
+        });
+        let replacement = store
+            .update_mempool(|mempool| {
+                replaced_transaction(&blob_tx, sender, &store.mempool_config(), mempool)
+            })
+            .unwrap();
+        assert!(matches!(
+            replacement,
+            Err(MempoolError::ReplacementTypeMismatch)
+        ));
+    }
+
+    #[test]
+    fn replacement_that_doesnt_fit_keeps_pooled_transaction() {
+        let (config, header) = build_basic_config_and_header(false, false);
+        let sender = Address::random();
+        let pooled_tx = eip1559_tx(10, 100);
+        let pooled_hash = pooled_tx.compute_hash();
+        let other_tx = eip1559_tx(50, 100);
+        // Pays enough to replace the pooled transaction, but it's bigger and can't evict the
+        // other one, which pays a higher tip
+        let Transaction::EIP1559Transaction(mut replacement) = eip1559_tx(20, 200) else {
+            unreachable!()
+        };
+        replacement.data = Bytes::from(vec![1; 1024]);
+        let replacement = Transaction::EIP1559Transaction(replacement);
+
+        let max_size =
+            pooled_tx.encode_canonical_to_vec().len() + other_tx.encode_canonical_to_vec().len();
+        let store = setup_storage(config, header)
+            .expect("Storage setup")
+            .with_mempool_config(MempoolConfig {
+                max_size,
+                ..Default::default()
+            });
+        store
+            .add_transaction_to_pool(pooled_hash, MempoolTransaction::new(pooled_tx, sender))
+            .unwrap();
+        store
+            .add_transaction_to_pool(
+                other_tx.compute_hash(),
+                MempoolTransaction::new(other_tx, Address::random()),
+            )
+            .unwrap();
+
+        let events = EventBus::default();
+        let mut subscriber = events.subscribe();
+        let result = pool_transaction(replacement, sender, None, &store, &events);
+        assert!(matches!(result, Err(MempoolError::PoolFull)));
+        let (pooled, _) = store
+            .get_pool_transaction_by_nonce(sender, 3)
+            .unwrap()
+            .unwrap();
+        assert_eq!(pooled, pooled_hash);
+        assert_eq!(store.get_pool_stats().unwrap().transactions, 2);
+        assert_eq!(subscriber.try_recv(), None);
+    }
+
+    #[test]
+    fn full_pool_evicts_transactions_paying_a_lower_tip() {
+        let (config, header) = build_basic_config_and_header(false, false);
+        let store = setup_storage(config, header)
+            .expect("Storage setup")
+            .with_mempool_config(MempoolConfig {
+                max_transactions: 1,
+                ..Default::default()
+            });
+
+        let pooled_tx = eip1559_tx(10, 100);
+        let pooled_hash = pooled_tx.compute_hash();
+        store
+            .add_transaction_to_pool(
+                pooled_hash,
+                MempoolTransaction::new(pooled_tx, Address::random()),
+            )
+            .unwrap();
+
+        let make_room_for = |tx: &Transaction| {
+            store
+                .update_mempool(|mempool| {
+                    make_room(
+                        tx,
+                        Address::random(),
+                        None,
+                        None,
+                        None,
+                        &store.mempool_config(),
+                        mempool,
+                    )
+                })
+                .unwrap()
+        };
+        assert!(matches!(
+            make_room_for(&eip1559_tx(5, 100)),
+            Err(MempoolError::PoolFull)
+        ));
+        assert_eq!(
+            make_room_for(&eip1559_tx(20, 100)).unwrap(),
+            vec![pooled_hash]
+        );
+        // Nothing is evicted until the transaction is added
+        assert_eq!(store.get_pool_stats().unwrap().transactions, 1);
+    }
+
+    #[test]
+    fn transaction_above_account_slots_should_fail() {
+        let (config, header) = build_basic_config_and_header(false, false);
+        let store = setup_storage(config, header)
+            .expect("Storage setup")
+            .with_mempool_config(MempoolConfig {
+                max_account_slots: 1,
+                ..Default::default()
+            });
+        let sender = Address::random();
+
+        let pooled_tx = eip1559_tx(10, 100);
+        store
+            .add_transaction_to_pool(
+                pooled_tx.compute_hash(),
+                MempoolTransaction::new(pooled_tx, sender),
+            )
+            .unwrap();
+
+        let Transaction::EIP1559Transaction(mut tx) = eip1559_tx(10, 100) else {
+            unreachable!()
+        };
+        tx.nonce = 4;
+        let result = store
+            .update_mempool(|mempool| {
+                make_room(
+                    &Transaction::EIP1559Transaction(tx),
+                    sender,
+                    None,
+                    None,
+                    None,
+                    &store.mempool_config(),
+                    mempool,
+                )
+            })
+            .unwrap();
+        assert!(matches!(result, Err(MempoolError::AccountSlotsExceeded(1))));
+    }
+
+    #[test]
+    fn transaction_above_account_queue_should_fail() {
+        let (config, header) = build_basic_config_and_header(false, false);
+        let store = setup_storage(config, header)
+            .expect("Storage setup")
+            .with_mempool_config(MempoolConfig {
+                max_account_queue: 1,
+                ..Default::default()
+            });
+        let sender = Address::random();
+
+        // The pooled transaction has nonce 3 while the sender's nonce is 0
+        let pooled_tx = eip1559_tx(10, 100);
+        store
+            .add_transaction_to_pool(
+                pooled_tx.compute_hash(),
+                MempoolTransaction::new(pooled_tx, sender),
+            )
+            .unwrap();
+        store.reorganize_pool_transactions(sender, 0).unwrap();
+
+        let Transaction::EIP1559Transaction(mut tx) = eip1559_tx(10, 100) else {
+            unreachable!()
+        };
+        let check = |tx: &EIP1559Transaction| {
+            store
+                .update_mempool(|mempool| {
+                    check_account_queue(
+                        &Transaction::EIP1559Transaction(tx.clone()),
+                        sender,
+                        0,
+                        &store.mempool_config(),
+                        mempool,
+                    )
+                })
+                .unwrap()
+        };
+        tx.nonce = 4;
+        assert!(matches!(
+            check(&tx),
+            Err(MempoolError::AccountQueueExceeded(1))
+        ));
+
+        // Executable transactions are not limited by the queue
+        tx.nonce = 0;
+        check(&tx).unwrap();
+    }
+
+    fn bundle(block_number: u64, transactions: Vec<Transaction>) -> Bundle {
+        Bundle {
+            transactions,
+            block_number,
+            min_timestamp: None,
+            max_timestamp: None,
+            reverting_tx_hashes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn bundle_for_the_next_block_is_pooled() {
+        let (config, header) = build_basic_config_and_header(false, false);
+        let store = setup_storage(config, header).expect("Storage setup");
+
+        let hash = add_bundle(bundle(6, vec![eip1559_tx(10, 100)]), &store).unwrap();
+        let pooled = store.get_pool_bundles(6, 10).unwrap();
+        assert_eq!(pooled.len(), 1);
+        assert_eq!(pooled[0].hash(), hash);
+    }
+
+    #[test]
+    fn invalid_bundles_should_fail() {
+        let (config, header) = build_basic_config_and_header(false, false);
+        let store = setup_storage(config, header).expect("Storage setup");
+
+        // Empty
+        assert!(matches!(
+            add_bundle(bundle(6, Vec::new()), &store),
+            Err(MempoolError::InvalidBundle(_))
+        ));
+        // Targeting a known block
+        assert!(matches!(
+            add_bundle(bundle(5, vec![eip1559_tx(10, 100)]), &store),
+            Err(MempoolError::InvalidBundle(_))
+        ));
+        // Including a blob transaction
+        let blob_tx = Transaction::EIP4844Transaction(EIP4844Transaction::default());
+        assert!(matches!(
+            add_bundle(bundle(6, vec![blob_tx]), &store),
+            Err(MempoolError::InvalidBundle(_))
+        ));
+        assert_eq!(store.get_pool_block_bundles(6).unwrap(), 0);
+    }
 }
@@ -1,5 +1,5 @@
 use ethrex_core::types::TxType;
-use prometheus::{Encoder, IntCounter, IntCounterVec, Opts, Registry, TextEncoder};
+use prometheus::{Encoder, IntCounter, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder};
 use std::sync::{Arc, LazyLock, Mutex};
 
 use crate::MetricsError;
@@ -9,6 +9,9 @@ pub static METRICS_TX: LazyLock<MetricsTx> = LazyLock::new(MetricsTx::default);
 pub struct MetricsTx {
     pub transactions_tracker: Arc<Mutex<IntCounterVec>>,
     pub transactions_total: Arc<Mutex<IntCounter>>,
+    pub mempool_tracker: Arc<Mutex<IntGaugeVec>>,
+    pub mempool_dropped: Arc<Mutex<IntCounterVec>>,
+    pub bundles_tracker: Arc<Mutex<IntCounterVec>>,
 }
 
 impl Default for MetricsTx {
@@ -33,6 +36,36 @@ impl MetricsTx {
             transactions_total: Arc::new(Mutex::new(
                 IntCounter::new("transactions_total", "Keeps track of all transactions").unwrap(),
             )),
+            mempool_tracker: Arc::new(Mutex::new(
+                IntGaugeVec::new(
+                    Opts::new(
+                        "mempool_tracker",
+                        "Keeps track of the amount and size of the pooled transactions and blobs bundles",
+                    ),
+                    &["stat"],
+                )
+                .unwrap(),
+            )),
+            mempool_dropped: Arc::new(Mutex::new(
+                IntCounterVec::new(
+                    Opts::new(
+                        "mempool_dropped",
+                        "Keeps track of the transactions dropped from the mempool depending on the reason",
+                    ),
+                    &["reason"],
+                )
+                .unwrap(),
+            )),
+            bundles_tracker: Arc::new(Mutex::new(
+                IntCounterVec::new(
+                    Opts::new(
+                        "bundles_tracker",
+                        "Keeps track of the bundles tried by the payload builder depending on the outcome",
+                    ),
+                    &["status"],
+                )
+                .unwrap(),
+            )),
         }
     }
 
@@ -73,6 +106,80 @@ impl MetricsTx {
         txs_lock.inc();
     }
 
+    pub fn set_mempool_stats(
+        &self,
+        transactions: usize,
+        size: usize,
+        blob_transactions: usize,
+        blobs_size: usize,
+    ) {
+        let mempool = self.mempool_tracker.clone();
+
+        let mempool_lock = match mempool.lock() {
+            Ok(lock) => lock,
+            Err(e) => {
+                tracing::error!("Failed to lock mutex: {e}");
+                return;
+            }
+        };
+
+        for (stat, value) in [
+            ("transactions", transactions),
+            ("size", size),
+            ("blob_transactions", blob_transactions),
+            ("blobs_size", blobs_size),
+        ] {
+            match mempool_lock.get_metric_with_label_values(&[stat]) {
+                Ok(gauge) => gauge.set(value.try_into().unwrap_or(i64::MAX)),
+                Err(e) => tracing::error!("Failed to build Metric: {e}"),
+            }
+        }
+    }
+
+    pub fn inc_mempool_dropped(&self, reason: MetricsMempoolDropReason) {
+        let dropped = self.mempool_dropped.clone();
+
+        let dropped_lock = match dropped.lock() {
+            Ok(lock) => lock,
+            Err(e) => {
+                tracing::error!("Failed to lock mutex: {e}");
+                return;
+            }
+        };
+
+        let dropped_builder = match dropped_lock.get_metric_with_label_values(&[reason.to_str()]) {
+            Ok(builder) => builder,
+            Err(e) => {
+                tracing::error!("Failed to build Metric: {e}");
+                return;
+            }
+        };
+
+        dropped_builder.inc();
+    }
+
+    pub fn inc_bundle(&self, status: MetricsBundleStatus) {
+        let bundles = self.bundles_tracker.clone();
+
+        let bundles_lock = match bundles.lock() {
+            Ok(lock) => lock,
+            Err(e) => {
+                tracing::error!("Failed to lock mutex: {e}");
+                return;
+            }
+        };
+
+        let bundles_builder = match bundles_lock.get_metric_with_label_values(&[status.to_str()]) {
+            Ok(builder) => builder,
+            Err(e) => {
+                tracing::error!("Failed to build Metric: {e}");
+                return;
+            }
+        };
+
+        bundles_builder.inc();
+    }
+
     pub fn gather_metrics(&self) -> Result<String, MetricsError> {
         let r = Registry::new();
 
@@ -91,6 +198,29 @@ impl MetricsTx {
         r.register(Box::new(txs_tracker_lock.clone()))
             .map_err(|e| MetricsError::PrometheusErr(e.to_string()))?;
 
+        let mempool_tracker = self.mempool_tracker.clone();
+        let mempool_tracker_lock = mempool_tracker
+            .lock()
+            .map_err(|e| MetricsError::MutexLockError(e.to_string()))?;
+
+        let mempool_dropped = self.mempool_dropped.clone();
+        let mempool_dropped_lock = mempool_dropped
+            .lock()
+            .map_err(|e| MetricsError::MutexLockError(e.to_string()))?;
+
+        r.register(Box::new(mempool_tracker_lock.clone()))
+            .map_err(|e| MetricsError::PrometheusErr(e.to_string()))?;
+        r.register(Box::new(mempool_dropped_lock.clone()))
+            .map_err(|e| MetricsError::PrometheusErr(e.to_string()))?;
+
+        let bundles_tracker = self.bundles_tracker.clone();
+        let bundles_tracker_lock = bundles_tracker
+            .lock()
+            .map_err(|e| MetricsError::MutexLockError(e.to_string()))?;
+
+        r.register(Box::new(bundles_tracker_lock.clone()))
+            .map_err(|e| MetricsError::PrometheusErr(e.to_string()))?;
+
         let encoder = TextEncoder::new();
         let metric_families = r.gather();
 
@@ -119,6 +249,39 @@ impl MetricsTxStatus {
     }
 }
 
+pub enum MetricsMempoolDropReason {
+    Evicted,
+    Expired,
+}
+
+impl MetricsMempoolDropReason {
+    pub fn to_str(&self) -> &str {
+        match self {
+            MetricsMempoolDropReason::Evicted => "evicted",
+            MetricsMempoolDropReason::Expired => "expired",
+        }
+    }
+}
+
+pub enum MetricsBundleStatus {
+    /// All of the bundle's transactions were included
+    Included,
+    /// A transaction not allowed to revert did
+    Reverted,
+    /// A transaction couldn't be executed
+    Failed,
+}
+
+impl MetricsBundleStatus {
+    pub fn to_str(&self) -> &str {
+        match self {
+            MetricsBundleStatus::Included => "included",
+            MetricsBundleStatus::Reverted => "reverted",
+            MetricsBundleStatus::Failed => "failed",
+        }
+    }
+}
+
 pub struct MetricsTxType(pub TxType);
 
 impl MetricsTxType {
@@ -1,6 +1,12 @@
 use std::{
-    cmp::{max, Ordering},
+    cmp::{max, min, Ordering},
     collections::HashMap,
+    sync::{
+        mpsc::{self, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use ethrex_core::{
@@ -13,19 +19,16 @@ use ethrex_core::{
     Address, Bloom, Bytes, H256, U256,
 };
 use ethrex_rlp::encode::RLPEncode;
-use ethrex_storage::{error::StoreError, Store};
+use ethrex_storage::{error::StoreError, Bundle, Store};
 #[cfg(feature = "levm")]
 use ethrex_vm::{db::StoreWrapper, execute_tx_levm};
 
-#[cfg(feature = "levm")]
-use std::sync::Arc;
-
 #[cfg(not(feature = "levm"))]
 use ethrex_vm::execute_tx;
 
 use ethrex_vm::{
     beacon_root_contract_call, evm_state, get_state_transitions, process_withdrawals, spec_id,
-    EvmError, EvmState, SpecId,
+    EvmError, EvmState, EvmStateSnapshot, SpecId,
 };
 
 use sha3::{Digest, Keccak256};
@@ -33,18 +36,97 @@ use sha3::{Digest, Keccak256};
 use ethrex_metrics::metrics;
 
 #[cfg(feature = "metrics")]
-use ethrex_metrics::metrics_transactions::{MetricsTxStatus, MetricsTxType, METRICS_TX};
+use ethrex_metrics::metrics_transactions::{
+    MetricsBundleStatus, MetricsTxStatus, MetricsTxType, METRICS_TX,
+};
 
 use crate::{
     constants::{
-        GAS_LIMIT_BOUND_DIVISOR, GAS_PER_BLOB, MAX_BLOB_GAS_PER_BLOCK, MIN_GAS_LIMIT,
+        GAS_LIMIT_BOUND_DIVISOR, GAS_PER_BLOB, MAX_BLOB_NUMBER_PER_BLOCK, MIN_GAS_LIMIT,
         TARGET_BLOB_GAS_PER_BLOCK, TX_GAS_COST,
     },
-    error::{ChainError, InvalidBlockError},
+    error::{ChainError, InvalidBlockError, InvalidBuilderConfig},
+    events::EventBus,
     mempool::{self, PendingTxFilter},
+    Blockchain,
 };
 
-use tracing::debug;
+use tracing::{debug, warn};
+
+/// Gas limit the built blocks move towards by default
+pub const DEFAULT_BUILDER_GAS_CEIL: u64 = 30_000_000;
+/// Maximum length of a block header's extra data
+pub const MAX_EXTRA_DATA_SIZE: usize = 32;
+/// How long a payload keeps being rebuilt by default if it is never requested, one slot
+pub const DEFAULT_PAYLOAD_BUILD_TIMEOUT: Duration = Duration::from_secs(12);
+/// Time waited between two builds of the same payload
+const PAYLOAD_REBUILD_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Settings used by the [`Blockchain`] when building payloads
+#[derive(Debug, Clone)]
+pub struct BuilderConfig {
+    /// Gas limit the built blocks move towards, as far as the parent's gas limit allows
+    pub gas_ceil: u64,
+    /// Extra data set in the built block headers, at most [`MAX_EXTRA_DATA_SIZE`] bytes long
+    pub extra_data: Bytes,
+    /// Minimum effective tip a transaction has to pay to be included
+    pub min_tip: u64,
+    /// Maximum amount of blobs included per block, bounded by the protocol's own limit
+    pub max_blobs_per_block: u64,
+    /// How long a payload keeps being rebuilt in the background if it is never requested
+    pub build_timeout: Duration,
+}
+
+impl Default for BuilderConfig {
+    fn default() -> Self {
+        Self {
+            gas_ceil: DEFAULT_BUILDER_GAS_CEIL,
+            extra_data: Bytes::new(),
+            min_tip: 0,
+            max_blobs_per_block: MAX_BLOB_NUMBER_PER_BLOCK,
+            build_timeout: DEFAULT_PAYLOAD_BUILD_TIMEOUT,
+        }
+    }
+}
+
+impl BuilderConfig {
+    pub fn validate(&self) -> Result<(), InvalidBuilderConfig> {
+        if self.extra_data.len() > MAX_EXTRA_DATA_SIZE {
+            return Err(InvalidBuilderConfig::ExtraDataTooLong(
+                self.extra_data.len(),
+                MAX_EXTRA_DATA_SIZE,
+            ));
+        }
+        if self.gas_ceil < MIN_GAS_LIMIT {
+            return Err(InvalidBuilderConfig::GasCeilTooLow(
+                self.gas_ceil,
+                MIN_GAS_LIMIT,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Background task rebuilding a payload until it is requested or times out
+#[derive(Debug)]
+pub(crate) struct PayloadBuildTask {
+    /// Set once the payload is requested. The task holds the lock while storing a build, so
+    /// nothing is stored once it's set
+    stopped: Arc<Mutex<bool>>,
+    /// Wakes the task up between builds, so it notices it was stopped
+    wake: Sender<()>,
+    handle: JoinHandle<()>,
+}
+
+/// Outcome of executing one of the transactions of a bundle
+#[derive(Debug, Clone)]
+pub struct BundleTxResult {
+    pub tx_hash: H256,
+    pub gas_used: u64,
+    /// Tip paid to the fee recipient
+    pub coinbase_payment: U256,
+    pub succeeded: bool,
+}
 
 pub struct BuildPayloadArgs {
     pub parent: BlockHash,
@@ -76,65 +158,196 @@ impl BuildPayloadArgs {
     }
 }
 
-/// Creates a new payload based on the payload arguments
-// Basic payload block building, can and should be improved
-pub fn create_payload(args: &BuildPayloadArgs, storage: &Store) -> Result<Block, ChainError> {
-    // TODO: check where we should get builder values from
-    const DEFAULT_BUILDER_GAS_CEIL: u64 = 30_000_000;
-    let parent_block = storage
-        .get_block_header_by_hash(args.parent)?
-        .ok_or_else(|| ChainError::ParentNotFound)?;
-    let chain_config = storage.get_chain_config()?;
-    let gas_limit = calc_gas_limit(parent_block.gas_limit, DEFAULT_BUILDER_GAS_CEIL);
-
-    let header = BlockHeader {
-        parent_hash: args.parent,
-        ommers_hash: *DEFAULT_OMMERS_HASH,
-        coinbase: args.fee_recipient,
-        state_root: parent_block.state_root,
-        transactions_root: compute_transactions_root(&[]),
-        receipts_root: compute_receipts_root(&[]),
-        logs_bloom: Bloom::default(),
-        difficulty: U256::zero(),
-        number: parent_block.number.saturating_add(1),
-        gas_limit,
-        gas_used: 0,
-        timestamp: args.timestamp,
-        // TODO: should use builder config's extra_data
-        extra_data: Bytes::new(),
-        prev_randao: args.random,
-        nonce: 0,
-        base_fee_per_gas: calculate_base_fee_per_gas(
+impl Blockchain {
+    /// Creates a new payload based on the payload arguments
+    // Basic payload block building, can and should be improved
+    pub fn create_payload(&self, args: &BuildPayloadArgs) -> Result<Block, ChainError> {
+        let parent_block = self
+            .storage
+            .get_block_header_by_hash(args.parent)?
+            .ok_or_else(|| ChainError::ParentNotFound)?;
+        let chain_config = self.chain_config()?;
+        let gas_limit = calc_gas_limit(parent_block.gas_limit, self.builder_config.gas_ceil);
+
+        let header = BlockHeader {
+            parent_hash: args.parent,
+            ommers_hash: *DEFAULT_OMMERS_HASH,
+            coinbase: args.fee_recipient,
+            state_root: parent_block.state_root,
+            transactions_root: compute_transactions_root(&[]),
+            receipts_root: compute_receipts_root(&[]),
+            logs_bloom: Bloom::default(),
+            difficulty: U256::zero(),
+            number: parent_block.number.saturating_add(1),
             gas_limit,
-            parent_block.gas_limit,
-            parent_block.gas_used,
-            parent_block.base_fee_per_gas.unwrap_or_default(),
-        ),
-        withdrawals_root: chain_config
-            .is_shanghai_activated(args.timestamp)
-            .then_some(compute_withdrawals_root(
-                args.withdrawals.as_ref().unwrap_or(&Vec::new()),
-            )),
-        blob_gas_used: chain_config
-            .is_cancun_activated(args.timestamp)
-            .then_some(0),
-        excess_blob_gas: chain_config.is_cancun_activated(args.timestamp).then_some(
-            calc_excess_blob_gas(
-                parent_block.excess_blob_gas.unwrap_or_default(),
-                parent_block.blob_gas_used.unwrap_or_default(),
+            gas_used: 0,
+            timestamp: args.timestamp,
+            extra_data: self.builder_config.extra_data.clone(),
+            prev_randao: args.random,
+            nonce: 0,
+            base_fee_per_gas: calculate_base_fee_per_gas(
+                gas_limit,
+                parent_block.gas_limit,
+                parent_block.gas_used,
+                parent_block.base_fee_per_gas.unwrap_or_default(),
             ),
-        ),
-        parent_beacon_block_root: args.beacon_root,
-    };
+            withdrawals_root: chain_config
+                .is_shanghai_activated(args.timestamp)
+                .then_some(compute_withdrawals_root(
+                    args.withdrawals.as_ref().unwrap_or(&Vec::new()),
+                )),
+            blob_gas_used: chain_config
+                .is_cancun_activated(args.timestamp)
+                .then_some(0),
+            excess_blob_gas: chain_config.is_cancun_activated(args.timestamp).then_some(
+                calc_excess_blob_gas(
+                    parent_block.excess_blob_gas.unwrap_or_default(),
+                    parent_block.blob_gas_used.unwrap_or_default(),
+                ),
+            ),
+            parent_beacon_block_root: args.beacon_root,
+        };
 
-    let body = BlockBody {
-        transactions: Vec::new(),
-        ommers: Vec::new(),
-        withdrawals: args.withdrawals.clone(),
-    };
+        let body = BlockBody {
+            transactions: Vec::new(),
+            ommers: Vec::new(),
+            withdrawals: args.withdrawals.clone(),
+        };
+
+        // Delay applying withdrawals until the payload is requested and built
+        Ok(Block::new(header, body))
+    }
+
+    /// Completes the payload building process, return the block value
+    pub fn build_payload(&self, payload: &mut Block) -> Result<(BlobsBundle, U256), ChainError> {
+        debug!("Building payload");
+        let mut evm_state = evm_state(self.storage.clone(), payload.header.parent_hash);
+        let mut context = PayloadBuildContext::new(payload, &mut evm_state, &self.builder_config);
+        apply_withdrawals(&mut context)?;
+        fill_transactions(&mut context, &self.events)?;
+        finalize_payload(&mut context)?;
+        Ok((context.blobs_bundle, context.block_value))
+    }
+
+    /// Executes the bundle on top of the given block, as the first transactions of its child with
+    /// the given timestamp and fee recipient, and returns the outcome of each transaction.
+    /// Nothing is stored.
+    pub fn call_bundle(
+        &self,
+        bundle: &Bundle,
+        parent: BlockHash,
+        timestamp: u64,
+        coinbase: Address,
+    ) -> Result<Vec<BundleTxResult>, ChainError> {
+        let chain_config = self.chain_config()?;
+        let args = BuildPayloadArgs {
+            parent,
+            timestamp,
+            fee_recipient: coinbase,
+            random: H256::zero(),
+            withdrawals: chain_config.is_shanghai_activated(timestamp).then(Vec::new),
+            beacon_root: chain_config.is_cancun_activated(timestamp).then(H256::zero),
+            version: 3,
+        };
+        let mut payload = self.create_payload(&args)?;
+        let mut evm_state = evm_state(self.storage.clone(), parent);
+        let mut context =
+            PayloadBuildContext::new(&mut payload, &mut evm_state, &self.builder_config);
+        apply_bundle(bundle, &mut context)
+    }
+
+    /// Stores the given empty payload and keeps rebuilding it in a background task, so
+    /// transactions arriving during the slot are included. The most valuable build is stored
+    /// as completed under the payload id, until the payload is requested or the task times out.
+    /// Does nothing if the payload is already being built.
+    pub fn start_payload_build(
+        self: &Arc<Self>,
+        payload_id: u64,
+        payload: Block,
+    ) -> Result<(), ChainError> {
+        let mut tasks = self.payload_build_tasks()?;
+        tasks.retain(|_, task| !task.handle.is_finished());
+        if tasks.contains_key(&payload_id) {
+            return Ok(());
+        }
+        self.storage.add_payload(payload_id, payload.clone())?;
+        let stopped = Arc::new(Mutex::new(false));
+        let (wake, wake_receiver) = mpsc::channel();
+        let blockchain = self.clone();
+        let task_stopped = stopped.clone();
+        let handle = thread::spawn(move || {
+            blockchain.rebuild_payload(payload_id, payload, task_stopped, wake_receiver);
+        });
+        tasks.insert(
+            payload_id,
+            PayloadBuildTask {
+                stopped,
+                wake,
+                handle,
+            },
+        );
+        Ok(())
+    }
 
-    // Delay applying withdrawals until the payload is requested and built
-    Ok(Block::new(header, body))
+    /// Stops the background task building the payload, if any. The build in progress is
+    /// discarded rather than waited for, so the stored payload doesn't change after this returns
+    pub fn stop_payload_build(&self, payload_id: u64) -> Result<(), ChainError> {
+        let Some(task) = self.payload_build_tasks()?.remove(&payload_id) else {
+            return Ok(());
+        };
+        *task
+            .stopped
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))? = true;
+        // The task may have already finished and dropped its receiver
+        let _ = task.wake.send(());
+        Ok(())
+    }
+
+    fn rebuild_payload(
+        &self,
+        payload_id: u64,
+        empty_payload: Block,
+        stopped: Arc<Mutex<bool>>,
+        wake: mpsc::Receiver<()>,
+    ) {
+        let deadline = Instant::now() + self.builder_config.build_timeout;
+        let mut best_value = None;
+        while Instant::now() < deadline {
+            let mut payload = empty_payload.clone();
+            match self.build_payload(&mut payload) {
+                Ok((blobs_bundle, block_value))
+                    if best_value.map_or(true, |best_value| block_value > best_value) =>
+                {
+                    let Ok(stopped) = stopped.lock() else {
+                        return;
+                    };
+                    if *stopped {
+                        return;
+                    }
+                    debug!("Storing payload {payload_id:#018x} with value {block_value}");
+                    match self.storage.update_payload(
+                        payload_id,
+                        payload,
+                        block_value,
+                        blobs_bundle,
+                        true,
+                    ) {
+                        Ok(()) => best_value = Some(block_value),
+                        Err(error) => warn!("Failed to store payload {payload_id:#018x}: {error}"),
+                    }
+                }
+                Ok(_) => {}
+                // The last good build stays stored and the next one may succeed
+                Err(error) => warn!("Failed to build payload {payload_id:#018x}: {error}"),
+            }
+            match wake.recv_timeout(PAYLOAD_REBUILD_INTERVAL) {
+                Err(RecvTimeoutError::Timeout) => continue,
+                Ok(()) | Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        debug!("Stopped building payload {payload_id:#018x} after the build timeout");
+    }
 }
 
 fn calc_gas_limit(parent_gas_limit: u64, desired_limit: u64) -> u64 {
@@ -174,10 +387,16 @@ pub struct PayloadBuildContext<'a> {
     pub block_value: U256,
     base_fee_per_blob_gas: U256,
     pub blobs_bundle: BlobsBundle,
+    min_tip: u64,
+    max_blobs: u64,
 }
 
 impl<'a> PayloadBuildContext<'a> {
-    fn new(payload: &'a mut Block, evm_state: &'a mut EvmState) -> Self {
+    fn new(
+        payload: &'a mut Block,
+        evm_state: &'a mut EvmState,
+        builder_config: &BuilderConfig,
+    ) -> Self {
         PayloadBuildContext {
             remaining_gas: payload.header.gas_limit,
             receipts: vec![],
@@ -188,11 +407,48 @@ impl<'a> PayloadBuildContext<'a> {
             payload,
             evm_state,
             blobs_bundle: BlobsBundle::default(),
+            min_tip: builder_config.min_tip,
+            max_blobs: min(
+                builder_config.max_blobs_per_block,
+                MAX_BLOB_NUMBER_PER_BLOCK,
+            ),
         }
     }
 }
 
+/// State of a [`PayloadBuildContext`] at some point, used to undo the transactions added after it
+struct PayloadCheckpoint {
+    evm_state: EvmStateSnapshot,
+    remaining_gas: u64,
+    block_value: U256,
+    transactions: usize,
+    receipts: usize,
+}
+
 impl<'a> PayloadBuildContext<'a> {
+    fn checkpoint(&self) -> PayloadCheckpoint {
+        PayloadCheckpoint {
+            evm_state: self.evm_state.snapshot(),
+            remaining_gas: self.remaining_gas,
+            block_value: self.block_value,
+            transactions: self.payload.body.transactions.len(),
+            receipts: self.receipts.len(),
+        }
+    }
+
+    /// Discards the transactions added since the checkpoint. Blob transactions must not have
+    /// been added, as their blobs aren't tracked by the checkpoint
+    fn revert(&mut self, checkpoint: PayloadCheckpoint) {
+        self.evm_state.restore(checkpoint.evm_state);
+        self.remaining_gas = checkpoint.remaining_gas;
+        self.block_value = checkpoint.block_value;
+        self.payload
+            .body
+            .transactions
+            .truncate(checkpoint.transactions);
+        self.receipts.truncate(checkpoint.receipts);
+    }
+
     fn parent_hash(&self) -> BlockHash {
         self.payload.header.parent_hash
     }
@@ -214,20 +470,6 @@ impl<'a> PayloadBuildContext<'a> {
     }
 }
 
-/// Completes the payload building process, return the block value
-pub fn build_payload(
-    payload: &mut Block,
-    store: &Store,
-) -> Result<(BlobsBundle, U256), ChainError> {
-    debug!("Building payload");
-    let mut evm_state = evm_state(store.clone(), payload.header.parent_hash);
-    let mut context = PayloadBuildContext::new(payload, &mut evm_state);
-    apply_withdrawals(&mut context)?;
-    fill_transactions(&mut context)?;
-    finalize_payload(&mut context)?;
-    Ok((context.blobs_bundle, context.block_value))
-}
-
 pub fn apply_withdrawals(context: &mut PayloadBuildContext) -> Result<(), EvmError> {
     // Apply withdrawals & call beacon root contract, and obtain the new state root
     let spec_id = spec_id(&context.chain_config()?, context.payload.header.timestamp);
@@ -245,7 +487,7 @@ fn fetch_mempool_transactions(
     context: &mut PayloadBuildContext,
 ) -> Result<(TransactionQueue, TransactionQueue), ChainError> {
     let tx_filter = PendingTxFilter {
-        /*TODO(https://github.com/lambdaclass/ethrex/issues/680): add tip filter */
+        min_tip: Some(context.min_tip),
         base_fee: context.base_fee_per_gas(),
         blob_fee: Some(context.base_fee_per_blob_gas),
         ..Default::default()
@@ -275,10 +517,14 @@ fn fetch_mempool_transactions(
     ))
 }
 
-/// Fills the payload with transactions taken from the mempool
-/// Returns the block value
-pub fn fill_transactions(context: &mut PayloadBuildContext) -> Result<(), ChainError> {
+/// Fills the payload with the pooled bundles targeting its block, followed by transactions taken
+/// from the mempool
+pub fn fill_transactions(
+    context: &mut PayloadBuildContext,
+    events: &EventBus,
+) -> Result<(), ChainError> {
     let chain_config = context.chain_config()?;
+    apply_bundles(context)?;
     debug!("Fetching transactions from mempool");
     // Fetch mempool transactions
     let (mut plain_txs, mut blob_txs) = fetch_mempool_transactions(context)?;
@@ -289,9 +535,7 @@ pub fn fill_transactions(context: &mut PayloadBuildContext) -> Result<(), ChainE
             debug!("No more gas to run transactions");
             break;
         };
-        if !blob_txs.is_empty()
-            && context.blobs_bundle.blobs.len() as u64 * GAS_PER_BLOB >= MAX_BLOB_GAS_PER_BLOCK
-        {
+        if !blob_txs.is_empty() && context.blobs_bundle.blobs.len() as u64 >= context.max_blobs {
             debug!("No more blob gas to run blob transactions");
             blob_txs.clear();
         }
@@ -335,6 +579,7 @@ pub fn fill_transactions(context: &mut PayloadBuildContext) -> Result<(), ChainE
                 context
                     .store()
                     .ok_or(ChainError::StoreError(StoreError::MissingStore))?,
+                events,
             )?;
             continue;
         }
@@ -348,14 +593,8 @@ pub fn fill_transactions(context: &mut PayloadBuildContext) -> Result<(), ChainE
         let receipt = match apply_transaction(&head_tx, context) {
             Ok(receipt) => {
                 txs.shift()?;
-                // Pull transaction from the mempool
-                mempool::remove_transaction(
-                    &head_tx.tx.compute_hash(),
-                    context
-                        .store()
-                        .ok_or(ChainError::StoreError(StoreError::MissingStore))?,
-                )?;
-
+                // The transaction stays in the mempool so later builds of the payload can include
+                // it too, it is removed once its block becomes canonical
                 metrics!(METRICS_TX.inc_tx_with_status_and_type(
                     MetricsTxStatus::Succeeded,
                     MetricsTxType(head_tx.tx_type())
@@ -382,6 +621,83 @@ pub fn fill_transactions(context: &mut PayloadBuildContext) -> Result<(), ChainE
     Ok(())
 }
 
+/// Adds the pooled bundles targeting the payload's block, in submission order. Each bundle is
+/// simulated on top of the payload and either included whole or skipped
+fn apply_bundles(context: &mut PayloadBuildContext) -> Result<(), ChainError> {
+    let bundles = context
+        .store()
+        .ok_or(ChainError::StoreError(StoreError::MissingStore))?
+        .get_pool_bundles(context.block_number(), context.payload.header.timestamp)?;
+    for bundle in bundles {
+        let bundle_hash = bundle.hash();
+        let checkpoint = context.checkpoint();
+        match apply_bundle(&bundle, context) {
+            Ok(results)
+                if results
+                    .iter()
+                    .all(|result| result.succeeded || bundle.may_revert(&result.tx_hash)) =>
+            {
+                debug!("Adding bundle: {bundle_hash:#x} to payload");
+                metrics!(METRICS_TX.inc_bundle(MetricsBundleStatus::Included));
+            }
+            Ok(_) => {
+                debug!("Skipping bundle: {bundle_hash:#x}, a transaction reverted");
+                context.revert(checkpoint);
+                metrics!(METRICS_TX.inc_bundle(MetricsBundleStatus::Reverted));
+            }
+            Err(error) => {
+                debug!("Skipping bundle: {bundle_hash:#x}, {error}");
+                context.revert(checkpoint);
+                metrics!(METRICS_TX.inc_bundle(MetricsBundleStatus::Failed));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Executes the bundle's transactions in order on top of the payload and adds them to it,
+/// failing if any of them can't be executed. Reverted transactions don't fail the bundle, they
+/// are reported in the results
+fn apply_bundle(
+    bundle: &Bundle,
+    context: &mut PayloadBuildContext,
+) -> Result<Vec<BundleTxResult>, ChainError> {
+    let mut results = Vec::with_capacity(bundle.transactions.len());
+    for tx in &bundle.transactions {
+        let tx_hash = tx.compute_hash();
+        if context.remaining_gas < tx.gas_limit() {
+            // This error will only be used for debug tracing
+            return Err(
+                EvmError::Custom(format!("no gas left for transaction {tx_hash:#x}")).into(),
+            );
+        }
+        let tip = tx
+            .effective_gas_tip(context.base_fee_per_gas())
+            .ok_or_else(|| {
+                InvalidBlockError::InvalidTransaction(format!(
+                    "transaction {tx_hash:#x} doesn't cover the base fee"
+                ))
+            })?;
+        let sender = tx.sender();
+        let head = HeadTransaction {
+            tx: MempoolTransaction::new(tx.clone(), sender),
+            sender,
+            tip,
+        };
+        let (remaining_gas, block_value) = (context.remaining_gas, context.block_value);
+        let receipt = apply_plain_transaction(&head, context)?;
+        results.push(BundleTxResult {
+            tx_hash,
+            gas_used: remaining_gas - context.remaining_gas,
+            coinbase_payment: context.block_value - block_value,
+            succeeded: receipt.succeeded,
+        });
+        context.payload.body.transactions.push(tx.clone());
+        context.receipts.push(receipt);
+    }
+    Ok(results)
+}
+
 /// Executes the transaction, updates gas-related context values & return the receipt
 /// The payload build context should have enough remaining gas to cover the transaction's gas_limit
 fn apply_transaction(
@@ -411,9 +727,7 @@ fn apply_blob_transaction(
             StoreError::Custom(format!("No blobs bundle found for blob tx {tx_hash}")).into(),
         );
     };
-    if (context.blobs_bundle.blobs.len() + blobs_bundle.blobs.len()) as u64 * GAS_PER_BLOB
-        > MAX_BLOB_GAS_PER_BLOCK
-    {
+    if (context.blobs_bundle.blobs.len() + blobs_bundle.blobs.len()) as u64 > context.max_blobs {
         // This error will only be used for debug tracing
         return Err(EvmError::Custom("max data blobs reached".to_string()).into());
     };
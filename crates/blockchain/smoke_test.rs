@@ -1,32 +1,46 @@
 #[cfg(test)]
 mod blockchain_integration_test {
-    use std::{fs::File, io::BufReader};
+    use std::{
+        fs::{self, File},
+        io::BufReader,
+        path::Path,
+        sync::Arc,
+        thread,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    };
 
     use crate::{
-        add_block,
-        error::{ChainError, InvalidForkChoice},
-        fork_choice::apply_fork_choice,
-        is_canonical, latest_canonical_block_hash,
-        payload::{build_payload, create_payload, BuildPayloadArgs},
+        constants::{GAS_PER_BLOB, MIN_GAS_LIMIT},
+        error::{ChainError, InvalidBlockError, InvalidBuilderConfig, InvalidForkChoice},
+        events::ChainEvent,
+        is_canonical, latest_canonical_block_hash, mempool,
+        payload::{BuildPayloadArgs, BuilderConfig, MAX_EXTRA_DATA_SIZE},
+        Blockchain, GcMode, PendingBlocksConfig, MAX_MEMPOOL_UPDATE_DEPTH,
     };
 
     use ethrex_core::{
-        types::{Block, BlockHeader},
-        H160, H256,
+        types::{
+            BlobsBundle, Block, BlockBody, BlockHeader, EIP1559Transaction, EIP4844Transaction,
+            Genesis, GenesisAccount, MempoolTransaction, Signable, Transaction, TxKind,
+            BYTES_PER_BLOB, VERSIONED_HASH_VERSION_KZG,
+        },
+        Address, Bytes, H160, H256, U256,
     };
-    use ethrex_storage::{EngineType, Store};
+    use ethrex_storage::{Bundle, EngineType, Store};
+    use secp256k1::SecretKey;
 
     #[test]
     fn test_small_to_long_reorg() {
         // Store and genesis
-        let store = test_store();
+        let blockchain = test_blockchain();
+        let store = blockchain.storage.clone();
         let genesis_header = store.get_block_header(0).unwrap().unwrap();
         let genesis_hash = genesis_header.compute_block_hash();
 
         // Add first block. We'll make it canonical.
-        let block_1a = new_block(&store, &genesis_header);
+        let block_1a = new_block(&blockchain, &genesis_header);
         let hash_1a = block_1a.hash();
-        add_block(&block_1a, &store).unwrap();
+        blockchain.add_block(&block_1a).unwrap();
         store.set_canonical_block(1, hash_1a).unwrap();
         let retrieved_1a = store.get_block_header(1).unwrap().unwrap();
 
@@ -34,31 +48,35 @@ mod blockchain_integration_test {
         assert!(is_canonical(&store, 1, hash_1a).unwrap());
 
         // Add second block at height 1. Will not be canonical.
-        let block_1b = new_block(&store, &genesis_header);
+        let block_1b = new_block(&blockchain, &genesis_header);
         let hash_1b = block_1b.hash();
-        add_block(&block_1b, &store).expect("Could not add block 1b.");
+        blockchain
+            .add_block(&block_1b)
+            .expect("Could not add block 1b.");
         let retrieved_1b = store.get_block_header_by_hash(hash_1b).unwrap().unwrap();
 
         assert_ne!(retrieved_1a, retrieved_1b);
         assert!(!is_canonical(&store, 1, hash_1b).unwrap());
 
         // Add a third block at height 2, child to the non canonical block.
-        let block_2 = new_block(&store, &block_1b.header);
+        let block_2 = new_block(&blockchain, &block_1b.header);
         let hash_2 = block_2.hash();
-        add_block(&block_2, &store).expect("Could not add block 2.");
+        blockchain
+            .add_block(&block_2)
+            .expect("Could not add block 2.");
         let retrieved_2 = store.get_block_header_by_hash(hash_2).unwrap();
 
         assert!(retrieved_2.is_some());
         assert!(store.get_canonical_block_hash(2).unwrap().is_none());
 
         // Receive block 2 as new head.
-        apply_fork_choice(
-            &store,
-            block_2.hash(),
-            genesis_header.compute_block_hash(),
-            genesis_header.compute_block_hash(),
-        )
-        .unwrap();
+        blockchain
+            .apply_fork_choice(
+                block_2.hash(),
+                genesis_header.compute_block_hash(),
+                genesis_header.compute_block_hash(),
+            )
+            .unwrap();
 
         // Check that canonical blocks changed to the new branch.
         assert!(is_canonical(&store, 0, genesis_hash).unwrap());
@@ -69,26 +87,29 @@ mod blockchain_integration_test {
 
     #[test]
     fn test_sync_not_supported_yet() {
-        let store = test_store();
+        let blockchain = test_blockchain();
+        let store = blockchain.storage.clone();
         let genesis_header = store.get_block_header(0).unwrap().unwrap();
 
         // Build a single valid block.
-        let block_1 = new_block(&store, &genesis_header);
+        let block_1 = new_block(&blockchain, &genesis_header);
         let hash_1 = block_1.header.compute_block_hash();
-        add_block(&block_1, &store).unwrap();
-        apply_fork_choice(&store, hash_1, H256::zero(), H256::zero()).unwrap();
+        blockchain.add_block(&block_1).unwrap();
+        blockchain
+            .apply_fork_choice(hash_1, H256::zero(), H256::zero())
+            .unwrap();
 
         // Build a child, then change its parent, making it effectively a pending block.
-        let mut block_2 = new_block(&store, &block_1.header);
+        let mut block_2 = new_block(&blockchain, &block_1.header);
         block_2.header.parent_hash = H256::random();
         let hash_2 = block_2.header.compute_block_hash();
-        let result = add_block(&block_2, &store);
+        let result = blockchain.add_block(&block_2);
         assert!(matches!(result, Err(ChainError::ParentNotFound)));
 
         // block 2 should now be pending.
         assert!(store.get_pending_block(hash_2).unwrap().is_some());
 
-        let fc_result = apply_fork_choice(&store, hash_2, H256::zero(), H256::zero());
+        let fc_result = blockchain.apply_fork_choice(hash_2, H256::zero(), H256::zero());
         assert!(matches!(fc_result, Err(InvalidForkChoice::Syncing)));
 
         // block 2 should still be pending.
@@ -98,23 +119,28 @@ mod blockchain_integration_test {
     #[test]
     fn test_reorg_from_long_to_short_chain() {
         // Store and genesis
-        let store = test_store();
+        let blockchain = test_blockchain();
+        let store = blockchain.storage.clone();
         let genesis_header = store.get_block_header(0).unwrap().unwrap();
         let genesis_hash = genesis_header.compute_block_hash();
 
         // Add first block. Not canonical.
-        let block_1a = new_block(&store, &genesis_header);
+        let block_1a = new_block(&blockchain, &genesis_header);
         let hash_1a = block_1a.hash();
-        add_block(&block_1a, &store).unwrap();
+        blockchain.add_block(&block_1a).unwrap();
         let retrieved_1a = store.get_block_header_by_hash(hash_1a).unwrap().unwrap();
 
         assert!(!is_canonical(&store, 1, hash_1a).unwrap());
 
         // Add second block at height 1. Canonical.
-        let block_1b = new_block(&store, &genesis_header);
+        let block_1b = new_block(&blockchain, &genesis_header);
         let hash_1b = block_1b.hash();
-        add_block(&block_1b, &store).expect("Could not add block 1b.");
-        apply_fork_choice(&store, hash_1b, genesis_hash, genesis_hash).unwrap();
+        blockchain
+            .add_block(&block_1b)
+            .expect("Could not add block 1b.");
+        blockchain
+            .apply_fork_choice(hash_1b, genesis_hash, genesis_hash)
+            .unwrap();
         let retrieved_1b = store.get_block_header(1).unwrap().unwrap();
 
         assert_ne!(retrieved_1a, retrieved_1b);
@@ -123,10 +149,14 @@ mod blockchain_integration_test {
         assert_eq!(latest_canonical_block_hash(&store).unwrap(), hash_1b);
 
         // Add a third block at height 2, child to the canonical one.
-        let block_2 = new_block(&store, &block_1b.header);
+        let block_2 = new_block(&blockchain, &block_1b.header);
         let hash_2 = block_2.hash();
-        add_block(&block_2, &store).expect("Could not add block 2.");
-        apply_fork_choice(&store, hash_2, genesis_hash, genesis_hash).unwrap();
+        blockchain
+            .add_block(&block_2)
+            .expect("Could not add block 2.");
+        blockchain
+            .apply_fork_choice(hash_2, genesis_hash, genesis_hash)
+            .unwrap();
         let retrieved_2 = store.get_block_header_by_hash(hash_2).unwrap();
         assert_eq!(latest_canonical_block_hash(&store).unwrap(), hash_2);
 
@@ -135,13 +165,13 @@ mod blockchain_integration_test {
         assert_eq!(store.get_canonical_block_hash(2).unwrap().unwrap(), hash_2);
 
         // Receive block 1a as new head.
-        apply_fork_choice(
-            &store,
-            block_1a.hash(),
-            genesis_header.compute_block_hash(),
-            genesis_header.compute_block_hash(),
-        )
-        .unwrap();
+        blockchain
+            .apply_fork_choice(
+                block_1a.hash(),
+                genesis_header.compute_block_hash(),
+                genesis_header.compute_block_hash(),
+            )
+            .unwrap();
 
         // Check that canonical blocks changed to the new branch.
         assert!(is_canonical(&store, 0, genesis_hash).unwrap());
@@ -153,30 +183,37 @@ mod blockchain_integration_test {
     #[test]
     fn new_head_with_canonical_ancestor_should_skip() {
         // Store and genesis
-        let store = test_store();
+        let blockchain = test_blockchain();
+        let store = blockchain.storage.clone();
         let genesis_header = store.get_block_header(0).unwrap().unwrap();
         let genesis_hash = genesis_header.compute_block_hash();
 
         // Add block at height 1.
-        let block_1 = new_block(&store, &genesis_header);
+        let block_1 = new_block(&blockchain, &genesis_header);
         let hash_1 = block_1.hash();
-        add_block(&block_1, &store).expect("Could not add block 1b.");
+        blockchain
+            .add_block(&block_1)
+            .expect("Could not add block 1b.");
 
         // Add child at height 2.
-        let block_2 = new_block(&store, &block_1.header);
+        let block_2 = new_block(&blockchain, &block_1.header);
         let hash_2 = block_2.hash();
-        add_block(&block_2, &store).expect("Could not add block 2.");
+        blockchain
+            .add_block(&block_2)
+            .expect("Could not add block 2.");
 
         assert!(!is_canonical(&store, 1, hash_1).unwrap());
         assert!(!is_canonical(&store, 2, hash_2).unwrap());
 
         // Make that chain the canonical one.
-        apply_fork_choice(&store, hash_2, genesis_hash, genesis_hash).unwrap();
+        blockchain
+            .apply_fork_choice(hash_2, genesis_hash, genesis_hash)
+            .unwrap();
 
         assert!(is_canonical(&store, 1, hash_1).unwrap());
         assert!(is_canonical(&store, 2, hash_2).unwrap());
 
-        let result = apply_fork_choice(&store, hash_1, hash_1, hash_1);
+        let result = blockchain.apply_fork_choice(hash_1, hash_1, hash_1);
 
         assert!(matches!(
             result,
@@ -189,48 +226,823 @@ mod blockchain_integration_test {
         assert!(store.get_latest_block_number().unwrap() == 2);
     }
 
+    #[test]
+    fn head_is_rewound_to_canonical_ancestor() {
+        let blockchain = test_blockchain();
+        let store = blockchain.storage.clone();
+        let genesis_header = store.get_block_header(0).unwrap().unwrap();
+
+        let block_1 = new_block(&blockchain, &genesis_header);
+        blockchain.add_block(&block_1).unwrap();
+        let block_2 = new_block(&blockchain, &block_1.header);
+        blockchain.add_block(&block_2).unwrap();
+        let fork_block = new_block(&blockchain, &genesis_header);
+        blockchain.add_block(&fork_block).unwrap();
+        blockchain
+            .apply_fork_choice(block_2.hash(), block_2.hash(), block_2.hash())
+            .unwrap();
+
+        // Blocks off the canonical chain can't be rewound to
+        assert!(matches!(
+            blockchain.rewind_head(fork_block.hash()),
+            Err(InvalidForkChoice::Disconnected(_, _))
+        ));
+
+        let head = blockchain.rewind_head(block_1.hash()).unwrap();
+        assert_eq!(head, block_1.header);
+        assert_eq!(store.get_latest_block_number().unwrap(), 1);
+        assert_eq!(store.get_safe_block_number().unwrap(), Some(1));
+        assert_eq!(store.get_finalized_block_number().unwrap(), Some(1));
+        assert!(is_canonical(&store, 1, block_1.hash()).unwrap());
+        assert!(store.get_canonical_block_hash(2).unwrap().is_none());
+        assert_eq!(blockchain.latest_block_header().unwrap(), block_1.header);
+
+        // The chain can go on from the new head
+        let block_2b = new_block(&blockchain, &block_1.header);
+        blockchain.add_block(&block_2b).unwrap();
+        blockchain
+            .apply_fork_choice(block_2b.hash(), block_1.hash(), block_1.hash())
+            .unwrap();
+        assert!(is_canonical(&store, 2, block_2b.hash()).unwrap());
+    }
+
     #[test]
     fn latest_block_number_should_always_be_the_canonical_head() {
         // Goal: put a, b in the same branch, both canonical.
         // Then add one in a different branch. Check that the last one is still the same.
 
         // Store and genesis
-        let store = test_store();
+        let blockchain = test_blockchain();
+        let store = blockchain.storage.clone();
         let genesis_header = store.get_block_header(0).unwrap().unwrap();
         let genesis_hash = genesis_header.compute_block_hash();
 
         // Add block at height 1.
-        let block_1 = new_block(&store, &genesis_header);
-        add_block(&block_1, &store).expect("Could not add block 1b.");
+        let block_1 = new_block(&blockchain, &genesis_header);
+        blockchain
+            .add_block(&block_1)
+            .expect("Could not add block 1b.");
 
         // Add child at height 2.
-        let block_2 = new_block(&store, &block_1.header);
+        let block_2 = new_block(&blockchain, &block_1.header);
         let hash_2 = block_2.hash();
-        add_block(&block_2, &store).expect("Could not add block 2.");
+        blockchain
+            .add_block(&block_2)
+            .expect("Could not add block 2.");
 
         assert_eq!(latest_canonical_block_hash(&store).unwrap(), genesis_hash);
 
         // Make that chain the canonical one.
-        apply_fork_choice(&store, hash_2, genesis_hash, genesis_hash).unwrap();
+        blockchain
+            .apply_fork_choice(hash_2, genesis_hash, genesis_hash)
+            .unwrap();
 
         assert_eq!(latest_canonical_block_hash(&store).unwrap(), hash_2);
 
         // Add a new, non canonical block, starting from genesis.
-        let block_1b = new_block(&store, &genesis_header);
+        let block_1b = new_block(&blockchain, &genesis_header);
         let hash_b = block_1b.hash();
-        add_block(&block_1b, &store).expect("Could not add block b.");
+        blockchain
+            .add_block(&block_1b)
+            .expect("Could not add block b.");
 
         // The latest block should be the same.
         assert_eq!(latest_canonical_block_hash(&store).unwrap(), hash_2);
 
         // if we apply fork choice to the new one, then we should
-        apply_fork_choice(&store, hash_b, genesis_hash, genesis_hash).unwrap();
+        blockchain
+            .apply_fork_choice(hash_b, genesis_hash, genesis_hash)
+            .unwrap();
 
         // The latest block should now be the new head.
         assert_eq!(latest_canonical_block_hash(&store).unwrap(), hash_b);
     }
 
-    fn new_block(store: &Store, parent: &BlockHeader) -> Block {
+    #[test]
+    fn invalid_block_descendants_are_rejected() {
+        let blockchain = test_blockchain();
+        let store = blockchain.storage.clone();
+        let genesis_header = store.get_block_header(0).unwrap().unwrap();
+        let genesis_hash = genesis_header.compute_block_hash();
+
+        // Build a block whose header doesn't match its execution result.
+        let valid_block = new_block(&blockchain, &genesis_header);
+        let mut invalid_block = valid_block.clone();
+        invalid_block.header.state_root = H256::random();
+        let invalid_hash = invalid_block.hash();
+        let result = blockchain.add_block(&invalid_block);
+        assert!(matches!(
+            result,
+            Err(ChainError::InvalidBlock(
+                InvalidBlockError::StateRootMismatch
+            ))
+        ));
+        assert_eq!(
+            blockchain.latest_valid_ancestor(invalid_hash).unwrap(),
+            Some(genesis_hash)
+        );
+
+        // A child of the invalid block is rejected without being stored as pending.
+        blockchain.add_block(&valid_block).unwrap();
+        let mut child = new_block(&blockchain, &valid_block.header);
+        child.header.parent_hash = invalid_hash;
+        let child_hash = child.hash();
+        let result = blockchain.add_block(&child);
+        assert!(matches!(
+            result,
+            Err(ChainError::InvalidBlock(InvalidBlockError::InvalidAncestor(hash))) if hash == genesis_hash
+        ));
+        assert!(store.get_pending_block(child_hash).unwrap().is_none());
+        assert_eq!(
+            blockchain.latest_valid_ancestor(child_hash).unwrap(),
+            Some(genesis_hash)
+        );
+
+        // Neither can become the head.
+        let fc_result = blockchain.apply_fork_choice(child_hash, genesis_hash, genesis_hash);
+        assert!(matches!(
+            fc_result,
+            Err(InvalidForkChoice::InvalidAncestor(hash)) if hash == genesis_hash
+        ));
+    }
+
+    #[test]
+    fn pending_block_is_imported_when_parent_arrives() {
+        // Build a short chain on a separate node.
+        let builder = test_blockchain();
+        let genesis_header = builder.storage.get_block_header(0).unwrap().unwrap();
+        let block_1 = new_block(&builder, &genesis_header);
+        builder.add_block(&block_1).unwrap();
+        let block_2 = new_block(&builder, &block_1.header);
+        let hash_2 = block_2.hash();
+
+        // Receive the child before its parent.
+        let blockchain = test_blockchain();
+        let store = blockchain.storage.clone();
+        let result = blockchain.add_block(&block_2);
+        assert!(matches!(result, Err(ChainError::ParentNotFound)));
+        assert!(store.get_pending_block(hash_2).unwrap().is_some());
+
+        // Adding the parent imports the pending child too.
+        blockchain.add_block(&block_1).unwrap();
+        assert!(store.get_pending_block(hash_2).unwrap().is_none());
+        assert!(store.get_block_header_by_hash(hash_2).unwrap().is_some());
+        blockchain
+            .apply_fork_choice(hash_2, H256::zero(), H256::zero())
+            .unwrap();
+        assert!(is_canonical(&store, 2, hash_2).unwrap());
+    }
+
+    #[test]
+    fn stale_pending_blocks_are_pruned() {
+        let blockchain = test_blockchain().with_pending_blocks_config(PendingBlocksConfig {
+            max_depth: 10,
+            max_age: Duration::from_secs(1),
+        });
+        let store = blockchain.storage.clone();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let add_pending_block = |number, timestamp| {
+            let header = BlockHeader {
+                number,
+                timestamp,
+                parent_hash: H256::random(),
+                ..Default::default()
+            };
+            let block = Block::new(header, BlockBody::default());
+            let hash = block.hash();
+            store.add_pending_block(block).unwrap();
+            hash
+        };
+        let waited_too_long = add_pending_block(101, now);
+        std::thread::sleep(Duration::from_millis(1100));
+        let recent = add_pending_block(95, now);
+        let too_deep = add_pending_block(89, now);
+        // Age is counted from when the block arrived, not from its timestamp
+        let historical = add_pending_block(99, now - 120);
+        let future = add_pending_block(102, now + 120);
+
+        let head = BlockHeader {
+            number: 100,
+            ..Default::default()
+        };
+        blockchain.prune_pending_blocks(&head).unwrap();
+        assert!(store.get_pending_block(recent).unwrap().is_some());
+        assert!(store.get_pending_block(historical).unwrap().is_some());
+        assert!(store.get_pending_block(future).unwrap().is_some());
+        assert!(store.get_pending_block(too_deep).unwrap().is_none());
+        assert!(store.get_pending_block(waited_too_long).unwrap().is_none());
+    }
+
+    #[test]
+    fn head_change_branches_stop_at_common_ancestor() {
+        let blockchain = test_blockchain();
+        let store = blockchain.storage.clone();
+        let genesis_header = store.get_block_header(0).unwrap().unwrap();
+
+        // Build two branches: genesis <- 1a <- 2a and genesis <- 1b
+        let block_1a = new_block(&blockchain, &genesis_header);
+        blockchain.add_block(&block_1a).unwrap();
+        let block_2a = new_block(&blockchain, &block_1a.header);
+        blockchain.add_block(&block_2a).unwrap();
+        let block_1b = new_block(&blockchain, &genesis_header);
+        blockchain.add_block(&block_1b).unwrap();
+
+        let (old_branch, new_branch) = blockchain
+            .head_change_branches(&block_2a.header, &block_1b.header)
+            .unwrap()
+            .unwrap();
+        assert_eq!(old_branch, vec![block_2a.hash(), block_1a.hash()]);
+        assert_eq!(new_branch, vec![block_1b.hash()]);
+
+        // Moving the head forward only adds blocks
+        let (old_branch, new_branch) = blockchain
+            .head_change_branches(&genesis_header, &block_2a.header)
+            .unwrap()
+            .unwrap();
+        assert!(old_branch.is_empty());
+        assert_eq!(new_branch, vec![block_2a.hash(), block_1a.hash()]);
+    }
+
+    #[test]
+    fn payload_build_is_stored_until_stopped() {
+        let blockchain = Arc::new(test_blockchain());
+        let store = blockchain.storage.clone();
+        let genesis_header = store.get_block_header(0).unwrap().unwrap();
+        let args = BuildPayloadArgs {
+            parent: genesis_header.compute_block_hash(),
+            timestamp: genesis_header.timestamp + 12,
+            fee_recipient: H160::random(),
+            random: H256::random(),
+            withdrawals: Some(Vec::new()),
+            beacon_root: Some(H256::random()),
+            version: 1,
+        };
+        let payload_id = args.id();
+        let payload = blockchain.create_payload(&args).unwrap();
+
+        blockchain
+            .start_payload_build(payload_id, payload.clone())
+            .unwrap();
+        // Building the same payload again doesn't restart it
+        blockchain.start_payload_build(payload_id, payload).unwrap();
+        // Wait for the first build to be stored
+        let mut stored = None;
+        for _ in 0..100 {
+            let (block, _, _, completed) = store.get_payload(payload_id).unwrap().unwrap();
+            if completed {
+                stored = Some(block);
+                break;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        let block = stored.expect("Payload was never built");
+        assert_eq!(block.header.number, 1);
+        blockchain.stop_payload_build(payload_id).unwrap();
+
+        // The stored payload no longer changes once the build is stopped
+        thread::sleep(Duration::from_millis(600));
+        let (stopped_block, _, _, completed) = store.get_payload(payload_id).unwrap().unwrap();
+        assert!(completed);
+        assert_eq!(stopped_block, block);
+        // Stopping a payload that is no longer being built is a no-op
+        blockchain.stop_payload_build(payload_id).unwrap();
+    }
+
+    #[test]
+    fn blocks_are_added_in_batch() {
+        // Build a chain on a separate node.
+        let builder = test_blockchain();
+        let mut parent = builder.storage.get_block_header(0).unwrap().unwrap();
+        let mut blocks = Vec::new();
+        for _ in 0..3 {
+            let block = new_block(&builder, &parent);
+            builder.add_block(&block).unwrap();
+            parent = block.header.clone();
+            blocks.push(block);
+        }
+
+        // A batch with a gap is rejected
+        let blockchain = test_blockchain();
+        let store = blockchain.storage.clone();
+        let result = blockchain.add_blocks_in_batch(&[blocks[0].clone(), blocks[2].clone()]);
+        assert!(matches!(result, Err(ChainError::ParentNotFound)));
+
+        let mut subscriber = blockchain.subscribe();
+        blockchain.add_blocks_in_batch(&blocks).unwrap();
+        for block in &blocks {
+            assert!(store
+                .get_block_header_by_hash(block.hash())
+                .unwrap()
+                .is_some());
+            assert_eq!(
+                store.get_receipts_for_block(&block.hash()).unwrap().len(),
+                block.body.transactions.len()
+            );
+            assert!(is_canonical(&store, block.header.number, block.hash()).unwrap());
+            assert_eq!(
+                subscriber.try_recv(),
+                Some(ChainEvent::BlockImported {
+                    number: block.header.number,
+                    hash: block.hash(),
+                })
+            );
+        }
+        // The segment becomes the new head
+        assert_eq!(
+            subscriber.try_recv(),
+            Some(ChainEvent::NewHead(Box::new(parent.clone())))
+        );
+        assert_eq!(blockchain.latest_block_header().unwrap(), parent);
+        // The state after the batch is stored, so the chain can go on from there
+        let next_block = new_block(&builder, &parent);
+        blockchain.add_block(&next_block).unwrap();
+    }
+
+    #[test]
+    fn batch_with_a_bad_state_root_is_rejected() {
+        let builder = test_blockchain();
+        let mut parent = builder.storage.get_block_header(0).unwrap().unwrap();
+        let mut blocks = Vec::new();
+        for _ in 0..3 {
+            let block = new_block(&builder, &parent);
+            builder.add_block(&block).unwrap();
+            parent = block.header.clone();
+            blocks.push(block);
+        }
+        // The middle block claims a wrong state, while its child is still valid on top of it
+        let mut header = blocks[1].header.clone();
+        header.state_root = H256::random();
+        blocks[1] = Block::new(header, blocks[1].body.clone());
+        let mut header = blocks[2].header.clone();
+        header.parent_hash = blocks[1].hash();
+        blocks[2] = Block::new(header, blocks[2].body.clone());
+
+        let blockchain = test_blockchain();
+        let store = blockchain.storage.clone();
+        let result = blockchain.add_blocks_in_batch(&blocks);
+        assert!(matches!(
+            result,
+            Err(ChainError::InvalidBlock(
+                InvalidBlockError::StateRootMismatch
+            ))
+        ));
+        for block in &blocks {
+            assert!(store
+                .get_block_header_by_hash(block.hash())
+                .unwrap()
+                .is_none());
+        }
+    }
+
+    #[test]
+    fn chain_events_are_published() {
+        let blockchain = test_blockchain();
+        let genesis_header = blockchain.latest_block_header().unwrap();
+        let genesis_hash = genesis_header.compute_block_hash();
+        let mut subscriber = blockchain.subscribe();
+
+        let block_1a = new_block(&blockchain, &genesis_header);
+        let block_1b = new_block(&blockchain, &genesis_header);
+        let block_2 = new_block(&blockchain, &block_1b.header);
+        for block in [&block_1a, &block_1b, &block_2] {
+            blockchain.add_block(block).unwrap();
+            assert_eq!(
+                subscriber.try_recv(),
+                Some(ChainEvent::BlockImported {
+                    number: block.header.number,
+                    hash: block.hash(),
+                })
+            );
+        }
+
+        blockchain
+            .apply_fork_choice(block_2.hash(), genesis_hash, genesis_hash)
+            .unwrap();
+        assert_eq!(
+            subscriber.try_recv(),
+            Some(ChainEvent::NewHead(Box::new(block_2.header.clone())))
+        );
+
+        // Switching to the shorter branch is published as a reorg before the new head
+        blockchain
+            .apply_fork_choice(block_1a.hash(), genesis_hash, genesis_hash)
+            .unwrap();
+        assert_eq!(
+            subscriber.try_recv(),
+            Some(ChainEvent::Reorg {
+                removed: vec![block_2.hash(), block_1b.hash()],
+                added: vec![block_1a.hash()],
+            })
+        );
+        assert_eq!(
+            subscriber.try_recv(),
+            Some(ChainEvent::NewHead(Box::new(block_1a.header.clone())))
+        );
+        assert_eq!(subscriber.try_recv(), None);
+    }
+
+    #[test]
+    fn deep_reorgs_are_published() {
+        let blockchain = test_blockchain();
+        let genesis_header = blockchain.latest_block_header().unwrap();
+        let genesis_hash = genesis_header.compute_block_hash();
+
+        // A branch longer than the mempool is updated for
+        let mut parent = genesis_header.clone();
+        let mut removed = Vec::new();
+        for _ in 0..=MAX_MEMPOOL_UPDATE_DEPTH {
+            let block = new_block(&blockchain, &parent);
+            blockchain.add_block(&block).unwrap();
+            parent = block.header.clone();
+            removed.insert(0, block.hash());
+        }
+        blockchain
+            .apply_fork_choice(parent.compute_block_hash(), genesis_hash, genesis_hash)
+            .unwrap();
+
+        let block_1b = new_block(&blockchain, &genesis_header);
+        blockchain.add_block(&block_1b).unwrap();
+        let mut subscriber = blockchain.subscribe();
+        blockchain
+            .apply_fork_choice(block_1b.hash(), genesis_hash, genesis_hash)
+            .unwrap();
+        assert_eq!(
+            subscriber.try_recv(),
+            Some(ChainEvent::Reorg {
+                removed,
+                added: vec![block_1b.hash()],
+            })
+        );
+        assert_eq!(
+            subscriber.try_recv(),
+            Some(ChainEvent::NewHead(Box::new(block_1b.header.clone())))
+        );
+    }
+
+    #[test]
+    fn state_of_old_blocks_is_pruned() {
+        let blockchain = test_blockchain().with_gc_mode(GcMode::Full { retained_blocks: 2 });
+        let store = blockchain.storage.clone();
+        let genesis_header = store.get_block_header(0).unwrap().unwrap();
+
+        let mut canonical = vec![genesis_header.clone()];
+        for _ in 0..2 {
+            let block = new_block(&blockchain, canonical.last().unwrap());
+            blockchain.add_block(&block).unwrap();
+            blockchain
+                .apply_fork_choice(block.hash(), block.hash(), block.hash())
+                .unwrap();
+            canonical.push(block.header);
+        }
+        // The window didn't move far enough yet
+        blockchain.wait_for_state_pruning().unwrap();
+        assert_eq!(store.get_earliest_state_block_number().unwrap(), None);
+
+        // A side branch on top of block 2 survives pruning
+        let block_3b = new_block(&blockchain, &canonical[2]);
+        blockchain.add_block(&block_3b).unwrap();
+        for _ in 0..2 {
+            let block = new_block(&blockchain, canonical.last().unwrap());
+            blockchain.add_block(&block).unwrap();
+            blockchain
+                .apply_fork_choice(
+                    block.hash(),
+                    canonical[2].compute_block_hash(),
+                    H256::zero(),
+                )
+                .unwrap();
+            canonical.push(block.header);
+        }
+        // Pruning runs in the background
+        blockchain.wait_for_state_pruning().unwrap();
+        assert_eq!(store.get_earliest_state_block_number().unwrap(), Some(2));
+
+        for (number, header) in canonical.iter().enumerate() {
+            let state = store.state_trie(header.compute_block_hash()).unwrap();
+            assert_eq!(state.is_some(), number >= 2);
+        }
+        let block_4b = new_block(&blockchain, &block_3b.header);
+        blockchain.add_block(&block_4b).unwrap();
+    }
+
+    #[test]
+    fn mempool_follows_the_canonical_chain() {
+        let secret_key = SecretKey::from_slice(&[1; 32]).unwrap();
+        let tx = transfer(&secret_key, 0, 1_000_000_000);
+        let tx_hash = tx.compute_hash();
+        let blockchain = funded_blockchain(&[tx.sender()]);
+        let store = blockchain.storage.clone();
+        let genesis_header = store.get_block_header(0).unwrap().unwrap();
+        let genesis_hash = genesis_header.compute_block_hash();
+        let is_pooled = || {
+            store
+                .get_pool_transaction_by_nonce(tx.sender(), 0)
+                .unwrap()
+                .is_some_and(|(hash, _)| hash == tx_hash)
+        };
+
+        mempool::add_transaction(tx.clone(), &store, blockchain.events()).unwrap();
+        assert!(is_pooled());
+
+        // Transactions included in the new head leave the pool
+        let block_1a = new_block(&blockchain, &genesis_header);
+        assert_eq!(block_1a.body.transactions, vec![tx.clone()]);
+        blockchain.add_block(&block_1a).unwrap();
+        blockchain
+            .apply_fork_choice(block_1a.hash(), genesis_hash, genesis_hash)
+            .unwrap();
+        assert!(!is_pooled());
+
+        // And are added back once their block is reorged out
+        let block_1b = new_block(&blockchain, &genesis_header);
+        let block_2b = new_block(&blockchain, &block_1b.header);
+        for block in [&block_1b, &block_2b] {
+            assert!(block.body.transactions.is_empty());
+            blockchain.add_block(block).unwrap();
+        }
+        blockchain
+            .apply_fork_choice(block_2b.hash(), genesis_hash, genesis_hash)
+            .unwrap();
+        assert!(is_pooled());
+    }
+
+    #[test]
+    fn journaled_transactions_are_revalidated_when_loaded() {
+        let path = Path::new("mempool-journal-test-load");
+        let _ = fs::remove_file(path);
+        let (key_a, key_b) = (
+            SecretKey::from_slice(&[1; 32]).unwrap(),
+            SecretKey::from_slice(&[2; 32]).unwrap(),
+        );
+        let (tx_a0, tx_a1, tx_b0) = (
+            transfer(&key_a, 0, 1_000_000_000),
+            transfer(&key_a, 1, 1_000_000_000),
+            transfer(&key_b, 0, 1_000_000_000),
+        );
+        let remote_tx = transfer(&key_b, 1, 1_000_000_000);
+
+        // Just the locally submitted transactions are journaled
+        let blockchain = funded_blockchain(&[tx_a0.sender(), tx_b0.sender()]);
+        let store = blockchain.storage.clone();
+        store.open_mempool_journal(path, []).unwrap();
+        for tx in [&tx_a0, &tx_a1, &tx_b0] {
+            mempool::add_local_transaction(tx.clone(), &store, blockchain.events()).unwrap();
+        }
+        mempool::add_transaction(remote_tx, &store, blockchain.events()).unwrap();
+        store.flush_mempool_journal().unwrap();
+        let journaled: Vec<_> = Store::read_mempool_journal(path)
+            .unwrap()
+            .into_iter()
+            .map(|(tx, _)| tx)
+            .collect();
+        assert_eq!(journaled, vec![tx_a0.clone(), tx_a1.clone(), tx_b0.clone()]);
+
+        // On a chain where the nonce of the first one was used and the sender of the last one
+        // has no funds, only the second one is valid
+        let blockchain = funded_blockchain(&[tx_a0.sender()]);
+        let store = blockchain.storage.clone();
+        let genesis_header = store.get_block_header(0).unwrap().unwrap();
+        let genesis_hash = genesis_header.compute_block_hash();
+        mempool::add_transaction(
+            transfer(&key_a, 0, 2_000_000_000),
+            &store,
+            blockchain.events(),
+        )
+        .unwrap();
+        let block = new_block(&blockchain, &genesis_header);
+        blockchain.add_block(&block).unwrap();
+        blockchain
+            .apply_fork_choice(block.hash(), genesis_hash, genesis_hash)
+            .unwrap();
+
+        assert_eq!(
+            mempool::load_journal(path, &store, blockchain.events()).unwrap(),
+            1
+        );
+        let pooled: Vec<_> = store
+            .get_pool_transactions_by_sender(tx_a1.sender())
+            .unwrap()
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect();
+        assert_eq!(pooled, vec![tx_a1.compute_hash()]);
+        assert!(store
+            .get_pool_transactions_by_sender(tx_b0.sender())
+            .unwrap()
+            .is_empty());
+        // The journal is rewritten with just the loaded transactions
+        assert_eq!(
+            Store::read_mempool_journal(path).unwrap(),
+            vec![(tx_a1, None)]
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn invalid_builder_config_is_rejected() {
+        let config = BuilderConfig {
+            extra_data: Bytes::from(vec![0; MAX_EXTRA_DATA_SIZE + 1]),
+            ..Default::default()
+        };
+        assert!(matches!(
+            test_blockchain().with_builder_config(config),
+            Err(InvalidBuilderConfig::ExtraDataTooLong(..))
+        ));
+        let config = BuilderConfig {
+            gas_ceil: MIN_GAS_LIMIT - 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            test_blockchain().with_builder_config(config),
+            Err(InvalidBuilderConfig::GasCeilTooLow(..))
+        ));
+        test_blockchain()
+            .with_builder_config(BuilderConfig::default())
+            .unwrap();
+    }
+
+    #[test]
+    fn transactions_below_the_min_tip_are_not_included() {
+        let cheap_key = SecretKey::from_slice(&[1; 32]).unwrap();
+        let cheap_tx = transfer(&cheap_key, 0, 1_000_000_000);
+        let paying_key = SecretKey::from_slice(&[2; 32]).unwrap();
+        let paying_tx = transfer(&paying_key, 0, 3_000_000_000);
+        let blockchain = funded_blockchain(&[cheap_tx.sender(), paying_tx.sender()])
+            .with_builder_config(BuilderConfig {
+                min_tip: 2_000_000_000,
+                ..Default::default()
+            })
+            .unwrap();
+        let store = blockchain.storage.clone();
+        for tx in [&cheap_tx, &paying_tx] {
+            mempool::add_transaction(tx.clone(), &store, blockchain.events()).unwrap();
+        }
+
+        let genesis_header = store.get_block_header(0).unwrap().unwrap();
+        let block = new_block(&blockchain, &genesis_header);
+        assert_eq!(block.body.transactions, vec![paying_tx]);
+    }
+
+    #[test]
+    fn blobs_per_block_are_limited() {
+        let secret_key = SecretKey::from_slice(&[1; 32]).unwrap();
+        let sender = transfer(&secret_key, 0, 0).sender();
+        let blockchain = funded_blockchain(&[sender])
+            .with_builder_config(BuilderConfig {
+                max_blobs_per_block: 1,
+                ..Default::default()
+            })
+            .unwrap();
+        let store = blockchain.storage.clone();
+        let chain_id = test_genesis().config.chain_id;
+        // Pooled directly, as the blobs are not valid
+        let blob_txs: Vec<_> = (0..2)
+            .map(|nonce| {
+                let mut versioned_hash = H256::random();
+                versioned_hash.0[0] = VERSIONED_HASH_VERSION_KZG;
+                Transaction::EIP4844Transaction(EIP4844Transaction {
+                    chain_id,
+                    nonce,
+                    max_priority_fee_per_gas: 1_000_000_000,
+                    max_fee_per_gas: 100_000_000_000,
+                    max_fee_per_blob_gas: U256::from(1_000_000_000),
+                    gas: 21_000,
+                    to: H160::random(),
+                    blob_versioned_hashes: vec![versioned_hash],
+                    ..Default::default()
+                })
+                .sign(&secret_key)
+            })
+            .collect();
+        for tx in &blob_txs {
+            let hash = tx.compute_hash();
+            store
+                .add_transaction_to_pool(hash, MempoolTransaction::new(tx.clone(), sender))
+                .unwrap();
+            store
+                .add_blobs_bundle_to_pool(
+                    hash,
+                    BlobsBundle {
+                        blobs: vec![[0; BYTES_PER_BLOB]],
+                        commitments: vec![[0; 48]],
+                        proofs: vec![[0; 48]],
+                    },
+                )
+                .unwrap();
+        }
+
+        let genesis_header = store.get_block_header(0).unwrap().unwrap();
+        let block = new_block(&blockchain, &genesis_header);
+        assert_eq!(block.body.transactions, vec![blob_txs[0].clone()]);
+        assert_eq!(block.header.blob_gas_used, Some(GAS_PER_BLOB));
+    }
+
+    #[test]
+    fn bundles_are_included_whole_or_skipped() {
+        let key_a = SecretKey::from_slice(&[1; 32]).unwrap();
+        let key_b = SecretKey::from_slice(&[2; 32]).unwrap();
+        let sender_a = transfer(&key_a, 0, 0).sender();
+        let sender_b = transfer(&key_b, 0, 0).sender();
+        // Always reverts: PUSH0 PUSH0 REVERT
+        let reverting_contract = Address::from_low_u64_be(0xdead);
+        let mut genesis = funded_genesis(&[sender_a, sender_b]);
+        genesis.alloc.insert(
+            reverting_contract,
+            GenesisAccount {
+                code: Bytes::from(vec![0x5f, 0x5f, 0xfd]),
+                storage: Default::default(),
+                balance: U256::zero(),
+                nonce: 0,
+            },
+        );
+        let chain_id = genesis.config.chain_id;
+        let blockchain = blockchain_with_genesis(genesis);
+        let store = blockchain.storage.clone();
+        let reverting_call = Transaction::EIP1559Transaction(EIP1559Transaction {
+            chain_id,
+            nonce: 0,
+            max_priority_fee_per_gas: 1_000_000_000,
+            max_fee_per_gas: 100_000_000_000,
+            gas_limit: 50_000,
+            to: TxKind::Call(reverting_contract),
+            ..Default::default()
+        })
+        .sign(&key_b);
+        let bundle = |transactions: Vec<Transaction>, reverting_tx_hashes: Vec<H256>| Bundle {
+            transactions,
+            block_number: 1,
+            min_timestamp: None,
+            max_timestamp: None,
+            reverting_tx_hashes,
+        };
+
+        // A transaction reverts without being allowed to
+        let reverted = bundle(
+            vec![transfer(&key_a, 0, 1_000_000_000), reverting_call.clone()],
+            Vec::new(),
+        );
+        // A transaction can't be executed, its nonce is too high
+        let failed = bundle(
+            vec![
+                transfer(&key_a, 0, 1_000_000_000),
+                transfer(&key_b, 5, 1_000_000_000),
+            ],
+            Vec::new(),
+        );
+        let included = bundle(
+            vec![reverting_call.clone(), transfer(&key_a, 0, 1_000_000_000)],
+            vec![reverting_call.compute_hash()],
+        );
+        for bundle in [reverted, failed, included.clone()] {
+            mempool::add_bundle(bundle, &store).unwrap();
+        }
+
+        let genesis_header = store.get_block_header(0).unwrap().unwrap();
+        let block = new_block(&blockchain, &genesis_header);
+        // Only the last bundle made it, and the skipped ones left no trace in the state
+        assert_eq!(block.body.transactions, included.transactions);
+        blockchain.add_block(&block).unwrap();
+    }
+
+    /// Signs a transfer paying the given tip from the account of `secret_key`
+    fn transfer(secret_key: &SecretKey, nonce: u64, max_priority_fee_per_gas: u64) -> Transaction {
+        Transaction::EIP1559Transaction(EIP1559Transaction {
+            chain_id: test_genesis().config.chain_id,
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas: 100_000_000_000,
+            gas_limit: 21_000,
+            to: TxKind::Call(H160::random()),
+            value: U256::one(),
+            ..Default::default()
+        })
+        .sign(secret_key)
+    }
+
+    fn funded_blockchain(accounts: &[Address]) -> Blockchain {
+        blockchain_with_genesis(funded_genesis(accounts))
+    }
+
+    fn funded_genesis(accounts: &[Address]) -> Genesis {
+        let mut genesis = test_genesis();
+        for address in accounts {
+            genesis.alloc.insert(
+                *address,
+                GenesisAccount {
+                    code: Default::default(),
+                    storage: Default::default(),
+                    balance: U256::from(10).pow(U256::from(18)),
+                    nonce: 0,
+                },
+            );
+        }
+        genesis
+    }
+
+    fn new_block(blockchain: &Blockchain, parent: &BlockHeader) -> Block {
         let args = BuildPayloadArgs {
             parent: parent.compute_block_hash(),
             timestamp: parent.timestamp + 12,
@@ -241,18 +1053,23 @@ mod blockchain_integration_test {
             version: 1,
         };
 
-        let mut block = create_payload(&args, store).unwrap();
-        build_payload(&mut block, store).unwrap();
+        let mut block = blockchain.create_payload(&args).unwrap();
+        blockchain.build_payload(&mut block).unwrap();
         block
     }
 
-    fn test_store() -> Store {
-        // Get genesis
+    fn test_blockchain() -> Blockchain {
+        blockchain_with_genesis(test_genesis())
+    }
+
+    fn test_genesis() -> Genesis {
         let file = File::open("../../test_data/genesis-execution-api.json")
             .expect("Failed to open genesis file");
         let reader = BufReader::new(file);
-        let genesis = serde_json::from_reader(reader).expect("Failed to deserialize genesis file");
+        serde_json::from_reader(reader).expect("Failed to deserialize genesis file")
+    }
 
+    fn blockchain_with_genesis(genesis: Genesis) -> Blockchain {
         // Build store with genesis
         let store =
             Store::new("store.db", EngineType::InMemory).expect("Failed to build DB for testing");
@@ -261,6 +1078,6 @@ mod blockchain_integration_test {
             .add_initial_state(genesis)
             .expect("Failed to add genesis state");
 
-        store
+        Blockchain::new(store)
     }
 }
@@ -0,0 +1,219 @@
+use ethereum_types::{Address, H256, U256};
+use ethrex_rlp::{
+    decode::RLPDecode,
+    encode::RLPEncode,
+    error::RLPDecodeError,
+    structs::{Decoder, Encoder},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::serde_utils;
+
+use super::BlockNumber;
+
+/// Commitment data sent to the L1 by the proposer for a given L2 block.
+/// It is recorded once the commit transaction is sent, and updated with the
+/// verify transaction hash once the block's proof is accepted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchStatus {
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub block_number: BlockNumber,
+    pub commit_tx_hash: H256,
+    pub withdrawals_merkle_root: H256,
+    pub deposit_logs_hash: H256,
+    pub verify_tx_hash: Option<H256>,
+}
+
+/// Merkle proof of a withdrawal against the withdrawals root committed for its block.
+/// This is what the bridge contract expects in order to claim the withdrawal on L1.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawalProof {
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub block_number: BlockNumber,
+    /// Position of the withdrawal among the block's withdrawals
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub index: u64,
+    pub withdrawal_hash: H256,
+    pub merkle_root: H256,
+    pub merkle_proof: Vec<H256>,
+}
+
+/// Deposit initiated on the L1 bridge along with the L2 transaction that mints it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepositInfo {
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub l1_block_number: BlockNumber,
+    pub l2_tx_hash: H256,
+    pub recipient: Address,
+    pub value: U256,
+    pub deposit_id: U256,
+}
+
+/// L1 fee values seen by the committer the last time it sent a commitment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct L1FeeComponents {
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub l1_block_number: BlockNumber,
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub base_fee_per_gas: u64,
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub blob_base_fee: u64,
+    /// Price per blob gas paid by the committer, including its headroom
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub blob_gas_price: u64,
+}
+
+impl RLPEncode for BatchStatus {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.block_number)
+            .encode_field(&self.commit_tx_hash)
+            .encode_field(&self.withdrawals_merkle_root)
+            .encode_field(&self.deposit_logs_hash)
+            .encode_optional_field(&self.verify_tx_hash)
+            .finish();
+    }
+}
+
+impl RLPDecode for BatchStatus {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (block_number, decoder) = decoder.decode_field("block_number")?;
+        let (commit_tx_hash, decoder) = decoder.decode_field("commit_tx_hash")?;
+        let (withdrawals_merkle_root, decoder) = decoder.decode_field("withdrawals_merkle_root")?;
+        let (deposit_logs_hash, decoder) = decoder.decode_field("deposit_logs_hash")?;
+        let (verify_tx_hash, decoder) = decoder.decode_optional_field();
+        Ok((
+            Self {
+                block_number,
+                commit_tx_hash,
+                withdrawals_merkle_root,
+                deposit_logs_hash,
+                verify_tx_hash,
+            },
+            decoder.finish()?,
+        ))
+    }
+}
+
+impl RLPEncode for WithdrawalProof {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.block_number)
+            .encode_field(&self.index)
+            .encode_field(&self.withdrawal_hash)
+            .encode_field(&self.merkle_root)
+            .encode_field(&self.merkle_proof)
+            .finish();
+    }
+}
+
+impl RLPDecode for WithdrawalProof {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (block_number, decoder) = decoder.decode_field("block_number")?;
+        let (index, decoder) = decoder.decode_field("index")?;
+        let (withdrawal_hash, decoder) = decoder.decode_field("withdrawal_hash")?;
+        let (merkle_root, decoder) = decoder.decode_field("merkle_root")?;
+        let (merkle_proof, decoder) = decoder.decode_field("merkle_proof")?;
+        Ok((
+            Self {
+                block_number,
+                index,
+                withdrawal_hash,
+                merkle_root,
+                merkle_proof,
+            },
+            decoder.finish()?,
+        ))
+    }
+}
+
+impl RLPEncode for DepositInfo {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.l1_block_number)
+            .encode_field(&self.l2_tx_hash)
+            .encode_field(&self.recipient)
+            .encode_field(&self.value)
+            .encode_field(&self.deposit_id)
+            .finish();
+    }
+}
+
+impl RLPDecode for DepositInfo {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (l1_block_number, decoder) = decoder.decode_field("l1_block_number")?;
+        let (l2_tx_hash, decoder) = decoder.decode_field("l2_tx_hash")?;
+        let (recipient, decoder) = decoder.decode_field("recipient")?;
+        let (value, decoder) = decoder.decode_field("value")?;
+        let (deposit_id, decoder) = decoder.decode_field("deposit_id")?;
+        Ok((
+            Self {
+                l1_block_number,
+                l2_tx_hash,
+                recipient,
+                value,
+                deposit_id,
+            },
+            decoder.finish()?,
+        ))
+    }
+}
+
+impl RLPEncode for L1FeeComponents {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.l1_block_number)
+            .encode_field(&self.base_fee_per_gas)
+            .encode_field(&self.blob_base_fee)
+            .encode_field(&self.blob_gas_price)
+            .finish();
+    }
+}
+
+impl RLPDecode for L1FeeComponents {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(Self, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (l1_block_number, decoder) = decoder.decode_field("l1_block_number")?;
+        let (base_fee_per_gas, decoder) = decoder.decode_field("base_fee_per_gas")?;
+        let (blob_base_fee, decoder) = decoder.decode_field("blob_base_fee")?;
+        let (blob_gas_price, decoder) = decoder.decode_field("blob_gas_price")?;
+        Ok((
+            Self {
+                l1_block_number,
+                base_fee_per_gas,
+                blob_base_fee,
+                blob_gas_price,
+            },
+            decoder.finish()?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_status_rlp_roundtrip() {
+        let mut status = BatchStatus {
+            block_number: 7,
+            commit_tx_hash: H256::random(),
+            withdrawals_merkle_root: H256::random(),
+            deposit_logs_hash: H256::zero(),
+            verify_tx_hash: None,
+        };
+        let decoded = BatchStatus::decode(&status.encode_to_vec()).unwrap();
+        assert_eq!(decoded, status);
+
+        status.verify_tx_hash = Some(H256::random());
+        let decoded = BatchStatus::decode(&status.encode_to_vec()).unwrap();
+        assert_eq!(decoded, status);
+    }
+}
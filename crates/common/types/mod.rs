@@ -4,6 +4,7 @@ mod block;
 mod constants;
 mod fork_id;
 mod genesis;
+mod l2;
 mod receipt;
 pub mod transaction;
 
@@ -13,5 +14,6 @@ pub use block::*;
 pub use constants::*;
 pub use fork_id::*;
 pub use genesis::*;
+pub use l2::*;
 pub use receipt::*;
 pub use transaction::*;
@@ -8,13 +8,13 @@ use crate::{
 
 use ethrex_core::{
     types::{
-        blobs_bundle, fake_exponential_checked, BlobsBundle, BlobsBundleError, Block,
-        PrivilegedL2Transaction, PrivilegedTxType, Transaction, TxKind,
-        BLOB_BASE_FEE_UPDATE_FRACTION, MIN_BASE_FEE_PER_BLOB_GAS,
+        blobs_bundle, fake_exponential_checked, BatchStatus, BlobsBundle, BlobsBundleError, Block,
+        BlockHeader, L1FeeComponents, PrivilegedL2Transaction, PrivilegedTxType, Transaction,
+        TxKind, WithdrawalProof, BLOB_BASE_FEE_UPDATE_FRACTION, MIN_BASE_FEE_PER_BLOB_GAS,
     },
     Address, H256, U256,
 };
-use ethrex_l2_sdk::merkle_tree::merkelize;
+use ethrex_l2_sdk::merkle_tree::{merkelize, merkle_proofs};
 use ethrex_l2_sdk::{
     calldata::{encode_calldata, Value},
     eth_client::{eth_sender::Overrides, BlockByNumber, EthClient, WrappedTransaction},
@@ -25,7 +25,7 @@ use keccak_hash::keccak;
 use secp256k1::SecretKey;
 use std::{collections::HashMap, time::Duration};
 use tokio::time::sleep;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 use super::errors::BlobEstimationError;
 
@@ -112,7 +112,13 @@ impl Committer {
                 }
 
                 let withdrawal_logs_merkle_root =
-                    self.get_withdrawals_merkle_root(withdrawal_hashes)?;
+                    self.get_withdrawals_merkle_root(withdrawal_hashes.clone())?;
+                let withdrawal_proofs = self.get_withdrawal_proofs(
+                    block_to_commit.header.number,
+                    &withdrawals,
+                    withdrawal_hashes,
+                    withdrawal_logs_merkle_root,
+                )?;
                 let deposit_logs_hash = self.get_deposit_hash(
                     deposits
                         .iter()
@@ -141,6 +147,16 @@ impl Committer {
                 {
                     Ok(commit_tx_hash) => {
                         info!("Sent commitment to block {head_block_hash:#x}, with transaction hash {commit_tx_hash:#x}");
+                        self.record_commitment(
+                            withdrawal_proofs,
+                            BatchStatus {
+                                block_number: block_to_commit.header.number,
+                                commit_tx_hash,
+                                withdrawals_merkle_root: withdrawal_logs_merkle_root,
+                                deposit_logs_hash,
+                                verify_tx_hash: None,
+                            },
+                        );
                     }
                     Err(error) => {
                         return Err(CommitterError::FailedToSendCommitment(format!(
@@ -154,6 +170,23 @@ impl Committer {
         }
     }
 
+    /// Stores the withdrawal proofs and commitment data of a block once its commitment is sent.
+    /// The block is already committed on L1 by then, so failing to store them doesn't stop the
+    /// committer and is only logged.
+    fn record_commitment(
+        &self,
+        withdrawal_proofs: Vec<(H256, WithdrawalProof)>,
+        batch_status: BatchStatus,
+    ) {
+        let block_number = batch_status.block_number;
+        if let Err(error) = self.store.add_withdrawal_proofs(withdrawal_proofs) {
+            warn!("Failed to record withdrawal proofs for block {block_number}: {error}");
+        }
+        if let Err(error) = self.store.add_batch_status(block_number, batch_status) {
+            warn!("Failed to record commitment for block {block_number}: {error}");
+        }
+    }
+
     fn get_block_withdrawals(
         &self,
         block: &Block,
@@ -186,6 +219,40 @@ impl Committer {
         }
     }
 
+    /// Computes the merkle proof of each withdrawal against the block's withdrawals root.
+    /// They are stored along the commitment so they can be served to users claiming on L1.
+    fn get_withdrawal_proofs(
+        &self,
+        block_number: u64,
+        withdrawals: &[(H256, PrivilegedL2Transaction)],
+        withdrawal_hashes: Vec<H256>,
+        merkle_root: H256,
+    ) -> Result<Vec<(H256, WithdrawalProof)>, CommitterError> {
+        if withdrawal_hashes.is_empty() {
+            return Ok(vec![]);
+        }
+        let proofs = merkle_proofs(withdrawal_hashes.clone())?;
+        let mut withdrawal_proofs = vec![];
+        for (index, (((tx_hash, _), withdrawal_hash), proof)) in withdrawals
+            .iter()
+            .zip(withdrawal_hashes)
+            .zip(proofs)
+            .enumerate()
+        {
+            withdrawal_proofs.push((
+                *tx_hash,
+                WithdrawalProof {
+                    block_number,
+                    index: index.try_into()?,
+                    withdrawal_hash,
+                    merkle_root,
+                    merkle_proof: proof,
+                },
+            ));
+        }
+        Ok(withdrawal_proofs)
+    }
+
     fn get_block_deposits(&self, block: &Block) -> Vec<PrivilegedL2Transaction> {
         let deposits = block
             .body
@@ -346,13 +413,19 @@ impl Committer {
 
         let calldata = encode_calldata(COMMIT_FUNCTION_SIGNATURE, &calldata_values)?;
 
-        let le_bytes = estimate_blob_gas(
-            &self.eth_client,
+        let latest_l1_block = self
+            .eth_client
+            .get_block_by_number(BlockByNumber::Latest)
+            .await?;
+
+        let blob_gas_price = estimate_blob_gas(
+            &latest_l1_block.header,
             self.arbitrary_base_blob_gas_price,
             20, // 20% of headroom
-        )
-        .await?
-        .to_le_bytes();
+        )?;
+        self.update_l1_fee_components(&latest_l1_block.header, blob_gas_price)?;
+
+        let le_bytes = blob_gas_price.to_le_bytes();
 
         let gas_price_per_blob = Some(U256::from_little_endian(&le_bytes));
 
@@ -388,12 +461,36 @@ impl Committer {
 
         Ok(commit_tx_hash)
     }
+
+    /// Records the L1 fees seen when building the last commitment, these are served
+    /// through the `ethrex_getL1FeeComponents` endpoint.
+    fn update_l1_fee_components(
+        &self,
+        latest_l1_header: &BlockHeader,
+        blob_gas_price: u64,
+    ) -> Result<(), CommitterError> {
+        let blob_base_fee = fake_exponential_checked(
+            MIN_BASE_FEE_PER_BLOB_GAS,
+            latest_l1_header.excess_blob_gas.unwrap_or(0),
+            BLOB_BASE_FEE_UPDATE_FRACTION,
+        )
+        .map_err(BlobEstimationError::FakeExponentialError)?;
+
+        self.store
+            .update_l1_fee_components(L1FeeComponents {
+                l1_block_number: latest_l1_header.number,
+                base_fee_per_gas: latest_l1_header.base_fee_per_gas.unwrap_or(0),
+                blob_base_fee,
+                blob_gas_price,
+            })
+            .map_err(CommitterError::from)
+    }
 }
 
 /// Estimates the gas price for blob transactions based on the current state of the blockchain.
 ///
 /// # Parameters:
-/// - `eth_client`: The Ethereum client used to fetch the latest block.
+/// - `latest_block_header`: Header of the latest L1 block.
 /// - `arbitrary_base_blob_gas_price`: The base gas price that serves as the minimum price for blob transactions.
 /// - `headroom`: Percentage applied to the estimated gas price to provide a buffer against fluctuations.
 ///
@@ -403,17 +500,13 @@ impl Committer {
 /// ```txt
 ///    blob_gas = arbitrary_base_blob_gas_price + (excess_blob_gas + blob_gas_used) * headroom
 /// ```
-async fn estimate_blob_gas(
-    eth_client: &EthClient,
+fn estimate_blob_gas(
+    latest_block_header: &BlockHeader,
     arbitrary_base_blob_gas_price: u64,
     headroom: u64,
 ) -> Result<u64, CommitterError> {
-    let latest_block = eth_client
-        .get_block_by_number(BlockByNumber::Latest)
-        .await?;
-
-    let blob_gas_used = latest_block.header.blob_gas_used.unwrap_or(0);
-    let excess_blob_gas = latest_block.header.excess_blob_gas.unwrap_or(0);
+    let blob_gas_used = latest_block_header.blob_gas_used.unwrap_or(0);
+    let excess_blob_gas = latest_block_header.excess_blob_gas.unwrap_or(0);
 
     // Using the formula from the EIP-4844
     // https://eips.ethereum.org/EIPS/eip-4844
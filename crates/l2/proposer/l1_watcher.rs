@@ -4,8 +4,8 @@ use crate::{
 };
 use bytes::Bytes;
 use ethereum_types::{Address, BigEndianHash, H256, U256};
-use ethrex_blockchain::{constants::TX_GAS_COST, mempool};
-use ethrex_core::types::PrivilegedTxType;
+use ethrex_blockchain::{constants::TX_GAS_COST, events::EventBus, mempool};
+use ethrex_core::types::{DepositInfo, PrivilegedTxType};
 use ethrex_core::types::{Signable, Transaction};
 use ethrex_l2_sdk::eth_client::{errors::EthClientError, eth_sender::Overrides, EthClient};
 use ethrex_rpc::types::receipt::RpcLog;
@@ -16,11 +16,11 @@ use std::{cmp::min, ops::Mul, time::Duration};
 use tokio::time::sleep;
 use tracing::{debug, error, info, warn};
 
-pub async fn start_l1_watcher(store: Store) -> Result<(), ConfigError> {
+pub async fn start_l1_watcher(store: Store, events: EventBus) -> Result<(), ConfigError> {
     let eth_config = EthConfig::from_env()?;
     let watcher_config = L1WatcherConfig::from_env()?;
     let mut l1_watcher = L1Watcher::new_from_config(watcher_config, eth_config).await?;
-    l1_watcher.run(&store).await;
+    l1_watcher.run(&store, &events).await;
     Ok(())
 }
 
@@ -56,9 +56,9 @@ impl L1Watcher {
         })
     }
 
-    pub async fn run(&mut self, store: &Store) {
+    pub async fn run(&mut self, store: &Store, events: &EventBus) {
         loop {
-            if let Err(err) = self.main_logic(store).await {
+            if let Err(err) = self.main_logic(store, events).await {
                 error!("L1 Watcher Error: {}", err);
             }
 
@@ -66,7 +66,7 @@ impl L1Watcher {
         }
     }
 
-    async fn main_logic(&mut self, store: &Store) -> Result<(), L1WatcherError> {
+    async fn main_logic(&mut self, store: &Store, events: &EventBus) -> Result<(), L1WatcherError> {
         loop {
             sleep(self.check_interval).await;
 
@@ -79,7 +79,7 @@ impl L1Watcher {
 
             let pending_deposit_logs = self.get_pending_deposit_logs().await?;
             let _deposit_txs = self
-                .process_logs(logs, &pending_deposit_logs, store)
+                .process_logs(logs, &pending_deposit_logs, store, events)
                 .await?;
         }
     }
@@ -163,6 +163,7 @@ impl L1Watcher {
         logs: Vec<RpcLog>,
         pending_deposit_logs: &[H256],
         store: &Store,
+        events: &EventBus,
     ) -> Result<Vec<H256>, L1WatcherError> {
         let mut deposit_txs = Vec::new();
 
@@ -272,9 +273,20 @@ impl L1Watcher {
             match mempool::add_transaction(
                 Transaction::PrivilegedL2Transaction(mint_transaction),
                 store,
+                events,
             ) {
                 Ok(hash) => {
                     info!("Mint transaction added to mempool {hash:#x}",);
+                    let deposit = DepositInfo {
+                        l1_block_number: log.block_number,
+                        l2_tx_hash: hash,
+                        recipient: beneficiary,
+                        value: mint_value,
+                        deposit_id,
+                    };
+                    if let Err(e) = store.add_deposit(log.transaction_hash, deposit) {
+                        warn!("Failed to store deposit {deposit_id}: {e}");
+                    }
                     deposit_txs.push(hash);
                 }
                 Err(e) => {
@@ -485,9 +485,22 @@ impl ProverServer {
 
         info!("Sent proof for block {block_number}, with transaction hash {verify_tx_hash:#x}");
 
+        self.record_verify_tx_hash(block_number, verify_tx_hash);
+
         Ok(verify_tx_hash)
     }
 
+    /// Stores the verify transaction hash along the block's commitment data.
+    /// Failing to do so doesn't affect the proving flow, so it's only logged.
+    fn record_verify_tx_hash(&self, block_number: u64, verify_tx_hash: H256) {
+        if let Err(error) = self
+            .store
+            .update_batch_verify_tx_hash(block_number, verify_tx_hash)
+        {
+            warn!("Failed to record verify transaction for block {block_number}: {error}");
+        }
+    }
+
     pub async fn main_logic_dev(&self) -> Result<(), ProverServerError> {
         loop {
             thread::sleep(Duration::from_millis(200));
@@ -557,6 +570,8 @@ impl ProverServer {
 
             info!("Sent proof for block {last_verified_block}, with transaction hash {verify_tx_hash:#x}");
 
+            self.record_verify_tx_hash(last_verified_block + 1, verify_tx_hash);
+
             info!(
                 "Mocked verify transaction sent for block {}",
                 last_verified_block + 1
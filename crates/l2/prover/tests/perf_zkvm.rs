@@ -4,7 +4,7 @@ use ethrex_core::types::Block;
 use std::path::Path;
 use tracing::info;
 
-use ethrex_blockchain::add_block;
+use ethrex_blockchain::Blockchain;
 use ethrex_prover_lib::prover::{Prover, Risc0Prover, Sp1Prover};
 use ethrex_storage::{EngineType, Store};
 use ethrex_vm::execution_db::ExecutionDB;
@@ -75,8 +75,9 @@ async fn setup() -> (ProgramInput, Block) {
     let blocks = ethrex_l2::utils::test_data_io::read_chain_file(chain_file_path.to_str().unwrap());
     info!("Number of blocks to insert: {}", blocks.len());
 
+    let blockchain = Blockchain::new(store.clone());
     for block in &blocks {
-        add_block(block, &store).unwrap();
+        blockchain.add_block(block).unwrap();
     }
     let block_to_prove = blocks.last().unwrap();
 
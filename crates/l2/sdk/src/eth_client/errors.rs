@@ -32,6 +32,8 @@ pub enum EthClientError {
     GetBalanceError(#[from] GetBalanceError),
     #[error("eth_getTransactionByHash request error: {0}")]
     GetTransactionByHashError(#[from] GetTransactionByHashError),
+    #[error("ethrex_getWithdrawalProof request error: {0}")]
+    GetWithdrawalProofError(#[from] GetWithdrawalProofError),
     #[error("Unreachable nonce")]
     UnrecheableNonce,
     #[error("Error: {0}")]
@@ -191,3 +193,13 @@ pub enum CalldataEncodeError {
     #[error("Internal Calldata encoding error. This is most likely a bug")]
     InternalError,
 }
+
+#[derive(Debug, thiserror::Error)]
+pub enum GetWithdrawalProofError {
+    #[error("{0}")]
+    ReqwestError(#[from] reqwest::Error),
+    #[error("{0}")]
+    SerdeJSONError(#[from] serde_json::Error),
+    #[error("{0}")]
+    RPCError(String),
+}
@@ -4,14 +4,15 @@ use bytes::Bytes;
 use errors::{
     EstimateGasPriceError, EthClientError, GetBalanceError, GetBlockByHashError,
     GetBlockByNumberError, GetBlockNumberError, GetGasPriceError, GetLogsError, GetNonceError,
-    GetTransactionByHashError, GetTransactionReceiptError, SendRawTransactionError,
+    GetTransactionByHashError, GetTransactionReceiptError, GetWithdrawalProofError,
+    SendRawTransactionError,
 };
 use eth_sender::Overrides;
 use ethereum_types::{Address, H256, U256};
 use ethrex_core::{
     types::{
         BlobsBundle, EIP1559Transaction, EIP4844Transaction, GenericTransaction,
-        PrivilegedL2Transaction, PrivilegedTxType, Signable, TxKind, TxType,
+        PrivilegedL2Transaction, PrivilegedTxType, Signable, TxKind, TxType, WithdrawalProof,
         WrappedEIP4844Transaction,
     },
     H160,
@@ -616,6 +617,30 @@ impl EthClient {
         }
     }
 
+    /// Fetches the merkle proof needed to claim a withdrawal on L1.
+    /// Only available on L2 nodes, once the withdrawal's block has been committed.
+    pub async fn get_withdrawal_proof(
+        &self,
+        tx_hash: H256,
+    ) -> Result<Option<WithdrawalProof>, EthClientError> {
+        let request = RpcRequest {
+            id: RpcRequestId::Number(1),
+            jsonrpc: "2.0".to_string(),
+            method: "ethrex_getWithdrawalProof".to_string(),
+            params: Some(vec![json!(format!("{:#x}", tx_hash))]),
+        };
+
+        match self.send_request(request).await {
+            Ok(RpcResponse::Success(result)) => serde_json::from_value(result.result)
+                .map_err(GetWithdrawalProofError::SerdeJSONError)
+                .map_err(EthClientError::from),
+            Ok(RpcResponse::Error(error_response)) => {
+                Err(GetWithdrawalProofError::RPCError(error_response.error.message).into())
+            }
+            Err(error) => Err(error),
+        }
+    }
+
     pub async fn get_balance(&self, address: Address) -> Result<U256, EthClientError> {
         let request = RpcRequest {
             id: RpcRequestId::Number(1),
@@ -71,3 +71,94 @@ pub fn merkle_proof(data: Vec<H256>, base_element: H256) -> Result<Option<Vec<H2
 
     Ok(Some(proof))
 }
+
+/// Computes the proof of every element of `data`, in order, building the tree only once.
+/// Proofs are indexed by position, as the bridge verifies them, so repeated elements get
+/// their own proof.
+pub fn merkle_proofs(data: Vec<H256>) -> Result<Vec<Vec<H256>>, MerkleError> {
+    if data.is_empty() {
+        return Err(MerkleError::DataVectorIsEmpty());
+    }
+
+    let mut layers = vec![data];
+    let mut first = true;
+    while layers.last().map_or(0, Vec::len) > 1 || first {
+        first = false;
+        let layer = layers.last().ok_or(MerkleError::DataVectorIsEmpty())?;
+        let next = layer
+            .chunks(2)
+            .map(|chunk| -> Result<H256, MerkleError> {
+                let left = chunk.first().ok_or(MerkleError::LeftElementIsNone())?;
+                let right = chunk.get(1).unwrap_or(left);
+                Ok(keccak([left.as_bytes(), right.as_bytes()].concat())
+                    .as_fixed_bytes()
+                    .into())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        layers.push(next);
+    }
+
+    // The last layer only holds the root, which isn't part of any proof
+    let proof_layers = &layers[..layers.len() - 1];
+    let leaves = proof_layers.first().map_or(0, Vec::len);
+    let proofs = (0..leaves)
+        .map(|leaf| {
+            let mut index = leaf;
+            proof_layers
+                .iter()
+                .map(|layer| {
+                    let sibling = if index % 2 == 0 {
+                        layer.get(index + 1).unwrap_or(&layer[index])
+                    } else {
+                        &layer[index - 1]
+                    };
+                    index /= 2;
+                    *sibling
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(proofs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mirrors the proof verification done by the bridge contract when claiming a withdrawal
+    fn verify_proof(leaf: H256, mut index: usize, proof: &[H256], root: H256) -> bool {
+        let mut hash = leaf;
+        for sibling in proof {
+            let (left, right) = if index % 2 == 0 {
+                (hash, *sibling)
+            } else {
+                (*sibling, hash)
+            };
+            hash = keccak([left.as_bytes(), right.as_bytes()].concat())
+                .as_fixed_bytes()
+                .into();
+            index /= 2;
+        }
+        hash == root
+    }
+
+    #[test]
+    fn proofs_verify_against_the_root() {
+        for len in 1..=9 {
+            let data: Vec<H256> = (0..len).map(|_| H256::random()).collect();
+            let root = merkelize(data.clone()).unwrap();
+            let proofs = merkle_proofs(data.clone()).unwrap();
+            assert_eq!(proofs.len(), data.len());
+            for (index, (leaf, proof)) in data.iter().zip(&proofs).enumerate() {
+                assert!(verify_proof(*leaf, index, proof, root));
+                assert_eq!(
+                    merkle_proof(data.clone(), *leaf).unwrap().as_ref(),
+                    Some(proof)
+                );
+                // A proof doesn't hold for another element
+                assert!(!verify_proof(H256::random(), index, proof, root));
+            }
+        }
+    }
+}
@@ -5,11 +5,9 @@ use eth_client::{
     EthClient,
 };
 use ethereum_types::{Address, H160, H256, U256};
-use ethrex_core::types::{PrivilegedTxType, Transaction};
-use ethrex_rpc::types::{block::BlockBodyWrapper, receipt::RpcReceipt};
-use itertools::Itertools;
+use ethrex_core::types::PrivilegedTxType;
+use ethrex_rpc::types::receipt::RpcReceipt;
 use keccak_hash::keccak;
-use merkle_tree::merkle_proof;
 use secp256k1::SecretKey;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 pub mod calldata;
@@ -212,64 +210,15 @@ pub async fn get_withdraw_merkle_proof(
     client: &EthClient,
     tx_hash: H256,
 ) -> Result<(u64, Vec<H256>), EthClientError> {
-    let tx_receipt =
-        client
-            .get_transaction_receipt(tx_hash)
-            .await?
-            .ok_or(EthClientError::Custom(
-                "Failed to get transaction receipt".to_string(),
-            ))?;
-
-    let block = client
-        .get_block_by_hash(tx_receipt.block_info.block_hash)
-        .await?;
-
-    let transactions = match block.body {
-        BlockBodyWrapper::Full(body) => body.transactions,
-        BlockBodyWrapper::OnlyHashes(_) => unreachable!(),
-    };
-    let Some(Some((index, tx_withdrawal_hash))) = transactions
-        .iter()
-        .filter(|tx| match &tx.tx {
-            Transaction::PrivilegedL2Transaction(tx) => tx.tx_type == PrivilegedTxType::Withdrawal,
-            _ => false,
-        })
-        .find_position(|tx| tx.hash == tx_hash)
-        .map(|(i, tx)| match &tx.tx {
-            Transaction::PrivilegedL2Transaction(privileged_l2_transaction) => {
-                privileged_l2_transaction
-                    .get_withdrawal_hash()
-                    .map(|withdrawal_hash| (i, (withdrawal_hash)))
-            }
-            _ => unreachable!(),
-        })
-    else {
-        return Err(EthClientError::Custom(
-            "Failed to get widthdrawal hash, transaction is not a withdrawal".to_string(),
-        ));
-    };
-
-    let path = merkle_proof(
-        transactions
-            .iter()
-            .filter_map(|tx| match &tx.tx {
-                Transaction::PrivilegedL2Transaction(tx) => tx.get_withdrawal_hash(),
-                _ => None,
-            })
-            .collect(),
-        tx_withdrawal_hash,
-    )
-    .map_err(|err| EthClientError::Custom(format!("Failed to generate merkle proof: {err}")))?
-    .ok_or(EthClientError::Custom(
-        "Failed to generate merkle proof, element is not on the tree".to_string(),
-    ))?;
+    // The proof is computed by the proposer when committing the withdrawal's block
+    let proof = client
+        .get_withdrawal_proof(tx_hash)
+        .await?
+        .ok_or(EthClientError::Custom(
+            "Withdrawal proof not found, its block may not be committed yet".to_string(),
+        ))?;
 
-    Ok((
-        index
-            .try_into()
-            .map_err(|err| EthClientError::Custom(format!("index does not fit in u64: {}", err)))?,
-        path,
-    ))
+    Ok((proof.index, proof.merkle_proof))
 }
 
 pub fn secret_key_deserializer<'de, D>(deserializer: D) -> Result<SecretKey, D::Error>
@@ -1,4 +1,6 @@
+use bytes::Bytes;
 use ethereum_types::Address;
+use ethrex_blockchain::payload::BuilderConfig;
 use serde::Deserialize;
 
 use super::errors::ConfigError;
@@ -7,6 +9,14 @@ use super::errors::ConfigError;
 pub struct ProposerConfig {
     pub interval_ms: u64,
     pub coinbase_address: Address,
+    /// Gas limit the proposed blocks move towards
+    pub gas_ceil: Option<u64>,
+    /// Extra data set in the proposed block headers, at most 32 bytes long
+    pub extra_data: Option<String>,
+    /// Minimum effective tip a transaction has to pay to be included
+    pub min_tip: Option<u64>,
+    /// Maximum amount of blobs included per block
+    pub max_blobs_per_block: Option<u64>,
 }
 
 impl ProposerConfig {
@@ -15,4 +25,21 @@ impl ProposerConfig {
             .from_env::<Self>()
             .map_err(ConfigError::from)
     }
+
+    /// Overrides the given block builder settings with the ones set for the proposer
+    pub fn builder_config(&self, mut config: BuilderConfig) -> BuilderConfig {
+        if let Some(gas_ceil) = self.gas_ceil {
+            config.gas_ceil = gas_ceil;
+        }
+        if let Some(extra_data) = &self.extra_data {
+            config.extra_data = Bytes::from(extra_data.clone().into_bytes());
+        }
+        if let Some(min_tip) = self.min_tip {
+            config.min_tip = min_tip;
+        }
+        if let Some(max_blobs_per_block) = self.max_blobs_per_block {
+            config.max_blobs_per_block = max_blobs_per_block;
+        }
+        config
+    }
 }
@@ -1,7 +1,7 @@
 #![allow(clippy::unwrap_used)]
 #![allow(clippy::expect_used)]
 
-use ethrex_blockchain::add_block;
+use ethrex_blockchain::Blockchain;
 use ethrex_core::types::{Block, Genesis};
 use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
 use ethrex_storage::{EngineType, Store};
@@ -72,8 +72,9 @@ pub fn generate_program_input(
     // create store
     let store = Store::new("memory", EngineType::InMemory)?;
     store.add_initial_state(genesis)?;
+    let blockchain = Blockchain::new(store.clone());
     for block in chain {
-        add_block(&block, &store)?;
+        blockchain.add_block(&block)?;
     }
 
     let parent_block_header = store
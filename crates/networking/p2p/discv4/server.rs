@@ -639,6 +639,7 @@ pub(super) mod tests {
     use crate::{
         node_id_from_signing_key, rlpx::message::Message as RLPxMessage, MAX_MESSAGES_TO_BROADCAST,
     };
+    use ethrex_blockchain::events::EventBus;
     use ethrex_storage::{EngineType, Store};
     use k256::ecdsa::SigningKey;
     use rand::rngs::OsRng;
@@ -699,6 +700,7 @@ pub(super) mod tests {
             signer,
             table,
             storage,
+            events: EventBus::default(),
             broadcast,
         };
 
@@ -3,6 +3,7 @@ use discv4::{
     helpers::current_unix_time,
     server::{DiscoveryError, Discv4Server},
 };
+use ethrex_blockchain::events::EventBus;
 use ethrex_core::H512;
 use ethrex_storage::Store;
 use k256::{
@@ -54,6 +55,7 @@ struct P2PContext {
     signer: SigningKey,
     table: Arc<Mutex<KademliaTable>>,
     storage: Store,
+    events: EventBus,
     broadcast: RLPxConnBroadcastSender,
     local_node: Node,
     enr_seq: u64,
@@ -66,6 +68,7 @@ pub async fn start_network(
     signer: SigningKey,
     peer_table: Arc<Mutex<KademliaTable>>,
     storage: Store,
+    events: EventBus,
 ) -> Result<(), NetworkError> {
     let (channel_broadcast_send_end, _) = tokio::sync::broadcast::channel::<(
         tokio::task::Id,
@@ -82,6 +85,7 @@ pub async fn start_network(
         signer,
         table: peer_table,
         storage,
+        events,
         broadcast: channel_broadcast_send_end,
     };
     let discovery = Discv4Server::try_new(context.clone())
@@ -137,8 +141,13 @@ fn listener(tcp_addr: SocketAddr) -> Result<TcpListener, io::Error> {
 }
 
 async fn handle_peer_as_receiver(context: P2PContext, peer_addr: SocketAddr, stream: TcpStream) {
-    let mut conn =
-        RLPxConnection::receiver(context.signer, stream, context.storage, context.broadcast);
+    let mut conn = RLPxConnection::receiver(
+        context.signer,
+        stream,
+        context.storage,
+        context.events,
+        context.broadcast,
+    );
     conn.start_peer(peer_addr, context.table).await;
 }
 
@@ -159,6 +168,7 @@ async fn handle_peer_as_initiator(context: P2PContext, node: Node) {
         node.node_id,
         stream,
         context.storage,
+        context.events,
         context.broadcast,
     ) {
         Ok(mut conn) => conn.start_peer(node.udp_addr(), context.table).await,
@@ -22,13 +22,13 @@ use crate::{
 
 use super::{
     error::RLPxError,
-    eth::{receipts::GetReceipts, transactions::GetPooledTransactions},
+    eth::transactions::GetPooledTransactions,
     frame::RLPxCodec,
     handshake::{decode_ack_message, decode_auth_message, encode_auth_message},
     message as rlpx,
     p2p::Capability,
 };
-use ethrex_blockchain::mempool::{self};
+use ethrex_blockchain::{events::EventBus, mempool};
 use ethrex_core::{H256, H512};
 use ethrex_storage::Store;
 use futures::SinkExt;
@@ -84,6 +84,7 @@ pub(crate) struct RLPxConnection<S> {
     mode: RLPxConnectionMode,
     framed: Framed<S, RLPxCodec>,
     storage: Store,
+    events: EventBus,
     capabilities: Vec<(Capability, u8)>,
     next_periodic_task_check: Instant,
     /// Send end of the channel used to broadcast messages
@@ -104,6 +105,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
         stream: S,
         mode: RLPxConnectionMode,
         storage: Store,
+        events: EventBus,
         connection_broadcast: RLPxConnBroadcastSender,
     ) -> Self {
         Self {
@@ -113,6 +115,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
             // Creating RLPxCodec with default values. They will be updated during the handshake
             framed: Framed::new(stream, RLPxCodec::default()),
             storage,
+            events,
             capabilities: vec![],
             next_periodic_task_check: Instant::now() + PERIODIC_TASKS_CHECK_INTERVAL,
             connection_broadcast_send: connection_broadcast,
@@ -123,6 +126,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
         signer: SigningKey,
         stream: S,
         storage: Store,
+        events: EventBus,
         connection_broadcast: broadcast::Sender<(task::Id, Arc<Message>)>,
     ) -> Self {
         Self::new(
@@ -132,6 +136,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
             stream,
             RLPxConnectionMode::Receiver,
             storage,
+            events,
             connection_broadcast,
         )
     }
@@ -141,6 +146,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
         remote_node_id: H512,
         stream: S,
         storage: Store,
+        events: EventBus,
         connection_broadcast_send: broadcast::Sender<(task::Id, Arc<Message>)>,
     ) -> Result<Self, RLPxError> {
         Ok(RLPxConnection::new(
@@ -149,6 +155,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
             stream,
             RLPxConnectionMode::Initiator,
             storage,
+            events,
             connection_broadcast_send,
         ))
     }
@@ -384,7 +391,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
             Message::Transactions(txs) if peer_supports_eth => {
                 if is_synced {
                     for tx in &txs.transactions {
-                        mempool::add_transaction(tx.clone(), &self.storage)?;
+                        mempool::add_transaction(tx.clone(), &self.storage, &self.events)?;
                     }
                     self.broadcast_message(Message::Transactions(txs)).await?;
                 }
@@ -403,14 +410,10 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
                 };
                 self.send(Message::BlockBodies(response)).await?;
             }
-            Message::GetReceipts(GetReceipts { id, block_hashes }) if peer_supports_eth => {
-                let receipts: Result<_, _> = block_hashes
-                    .iter()
-                    .map(|hash| self.storage.get_receipts_for_block(hash))
-                    .collect();
+            Message::GetReceipts(req) if peer_supports_eth => {
                 let response = Receipts {
-                    id,
-                    receipts: receipts?,
+                    id: req.id,
+                    receipts: req.fetch_receipts(&self.storage)?,
                 };
                 self.send(Message::Receipts(response)).await?;
             }
@@ -431,7 +434,7 @@ impl<S: AsyncWrite + AsyncRead + std::marker::Unpin> RLPxConnection<S> {
             }
             Message::PooledTransactions(msg) if peer_supports_eth => {
                 if is_synced {
-                    msg.handle(&self.storage)?;
+                    msg.handle(&self.storage, &self.events)?;
                 }
             }
             Message::GetStorageRanges(req) => {
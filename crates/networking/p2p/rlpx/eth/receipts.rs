@@ -8,6 +8,7 @@ use ethrex_rlp::{
     error::{RLPDecodeError, RLPEncodeError},
     structs::{Decoder, Encoder},
 };
+use ethrex_storage::{error::StoreError, Store};
 
 // https://github.com/ethereum/devp2p/blob/master/caps/eth.md#getreceipts-0x0f
 #[derive(Debug)]
@@ -22,6 +23,22 @@ impl GetReceipts {
     pub fn new(id: u64, block_hashes: Vec<BlockHash>) -> Self {
         Self { block_hashes, id }
     }
+
+    /// Fetches the receipts of the requested blocks, skipping unknown blocks and those whose
+    /// history was expired instead of answering with empty receipts for them
+    pub fn fetch_receipts(&self, storage: &Store) -> Result<Vec<Vec<Receipt>>, StoreError> {
+        let mut receipts = vec![];
+        for block_hash in &self.block_hashes {
+            let Some(block_number) = storage.get_block_number(*block_hash)? else {
+                continue;
+            };
+            if storage.is_history_expired(block_number)? {
+                continue;
+            }
+            receipts.push(storage.get_receipts_for_block(block_hash)?);
+        }
+        Ok(receipts)
+    }
 }
 
 impl RLPxMessage for GetReceipts {
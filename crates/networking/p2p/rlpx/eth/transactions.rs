@@ -1,7 +1,7 @@
 use bytes::BufMut;
 use bytes::Bytes;
 use ethrex_blockchain::error::MempoolError;
-use ethrex_blockchain::mempool;
+use ethrex_blockchain::{events::EventBus, mempool};
 use ethrex_core::types::P2PTransaction;
 use ethrex_core::types::WrappedEIP4844Transaction;
 use ethrex_core::{types::Transaction, H256};
@@ -250,15 +250,15 @@ impl PooledTransactions {
 
     /// Saves every incoming pooled transaction to the mempool.
 
-    pub fn handle(self, store: &Store) -> Result<(), MempoolError> {
+    pub fn handle(self, store: &Store, events: &EventBus) -> Result<(), MempoolError> {
         for tx in self.pooled_transactions {
             if let P2PTransaction::EIP4844TransactionWithBlobs(itx) = tx {
-                mempool::add_blob_transaction(itx.tx, itx.blobs_bundle, store)?;
+                mempool::add_blob_transaction(itx.tx, itx.blobs_bundle, store, events)?;
             } else {
                 let regular_tx = tx
                     .try_into()
                     .map_err(|error| MempoolError::StoreError(StoreError::Custom(error)))?;
-                mempool::add_transaction(regular_tx, store)?;
+                mempool::add_transaction(regular_tx, store, events)?;
             }
         }
         Ok(())
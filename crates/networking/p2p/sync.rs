@@ -1,4 +1,4 @@
-use ethrex_blockchain::error::ChainError;
+use ethrex_blockchain::{error::ChainError, Blockchain};
 use ethrex_core::{
     types::{AccountState, Block, BlockHash, EMPTY_KECCACK_HASH},
     H256,
@@ -70,10 +70,22 @@ impl SyncManager {
     /// After the sync cycle is complete, the sync mode will be set to full
     /// If the sync fails, no error will be returned but a warning will be emitted
     /// [WARNING] Sync is done optimistically, so headers and bodies may be stored even if their data has not been fully synced if the sync is aborted halfway
-    pub async fn start_sync(&mut self, current_head: H256, sync_head: H256, store: Store) {
+    pub async fn start_sync(
+        &mut self,
+        current_head: H256,
+        sync_head: H256,
+        blockchain: Arc<Blockchain>,
+    ) {
         info!("Syncing from current head {current_head} to sync_head {sync_head}");
         let start_time = Instant::now();
-        match self.sync_cycle(current_head, sync_head, store).await {
+        let result = self
+            .sync_cycle(current_head, sync_head, blockchain.clone())
+            .await;
+        // The canonical chain was updated directly on the store, so the cached head is outdated
+        if let Err(error) = blockchain.refresh_fork_choice() {
+            warn!("Failed to refresh fork choice after sync: {error}");
+        }
+        match result {
             Ok(()) => {
                 info!(
                     "Sync finished, time elapsed: {} secs",
@@ -92,8 +104,9 @@ impl SyncManager {
         &mut self,
         mut current_head: H256,
         sync_head: H256,
-        store: Store,
+        blockchain: Arc<Blockchain>,
     ) -> Result<(), SyncError> {
+        let store = blockchain.storage.clone();
         // Request all block headers between the current head and the sync head
         // We will begin from the current head so that we download the earliest state first
         // This step is not parallelized
@@ -188,7 +201,7 @@ impl SyncManager {
                     } else {
                         store.set_canonical_block(block.header.number, hash)?;
                         store.update_latest_block_number(block.header.number)?;
-                        ethrex_blockchain::add_block(&block, &store)?;
+                        blockchain.add_block(&block)?;
                     }
                 }
                 store_receipts_handle.await??;
@@ -198,7 +211,7 @@ impl SyncManager {
             }
             SyncMode::Full => {
                 // full-sync: Fetch all block bodies and execute them sequentially to build the state
-                download_and_run_blocks(all_block_hashes, self.peers.clone(), store.clone()).await?
+                download_and_run_blocks(all_block_hashes, self.peers.clone(), blockchain).await?
             }
         }
         Ok(())
@@ -210,30 +223,43 @@ impl SyncManager {
 async fn download_and_run_blocks(
     mut block_hashes: Vec<BlockHash>,
     peers: Arc<Mutex<KademliaTable>>,
-    store: Store,
+    blockchain: Arc<Blockchain>,
 ) -> Result<(), SyncError> {
+    let store = &blockchain.storage;
     loop {
         let peer = get_peer_channel_with_retry(peers.clone(), Capability::Eth).await;
         debug!("Requesting Block Bodies ");
         if let Some(block_bodies) = peer.request_block_bodies(block_hashes.clone()).await {
             let block_bodies_len = block_bodies.len();
             debug!("Received {} Block Bodies", block_bodies_len);
-            // Execute and store blocks
-            for (hash, body) in block_hashes
+            let blocks = block_hashes
                 .drain(..block_bodies_len)
                 .zip(block_bodies.into_iter())
-            {
-                let header = store
-                    .get_block_header_by_hash(hash)?
-                    .ok_or(SyncError::CorruptDB)?;
-                let number = header.number;
-                let block = Block::new(header, body);
-                if let Err(error) = ethrex_blockchain::add_block(&block, &store) {
-                    warn!("Failed to add block during FullSync: {error}");
-                    return Err(error.into());
+                .map(|(hash, body)| {
+                    let header = store
+                        .get_block_header_by_hash(hash)?
+                        .ok_or(SyncError::CorruptDB)?;
+                    Ok(Block::new(header, body))
+                })
+                .collect::<Result<Vec<_>, SyncError>>()?;
+            // Execute and store blocks, going one by one if the batch fails to tell which block
+            // is at fault
+            let batch_result = blockchain.add_blocks_in_batch(&blocks);
+            if let Err(error) = &batch_result {
+                debug!(
+                    "Failed to add block batch during FullSync, adding blocks one by one: {error}"
+                );
+            }
+            // A successful batch is already canonical
+            if batch_result.is_err() {
+                for block in &blocks {
+                    if let Err(error) = blockchain.add_block(block) {
+                        warn!("Failed to add block during FullSync: {error}");
+                        return Err(error.into());
+                    }
+                    store.set_canonical_block(block.header.number, block.hash())?;
+                    store.update_latest_block_number(block.header.number)?;
                 }
-                store.set_canonical_block(number, hash)?;
-                store.update_latest_block_number(number)?;
             }
             debug!("Executed & stored {} blocks", block_bodies_len);
             // Check if we need to ask for another batch
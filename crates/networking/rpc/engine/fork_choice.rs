@@ -1,8 +1,6 @@
 use ethrex_blockchain::{
     error::{ChainError, InvalidForkChoice},
-    fork_choice::apply_fork_choice,
-    latest_canonical_block_hash,
-    payload::{create_payload, BuildPayloadArgs},
+    payload::BuildPayloadArgs,
 };
 use ethrex_core::types::BlockHeader;
 use serde_json::Value;
@@ -171,8 +169,7 @@ fn handle_forkchoice(
         fork_choice_state.finalized_block_hash
     );
 
-    match apply_fork_choice(
-        &context.storage,
+    match context.blockchain.apply_fork_choice(
         fork_choice_state.head_block_hash,
         fork_choice_state.safe_block_hash,
         fork_choice_state.finalized_block_hash,
@@ -187,9 +184,15 @@ fn handle_forkchoice(
             let forkchoice_response = match forkchoice_error {
                 InvalidForkChoice::NewHeadAlreadyCanonical => {
                     ForkChoiceResponse::from(PayloadStatus::valid_with_hash(
-                        latest_canonical_block_hash(&context.storage).unwrap(),
+                        context
+                            .blockchain
+                            .latest_block_header()?
+                            .compute_block_hash(),
                     ))
                 }
+                InvalidForkChoice::InvalidAncestor(latest_valid_hash) => ForkChoiceResponse::from(
+                    PayloadStatus::invalid_with(latest_valid_hash, forkchoice_error.to_string()),
+                ),
                 InvalidForkChoice::Syncing => {
                     // Start sync
                     context
@@ -209,7 +212,7 @@ fn handle_forkchoice(
                         // If we can't get hold of the syncer, then it means that there is an active sync in process
                         if let Ok(mut syncer) = context.syncer.try_lock() {
                             syncer
-                                .start_sync(current_head, sync_head, context.storage.clone())
+                                .start_sync(current_head, sync_head, context.blockchain.clone())
                                 .await
                         }
                     });
@@ -298,14 +301,18 @@ fn build_payload(
         version,
     };
     let payload_id = args.id();
-    let payload = match create_payload(&args, &context.storage) {
+    let payload = match context.blockchain.create_payload(&args) {
         Ok(payload) => payload,
         Err(ChainError::EvmError(error)) => return Err(error.into()),
         // Parent block is guaranteed to be present at this point,
         // so the only errors that may be returned are internal storage errors
         Err(error) => return Err(RpcErr::Internal(error.to_string())),
     };
-    context.storage.add_payload(payload_id, payload)?;
+    // Transactions are added in the background so the response isn't delayed by them
+    context
+        .blockchain
+        .start_payload_build(payload_id, payload)
+        .map_err(|error| RpcErr::Internal(error.to_string()))?;
 
     Ok(payload_id)
 }
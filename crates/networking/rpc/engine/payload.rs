@@ -1,6 +1,4 @@
-use ethrex_blockchain::add_block;
-use ethrex_blockchain::error::ChainError;
-use ethrex_blockchain::payload::build_payload;
+use ethrex_blockchain::error::{ChainError, InvalidBlockError};
 use ethrex_core::types::{BlobsBundle, Block, BlockBody, BlockHash, BlockNumber, Fork};
 use ethrex_core::{H256, U256};
 use serde_json::Value;
@@ -388,7 +386,7 @@ fn execute_payload(block: &Block, context: &RpcApiContext) -> Result<PayloadStat
 
     // Execute and store the block
     info!("Executing payload with block hash: {block_hash:#x}");
-    match add_block(block, storage) {
+    match context.blockchain.add_block(block) {
         Err(ChainError::ParentNotFound) => Ok(PayloadStatus::syncing()),
         // Under the current implementation this is not possible: we always calculate the state
         // transition of any new payload as long as the parent is present. If we received the
@@ -399,6 +397,14 @@ fn execute_payload(block: &Block, context: &RpcApiContext) -> Result<PayloadStat
             error!("{e} for block {block_hash}");
             Err(RpcErr::Internal(e.to_string()))
         }
+        // The block or one of its ancestors is already known to be invalid, so it wasn't executed
+        Err(ChainError::InvalidBlock(InvalidBlockError::InvalidAncestor(latest_valid_hash))) => {
+            warn!("Rejected block {block_hash:#x}, it is known to be invalid");
+            Ok(PayloadStatus::invalid_with(
+                latest_valid_hash,
+                InvalidBlockError::InvalidAncestor(latest_valid_hash).to_string(),
+            ))
+        }
         Err(ChainError::InvalidBlock(error)) => {
             warn!("Error adding block: {error}");
             // TODO(#982): this is only valid for the cases where the parent was found, but fully invalid ones may also happen.
@@ -453,6 +459,11 @@ fn get_payload(
     context: &RpcApiContext,
 ) -> Result<(Block, U256, BlobsBundle, bool), RpcErr> {
     info!("Requested payload with id: {:#018x}", payload_id);
+    // Keep the payload from changing once it is handed out
+    context
+        .blockchain
+        .stop_payload_build(payload_id)
+        .map_err(|error| RpcErr::Internal(error.to_string()))?;
     let payload = context.storage.get_payload(payload_id)?;
 
     let Some((payload_block, block_value, blobs_bundle, completed)) = payload else {
@@ -489,7 +500,9 @@ fn build_execution_payload_response(
             should_override_builder,
         })
     } else {
-        let (blobs_bundle, block_value) = build_payload(&mut payload_block, &context.storage)
+        let (blobs_bundle, block_value) = context
+            .blockchain
+            .build_payload(&mut payload_block)
             .map_err(|err| RpcErr::Internal(err.to_string()))?;
 
         context.storage.update_payload(
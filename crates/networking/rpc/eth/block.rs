@@ -9,7 +9,7 @@ use crate::{
         block_identifier::{BlockIdentifier, BlockIdentifierOrHash},
         receipt::{RpcReceipt, RpcReceiptBlockInfo, RpcReceiptTxInfo},
     },
-    utils::RpcErr,
+    utils::{check_history_available, RpcErr},
     RpcApiContext, RpcHandler,
 };
 use ethrex_core::{
@@ -76,6 +76,7 @@ impl RpcHandler for GetBlockByNumberRequest {
             _ => return Ok(Value::Null),
         };
         let header = storage.get_block_header(block_number)?;
+        check_history_available(storage, block_number)?;
         let body = storage.get_block_body(block_number)?;
         let (header, body) = match (header, body) {
             (Some(header), Some(body)) => (header, body),
@@ -118,6 +119,7 @@ impl RpcHandler for GetBlockByHashRequest {
             _ => return Ok(Value::Null),
         };
         let header = storage.get_block_header(block_number)?;
+        check_history_available(storage, block_number)?;
         let body = storage.get_block_body(block_number)?;
         let (header, body) = match (header, body) {
             (Some(header), Some(body)) => (header, body),
@@ -160,6 +162,7 @@ impl RpcHandler for GetBlockTransactionCountRequest {
             Some(block_number) => block_number,
             _ => return Ok(Value::Null),
         };
+        check_history_available(&context.storage, block_number)?;
         let block_body = match context.storage.get_block_body(block_number)? {
             Some(block_body) => block_body,
             _ => return Ok(Value::Null),
@@ -192,6 +195,7 @@ impl RpcHandler for GetBlockReceiptsRequest {
             _ => return Ok(Value::Null),
         };
         let header = storage.get_block_header(block_number)?;
+        check_history_available(storage, block_number)?;
         let body = storage.get_block_body(block_number)?;
         let (header, body) = match (header, body) {
             (Some(header), Some(body)) => (header, body),
@@ -257,6 +261,7 @@ impl RpcHandler for GetRawBlockRequest {
             _ => return Ok(Value::Null),
         };
         let header = context.storage.get_block_header(block_number)?;
+        check_history_available(&context.storage, block_number)?;
         let body = context.storage.get_block_body(block_number)?;
         let (header, body) = match (header, body) {
             (Some(header), Some(body)) => (header, body),
@@ -290,6 +295,7 @@ impl RpcHandler for GetRawReceipts {
             _ => return Ok(Value::Null),
         };
         let header = storage.get_block_header(block_number)?;
+        check_history_available(storage, block_number)?;
         let body = storage.get_block_body(block_number)?;
         let (header, body) = match (header, body) {
             (Some(header), Some(body)) => (header, body),
@@ -0,0 +1,251 @@
+use crate::{
+    types::{block_identifier::BlockIdentifier, transaction::SendRawTransactionRequest},
+    utils::RpcErr,
+    RpcApiContext, RpcHandler,
+};
+use ethrex_blockchain::{error::ChainError, mempool};
+use ethrex_core::{
+    types::{BlockNumber, Transaction},
+    Address, H256, U256,
+};
+use ethrex_storage::Bundle;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+/// Time between the state block and the simulated one when no timestamp is given
+const SIMULATED_BLOCK_TIME: u64 = 12;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SendBundleRequest {
+    txs: Vec<String>,
+    #[serde(with = "ethrex_core::serde_utils::u64::hex_str")]
+    block_number: BlockNumber,
+    #[serde(default)]
+    min_timestamp: Option<u64>,
+    #[serde(default)]
+    max_timestamp: Option<u64>,
+    #[serde(default)]
+    reverting_tx_hashes: Vec<H256>,
+}
+
+pub struct CallBundleRequest {
+    txs: Vec<String>,
+    state_block: BlockIdentifier,
+    timestamp: Option<u64>,
+    coinbase: Option<Address>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SendBundleResponse {
+    bundle_hash: H256,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CallBundleResponse {
+    bundle_hash: H256,
+    coinbase_diff: U256,
+    results: Vec<CallBundleTxResult>,
+    #[serde(with = "ethrex_core::serde_utils::u64::hex_str")]
+    state_block_number: BlockNumber,
+    #[serde(with = "ethrex_core::serde_utils::u64::hex_str")]
+    total_gas_used: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CallBundleTxResult {
+    tx_hash: H256,
+    coinbase_diff: U256,
+    #[serde(with = "ethrex_core::serde_utils::u64::hex_str")]
+    gas_used: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl RpcHandler for SendBundleRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 1 {
+            return Err(RpcErr::BadParams(format!(
+                "Expected one param and {} were provided",
+                params.len()
+            )));
+        };
+        Ok(serde_json::from_value(params[0].clone())?)
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let bundle = Bundle {
+            transactions: decode_transactions(&self.txs)?,
+            block_number: self.block_number,
+            min_timestamp: self.min_timestamp,
+            max_timestamp: self.max_timestamp,
+            reverting_tx_hashes: self.reverting_tx_hashes.clone(),
+        };
+        let bundle_hash = mempool::add_bundle(bundle, &context.storage)?;
+        info!(
+            "Received bundle {bundle_hash:#x} for block {}",
+            self.block_number
+        );
+        serde_json::to_value(SendBundleResponse { bundle_hash })
+            .map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+impl RpcHandler for CallBundleRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct CallBundleParams {
+            txs: Vec<String>,
+            #[serde(default)]
+            state_block_number: Option<Value>,
+            #[serde(default)]
+            timestamp: Option<u64>,
+            #[serde(default)]
+            coinbase: Option<Address>,
+        }
+
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 1 {
+            return Err(RpcErr::BadParams(format!(
+                "Expected one param and {} were provided",
+                params.len()
+            )));
+        };
+        let call_params: CallBundleParams = serde_json::from_value(params[0].clone())?;
+        let state_block = match call_params.state_block_number {
+            Some(value) => BlockIdentifier::parse(value, 0)?,
+            None => BlockIdentifier::default(),
+        };
+        Ok(Self {
+            txs: call_params.txs,
+            state_block,
+            timestamp: call_params.timestamp,
+            coinbase: call_params.coinbase,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let Some(header) = self.state_block.resolve_block_header(&context.storage)? else {
+            return Err(RpcErr::BadParams(format!(
+                "Unknown state block: {}",
+                self.state_block
+            )));
+        };
+        let bundle = Bundle {
+            transactions: decode_transactions(&self.txs)?,
+            block_number: header.number + 1,
+            min_timestamp: None,
+            max_timestamp: None,
+            reverting_tx_hashes: Vec::new(),
+        };
+        let timestamp = self
+            .timestamp
+            .unwrap_or(header.timestamp + SIMULATED_BLOCK_TIME);
+        // Payments to the coinbase go to the one our blocks would use, or the state block's if
+        // blocks are built elsewhere
+        let coinbase = self.coinbase.unwrap_or_else(|| {
+            context
+                .dev_chain
+                .as_ref()
+                .map_or(header.coinbase, |dev_chain| dev_chain.coinbase())
+        });
+        info!(
+            "Simulating bundle {:#x} on top of block {}",
+            bundle.hash(),
+            header.number
+        );
+        let results = match context.blockchain.call_bundle(
+            &bundle,
+            header.compute_block_hash(),
+            timestamp,
+            coinbase,
+        ) {
+            Ok(results) => results,
+            Err(ChainError::EvmError(error)) => return Err(error.into()),
+            Err(ChainError::InvalidBlock(error)) => {
+                return Err(RpcErr::BadParams(error.to_string()))
+            }
+            Err(error) => return Err(RpcErr::Internal(error.to_string())),
+        };
+        let response = CallBundleResponse {
+            bundle_hash: bundle.hash(),
+            coinbase_diff: results.iter().fold(U256::zero(), |total, result| {
+                total + result.coinbase_payment
+            }),
+            total_gas_used: results.iter().map(|result| result.gas_used).sum(),
+            state_block_number: header.number,
+            results: results
+                .into_iter()
+                .map(|result| CallBundleTxResult {
+                    tx_hash: result.tx_hash,
+                    coinbase_diff: result.coinbase_payment,
+                    gas_used: result.gas_used,
+                    error: (!result.succeeded).then(|| "execution reverted".to_string()),
+                })
+                .collect(),
+        };
+        serde_json::to_value(response).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+/// Decodes the bundle's raw transactions, which can't be blob transactions
+fn decode_transactions(raw_transactions: &[String]) -> Result<Vec<Transaction>, RpcErr> {
+    raw_transactions
+        .iter()
+        .map(|raw_transaction| {
+            let data = raw_transaction.strip_prefix("0x").ok_or(RpcErr::BadParams(
+                "Transactions are not 0x prefixed".to_owned(),
+            ))?;
+            let data = hex::decode(data).map_err(|error| RpcErr::BadParams(error.to_string()))?;
+            match SendRawTransactionRequest::decode_canonical(&data)
+                .map_err(|error| RpcErr::BadParams(error.to_string()))?
+            {
+                SendRawTransactionRequest::EIP4844(_) => Err(RpcErr::BadParams(
+                    "Blob transactions are not supported in bundles".to_owned(),
+                )),
+                transaction => Ok(transaction.to_transaction()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parse_send_bundle_request() {
+        let params = Some(vec![json!({
+            "txs": [],
+            "blockNumber": "0x10",
+            "minTimestamp": 100,
+            "revertingTxHashes": [H256::repeat_byte(1)]
+        })]);
+        let request = SendBundleRequest::parse(&params).unwrap();
+        assert_eq!(request.block_number, 16);
+        assert_eq!(request.min_timestamp, Some(100));
+        assert_eq!(request.max_timestamp, None);
+        assert_eq!(request.reverting_tx_hashes, vec![H256::repeat_byte(1)]);
+    }
+
+    #[test]
+    fn parse_call_bundle_request_defaults_to_latest_state() {
+        let params = Some(vec![json!({ "txs": [], "blockNumber": "0x10" })]);
+        let request = CallBundleRequest::parse(&params).unwrap();
+        assert!(request.txs.is_empty());
+        assert_eq!(request.state_block.to_string(), "Latest");
+        assert_eq!(request.timestamp, None);
+        assert_eq!(request.coinbase, None);
+    }
+}
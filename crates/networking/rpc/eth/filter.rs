@@ -267,6 +267,7 @@ mod tests {
         types::block_identifier::BlockIdentifier,
         utils::{test_utils::example_p2p_node, RpcRequest},
     };
+    use ethrex_blockchain::Blockchain;
     use ethrex_core::types::Genesis;
     use ethrex_net::sync::SyncManager;
     use ethrex_storage::{EngineType, Store};
@@ -432,13 +433,16 @@ mod tests {
         json_req: serde_json::Value,
         filters_pointer: ActiveFilters,
     ) -> u64 {
+        let storage = Store::new("in-mem", EngineType::InMemory)
+            .expect("Fatal: could not create in memory test db");
         let context = RpcApiContext {
-            storage: Store::new("in-mem", EngineType::InMemory)
-                .expect("Fatal: could not create in memory test db"),
+            storage: storage.clone(),
+            blockchain: Arc::new(Blockchain::new(storage)),
             jwt_secret: Default::default(),
             local_p2p_node: example_p2p_node(),
             active_filters: filters_pointer.clone(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            dev_chain: None,
         };
         let request: RpcRequest = serde_json::from_value(json_req).expect("Test json is incorrect");
         let genesis_config: Genesis =
@@ -486,12 +490,15 @@ mod tests {
             ),
         );
         let active_filters = Arc::new(Mutex::new(HashMap::from([filter])));
+        let storage = Store::new("in-mem", EngineType::InMemory).unwrap();
         let context = RpcApiContext {
-            storage: Store::new("in-mem", EngineType::InMemory).unwrap(),
+            storage: storage.clone(),
+            blockchain: Arc::new(Blockchain::new(storage)),
             local_p2p_node: example_p2p_node(),
             jwt_secret: Default::default(),
             active_filters: active_filters.clone(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            dev_chain: None,
         };
 
         map_http_requests(&uninstall_filter_req, context).unwrap();
@@ -506,12 +513,15 @@ mod tests {
     fn removing_non_existing_filter_returns_false() {
         let active_filters = Arc::new(Mutex::new(HashMap::new()));
 
+        let storage = Store::new("in-mem", EngineType::InMemory).unwrap();
         let context = RpcApiContext {
-            storage: Store::new("in-mem", EngineType::InMemory).unwrap(),
+            storage: storage.clone(),
+            blockchain: Arc::new(Blockchain::new(storage)),
             local_p2p_node: example_p2p_node(),
             active_filters: active_filters.clone(),
             jwt_secret: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            dev_chain: None,
         };
         let uninstall_filter_req: RpcRequest = serde_json::from_value(json!(
         {
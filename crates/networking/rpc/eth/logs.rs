@@ -4,9 +4,10 @@
 // - Ethereum's reference: https://ethereum.org/en/developers/docs/apis/json-rpc/#eth_newfilter
 use crate::{
     types::{block_identifier::BlockIdentifier, receipt::RpcLog},
+    utils::check_history_available,
     RpcApiContext, RpcErr, RpcHandler,
 };
-use ethrex_core::{H160, H256};
+use ethrex_core::{types::BlockNumber, H160, H256};
 use ethrex_storage::Store;
 use serde::Deserialize;
 use serde_json::Value;
@@ -117,18 +118,41 @@ pub(crate) fn fetch_logs_with_filter(
     if (from..=to).is_empty() {
         return Err(RpcErr::BadParams("Empty range".to_string()));
     }
+    // Logs from expired blocks are gone, so the range can't be fully served
+    check_history_available(&storage, from)?;
     let address_filter: HashSet<_> = match &filter.address_filters {
         Some(AddressFilter::Single(address)) => std::iter::once(address).collect(),
         Some(AddressFilter::Many(addresses)) => addresses.iter().collect(),
         None => HashSet::new(),
     };
 
+    // The log index, when enabled, narrows down the blocks that may hold matching logs
+    let addresses: Vec<H160> = address_filter.iter().map(|address| **address).collect();
+    let topics: Vec<Vec<H256>> = filter
+        .topics
+        .iter()
+        .map(|topic_filter| match topic_filter {
+            TopicFilter::Topic(topic) => topic.iter().copied().collect(),
+            // A wildcard among the alternatives matches any topic
+            TopicFilter::Topics(sub_topics) => sub_topics
+                .iter()
+                .copied()
+                .collect::<Option<Vec<_>>>()
+                .unwrap_or_default(),
+        })
+        .collect();
+    let block_numbers: Box<dyn Iterator<Item = BlockNumber>> =
+        match storage.get_log_blocks(&addresses, &topics, from, to)? {
+            Some(block_numbers) => Box::new(block_numbers.into_iter()),
+            None => Box::new(from..=to),
+        };
+
     let mut logs: Vec<RpcLog> = Vec::new();
     // The idea here is to fetch every log and filter by address, if given.
     // For that, we'll need each block in range, and its transactions,
     // and for each transaction, we'll need its receipts, which
     // contain the actual logs we want.
-    for block_num in from..=to {
+    for block_num in block_numbers {
         // Take the header of the block, we
         // will use it to access the transactions.
         let block_body = storage
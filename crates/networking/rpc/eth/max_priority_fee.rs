@@ -43,14 +43,17 @@ mod tests {
         utils::{parse_json_hex, test_utils::example_p2p_node, RpcRequest},
         RpcApiContext, RpcHandler,
     };
+    use ethrex_blockchain::Blockchain;
     use ethrex_net::{sync::SyncManager, types::Node};
     use serde_json::{json, Value};
     use std::{net::Ipv4Addr, sync::Arc};
     use tokio::sync::Mutex;
 
     fn default_context() -> RpcApiContext {
+        let storage = setup_store();
         RpcApiContext {
-            storage: setup_store(),
+            storage: storage.clone(),
+            blockchain: Arc::new(Blockchain::new(storage)),
             jwt_secret: Default::default(),
             local_p2p_node: Node {
                 ip: std::net::IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
@@ -60,6 +63,7 @@ mod tests {
             },
             active_filters: Default::default(),
             syncer: Arc::new(Mutex::new(SyncManager::dummy())),
+            dev_chain: None,
         }
     }
 
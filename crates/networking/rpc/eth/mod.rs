@@ -1,9 +1,11 @@
 pub(crate) mod account;
 pub(crate) mod block;
+pub(crate) mod bundle;
 pub(crate) mod client;
 pub(crate) mod fee_market;
 pub(crate) mod filter;
 pub(crate) mod logs;
+pub(crate) mod sign;
 pub(crate) mod transaction;
 
 mod fee_calculator;
@@ -0,0 +1,291 @@
+use ethrex_blockchain::mempool;
+use ethrex_core::{
+    types::{EIP1559Transaction, GenericTransaction, Signable, Transaction},
+    Address,
+};
+use keccak_hash::keccak;
+use secp256k1::{Message, SECP256K1};
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    eth::{fee_calculator::estimate_gas_tip, transaction::EstimateGasRequest},
+    evm::DevAccount,
+    utils::{parse_json_hex, RpcErr},
+    RpcApiContext, RpcHandler,
+};
+
+/// Lists the accounts the node signs for, which are only held by dev chains
+pub struct AccountsRequest;
+
+pub struct SendTransactionRequest {
+    pub transaction: GenericTransaction,
+}
+
+pub struct SignRequest {
+    pub address: Address,
+    pub message: Vec<u8>,
+}
+
+impl RpcHandler for AccountsRequest {
+    fn parse(_params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self)
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let accounts: Vec<Address> = context
+            .dev_chain
+            .iter()
+            .flat_map(|dev_chain| dev_chain.accounts())
+            .map(|account| account.address)
+            .collect();
+        serde_json::to_value(accounts).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+impl RpcHandler for SendTransactionRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 1 {
+            return Err(RpcErr::BadParams(format!(
+                "Expected one param and {} were provided",
+                params.len()
+            )));
+        };
+        Ok(Self {
+            transaction: serde_json::from_value(params[0].clone())?,
+        })
+    }
+
+    /// Fills in the fields missing from the transaction, signs it with the sender's key and adds
+    /// it to the mempool. Always sends EIP-1559 transactions.
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let account = dev_account(&context, self.transaction.from)?;
+        let storage = &context.storage;
+        let chain_id = storage.get_chain_config()?.chain_id;
+        if self
+            .transaction
+            .chain_id
+            .is_some_and(|tx_chain_id| tx_chain_id != chain_id)
+        {
+            return Err(RpcErr::BadParams(format!("Chain id must be {chain_id}")));
+        }
+        if !self.transaction.blob_versioned_hashes.is_empty() {
+            return Err(RpcErr::BadParams(
+                "Blob transactions can't be sent through eth_sendTransaction".to_owned(),
+            ));
+        }
+
+        let mut transaction = self.transaction.clone();
+        let head = context.blockchain.latest_block_header()?;
+        let nonce = match transaction.nonce {
+            Some(nonce) => nonce,
+            None => match mempool::get_nonce(&account.address, storage)? {
+                Some(nonce) => nonce,
+                None => storage
+                    .get_nonce_by_account_address(head.number, account.address)?
+                    .unwrap_or_default(),
+            },
+        };
+        transaction.nonce = Some(nonce);
+        let gas_limit = match transaction.gas {
+            Some(gas) => gas,
+            None => {
+                let estimate = EstimateGasRequest {
+                    transaction: transaction.clone(),
+                    block: None,
+                }
+                .handle(context.clone())?;
+                parse_json_hex(&estimate).map_err(RpcErr::Internal)?
+            }
+        };
+        let legacy_gas_price = (transaction.gas_price != 0).then_some(transaction.gas_price);
+        let max_priority_fee_per_gas =
+            match transaction.max_priority_fee_per_gas.or(legacy_gas_price) {
+                Some(fee) => fee,
+                None => estimate_gas_tip(storage)?.unwrap_or_default(),
+            };
+        // Leave room for the base fee to double before the transaction is included
+        let max_fee_per_gas = transaction
+            .max_fee_per_gas
+            .or(legacy_gas_price)
+            .unwrap_or(head.base_fee_per_gas.unwrap_or_default() * 2 + max_priority_fee_per_gas);
+
+        let mut signed = EIP1559Transaction {
+            chain_id,
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit,
+            to: transaction.to,
+            value: transaction.value,
+            data: transaction.input,
+            access_list: transaction
+                .access_list
+                .into_iter()
+                .map(|entry| (entry.address, entry.storage_keys))
+                .collect(),
+            ..Default::default()
+        };
+        signed.sign_inplace(&account.secret_key);
+        info!("Signed transaction from {:#x}", account.address);
+
+        let hash = mempool::add_local_transaction(
+            Transaction::EIP1559Transaction(signed),
+            storage,
+            context.blockchain.events(),
+        )?;
+        serde_json::to_value(format!("{:#x}", hash))
+            .map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+impl RpcHandler for SignRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 2 {
+            return Err(RpcErr::BadParams("Expected 2 params".to_owned()));
+        };
+        let message = serde_json::from_value::<String>(params[1].clone())?;
+        let message = message.strip_prefix("0x").ok_or(RpcErr::BadHexFormat(1))?;
+        Ok(Self {
+            address: serde_json::from_value(params[0].clone())?,
+            message: hex::decode(message).map_err(|error| RpcErr::BadParams(error.to_string()))?,
+        })
+    }
+
+    /// Signs the message as described in EIP-191, returning the signature with its recovery id
+    /// as the last byte
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let account = dev_account(&context, self.address)?;
+        let mut prefixed =
+            format!("\x19Ethereum Signed Message:\n{}", self.message.len()).into_bytes();
+        prefixed.extend(&self.message);
+        let digest = Message::from_digest(keccak(prefixed).0);
+        let (recovery_id, signature) = SECP256K1
+            .sign_ecdsa_recoverable(&digest, &account.secret_key)
+            .serialize_compact();
+
+        let mut signature = signature.to_vec();
+        signature.push(27 + recovery_id.to_i32() as u8);
+        serde_json::to_value(format!("0x{}", hex::encode(signature)))
+            .map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+fn dev_account(context: &RpcApiContext, address: Address) -> Result<DevAccount, RpcErr> {
+    context
+        .dev_chain
+        .as_ref()
+        .and_then(|dev_chain| dev_chain.account(address))
+        .cloned()
+        .ok_or(RpcErr::BadParams(format!("Unknown account {address:#x}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::{self, call, TEST_GENESIS};
+    use ethrex_core::types::{Genesis, GenesisAccount};
+    use ethrex_core::U256;
+    use secp256k1::{ecdsa::RecoverableSignature, SecretKey};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn setup(with_dev_chain: bool) -> (RpcApiContext, DevAccount) {
+        let account = DevAccount::new(SecretKey::from_slice(&[0x11; 32]).unwrap());
+        let mut genesis: Genesis = serde_json::from_str(TEST_GENESIS).unwrap();
+        genesis.alloc.insert(
+            account.address,
+            GenesisAccount {
+                code: Default::default(),
+                storage: HashMap::new(),
+                balance: U256::from(10).pow(U256::from(21)),
+                nonce: 0,
+            },
+        );
+        let context = test_utils::context(genesis, with_dev_chain.then(|| vec![account.clone()]));
+        (context, account)
+    }
+
+    #[test]
+    fn accounts_are_only_listed_on_dev_chains() {
+        let (context, account) = setup(true);
+        assert_eq!(
+            call(&context, "eth_accounts", json!([])).unwrap(),
+            json!([account.address])
+        );
+        let (context, _) = setup(false);
+        assert_eq!(
+            call(&context, "eth_accounts", json!([])).unwrap(),
+            json!([])
+        );
+    }
+
+    #[test]
+    fn sign_recovers_to_the_account() {
+        let (context, account) = setup(true);
+        let signature = call(&context, "eth_sign", json!([account.address, "0xdeadbeef"])).unwrap();
+        let signature = hex::decode(signature.as_str().unwrap().trim_start_matches("0x")).unwrap();
+        assert_eq!(signature.len(), 65);
+
+        let mut prefixed = b"\x19Ethereum Signed Message:\n4".to_vec();
+        prefixed.extend([0xde, 0xad, 0xbe, 0xef]);
+        let recovery_id =
+            secp256k1::ecdsa::RecoveryId::from_i32(signature[64] as i32 - 27).unwrap();
+        let signature = RecoverableSignature::from_compact(&signature[..64], recovery_id).unwrap();
+        let public_key = SECP256K1
+            .recover_ecdsa(&Message::from_digest(keccak(prefixed).0), &signature)
+            .unwrap();
+        assert_eq!(public_key, account.secret_key.public_key(SECP256K1));
+
+        assert!(matches!(
+            call(&context, "eth_sign", json!([Address::zero(), "0xdeadbeef"])),
+            Err(RpcErr::BadParams(_))
+        ));
+    }
+
+    #[test]
+    fn send_transaction_signs_and_adds_to_the_mempool() {
+        let (context, account) = setup(true);
+        let send = |value: u64| {
+            call(
+                &context,
+                "eth_sendTransaction",
+                json!([{
+                    "from": account.address,
+                    "to": Address::repeat_byte(0x22),
+                    "value": format!("{value:#x}"),
+                    "gas": "0x5208",
+                }]),
+            )
+        };
+        let first: ethrex_core::H256 = serde_json::from_value(send(1).unwrap()).unwrap();
+        let second: ethrex_core::H256 = serde_json::from_value(send(2).unwrap()).unwrap();
+
+        for (nonce, hash) in [first, second].into_iter().enumerate() {
+            let (pooled_hash, transaction) = context
+                .storage
+                .get_pool_transaction_by_nonce(account.address, nonce as u64)
+                .unwrap()
+                .unwrap();
+            assert_eq!(pooled_hash, hash);
+            assert_eq!(transaction.sender(), account.address);
+            assert_eq!(transaction.chain_id(), Some(9));
+        }
+
+        let (context, account) = setup(false);
+        assert!(matches!(
+            call(
+                &context,
+                "eth_sendTransaction",
+                json!([{"from": account.address, "to": Address::zero()}]),
+            ),
+            Err(RpcErr::BadParams(_))
+        ));
+    }
+}
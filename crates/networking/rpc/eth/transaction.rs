@@ -4,7 +4,7 @@ use crate::{
         block_identifier::BlockIdentifier,
         transaction::{RpcTransaction, SendRawTransactionRequest},
     },
-    utils::RpcErr,
+    utils::{check_history_available, RpcErr},
     RpcApiContext, RpcHandler,
 };
 use ethrex_core::{
@@ -141,6 +141,7 @@ impl RpcHandler for GetTransactionByBlockNumberAndIndexRequest {
             Some(block_number) => block_number,
             _ => return Ok(Value::Null),
         };
+        check_history_available(&context.storage, block_number)?;
         let block_body = match context.storage.get_block_body(block_number)? {
             Some(block_body) => block_body,
             _ => return Ok(Value::Null),
@@ -192,6 +193,7 @@ impl RpcHandler for GetTransactionByBlockHashAndIndexRequest {
             Some(number) => number,
             _ => return Ok(Value::Null),
         };
+        check_history_available(&context.storage, block_number)?;
         let block_body = match context.storage.get_block_body(block_number)? {
             Some(block_body) => block_body,
             _ => return Ok(Value::Null),
@@ -590,13 +592,18 @@ impl RpcHandler for SendRawTransactionRequest {
     }
     fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
         let hash = if let SendRawTransactionRequest::EIP4844(wrapped_blob_tx) = self {
-            mempool::add_blob_transaction(
+            mempool::add_local_blob_transaction(
                 wrapped_blob_tx.tx.clone(),
                 wrapped_blob_tx.blobs_bundle.clone(),
                 &context.storage,
+                context.blockchain.events(),
             )
         } else {
-            mempool::add_transaction(self.to_transaction(), &context.storage)
+            mempool::add_local_transaction(
+                self.to_transaction(),
+                &context.storage,
+                context.blockchain.events(),
+            )
         }?;
         serde_json::to_value(format!("{:#x}", hash))
             .map_err(|error| RpcErr::Internal(error.to_string()))
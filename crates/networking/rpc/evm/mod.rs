@@ -0,0 +1,473 @@
+use std::{
+    sync::{Arc, Mutex, MutexGuard},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethrex_blockchain::{payload::BuildPayloadArgs, Blockchain};
+use ethrex_core::{
+    types::{BlockHash, BlockHeader},
+    Address, H256,
+};
+use keccak_hash::keccak;
+use secp256k1::SecretKey;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    utils::{parse_json_hex, RpcErr},
+    RpcApiContext, RpcHandler,
+};
+
+/// Seals blocks on top of the canonical head as soon as they are asked for, without a consensus
+/// client. Used by development chains, both to produce blocks and to serve the `evm` namespace.
+#[derive(Debug)]
+pub struct DevChain {
+    blockchain: Arc<Blockchain>,
+    coinbase: Address,
+    accounts: Vec<DevAccount>,
+    clock: Mutex<DevClock>,
+}
+
+/// An account whose key is held by the node, which signs transactions and messages for it
+#[derive(Debug, Clone)]
+pub struct DevAccount {
+    pub address: Address,
+    pub secret_key: SecretKey,
+}
+
+impl DevAccount {
+    pub fn new(secret_key: SecretKey) -> Self {
+        let public_key = secret_key
+            .public_key(secp256k1::SECP256K1)
+            .serialize_uncompressed();
+        let address = Address::from_slice(&keccak(&public_key[1..]).as_bytes()[12..]);
+        Self {
+            address,
+            secret_key,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct DevClock {
+    /// Seconds added to the system time to get the timestamp of the next block
+    offset: u64,
+    /// Timestamp set for the next block, overriding the offset
+    next_timestamp: Option<u64>,
+    /// Saved by `evm_snapshot`, in the order they were taken
+    snapshots: Vec<DevSnapshot>,
+    /// Id of the next snapshot. Ids are never reused, even after reverting
+    next_snapshot_id: u64,
+}
+
+/// State of the dev chain saved by `evm_snapshot`
+#[derive(Debug, Clone, Copy)]
+struct DevSnapshot {
+    id: u64,
+    head: BlockHash,
+    /// Clock offset at the time, restored along with the head as other dev nodes do
+    offset: u64,
+}
+
+impl DevChain {
+    pub fn new(blockchain: Arc<Blockchain>, coinbase: Address) -> Self {
+        Self {
+            blockchain,
+            coinbase,
+            accounts: Vec::new(),
+            clock: Mutex::new(DevClock::default()),
+        }
+    }
+
+    /// Sets the accounts the node signs for
+    pub fn with_accounts(mut self, accounts: Vec<DevAccount>) -> Self {
+        self.accounts = accounts;
+        self
+    }
+
+    pub fn blockchain(&self) -> &Arc<Blockchain> {
+        &self.blockchain
+    }
+
+    /// Fee recipient of the sealed blocks
+    pub fn coinbase(&self) -> Address {
+        self.coinbase
+    }
+
+    pub fn accounts(&self) -> &[DevAccount] {
+        &self.accounts
+    }
+
+    pub fn account(&self, address: Address) -> Option<&DevAccount> {
+        self.accounts
+            .iter()
+            .find(|account| account.address == address)
+    }
+
+    /// Seals a block with the pending transactions of the mempool and makes it the head.
+    /// Unless a timestamp is given, the one set by `evm_setNextBlockTimestamp` is used, or else
+    /// the system time moved by `evm_increaseTime`. Blocks are at least one second apart.
+    pub fn mine(&self, timestamp: Option<u64>) -> Result<BlockHeader, RpcErr> {
+        // The clock stays locked while sealing, so blocks are sealed one at a time
+        let mut clock = self.clock()?;
+        self.seal(&mut clock, timestamp)
+    }
+
+    /// Seals a block as [`DevChain::mine`] does, but only if there are pending transactions
+    pub fn mine_pending(&self) -> Result<Option<BlockHeader>, RpcErr> {
+        let mut clock = self.clock()?;
+        let stats = self.blockchain.storage.get_pool_stats()?;
+        if stats.transactions == stats.queued {
+            return Ok(None);
+        }
+        self.seal(&mut clock, None).map(Some)
+    }
+
+    fn seal(&self, clock: &mut DevClock, timestamp: Option<u64>) -> Result<BlockHeader, RpcErr> {
+        let parent = self.blockchain.latest_block_header()?;
+        let timestamp = match timestamp.or(clock.next_timestamp) {
+            Some(timestamp) if timestamp <= parent.timestamp => {
+                return Err(RpcErr::BadParams(format!(
+                    "Timestamp {timestamp} is not after the head's timestamp {}",
+                    parent.timestamp
+                )))
+            }
+            Some(timestamp) => {
+                // Later blocks go on from the given timestamp
+                clock.offset = timestamp.saturating_sub(now()?);
+                timestamp
+            }
+            None => (now()? + clock.offset).max(parent.timestamp + 1),
+        };
+
+        let chain_config = self.blockchain.chain_config()?;
+        let args = BuildPayloadArgs {
+            parent: parent.compute_block_hash(),
+            timestamp,
+            fee_recipient: self.coinbase,
+            random: H256::zero(),
+            withdrawals: chain_config.is_shanghai_activated(timestamp).then(Vec::new),
+            beacon_root: chain_config.is_cancun_activated(timestamp).then(H256::zero),
+            version: 3,
+        };
+        let mut block = self
+            .blockchain
+            .create_payload(&args)
+            .map_err(|error| RpcErr::Internal(error.to_string()))?;
+        self.blockchain
+            .build_payload(&mut block)
+            .map_err(|error| RpcErr::Internal(error.to_string()))?;
+        self.blockchain
+            .add_block(&block)
+            .map_err(|error| RpcErr::Internal(error.to_string()))?;
+        let block_hash = block.hash();
+        self.blockchain
+            .apply_fork_choice(block_hash, block_hash, block_hash)
+            .map_err(|error| RpcErr::Internal(error.to_string()))?;
+        clock.next_timestamp = None;
+
+        info!(
+            "Sealed block {} {block_hash:#x} with {} transactions",
+            block.header.number,
+            block.body.transactions.len()
+        );
+        Ok(block.header)
+    }
+
+    /// Moves the clock forward, returning by how many seconds it is ahead of the system time
+    pub fn increase_time(&self, seconds: u64) -> Result<u64, RpcErr> {
+        let mut clock = self.clock()?;
+        clock.offset = clock.offset.saturating_add(seconds);
+        Ok(clock.offset)
+    }
+
+    /// Sets the timestamp of the next block, which must be after the head's
+    pub fn set_next_block_timestamp(&self, timestamp: u64) -> Result<(), RpcErr> {
+        let mut clock = self.clock()?;
+        let head = self.blockchain.latest_block_header()?;
+        if timestamp <= head.timestamp {
+            return Err(RpcErr::BadParams(format!(
+                "Timestamp {timestamp} is not after the head's timestamp {}",
+                head.timestamp
+            )));
+        }
+        clock.next_timestamp = Some(timestamp);
+        Ok(())
+    }
+
+    /// Saves the current head and clock, returning the id to revert to them
+    pub fn snapshot(&self) -> Result<u64, RpcErr> {
+        let mut clock = self.clock()?;
+        let head = self.blockchain.latest_block_header()?;
+        let snapshot = DevSnapshot {
+            id: clock.next_snapshot_id,
+            head: head.compute_block_hash(),
+            offset: clock.offset,
+        };
+        clock.snapshots.push(snapshot);
+        clock.next_snapshot_id += 1;
+        Ok(snapshot.id)
+    }
+
+    /// Moves the head back to the one saved by the given snapshot and sets the clock as it was.
+    /// The snapshot, and the ones taken after it, can't be used again. The transactions of the
+    /// removed blocks are dropped rather than sealed again. Returns false if there is no such
+    /// snapshot.
+    pub fn revert(&self, id: u64) -> Result<bool, RpcErr> {
+        let mut clock = self.clock()?;
+        let Some(position) = clock
+            .snapshots
+            .iter()
+            .position(|snapshot| snapshot.id == id)
+        else {
+            return Ok(false);
+        };
+        let snapshot = clock.snapshots[position];
+        // Never put back in the mempool, so they can't be sealed again
+        self.blockchain
+            .rewind_head_dropping_transactions(snapshot.head)
+            .map_err(|error| RpcErr::Internal(error.to_string()))?;
+        clock.snapshots.truncate(position);
+        clock.offset = snapshot.offset;
+        clock.next_timestamp = None;
+        info!(
+            "Reverted to snapshot {id}, head is now {:#x}",
+            snapshot.head
+        );
+        Ok(true)
+    }
+
+    fn clock(&self) -> Result<MutexGuard<'_, DevClock>, RpcErr> {
+        self.clock
+            .lock()
+            .map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+fn now() -> Result<u64, RpcErr> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .map_err(|error| RpcErr::Internal(error.to_string()))
+}
+
+/// Parses a quantity given either as a number or as a hex string
+fn parse_quantity(param: &Value) -> Result<u64, RpcErr> {
+    match param {
+        Value::Number(number) => number
+            .as_u64()
+            .ok_or(RpcErr::BadParams(format!("Invalid quantity {number}"))),
+        _ => parse_json_hex(param).map_err(RpcErr::BadParams),
+    }
+}
+
+fn single_param<'a>(params: &'a Option<Vec<Value>>, name: &str) -> Result<&'a Value, RpcErr> {
+    match params.as_deref() {
+        Some([param]) => Ok(param),
+        Some(_) => Err(RpcErr::BadParams("Expected 1 param".to_owned())),
+        None => Err(RpcErr::MissingParam(name.to_owned())),
+    }
+}
+
+fn dev_chain(context: &RpcApiContext) -> Result<&Arc<DevChain>, RpcErr> {
+    context.dev_chain.as_ref().ok_or(RpcErr::Internal(
+        "The node is not running a dev chain".to_owned(),
+    ))
+}
+
+pub struct MineRequest {
+    pub timestamp: Option<u64>,
+}
+
+pub struct IncreaseTimeRequest {
+    pub seconds: u64,
+}
+
+pub struct SetNextBlockTimestampRequest {
+    pub timestamp: u64,
+}
+
+pub struct SnapshotRequest;
+
+pub struct RevertRequest {
+    pub id: u64,
+}
+
+impl RpcHandler for MineRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        let timestamp = match params.as_deref() {
+            None | Some([]) => None,
+            Some([timestamp]) => Some(parse_quantity(timestamp)?),
+            Some(_) => return Err(RpcErr::BadParams("Expected at most 1 param".to_owned())),
+        };
+        Ok(Self { timestamp })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        dev_chain(&context)?.mine(self.timestamp)?;
+        Ok(Value::String("0x0".to_owned()))
+    }
+}
+
+impl RpcHandler for IncreaseTimeRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self {
+            seconds: parse_quantity(single_param(params, "seconds")?)?,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let offset = dev_chain(&context)?.increase_time(self.seconds)?;
+        Ok(Value::from(offset))
+    }
+}
+
+impl RpcHandler for SetNextBlockTimestampRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self {
+            timestamp: parse_quantity(single_param(params, "timestamp")?)?,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        dev_chain(&context)?.set_next_block_timestamp(self.timestamp)?;
+        Ok(Value::Null)
+    }
+}
+
+impl RpcHandler for SnapshotRequest {
+    fn parse(_params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self)
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let id = dev_chain(&context)?.snapshot()?;
+        Ok(Value::String(format!("{id:#x}")))
+    }
+}
+
+impl RpcHandler for RevertRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self {
+            id: parse_quantity(single_param(params, "snapshot id")?)?,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        let reverted = dev_chain(&context)?.revert(self.id)?;
+        Ok(Value::Bool(reverted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::{self, call, TEST_GENESIS};
+    use serde_json::json;
+
+    fn context(with_dev_chain: bool) -> RpcApiContext {
+        test_utils::context(
+            serde_json::from_str(TEST_GENESIS).unwrap(),
+            with_dev_chain.then(Vec::new),
+        )
+    }
+
+    #[test]
+    fn evm_namespace_needs_a_dev_chain() {
+        let context = context(false);
+        assert!(matches!(
+            call(&context, "evm_mine", json!([])),
+            Err(RpcErr::MethodNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn evm_mine_follows_the_clock() {
+        let context = context(true);
+        let genesis = context.blockchain.latest_block_header().unwrap();
+
+        call(&context, "evm_mine", json!([])).unwrap();
+        let block_1 = context.blockchain.latest_block_header().unwrap();
+        assert_eq!(block_1.number, 1);
+        assert_eq!(block_1.parent_hash, genesis.compute_block_hash());
+
+        let offset = call(&context, "evm_increaseTime", json!([3600])).unwrap();
+        assert_eq!(offset, json!(3600));
+        call(&context, "evm_mine", json!([])).unwrap();
+        let block_2 = context.blockchain.latest_block_header().unwrap();
+        assert!(block_2.timestamp >= block_1.timestamp + 3600);
+
+        let next_timestamp = block_2.timestamp + 100;
+        call(
+            &context,
+            "evm_setNextBlockTimestamp",
+            json!([format!("{next_timestamp:#x}")]),
+        )
+        .unwrap();
+        call(&context, "evm_mine", json!([])).unwrap();
+        let block_3 = context.blockchain.latest_block_header().unwrap();
+        assert_eq!(block_3.timestamp, next_timestamp);
+
+        // Timestamps can't go back
+        assert!(call(
+            &context,
+            "evm_setNextBlockTimestamp",
+            json!([block_3.timestamp])
+        )
+        .is_err());
+        assert!(call(&context, "evm_mine", json!([block_3.timestamp])).is_err());
+    }
+
+    #[test]
+    fn evm_revert_goes_back_to_the_snapshot() {
+        let context = context(true);
+        call(&context, "evm_mine", json!([])).unwrap();
+        let snapshot_head = context.blockchain.latest_block_header().unwrap();
+
+        let id = call(&context, "evm_snapshot", json!([])).unwrap();
+        assert_eq!(id, json!("0x0"));
+        call(&context, "evm_increaseTime", json!([3600])).unwrap();
+        call(&context, "evm_mine", json!([])).unwrap();
+        let later_id = call(&context, "evm_snapshot", json!([])).unwrap();
+        assert_eq!(later_id, json!("0x1"));
+        call(&context, "evm_mine", json!([])).unwrap();
+        assert_eq!(context.storage.get_latest_block_number().unwrap(), 3);
+
+        assert_eq!(
+            call(&context, "evm_revert", json!([id])).unwrap(),
+            json!(true)
+        );
+        assert_eq!(
+            context.blockchain.latest_block_header().unwrap(),
+            snapshot_head
+        );
+        assert_eq!(context.storage.get_latest_block_number().unwrap(), 1);
+        assert!(context
+            .storage
+            .get_canonical_block_hash(2)
+            .unwrap()
+            .is_none());
+        // The clock is set back too
+        assert_eq!(
+            call(&context, "evm_increaseTime", json!([0])).unwrap(),
+            json!(0)
+        );
+
+        // A snapshot can only be reverted to once, and the ones taken after it are gone too.
+        // Their ids are not handed out again
+        assert_eq!(
+            call(&context, "evm_revert", json!(["0x0"])).unwrap(),
+            json!(false)
+        );
+        assert_eq!(
+            call(&context, "evm_snapshot", json!([])).unwrap(),
+            json!("0x2")
+        );
+        assert_eq!(
+            call(&context, "evm_revert", json!([later_id])).unwrap(),
+            json!(false)
+        );
+        call(&context, "evm_mine", json!([])).unwrap();
+        assert_eq!(context.storage.get_latest_block_number().unwrap(), 2);
+    }
+}
@@ -0,0 +1,144 @@
+use ethrex_core::{
+    serde_utils,
+    types::{BatchStatus, BlockNumber},
+    H256,
+};
+use ethrex_storage::Store;
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    utils::{parse_json_hex, RpcErr},
+    RpcApiContext, RpcHandler,
+};
+
+/// Stage of an L2 block in its way to being settled on the L1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchState {
+    /// The block has not been committed yet
+    Pending,
+    /// The block was committed to the L1 but its proof has not been verified
+    Committed,
+    /// The block's proof was verified on the L1
+    Verified,
+}
+
+impl BatchState {
+    pub fn from_status(status: Option<&BatchStatus>) -> Self {
+        match status {
+            None => BatchState::Pending,
+            Some(status) if status.verify_tx_hash.is_some() => BatchState::Verified,
+            Some(_) => BatchState::Committed,
+        }
+    }
+
+    /// Returns the state of the given block, as recorded by the proposer
+    pub fn of_block(block_number: BlockNumber, storage: &Store) -> Result<Self, RpcErr> {
+        let status = storage.get_batch_status(block_number)?;
+        Ok(Self::from_status(status.as_ref()))
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchStatusResponse {
+    #[serde(with = "serde_utils::u64::hex_str")]
+    pub block_number: BlockNumber,
+    pub status: BatchState,
+    pub commit_tx_hash: Option<H256>,
+    pub verify_tx_hash: Option<H256>,
+    pub withdrawals_merkle_root: Option<H256>,
+    pub deposit_logs_hash: Option<H256>,
+}
+
+pub struct GetBatchStatusRequest {
+    pub block_number: BlockNumber,
+}
+
+impl RpcHandler for GetBatchStatusRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<GetBatchStatusRequest, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 1 {
+            return Err(RpcErr::BadParams(format!(
+                "Expected one param and {} were provided",
+                params.len()
+            )));
+        };
+        let block_number = parse_json_hex(&params[0]).map_err(|_| RpcErr::BadHexFormat(0))?;
+        Ok(GetBatchStatusRequest { block_number })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!("Requested batch status for block {}", self.block_number);
+        if context
+            .storage
+            .get_block_header(self.block_number)?
+            .is_none()
+        {
+            return Ok(Value::Null);
+        }
+        let status = context.storage.get_batch_status(self.block_number)?;
+        let response = BatchStatusResponse {
+            block_number: self.block_number,
+            status: BatchState::from_status(status.as_ref()),
+            commit_tx_hash: status.as_ref().map(|s| s.commit_tx_hash),
+            verify_tx_hash: status.as_ref().and_then(|s| s.verify_tx_hash),
+            withdrawals_merkle_root: status.as_ref().map(|s| s.withdrawals_merkle_root),
+            deposit_logs_hash: status.as_ref().map(|s| s.deposit_logs_hash),
+        };
+        serde_json::to_value(response).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::{self, call, TEST_GENESIS};
+    use serde_json::json;
+
+    fn get_batch_status(context: &RpcApiContext, block_number: &str) -> Value {
+        call(context, "ethrex_getBatchStatus", json!([block_number])).unwrap()
+    }
+
+    #[test]
+    fn batch_status_follows_proposer_progress() {
+        let context = test_utils::context(serde_json::from_str(TEST_GENESIS).unwrap(), None);
+
+        // Unknown block
+        assert_eq!(get_batch_status(&context, "0x5"), Value::Null);
+
+        // Known block that was not committed
+        let response = get_batch_status(&context, "0x0");
+        assert_eq!(response["status"], "pending");
+        assert_eq!(response["commitTxHash"], Value::Null);
+
+        let commit_tx_hash = H256::random();
+        context
+            .storage
+            .add_batch_status(
+                0,
+                BatchStatus {
+                    block_number: 0,
+                    commit_tx_hash,
+                    withdrawals_merkle_root: H256::zero(),
+                    deposit_logs_hash: H256::zero(),
+                    verify_tx_hash: None,
+                },
+            )
+            .unwrap();
+        let response = get_batch_status(&context, "0x0");
+        assert_eq!(response["status"], "committed");
+        assert_eq!(response["commitTxHash"], format!("{commit_tx_hash:#x}"));
+
+        context
+            .storage
+            .update_batch_verify_tx_hash(0, H256::random())
+            .unwrap();
+        let response = get_batch_status(&context, "0x0");
+        assert_eq!(response["status"], "verified");
+    }
+}
@@ -0,0 +1,87 @@
+use ethrex_core::{
+    serde_utils,
+    types::{BlockNumber, DepositInfo},
+    H256,
+};
+use serde::Serialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{utils::RpcErr, RpcApiContext, RpcHandler};
+
+use super::batch::BatchState;
+
+/// Stage of an L1 deposit in its way to being settled on the L1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DepositState {
+    /// The mint transaction is waiting in the mempool
+    Pending,
+    /// The mint transaction was included in an L2 block that has not been committed
+    Included,
+    /// The block containing the mint transaction was committed to the L1
+    Committed,
+    /// The block containing the mint transaction was verified on the L1
+    Verified,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepositStatusResponse {
+    pub status: DepositState,
+    #[serde(with = "serde_utils::u64::hex_str_opt")]
+    pub l2_block_number: Option<BlockNumber>,
+    #[serde(flatten)]
+    pub deposit: DepositInfo,
+}
+
+pub struct GetDepositStatusRequest {
+    pub l1_transaction_hash: H256,
+}
+
+impl RpcHandler for GetDepositStatusRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<GetDepositStatusRequest, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 1 {
+            return Err(RpcErr::BadParams(format!(
+                "Expected one param and {} were provided",
+                params.len()
+            )));
+        };
+        Ok(GetDepositStatusRequest {
+            l1_transaction_hash: serde_json::from_value(params[0].clone())?,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!(
+            "Requested deposit status for L1 transaction {:#x}",
+            self.l1_transaction_hash
+        );
+        let Some(deposit) = context.storage.get_deposit(self.l1_transaction_hash)? else {
+            return Ok(Value::Null);
+        };
+        let (status, l2_block_number) = match context
+            .storage
+            .get_transaction_location(deposit.l2_tx_hash)?
+        {
+            None => (DepositState::Pending, None),
+            Some((block_number, _, _)) => {
+                let status = match BatchState::of_block(block_number, &context.storage)? {
+                    BatchState::Pending => DepositState::Included,
+                    BatchState::Committed => DepositState::Committed,
+                    BatchState::Verified => DepositState::Verified,
+                };
+                (status, Some(block_number))
+            }
+        };
+        let response = DepositStatusResponse {
+            status,
+            l2_block_number,
+            deposit,
+        };
+        serde_json::to_value(response).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
@@ -0,0 +1,19 @@
+use serde_json::Value;
+use tracing::info;
+
+use crate::{utils::RpcErr, RpcApiContext, RpcHandler};
+
+pub struct GetL1FeeComponents;
+
+impl RpcHandler for GetL1FeeComponents {
+    fn parse(_params: &Option<Vec<Value>>) -> Result<Self, RpcErr> {
+        Ok(Self {})
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!("Requested L1 fee components");
+        // These are updated by the committer each time it sends a commitment
+        let components = context.storage.get_l1_fee_components()?;
+        serde_json::to_value(components).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
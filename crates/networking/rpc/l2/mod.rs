@@ -0,0 +1,7 @@
+//! `ethrex_` namespace, only served by L2 nodes.
+//! These endpoints expose the data the proposer tracks about the interaction with the L1
+//! (commitments, verifications, deposits and fees).
+pub(crate) mod batch;
+pub(crate) mod deposit;
+pub(crate) mod fees;
+pub(crate) mod withdrawal;
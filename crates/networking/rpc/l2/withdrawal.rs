@@ -0,0 +1,38 @@
+use ethrex_core::H256;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{utils::RpcErr, RpcApiContext, RpcHandler};
+
+pub struct GetWithdrawalProofRequest {
+    pub transaction_hash: H256,
+}
+
+impl RpcHandler for GetWithdrawalProofRequest {
+    fn parse(params: &Option<Vec<Value>>) -> Result<GetWithdrawalProofRequest, RpcErr> {
+        let params = params
+            .as_ref()
+            .ok_or(RpcErr::BadParams("No params provided".to_owned()))?;
+        if params.len() != 1 {
+            return Err(RpcErr::BadParams(format!(
+                "Expected one param and {} were provided",
+                params.len()
+            )));
+        };
+        Ok(GetWithdrawalProofRequest {
+            transaction_hash: serde_json::from_value(params[0].clone())?,
+        })
+    }
+
+    fn handle(&self, context: RpcApiContext) -> Result<Value, RpcErr> {
+        info!(
+            "Requested withdrawal proof for transaction {:#x}",
+            self.transaction_hash
+        );
+        // The proof is only available once the withdrawal's block has been committed
+        let proof = context
+            .storage
+            .get_withdrawal_proof(self.transaction_hash)?;
+        serde_json::to_value(proof).map_err(|error| RpcErr::Internal(error.to_string()))
+    }
+}
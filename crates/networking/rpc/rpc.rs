@@ -25,18 +25,25 @@ use eth::{
         GetBlockReceiptsRequest, GetBlockTransactionCountRequest, GetRawBlockRequest,
         GetRawHeaderRequest, GetRawReceipts,
     },
+    bundle::{CallBundleRequest, SendBundleRequest},
     client::{ChainId, Syncing},
     fee_market::FeeHistoryRequest,
     filter::{self, ActiveFilters, DeleteFilterRequest, FilterChangesRequest, NewFilterRequest},
     gas_price::GasPrice,
     logs::LogsFilter,
+    sign::{AccountsRequest, SendTransactionRequest, SignRequest},
     transaction::{
         CallRequest, CreateAccessListRequest, EstimateGasRequest, GetRawTransaction,
         GetTransactionByBlockHashAndIndexRequest, GetTransactionByBlockNumberAndIndexRequest,
         GetTransactionByHashRequest, GetTransactionReceiptRequest,
     },
 };
+use ethrex_blockchain::Blockchain;
 use ethrex_net::sync::SyncManager;
+use evm::{
+    DevChain, IncreaseTimeRequest, MineRequest, RevertRequest, SetNextBlockTimestampRequest,
+    SnapshotRequest,
+};
 use serde_json::Value;
 use std::{
     collections::HashMap,
@@ -56,7 +63,11 @@ mod admin;
 mod authentication;
 pub mod engine;
 mod eth;
+pub mod evm;
+#[cfg(feature = "l2")]
+mod l2;
 mod net;
+mod txpool;
 pub mod types;
 pub mod utils;
 mod web3;
@@ -68,10 +79,13 @@ use ethrex_storage::Store;
 #[derive(Debug, Clone)]
 pub struct RpcApiContext {
     storage: Store,
+    blockchain: Arc<Blockchain>,
     jwt_secret: Bytes,
     local_p2p_node: Node,
     active_filters: ActiveFilters,
     syncer: Arc<TokioMutex<SyncManager>>,
+    /// Only set for development chains, which serve the `evm` namespace
+    dev_chain: Option<Arc<DevChain>>,
 }
 
 trait RpcHandler: Sized {
@@ -97,19 +111,23 @@ pub async fn start_api(
     http_addr: SocketAddr,
     authrpc_addr: SocketAddr,
     storage: Store,
+    blockchain: Arc<Blockchain>,
     jwt_secret: Bytes,
     local_p2p_node: Node,
     syncer: SyncManager,
+    dev_chain: Option<Arc<DevChain>>,
 ) {
     // TODO: Refactor how filters are handled,
     // filters are used by the filters endpoints (eth_newFilter, eth_getFilterChanges, ...etc)
     let active_filters = Arc::new(Mutex::new(HashMap::new()));
     let service_context = RpcApiContext {
         storage: storage.clone(),
+        blockchain,
         jwt_secret,
         local_p2p_node,
         active_filters: active_filters.clone(),
         syncer: Arc::new(TokioMutex::new(syncer)),
+        dev_chain,
     };
 
     // Periodically clean up the active filters for the filters endpoints.
@@ -187,6 +205,10 @@ pub fn map_http_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Val
         Ok(RpcNamespace::Debug) => map_debug_requests(req, context),
         Ok(RpcNamespace::Web3) => map_web3_requests(req, context),
         Ok(RpcNamespace::Net) => map_net_requests(req, context),
+        Ok(RpcNamespace::Txpool) => map_txpool_requests(req, context),
+        Ok(RpcNamespace::Evm) => map_evm_requests(req, context),
+        #[cfg(feature = "l2")]
+        Ok(RpcNamespace::Ethrex) => map_ethrex_requests(req, context),
         _ => Err(RpcErr::MethodNotFound(req.method.clone())),
     }
 }
@@ -240,6 +262,11 @@ pub fn map_eth_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Valu
             FilterChangesRequest::stateful_call(req, context.storage, context.active_filters)
         }
         "eth_sendRawTransaction" => SendRawTransactionRequest::call(req, context),
+        "eth_sendBundle" => SendBundleRequest::call(req, context),
+        "eth_callBundle" => CallBundleRequest::call(req, context),
+        "eth_accounts" => AccountsRequest::call(req, context),
+        "eth_sendTransaction" => SendTransactionRequest::call(req, context),
+        "eth_sign" => SignRequest::call(req, context),
         "eth_getProof" => GetProofRequest::call(req, context),
         "eth_gasPrice" => GasPrice::call(req, context),
         "eth_maxPriorityFeePerGas" => eth::max_priority_fee::MaxPriorityFee::call(req, context),
@@ -299,6 +326,40 @@ pub fn map_net_requests(req: &RpcRequest, contex: RpcApiContext) -> Result<Value
     }
 }
 
+pub fn map_txpool_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
+    match req.method.as_str() {
+        "txpool_status" => txpool::status(req, context),
+        unknown_txpool_method => Err(RpcErr::MethodNotFound(unknown_txpool_method.to_owned())),
+    }
+}
+
+pub fn map_evm_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
+    if context.dev_chain.is_none() {
+        return Err(RpcErr::MethodNotFound(req.method.clone()));
+    }
+    match req.method.as_str() {
+        "evm_mine" => MineRequest::call(req, context),
+        "evm_increaseTime" => IncreaseTimeRequest::call(req, context),
+        "evm_setNextBlockTimestamp" => SetNextBlockTimestampRequest::call(req, context),
+        "evm_snapshot" => SnapshotRequest::call(req, context),
+        "evm_revert" => RevertRequest::call(req, context),
+        unknown_evm_method => Err(RpcErr::MethodNotFound(unknown_evm_method.to_owned())),
+    }
+}
+
+#[cfg(feature = "l2")]
+pub fn map_ethrex_requests(req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
+    match req.method.as_str() {
+        "ethrex_getWithdrawalProof" => {
+            l2::withdrawal::GetWithdrawalProofRequest::call(req, context)
+        }
+        "ethrex_getBatchStatus" => l2::batch::GetBatchStatusRequest::call(req, context),
+        "ethrex_getDepositStatus" => l2::deposit::GetDepositStatusRequest::call(req, context),
+        "ethrex_getL1FeeComponents" => l2::fees::GetL1FeeComponents::call(req, context),
+        unknown_ethrex_method => Err(RpcErr::MethodNotFound(unknown_ethrex_method.to_owned())),
+    }
+}
+
 fn rpc_response<E>(id: RpcRequestId, res: Result<Value, E>) -> Json<Value>
 where
     E: Into<RpcErrorMetadata>,
@@ -348,10 +409,12 @@ mod tests {
         storage.set_chain_config(&example_chain_config()).unwrap();
         let context = RpcApiContext {
             local_p2p_node,
-            storage,
+            storage: storage.clone(),
+            blockchain: Arc::new(Blockchain::new(storage)),
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            dev_chain: None,
         };
         let result = map_http_requests(&request, context);
         let rpc_response = rpc_response(request.id, result);
@@ -386,10 +449,12 @@ mod tests {
         // Process request
         let context = RpcApiContext {
             local_p2p_node,
-            storage,
+            storage: storage.clone(),
+            blockchain: Arc::new(Blockchain::new(storage)),
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            dev_chain: None,
         };
         let result = map_http_requests(&request, context);
         let response = rpc_response(request.id, result);
@@ -416,10 +481,12 @@ mod tests {
         // Process request
         let context = RpcApiContext {
             local_p2p_node,
-            storage,
+            storage: storage.clone(),
+            blockchain: Arc::new(Blockchain::new(storage)),
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            dev_chain: None,
         };
         let result = map_http_requests(&request, context);
         let response =
@@ -475,11 +542,13 @@ mod tests {
             .to_string();
         let local_p2p_node = example_p2p_node();
         let context = RpcApiContext {
-            storage,
+            storage: storage.clone(),
+            blockchain: Arc::new(Blockchain::new(storage)),
             local_p2p_node,
             jwt_secret: Default::default(),
             active_filters: Default::default(),
             syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            dev_chain: None,
         };
         // Process request
         let result = map_http_requests(&request, context);
@@ -0,0 +1,17 @@
+use serde_json::{json, Value};
+
+use crate::{
+    utils::{RpcErr, RpcRequest},
+    RpcApiContext,
+};
+
+/// Returns the amount of pending and queued transactions in the mempool
+pub fn status(_req: &RpcRequest, context: RpcApiContext) -> Result<Value, RpcErr> {
+    let stats = context.storage.get_pool_stats()?;
+    let pending = stats.transactions - stats.queued;
+
+    Ok(json!({
+        "pending": format!("{:#x}", pending),
+        "queued": format!("{:#x}", stats.queued),
+    }))
+}
@@ -1,4 +1,5 @@
-use ethrex_storage::error::StoreError;
+use ethrex_core::types::BlockNumber;
+use ethrex_storage::{error::StoreError, Store};
 use ethrex_vm::EvmError;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -23,6 +24,7 @@ pub enum RpcErr {
     InvalidForkChoiceState(String),
     InvalidPayloadAttributes(String),
     UnknownPayload(String),
+    PrunedHistory,
 }
 
 impl From<RpcErr> for RpcErrorMetadata {
@@ -122,6 +124,12 @@ impl From<RpcErr> for RpcErrorMetadata {
                 data: None,
                 message: format!("Unknown payload: {context}"),
             },
+            // Same as geth's, for blocks whose history was expired as in EIP-4444
+            RpcErr::PrunedHistory => RpcErrorMetadata {
+                code: 4444,
+                data: None,
+                message: "pruned history unavailable".to_string(),
+            },
         }
     }
 }
@@ -150,6 +158,10 @@ pub enum RpcNamespace {
     Debug,
     Web3,
     Net,
+    Txpool,
+    Evm,
+    #[cfg(feature = "l2")]
+    Ethrex,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -178,6 +190,10 @@ impl RpcRequest {
                 "debug" => Ok(RpcNamespace::Debug),
                 "web3" => Ok(RpcNamespace::Web3),
                 "net" => Ok(RpcNamespace::Net),
+                "txpool" => Ok(RpcNamespace::Txpool),
+                "evm" => Ok(RpcNamespace::Evm),
+                #[cfg(feature = "l2")]
+                "ethrex" => Ok(RpcNamespace::Ethrex),
                 _ => Err(RpcErr::MethodNotFound(self.method.clone())),
             }
         } else {
@@ -232,6 +248,15 @@ impl From<EvmError> for RpcErr {
     }
 }
 
+/// Fails with [`RpcErr::PrunedHistory`] if the body and receipts of the given block were deleted
+/// by history expiry
+pub fn check_history_available(storage: &Store, block_number: BlockNumber) -> Result<(), RpcErr> {
+    if storage.is_history_expired(block_number)? {
+        return Err(RpcErr::PrunedHistory);
+    }
+    Ok(())
+}
+
 fn get_message_from_revert_data(_data: &str) -> String {
     // TODO
     // Hive tests are not failing when revert message does not match, but currently it is not matching
@@ -252,15 +277,55 @@ pub fn parse_json_hex(hex: &serde_json::Value) -> Result<u64, String> {
 
 #[cfg(test)]
 pub mod test_utils {
-    use std::{net::SocketAddr, str::FromStr};
+    use std::{net::SocketAddr, str::FromStr, sync::Arc};
 
-    use ethrex_core::H512;
+    use ethrex_blockchain::Blockchain;
+    use ethrex_core::{types::Genesis, Address, H512};
     use ethrex_net::{sync::SyncManager, types::Node};
     use ethrex_storage::{EngineType, Store};
+    use serde_json::{json, Value};
+    use tokio::sync::Mutex as TokioMutex;
+
+    use crate::{
+        evm::{DevAccount, DevChain},
+        map_http_requests, start_api, RpcApiContext,
+    };
 
-    use crate::start_api;
+    use super::{RpcErr, RpcRequest};
 
     pub const TEST_GENESIS: &str = include_str!("../../../test_data/genesis-l1.json");
+
+    /// Builds the context of a node with an in-memory store holding the given genesis.
+    /// If `dev_chain` is given, the node runs a dev chain signing for those accounts
+    pub fn context(genesis: Genesis, dev_chain: Option<Vec<DevAccount>>) -> RpcApiContext {
+        let storage = Store::new("", EngineType::InMemory).expect("Failed to create test DB");
+        storage
+            .add_initial_state(genesis)
+            .expect("Failed to build test genesis");
+        let blockchain = Arc::new(Blockchain::new(storage.clone()));
+        let dev_chain = dev_chain.map(|accounts| {
+            Arc::new(DevChain::new(blockchain.clone(), Address::zero()).with_accounts(accounts))
+        });
+        RpcApiContext {
+            storage,
+            blockchain,
+            jwt_secret: Default::default(),
+            local_p2p_node: example_p2p_node(),
+            active_filters: Default::default(),
+            syncer: Arc::new(TokioMutex::new(SyncManager::dummy())),
+            dev_chain,
+        }
+    }
+
+    /// Handles a request to the given method as the HTTP endpoint would
+    pub fn call(context: &RpcApiContext, method: &str, params: Value) -> Result<Value, RpcErr> {
+        let request: RpcRequest = serde_json::from_value(
+            json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}),
+        )
+        .unwrap();
+        map_http_requests(&request, context.clone())
+    }
+
     pub fn example_p2p_node() -> Node {
         let node_id_1 = H512::from_str("d860a01f9722d78051619d1e2351aba3f43f943f6f00718d1b9baa4101932a1f5011f16bb2b1bb35db20d6fe28fa0bf09636d26a87d31de9ec6203eeedb1f666").unwrap();
         Node {
@@ -291,15 +356,18 @@ pub mod test_utils {
             .add_initial_state(serde_json::from_str(TEST_GENESIS).unwrap())
             .expect("Failed to build test genesis");
 
+        let blockchain = Arc::new(Blockchain::new(storage.clone()));
         let jwt_secret = Default::default();
         let local_p2p_node = example_p2p_node();
         start_api(
             http_addr,
             authrpc_addr,
             storage,
+            blockchain,
             jwt_secret,
             local_p2p_node,
             SyncManager::dummy(),
+            None,
         )
         .await;
     }
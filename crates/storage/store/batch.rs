@@ -0,0 +1,112 @@
+use bytes::Bytes;
+use ethereum_types::{H256, U256};
+use ethrex_core::types::{Block, BlockHash, BlockNumber, Index, Receipt};
+use ethrex_trie::NodeRLP;
+use std::{collections::HashMap, sync::Arc};
+
+use crate::log_index::LogIndexUpdate;
+use crate::snapshot::SnapshotLayer;
+
+/// Writes staged to be committed to the database in a single transaction, so that a crash never
+/// leaves a block partially stored, e.g. a body without its receipts or trie nodes without the
+/// header pointing to them.
+///
+/// Blocks and state changes are staged through the [`Store`](crate::Store), as they depend on
+/// what is already stored, and the batch is committed with [`Store::write_batch`](crate::Store::write_batch).
+/// Writes are applied in the order they were staged.
+#[derive(Debug, Default, Clone)]
+pub struct WriteBatch {
+    pub(crate) state_trie_nodes: StagedTrieNodes,
+    // Storage trie nodes by hashed address
+    pub(crate) storage_trie_nodes: HashMap<H256, StagedTrieNodes>,
+    pub(crate) account_codes: Vec<(H256, Bytes)>,
+    pub(crate) blocks: Vec<StagedBlock>,
+    pub(crate) receipts: Vec<(BlockHash, Vec<Receipt>)>,
+    // Canonical hashes to set, or to unset if None
+    pub(crate) canonical_blocks: Vec<(BlockNumber, Option<BlockHash>)>,
+    pub(crate) latest_block_number: Option<BlockNumber>,
+    pub(crate) finalized_block_number: Option<BlockNumber>,
+    pub(crate) safe_block_number: Option<BlockNumber>,
+    // TODO (#307): Remove TotalDifficulty.
+    pub(crate) latest_total_difficulty: Option<U256>,
+    // Filled in by the store when committing the batch, if the log index is enabled
+    pub(crate) log_index: Vec<LogIndexUpdate>,
+    // Added to the snapshot once the rest of the batch is committed
+    pub(crate) snapshot_layers: Vec<SnapshotLayer>,
+}
+
+/// Encoded trie nodes keyed by their hash. Shared with the tries opened over them, so that
+/// state changes can be staged on top of the ones staged before
+pub(crate) type StagedTrieNodes = Arc<HashMap<Vec<u8>, NodeRLP>>;
+
+#[derive(Debug, Clone)]
+pub(crate) struct StagedBlock {
+    pub hash: BlockHash,
+    pub block: Block,
+    pub total_difficulty: U256,
+}
+
+impl StagedBlock {
+    /// Returns the location of each of the block's transactions, as stored in the database
+    pub fn transaction_locations(&self) -> Vec<(H256, BlockNumber, BlockHash, Index)> {
+        self.block
+            .body
+            .transactions
+            .iter()
+            .enumerate()
+            .map(|(index, transaction)| {
+                (
+                    transaction.compute_hash(),
+                    self.block.header.number,
+                    self.hash,
+                    index as Index,
+                )
+            })
+            .collect()
+    }
+}
+
+impl WriteBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.state_trie_nodes.is_empty()
+            && self.storage_trie_nodes.is_empty()
+            && self.account_codes.is_empty()
+            && self.blocks.is_empty()
+            && self.receipts.is_empty()
+            && self.canonical_blocks.is_empty()
+            && self.latest_block_number.is_none()
+            && self.finalized_block_number.is_none()
+            && self.safe_block_number.is_none()
+            && self.latest_total_difficulty.is_none()
+            && self.log_index.is_empty()
+            && self.snapshot_layers.is_empty()
+    }
+
+    pub fn add_receipts(&mut self, block_hash: BlockHash, receipts: Vec<Receipt>) {
+        self.receipts.push((block_hash, receipts));
+    }
+
+    pub fn set_canonical_block(&mut self, number: BlockNumber, hash: BlockHash) {
+        self.canonical_blocks.push((number, Some(hash)));
+    }
+
+    pub fn unset_canonical_block(&mut self, number: BlockNumber) {
+        self.canonical_blocks.push((number, None));
+    }
+
+    pub fn update_latest_block_number(&mut self, block_number: BlockNumber) {
+        self.latest_block_number = Some(block_number);
+    }
+
+    pub fn update_finalized_block_number(&mut self, block_number: BlockNumber) {
+        self.finalized_block_number = Some(block_number);
+    }
+
+    pub fn update_safe_block_number(&mut self, block_number: BlockNumber) {
+        self.safe_block_number = Some(block_number);
+    }
+}
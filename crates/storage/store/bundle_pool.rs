@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+
+use ethereum_types::H256;
+use ethrex_core::types::{BlockNumber, Transaction};
+use sha3::{Digest as _, Keccak256};
+
+/// Ordered list of transactions that must be included together, in the same order, in a given
+/// block. Every transaction must succeed unless its hash is in `reverting_tx_hashes`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bundle {
+    pub transactions: Vec<Transaction>,
+    /// Number of the only block the bundle can be included in
+    pub block_number: BlockNumber,
+    /// Minimum timestamp of the block including the bundle
+    pub min_timestamp: Option<u64>,
+    /// Maximum timestamp of the block including the bundle
+    pub max_timestamp: Option<u64>,
+    /// Transactions allowed to revert without dropping the bundle
+    pub reverting_tx_hashes: Vec<H256>,
+}
+
+impl Bundle {
+    /// Hash of the concatenated hashes of the bundle's transactions
+    pub fn hash(&self) -> H256 {
+        let mut hasher = Keccak256::new();
+        for transaction in &self.transactions {
+            hasher.update(transaction.compute_hash());
+        }
+        H256::from_slice(&hasher.finalize())
+    }
+
+    /// Returns true if the bundle can be included in a block with the given timestamp
+    pub fn is_valid_at(&self, timestamp: u64) -> bool {
+        self.min_timestamp.unwrap_or(0) <= timestamp
+            && timestamp <= self.max_timestamp.unwrap_or(u64::MAX)
+    }
+
+    /// Returns true if the transaction is allowed to revert
+    pub fn may_revert(&self, tx_hash: &H256) -> bool {
+        self.reverting_tx_hashes.contains(tx_hash)
+    }
+}
+
+/// Bundles waiting to be included by the payload builder, grouped by the block they target and
+/// kept in submission order
+#[derive(Debug, Default)]
+pub struct BundlePool {
+    by_block: BTreeMap<BlockNumber, Vec<Bundle>>,
+}
+
+impl BundlePool {
+    /// Adds the bundle, unless it is already pooled for the same block, and returns its hash
+    pub fn insert(&mut self, bundle: Bundle) -> H256 {
+        let hash = bundle.hash();
+        let bundles = self.by_block.entry(bundle.block_number).or_default();
+        if !bundles.iter().any(|pooled| pooled.hash() == hash) {
+            bundles.push(bundle);
+        }
+        hash
+    }
+
+    /// Returns the amount of bundles targeting the given block
+    pub fn block_bundles(&self, block_number: BlockNumber) -> usize {
+        self.by_block.get(&block_number).map_or(0, Vec::len)
+    }
+
+    /// Returns the bundles that can be included in the block with the given number and timestamp,
+    /// in submission order
+    pub fn bundles_for(&self, block_number: BlockNumber, timestamp: u64) -> Vec<Bundle> {
+        self.by_block
+            .get(&block_number)
+            .map(|bundles| {
+                bundles
+                    .iter()
+                    .filter(|bundle| bundle.is_valid_at(timestamp))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Drops the bundles targeting blocks up to the given number, as they can no longer be
+    /// included
+    pub fn prune(&mut self, block_number: BlockNumber) {
+        self.by_block = self.by_block.split_off(&(block_number + 1));
+    }
+
+    /// Returns the amount of pooled bundles
+    pub fn len(&self) -> usize {
+        self.by_block.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_block.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_core::types::{EIP1559Transaction, TxKind};
+
+    fn transaction(nonce: u64) -> Transaction {
+        Transaction::EIP1559Transaction(EIP1559Transaction {
+            nonce,
+            gas_limit: 21_000,
+            to: TxKind::Call(Default::default()),
+            ..Default::default()
+        })
+    }
+
+    fn bundle(block_number: BlockNumber, nonces: &[u64]) -> Bundle {
+        Bundle {
+            transactions: nonces.iter().copied().map(transaction).collect(),
+            block_number,
+            min_timestamp: None,
+            max_timestamp: None,
+            reverting_tx_hashes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn insert_ignores_duplicated_bundles() {
+        let mut pool = BundlePool::default();
+        let hash = pool.insert(bundle(1, &[0, 1]));
+        assert_eq!(pool.insert(bundle(1, &[0, 1])), hash);
+        assert_ne!(pool.insert(bundle(1, &[1, 0])), hash);
+        // The same transactions may target another block
+        pool.insert(bundle(2, &[0, 1]));
+        assert_eq!(pool.block_bundles(1), 2);
+        assert_eq!(pool.len(), 3);
+    }
+
+    #[test]
+    fn bundles_for_filters_by_block_and_timestamp() {
+        let mut pool = BundlePool::default();
+        let early = Bundle {
+            max_timestamp: Some(10),
+            ..bundle(1, &[0])
+        };
+        let late = Bundle {
+            min_timestamp: Some(20),
+            ..bundle(1, &[1])
+        };
+        pool.insert(early.clone());
+        pool.insert(late.clone());
+        pool.insert(bundle(2, &[2]));
+
+        assert_eq!(pool.bundles_for(1, 5), vec![early]);
+        assert!(pool.bundles_for(1, 15).is_empty());
+        assert_eq!(pool.bundles_for(1, 20), vec![late]);
+        assert!(pool.bundles_for(3, 20).is_empty());
+    }
+
+    #[test]
+    fn prune_drops_bundles_up_to_the_block() {
+        let mut pool = BundlePool::default();
+        pool.insert(bundle(1, &[0]));
+        pool.insert(bundle(2, &[0]));
+        pool.insert(bundle(3, &[0]));
+
+        pool.prune(2);
+        assert_eq!(pool.block_bundles(1), 0);
+        assert_eq!(pool.block_bundles(2), 0);
+        assert_eq!(pool.block_bundles(3), 1);
+        pool.prune(3);
+        assert!(pool.is_empty());
+    }
+}
@@ -1,12 +1,18 @@
 use bytes::Bytes;
-use ethereum_types::{H256, U256};
+use ethereum_types::{Address, H256, U256};
 use ethrex_core::types::{
-    BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index,
-    Receipt, Transaction,
+    AccountState, BatchStatus, BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber,
+    ChainConfig, DepositInfo, Index, L1FeeComponents, Receipt, WithdrawalProof,
 };
 use std::{fmt::Debug, panic::RefUnwindSafe};
 
-use crate::error::StoreError;
+use crate::{
+    batch::WriteBatch,
+    error::StoreError,
+    inspect::{TableEntry, TableStats},
+    pruning::{PruneCursor, ReachableNodes},
+    snapshot::{SnapshotChanges, SnapshotLayer},
+};
 use ethrex_trie::Trie;
 
 pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
@@ -51,9 +57,22 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
         block_hash: BlockHash,
     ) -> Result<Option<BlockHeader>, StoreError>;
 
+    /// Add a block whose parent is not yet known, indexing it by its parent hash
     fn add_pending_block(&self, block: Block) -> Result<(), StoreError>;
     fn get_pending_block(&self, block_hash: BlockHash) -> Result<Option<Block>, StoreError>;
 
+    /// Obtain the pending blocks that are children of the given block
+    fn get_pending_blocks_by_parent(
+        &self,
+        parent_hash: BlockHash,
+    ) -> Result<Vec<Block>, StoreError>;
+
+    /// Obtain all pending blocks
+    fn get_pending_blocks(&self) -> Result<Vec<Block>, StoreError>;
+
+    /// Remove a block from the pending blocks, along with its parent hash index entry
+    fn remove_pending_block(&self, block_hash: BlockHash) -> Result<(), StoreError>;
+
     /// Add block number for a given hash
     fn add_block_number(
         &self,
@@ -123,45 +142,6 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
     /// Obtain account code via code hash
     fn get_account_code(&self, code_hash: H256) -> Result<Option<Bytes>, StoreError>;
 
-    fn get_transaction_by_hash(
-        &self,
-        transaction_hash: H256,
-    ) -> Result<Option<Transaction>, StoreError> {
-        let (_block_number, block_hash, index) =
-            match self.get_transaction_location(transaction_hash)? {
-                Some(location) => location,
-                None => return Ok(None),
-            };
-        self.get_transaction_by_location(block_hash, index)
-    }
-
-    fn get_transaction_by_location(
-        &self,
-        block_hash: H256,
-        index: u64,
-    ) -> Result<Option<Transaction>, StoreError> {
-        let block_body = match self.get_block_body_by_hash(block_hash)? {
-            Some(body) => body,
-            None => return Ok(None),
-        };
-        Ok(index
-            .try_into()
-            .ok()
-            .and_then(|index: usize| block_body.transactions.get(index).cloned()))
-    }
-
-    fn get_block_by_hash(&self, block_hash: BlockHash) -> Result<Option<Block>, StoreError> {
-        let header = match self.get_block_header_by_hash(block_hash)? {
-            Some(header) => header,
-            None => return Ok(None),
-        };
-        let body = match self.get_block_body_by_hash(block_hash)? {
-            Some(body) => body,
-            None => return Ok(None),
-        };
-        Ok(Some(Block::new(header, body)))
-    }
-
     // Get the canonical block hash for a given block number.
     fn get_canonical_block_hash(
         &self,
@@ -232,6 +212,10 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
     // Unsets canonical block for a block number.
     fn unset_canonical_block(&self, number: BlockNumber) -> Result<(), StoreError>;
 
+    /// Commit all the writes in the batch in a single transaction, so either all of them are
+    /// stored or none is
+    fn apply_write_batch(&self, batch: WriteBatch) -> Result<(), StoreError>;
+
     fn add_payload(&self, payload_id: u64, block: Block) -> Result<(), StoreError>;
 
     fn get_payload(
@@ -253,4 +237,153 @@ pub trait StoreEngine: Debug + Send + Sync + RefUnwindSafe {
     fn is_synced(&self) -> Result<bool, StoreError>;
 
     fn update_sync_status(&self, status: bool) -> Result<(), StoreError>;
+
+    /// Store the L1 commitment data of an L2 block, replacing any previous value
+    fn add_batch_status(
+        &self,
+        block_number: BlockNumber,
+        status: BatchStatus,
+    ) -> Result<(), StoreError>;
+
+    /// Obtain the L1 commitment data of an L2 block
+    fn get_batch_status(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Option<BatchStatus>, StoreError>;
+
+    /// Store withdrawal proofs in batch, indexed by the hash of their withdrawal transaction
+    fn add_withdrawal_proofs(&self, proofs: Vec<(H256, WithdrawalProof)>)
+        -> Result<(), StoreError>;
+
+    /// Obtain the withdrawal proof for a withdrawal transaction
+    fn get_withdrawal_proof(
+        &self,
+        transaction_hash: H256,
+    ) -> Result<Option<WithdrawalProof>, StoreError>;
+
+    /// Store an L1 deposit indexed by the hash of the L1 transaction that initiated it
+    fn add_deposit(
+        &self,
+        l1_transaction_hash: H256,
+        deposit: DepositInfo,
+    ) -> Result<(), StoreError>;
+
+    /// Obtain an L1 deposit by the hash of the L1 transaction that initiated it
+    fn get_deposit(&self, l1_transaction_hash: H256) -> Result<Option<DepositInfo>, StoreError>;
+
+    // Update the latest known L1 fee components
+    fn update_l1_fee_components(&self, components: L1FeeComponents) -> Result<(), StoreError>;
+
+    // Obtain the latest known L1 fee components
+    fn get_l1_fee_components(&self) -> Result<Option<L1FeeComponents>, StoreError>;
+
+    /// Delete the state and storage trie nodes that aren't reachable among the next `limit`
+    /// ones from `from`, in a single transaction. Returns the amount of nodes deleted and where
+    /// the sweep goes on from, or `None` once every node was checked
+    fn prune_state_tries(
+        &self,
+        reachable: &ReachableNodes,
+        from: PruneCursor,
+        limit: usize,
+    ) -> Result<(usize, Option<PruneCursor>), StoreError>;
+
+    // Update the earliest block whose state is kept
+    fn update_earliest_state_block_number(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<(), StoreError>;
+
+    // Obtain the earliest block whose state is kept, if the state was ever pruned
+    fn get_earliest_state_block_number(&self) -> Result<Option<BlockNumber>, StoreError>;
+
+    /// Delete the headers, bodies and receipts of the given blocks in a single transaction, once
+    /// they were moved to the freezer. Their block numbers are kept so they can still be found
+    fn remove_frozen_blocks(&self, block_hashes: &[BlockHash]) -> Result<(), StoreError>;
+
+    /// Delete the bodies, receipts and transaction locations of the given blocks and record the
+    /// earliest block whose history is kept, all in a single transaction
+    fn expire_block_history(
+        &self,
+        block_hashes: &[BlockHash],
+        transaction_locations: &[(H256, BlockNumber, BlockHash, Index)],
+        earliest_block: BlockNumber,
+    ) -> Result<(), StoreError>;
+
+    // Obtain the state root of the snapshot's disk layer, if it was generated
+    fn get_snapshot_root(&self) -> Result<Option<H256>, StoreError>;
+
+    /// Obtain an account from the snapshot's disk layer
+    fn get_account_snapshot(
+        &self,
+        hashed_address: H256,
+    ) -> Result<Option<AccountState>, StoreError>;
+
+    /// Obtain a storage value from the snapshot's disk layer
+    fn get_storage_snapshot(
+        &self,
+        hashed_address: H256,
+        hashed_key: H256,
+    ) -> Result<Option<U256>, StoreError>;
+
+    /// Store a snapshot diff layer
+    fn add_snapshot_layer(&self, layer: &SnapshotLayer) -> Result<(), StoreError>;
+
+    /// Obtain all stored snapshot diff layers
+    fn get_snapshot_layers(&self) -> Result<Vec<SnapshotLayer>, StoreError>;
+
+    /// Apply a diff layer's changes to the snapshot's disk layer, set its state root as the disk
+    /// layer's one and delete the diff layers up to its block number, all at once
+    fn flatten_snapshot_layer(&self, layer: &SnapshotLayer) -> Result<(), StoreError>;
+
+    /// Apply changes to the snapshot's disk layer without changing its state root. Used while
+    /// generating it
+    fn write_snapshot_changes(&self, changes: &SnapshotChanges) -> Result<(), StoreError>;
+
+    /// Delete the snapshot's disk layer along with its state root, keeping the diff layers
+    fn clear_snapshot(&self) -> Result<(), StoreError>;
+
+    /// Get the blocks within `from..=to` with logs emitted by `address`, by number and hash, as
+    /// recorded in the log index
+    fn get_address_log_blocks(
+        &self,
+        address: Address,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<Vec<(BlockNumber, BlockHash)>, StoreError>;
+
+    /// Get the blocks within `from..=to` with logs holding `topic`, by number and hash, as
+    /// recorded in the log index
+    fn get_topic_log_blocks(
+        &self,
+        topic: H256,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<Vec<(BlockNumber, BlockHash)>, StoreError>;
+
+    /// Update the lowest block number from which every canonical block is in the log index, or
+    /// unset it if the index can't be relied on
+    fn update_log_index_tail(&self, tail: Option<BlockNumber>) -> Result<(), StoreError>;
+
+    /// Obtain the lowest block number from which every canonical block is in the log index
+    fn get_log_index_tail(&self) -> Result<Option<BlockNumber>, StoreError>;
+
+    /// Update the version of the schema the database is written with
+    fn update_schema_version(&self, version: u64) -> Result<(), StoreError>;
+
+    /// Obtain the version of the schema the database is written with, if it was ever recorded
+    fn get_schema_version(&self) -> Result<Option<u64>, StoreError>;
+
+    /// Obtain the amount of entries of each table and the bytes taken by them
+    fn table_stats(&self) -> Result<Vec<TableStats>, StoreError>;
+
+    /// Obtain up to `limit` entries of the given table in key order, starting from the first one
+    /// whose key is not lower than `start`, if given. If `exact`, just the entries stored under
+    /// `start` are returned
+    fn read_table(
+        &self,
+        table: &str,
+        start: Option<&[u8]>,
+        exact: bool,
+        limit: usize,
+    ) -> Result<Vec<TableEntry>, StoreError>;
 }
@@ -1,12 +1,20 @@
-use crate::error::StoreError;
+use crate::{
+    batch::WriteBatch,
+    error::StoreError,
+    inspect::{TableEntry, TableStats},
+    log_index::{decode_log_index_key, log_index_range, LogIndexUpdate},
+    pruning::{PruneCursor, ReachableNodes},
+    snapshot::{SnapshotChanges, SnapshotLayer},
+};
 use bytes::Bytes;
-use ethereum_types::{H256, U256};
+use ethereum_types::{Address, H256, U256};
 use ethrex_core::types::{
-    BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index, Receipt,
+    AccountState, BatchStatus, BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber,
+    ChainConfig, DepositInfo, Index, L1FeeComponents, Receipt, WithdrawalProof,
 };
 use ethrex_trie::{InMemoryTrieDB, Trie};
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     fmt::Debug,
     sync::{Arc, Mutex, MutexGuard},
 };
@@ -38,6 +46,23 @@ struct StoreInner {
     // Stores local blocks by payload id
     payloads: HashMap<u64, (Block, U256, BlobsBundle, bool)>,
     pending_blocks: HashMap<BlockHash, Block>,
+    // Maps parent hashes to the hashes of their pending children
+    pending_blocks_by_parent: HashMap<BlockHash, Vec<BlockHash>>,
+    // L1 commitment data of L2 blocks
+    batch_statuses: HashMap<BlockNumber, BatchStatus>,
+    // Maps withdrawal transaction hashes to their proofs
+    withdrawal_proofs: HashMap<H256, WithdrawalProof>,
+    // Maps L1 transaction hashes to the deposits they initiated
+    deposits: HashMap<H256, DepositInfo>,
+    // Snapshot disk layer accounts by hashed address
+    account_snapshot: HashMap<H256, AccountState>,
+    // Snapshot disk layer storage by hashed address and hashed key
+    storage_snapshot: HashMap<H256, HashMap<H256, U256>>,
+    // Snapshot diff layers by state root
+    snapshot_layers: HashMap<H256, SnapshotLayer>,
+    // Log index entries by address and by topic, see `log_index_key`
+    address_log_index: BTreeSet<Vec<u8>>,
+    topic_log_index: BTreeSet<Vec<u8>>,
 }
 
 #[derive(Default, Debug)]
@@ -51,6 +76,11 @@ struct ChainData {
     latest_total_difficulty: Option<U256>,
     pending_block_number: Option<BlockNumber>,
     is_synced: bool,
+    l1_fee_components: Option<L1FeeComponents>,
+    earliest_state_block_number: Option<BlockNumber>,
+    snapshot_root: Option<H256>,
+    schema_version: Option<u64>,
+    log_index_tail: Option<BlockNumber>,
 }
 
 impl Store {
@@ -82,9 +112,16 @@ impl StoreEngine for Store {
     }
 
     fn add_pending_block(&self, block: Block) -> Result<(), StoreError> {
-        self.inner()
-            .pending_blocks
-            .insert(block.header.compute_block_hash(), block);
+        let block_hash = block.header.compute_block_hash();
+        let mut store = self.inner();
+        let children = store
+            .pending_blocks_by_parent
+            .entry(block.header.parent_hash)
+            .or_default();
+        if !children.contains(&block_hash) {
+            children.push(block_hash);
+        }
+        store.pending_blocks.insert(block_hash, block);
         Ok(())
     }
 
@@ -92,6 +129,39 @@ impl StoreEngine for Store {
         Ok(self.inner().pending_blocks.get(&block_hash).cloned())
     }
 
+    fn get_pending_blocks_by_parent(
+        &self,
+        parent_hash: BlockHash,
+    ) -> Result<Vec<Block>, StoreError> {
+        let store = self.inner();
+        Ok(store
+            .pending_blocks_by_parent
+            .get(&parent_hash)
+            .into_iter()
+            .flatten()
+            .filter_map(|hash| store.pending_blocks.get(hash).cloned())
+            .collect())
+    }
+
+    fn get_pending_blocks(&self) -> Result<Vec<Block>, StoreError> {
+        Ok(self.inner().pending_blocks.values().cloned().collect())
+    }
+
+    fn remove_pending_block(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        let mut store = self.inner();
+        let Some(block) = store.pending_blocks.remove(&block_hash) else {
+            return Ok(());
+        };
+        let parent_hash = block.header.parent_hash;
+        if let Some(children) = store.pending_blocks_by_parent.get_mut(&parent_hash) {
+            children.retain(|hash| *hash != block_hash);
+            if children.is_empty() {
+                store.pending_blocks_by_parent.remove(&parent_hash);
+            }
+        }
+        Ok(())
+    }
+
     fn add_block_header(
         &self,
         block_hash: BlockHash,
@@ -352,6 +422,86 @@ impl StoreEngine for Store {
         Ok(())
     }
 
+    fn apply_write_batch(&self, batch: WriteBatch) -> Result<(), StoreError> {
+        // Holding the lock for the whole batch makes it atomic to readers
+        let mut store = self.inner();
+        store
+            .state_trie_nodes
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))?
+            .extend(Arc::unwrap_or_clone(batch.state_trie_nodes));
+        for (hashed_address, nodes) in batch.storage_trie_nodes {
+            store
+                .storage_trie_nodes
+                .entry(hashed_address)
+                .or_default()
+                .lock()
+                .map_err(|error| StoreError::Custom(error.to_string()))?
+                .extend(Arc::unwrap_or_clone(nodes));
+        }
+        store.account_codes.extend(batch.account_codes);
+        for staged in batch.blocks {
+            for (transaction_hash, block_number, block_hash, index) in
+                staged.transaction_locations()
+            {
+                store
+                    .transaction_locations
+                    .entry(transaction_hash)
+                    .or_default()
+                    .push((block_number, block_hash, index));
+            }
+            store
+                .block_numbers
+                .insert(staged.hash, staged.block.header.number);
+            store
+                .block_total_difficulties
+                .insert(staged.hash, staged.total_difficulty);
+            store.headers.insert(staged.hash, staged.block.header);
+            store.bodies.insert(staged.hash, staged.block.body);
+        }
+        for (block_hash, receipts) in batch.receipts {
+            let entry = store.receipts.entry(block_hash).or_default();
+            for (index, receipt) in receipts.into_iter().enumerate() {
+                entry.insert(index as u64, receipt);
+            }
+        }
+        for (number, hash) in batch.canonical_blocks {
+            match hash {
+                Some(hash) => store.canonical_hashes.insert(number, hash),
+                None => store.canonical_hashes.remove(&number),
+            };
+        }
+        if let Some(block_number) = batch.latest_block_number {
+            store.chain_data.latest_block_number = Some(block_number);
+        }
+        if let Some(block_number) = batch.finalized_block_number {
+            store.chain_data.finalized_block_number = Some(block_number);
+        }
+        if let Some(block_number) = batch.safe_block_number {
+            store.chain_data.safe_block_number = Some(block_number);
+        }
+        if let Some(total_difficulty) = batch.latest_total_difficulty {
+            store.chain_data.latest_total_difficulty = Some(total_difficulty);
+        }
+        for update in batch.log_index {
+            match update {
+                LogIndexUpdate::Add(index) => {
+                    store.address_log_index.extend(index.address_keys());
+                    store.topic_log_index.extend(index.topic_keys());
+                }
+                LogIndexUpdate::Remove(index) => {
+                    for key in index.address_keys() {
+                        store.address_log_index.remove(&key);
+                    }
+                    for key in index.topic_keys() {
+                        store.topic_log_index.remove(&key);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn add_payload(&self, payload_id: u64, block: Block) -> Result<(), StoreError> {
         self.inner().payloads.insert(
             payload_id,
@@ -433,6 +583,337 @@ impl StoreEngine for Store {
         self.inner().chain_data.is_synced = status;
         Ok(())
     }
+
+    fn add_batch_status(
+        &self,
+        block_number: BlockNumber,
+        status: BatchStatus,
+    ) -> Result<(), StoreError> {
+        self.inner().batch_statuses.insert(block_number, status);
+        Ok(())
+    }
+
+    fn get_batch_status(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Option<BatchStatus>, StoreError> {
+        Ok(self.inner().batch_statuses.get(&block_number).cloned())
+    }
+
+    fn add_withdrawal_proofs(
+        &self,
+        proofs: Vec<(H256, WithdrawalProof)>,
+    ) -> Result<(), StoreError> {
+        self.inner().withdrawal_proofs.extend(proofs);
+        Ok(())
+    }
+
+    fn get_withdrawal_proof(
+        &self,
+        transaction_hash: H256,
+    ) -> Result<Option<WithdrawalProof>, StoreError> {
+        Ok(self
+            .inner()
+            .withdrawal_proofs
+            .get(&transaction_hash)
+            .cloned())
+    }
+
+    fn add_deposit(
+        &self,
+        l1_transaction_hash: H256,
+        deposit: DepositInfo,
+    ) -> Result<(), StoreError> {
+        self.inner().deposits.insert(l1_transaction_hash, deposit);
+        Ok(())
+    }
+
+    fn get_deposit(&self, l1_transaction_hash: H256) -> Result<Option<DepositInfo>, StoreError> {
+        Ok(self.inner().deposits.get(&l1_transaction_hash).cloned())
+    }
+
+    fn update_l1_fee_components(&self, components: L1FeeComponents) -> Result<(), StoreError> {
+        self.inner()
+            .chain_data
+            .l1_fee_components
+            .replace(components);
+        Ok(())
+    }
+
+    fn get_l1_fee_components(&self) -> Result<Option<L1FeeComponents>, StoreError> {
+        Ok(self.inner().chain_data.l1_fee_components)
+    }
+
+    fn prune_state_tries(
+        &self,
+        reachable: &ReachableNodes,
+        from: PruneCursor,
+        limit: usize,
+    ) -> Result<(usize, Option<PruneCursor>), StoreError> {
+        let store = self.inner();
+        match from {
+            PruneCursor::StateNodes(start) => {
+                let mut state_nodes = store
+                    .state_trie_nodes
+                    .lock()
+                    .map_err(|error| StoreError::Custom(error.to_string()))?;
+                let mut keys: Vec<Vec<u8>> = state_nodes
+                    .keys()
+                    .filter(|key| **key >= start)
+                    .cloned()
+                    .collect();
+                keys.sort_unstable();
+                let next = match keys.get(limit) {
+                    Some(key) => PruneCursor::StateNodes(key.clone()),
+                    None => PruneCursor::storage_nodes(),
+                };
+                let mut deleted = 0;
+                for key in keys.iter().take(limit) {
+                    if !reachable.has_state_node(key) {
+                        state_nodes.remove(key);
+                        deleted += 1;
+                    }
+                }
+                Ok((deleted, Some(next)))
+            }
+            PruneCursor::StorageNodes(start_address, start_key) => {
+                let mut addresses: Vec<&H256> = store
+                    .storage_trie_nodes
+                    .keys()
+                    .filter(|hashed_address| **hashed_address >= start_address)
+                    .collect();
+                addresses.sort_unstable();
+                let mut keys = Vec::new();
+                for hashed_address in addresses {
+                    let nodes = store.storage_trie_nodes[hashed_address]
+                        .lock()
+                        .map_err(|error| StoreError::Custom(error.to_string()))?;
+                    let mut account_keys: Vec<Vec<u8>> = nodes
+                        .keys()
+                        .filter(|key| *hashed_address > start_address || **key >= start_key)
+                        .cloned()
+                        .collect();
+                    account_keys.sort_unstable();
+                    keys.extend(account_keys.into_iter().map(|key| (*hashed_address, key)));
+                    if keys.len() > limit {
+                        break;
+                    }
+                }
+                let next = keys.get(limit).map(|(hashed_address, key)| {
+                    PruneCursor::StorageNodes(*hashed_address, key.clone())
+                });
+                let mut deleted = 0;
+                for (hashed_address, key) in keys.iter().take(limit) {
+                    if !reachable.has_storage_node(hashed_address, key) {
+                        store.storage_trie_nodes[hashed_address]
+                            .lock()
+                            .map_err(|error| StoreError::Custom(error.to_string()))?
+                            .remove(key);
+                        deleted += 1;
+                    }
+                }
+                Ok((deleted, next))
+            }
+        }
+    }
+
+    fn update_earliest_state_block_number(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<(), StoreError> {
+        self.inner()
+            .chain_data
+            .earliest_state_block_number
+            .replace(block_number);
+        Ok(())
+    }
+
+    fn get_earliest_state_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        Ok(self.inner().chain_data.earliest_state_block_number)
+    }
+
+    fn remove_frozen_blocks(&self, block_hashes: &[BlockHash]) -> Result<(), StoreError> {
+        let mut store = self.inner();
+        for block_hash in block_hashes {
+            store.headers.remove(block_hash);
+            store.bodies.remove(block_hash);
+            store.receipts.remove(block_hash);
+        }
+        Ok(())
+    }
+
+    fn expire_block_history(
+        &self,
+        block_hashes: &[BlockHash],
+        transaction_locations: &[(H256, BlockNumber, BlockHash, Index)],
+        earliest_block: BlockNumber,
+    ) -> Result<(), StoreError> {
+        let mut store = self.inner();
+        for block_hash in block_hashes {
+            store.bodies.remove(block_hash);
+            store.receipts.remove(block_hash);
+        }
+        for (transaction_hash, _, block_hash, _) in transaction_locations {
+            if let Some(locations) = store.transaction_locations.get_mut(transaction_hash) {
+                locations.retain(|(_, hash, _)| hash != block_hash);
+                if locations.is_empty() {
+                    store.transaction_locations.remove(transaction_hash);
+                }
+            }
+        }
+        store
+            .chain_data
+            .earliest_block_number
+            .replace(earliest_block);
+        Ok(())
+    }
+
+    fn get_snapshot_root(&self) -> Result<Option<H256>, StoreError> {
+        Ok(self.inner().chain_data.snapshot_root)
+    }
+
+    fn get_account_snapshot(
+        &self,
+        hashed_address: H256,
+    ) -> Result<Option<AccountState>, StoreError> {
+        Ok(self.inner().account_snapshot.get(&hashed_address).cloned())
+    }
+
+    fn get_storage_snapshot(
+        &self,
+        hashed_address: H256,
+        hashed_key: H256,
+    ) -> Result<Option<U256>, StoreError> {
+        Ok(self
+            .inner()
+            .storage_snapshot
+            .get(&hashed_address)
+            .and_then(|storage| storage.get(&hashed_key))
+            .copied())
+    }
+
+    fn add_snapshot_layer(&self, layer: &SnapshotLayer) -> Result<(), StoreError> {
+        self.inner()
+            .snapshot_layers
+            .insert(layer.state_root, layer.clone());
+        Ok(())
+    }
+
+    fn get_snapshot_layers(&self) -> Result<Vec<SnapshotLayer>, StoreError> {
+        Ok(self.inner().snapshot_layers.values().cloned().collect())
+    }
+
+    fn flatten_snapshot_layer(&self, layer: &SnapshotLayer) -> Result<(), StoreError> {
+        let mut store = self.inner();
+        store.apply_snapshot_changes(&layer.changes);
+        store.chain_data.snapshot_root = Some(layer.state_root);
+        store
+            .snapshot_layers
+            .retain(|_, stored| stored.number > layer.number);
+        Ok(())
+    }
+
+    fn write_snapshot_changes(&self, changes: &SnapshotChanges) -> Result<(), StoreError> {
+        self.inner().apply_snapshot_changes(changes);
+        Ok(())
+    }
+
+    fn clear_snapshot(&self) -> Result<(), StoreError> {
+        let mut store = self.inner();
+        store.account_snapshot.clear();
+        store.storage_snapshot.clear();
+        store.chain_data.snapshot_root = None;
+        Ok(())
+    }
+
+    fn get_address_log_blocks(
+        &self,
+        address: Address,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<Vec<(BlockNumber, BlockHash)>, StoreError> {
+        let (start, end) = log_index_range(address.as_bytes(), from, to);
+        Ok(self
+            .inner()
+            .address_log_index
+            .range(start..=end)
+            .map(|key| decode_log_index_key(key))
+            .collect())
+    }
+
+    fn get_topic_log_blocks(
+        &self,
+        topic: H256,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<Vec<(BlockNumber, BlockHash)>, StoreError> {
+        let (start, end) = log_index_range(topic.as_bytes(), from, to);
+        Ok(self
+            .inner()
+            .topic_log_index
+            .range(start..=end)
+            .map(|key| decode_log_index_key(key))
+            .collect())
+    }
+
+    fn update_log_index_tail(&self, tail: Option<BlockNumber>) -> Result<(), StoreError> {
+        self.inner().chain_data.log_index_tail = tail;
+        Ok(())
+    }
+
+    fn get_log_index_tail(&self) -> Result<Option<BlockNumber>, StoreError> {
+        Ok(self.inner().chain_data.log_index_tail)
+    }
+
+    fn update_schema_version(&self, version: u64) -> Result<(), StoreError> {
+        self.inner().chain_data.schema_version.replace(version);
+        Ok(())
+    }
+
+    fn get_schema_version(&self) -> Result<Option<u64>, StoreError> {
+        Ok(self.inner().chain_data.schema_version)
+    }
+
+    fn table_stats(&self) -> Result<Vec<TableStats>, StoreError> {
+        Err(StoreError::Custom(
+            "The in-memory engine has no tables to inspect".to_string(),
+        ))
+    }
+
+    fn read_table(
+        &self,
+        _table: &str,
+        _start: Option<&[u8]>,
+        _exact: bool,
+        _limit: usize,
+    ) -> Result<Vec<TableEntry>, StoreError> {
+        Err(StoreError::Custom(
+            "The in-memory engine has no tables to inspect".to_string(),
+        ))
+    }
+}
+
+impl StoreInner {
+    fn apply_snapshot_changes(&mut self, changes: &SnapshotChanges) {
+        for hashed_address in &changes.removed {
+            self.account_snapshot.remove(hashed_address);
+            self.storage_snapshot.remove(hashed_address);
+        }
+        for (hashed_address, account) in &changes.accounts {
+            self.account_snapshot
+                .insert(*hashed_address, account.clone());
+        }
+        for (hashed_address, slots) in &changes.storage {
+            let storage = self.storage_snapshot.entry(*hashed_address).or_default();
+            for (hashed_key, value) in slots {
+                if value.is_zero() {
+                    storage.remove(hashed_key);
+                } else {
+                    storage.insert(*hashed_key, *value);
+                }
+            }
+        }
+    }
 }
 
 impl Debug for Store {
@@ -1,21 +1,26 @@
 use super::api::StoreEngine;
 use super::utils::ChainDataIndex;
+use crate::batch::WriteBatch;
 use crate::error::StoreError;
+use crate::inspect::{Inspect, TableEntry, TableStats};
+use crate::log_index::{decode_log_index_key, log_index_range, LogIndexUpdate};
+use crate::pruning::{PruneCursor, ReachableNodes};
 use crate::rlp::{
     AccountCodeHashRLP, AccountCodeRLP, BlockBodyRLP, BlockHashRLP, BlockHeaderRLP, BlockRLP,
     BlockTotalDifficultyRLP, ReceiptRLP, Rlp, TransactionHashRLP, TupleRLP,
 };
+use crate::snapshot::{SnapshotChanges, SnapshotLayer};
 use anyhow::Result;
 use bytes::Bytes;
-use ethereum_types::{H256, U256};
+use ethereum_types::{Address, H256, U256};
 use ethrex_core::types::{
-    BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index,
-    Receipt, Transaction,
+    AccountState, BatchStatus, BlobsBundle, Block, BlockBody, BlockHash, BlockHeader, BlockNumber,
+    ChainConfig, DepositInfo, Index, L1FeeComponents, Receipt, WithdrawalProof,
 };
 use ethrex_rlp::decode::RLPDecode;
 use ethrex_rlp::encode::RLPEncode;
-use ethrex_trie::{LibmdbxDupsortTrieDB, LibmdbxTrieDB, Trie};
-use libmdbx::orm::{Decodable, Encodable, Table};
+use ethrex_trie::{db::node_hash_to_fixed_size, LibmdbxDupsortTrieDB, LibmdbxTrieDB, Trie};
+use libmdbx::orm::{Decodable, Encodable, Table, UntypedTable};
 use libmdbx::{
     dupsort,
     orm::{table, Database},
@@ -72,12 +77,139 @@ impl Store {
         txn.get::<T>(key).map_err(StoreError::LibmdbxError)
     }
 
+    // Reads the log index entries for `prefix` within the given block range
+    fn read_log_index<T: Table<Key = Vec<u8>>>(
+        &self,
+        prefix: &[u8],
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<Vec<(BlockNumber, BlockHash)>, StoreError> {
+        let (start, end) = log_index_range(prefix, from, to);
+        let txn = self.db.begin_read().map_err(StoreError::LibmdbxError)?;
+        let mut cursor = txn.cursor::<T>().map_err(StoreError::LibmdbxError)?;
+        let blocks = cursor
+            .walk(Some(start))
+            .map(|res| res.map(|(key, _)| key))
+            .take_while(|res| res.as_ref().map_or(true, |key| *key <= end))
+            .map(|res| res.map(|key| decode_log_index_key(&key)))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(StoreError::LibmdbxError)?;
+        Ok(blocks)
+    }
+
+    // Reads up to `limit` entries of a table from `start` on, as stored and decoded. If `exact`,
+    // just the ones under `start`
+    fn read_table_entries<T: Table>(
+        &self,
+        start: Option<&[u8]>,
+        exact: bool,
+        limit: usize,
+    ) -> Result<Vec<TableEntry>, StoreError>
+    where
+        T::Key: Decodable + Inspect,
+        T::Value: Inspect,
+    {
+        let txn = self.db.begin_read().map_err(StoreError::LibmdbxError)?;
+        let cursor = txn
+            .cursor::<UntypedTable<T>>()
+            .map_err(StoreError::LibmdbxError)?;
+        let entries = cursor
+            .walk(start.map(<[u8]>::to_vec))
+            .take_while(|res| {
+                !exact
+                    || res
+                        .as_ref()
+                        .map_or(true, |(key, _)| Some(key.as_slice()) == start)
+            })
+            .take(limit)
+            .map(|res| {
+                res.map(|(key, value)| TableEntry {
+                    decoded_key: inspect_bytes::<T::Key>(&key),
+                    decoded_value: inspect_bytes::<T::Value>(&value),
+                    key,
+                    value,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(StoreError::LibmdbxError)?;
+        Ok(entries)
+    }
+
     fn get_block_hash_by_block_number(
         &self,
         number: BlockNumber,
     ) -> Result<Option<BlockHash>, StoreError> {
         Ok(self.read::<CanonicalBlockHashes>(number)?.map(|a| a.to()))
     }
+
+    // Applies changes to the snapshot's disk layer and, if given a diff layer, sets its state
+    // root and deletes the diff layers up to its block number, in a single transaction
+    fn write_snapshot(
+        &self,
+        changes: &SnapshotChanges,
+        flattened: Option<&SnapshotLayer>,
+    ) -> Result<(), StoreError> {
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        for hashed_address in &changes.removed {
+            txn.delete::<AccountSnapshot>(hashed_address.0.to_vec(), None)
+                .map_err(StoreError::LibmdbxError)?;
+            let prefix = hashed_address.0.to_vec();
+            let keys = txn
+                .cursor::<StorageSnapshot>()
+                .map_err(StoreError::LibmdbxError)?
+                .walk(Some(prefix.clone()))
+                .map(|res| res.map(|(key, _)| key))
+                .take_while(|res| res.as_ref().map_or(true, |key| key.starts_with(&prefix)))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(StoreError::LibmdbxError)?;
+            for key in keys {
+                txn.delete::<StorageSnapshot>(key, None)
+                    .map_err(StoreError::LibmdbxError)?;
+            }
+        }
+        for (hashed_address, account) in &changes.accounts {
+            txn.upsert::<AccountSnapshot>(hashed_address.0.to_vec(), account.encode_to_vec())
+                .map_err(StoreError::LibmdbxError)?;
+        }
+        for (hashed_address, slots) in &changes.storage {
+            for (hashed_key, value) in slots {
+                let key = [hashed_address.0, hashed_key.0].concat();
+                if value.is_zero() {
+                    txn.delete::<StorageSnapshot>(key, None)
+                        .map_err(StoreError::LibmdbxError)?;
+                } else {
+                    txn.upsert::<StorageSnapshot>(key, (*value).into())
+                        .map_err(StoreError::LibmdbxError)?;
+                }
+            }
+        }
+        if let Some(layer) = flattened {
+            txn.upsert::<ChainData>(
+                ChainDataIndex::SnapshotRoot,
+                layer.state_root.encode_to_vec(),
+            )
+            .map_err(StoreError::LibmdbxError)?;
+            let stale_layers = txn
+                .cursor::<SnapshotLayers>()
+                .map_err(StoreError::LibmdbxError)?
+                .walk(None)
+                .map(|res| res.map(|(key, _)| key))
+                .take_while(|res| {
+                    res.as_ref()
+                        .map_or(true, |key| snapshot_layer_number(key) <= layer.number)
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(StoreError::LibmdbxError)?;
+            for key in stale_layers {
+                txn.delete::<SnapshotLayers>(key, None)
+                    .map_err(StoreError::LibmdbxError)?;
+            }
+        }
+        txn.commit().map_err(StoreError::LibmdbxError)
+    }
 }
 
 impl StoreEngine for Store {
@@ -403,45 +535,6 @@ impl StoreEngine for Store {
         )
     }
 
-    fn get_transaction_by_hash(
-        &self,
-        transaction_hash: H256,
-    ) -> Result<Option<Transaction>, StoreError> {
-        let (_block_number, block_hash, index) =
-            match self.get_transaction_location(transaction_hash)? {
-                Some(location) => location,
-                None => return Ok(None),
-            };
-        self.get_transaction_by_location(block_hash, index)
-    }
-
-    fn get_transaction_by_location(
-        &self,
-        block_hash: H256,
-        index: u64,
-    ) -> Result<Option<Transaction>, StoreError> {
-        let block_body = match self.get_block_body_by_hash(block_hash)? {
-            Some(body) => body,
-            None => return Ok(None),
-        };
-        Ok(index
-            .try_into()
-            .ok()
-            .and_then(|index: usize| block_body.transactions.get(index).cloned()))
-    }
-
-    fn get_block_by_hash(&self, block_hash: BlockHash) -> Result<Option<Block>, StoreError> {
-        let header = match self.get_block_header_by_hash(block_hash)? {
-            Some(header) => header,
-            None => return Ok(None),
-        };
-        let body = match self.get_block_body_by_hash(block_hash)? {
-            Some(body) => body,
-            None => return Ok(None),
-        };
-        Ok(Some(Block::new(header, body)))
-    }
-
     fn unset_canonical_block(&self, number: BlockNumber) -> Result<(), StoreError> {
         self.db
             .begin_readwrite()
@@ -451,8 +544,133 @@ impl StoreEngine for Store {
             .map_err(StoreError::LibmdbxError)
     }
 
+    fn apply_write_batch(&self, batch: WriteBatch) -> Result<(), StoreError> {
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        for (node_hash, node) in Arc::unwrap_or_clone(batch.state_trie_nodes) {
+            txn.upsert::<StateTrieNodes>(node_hash, node)
+                .map_err(StoreError::LibmdbxError)?;
+        }
+        for (hashed_address, nodes) in batch.storage_trie_nodes {
+            for (node_hash, node) in Arc::unwrap_or_clone(nodes) {
+                txn.upsert::<StorageTriesNodes>(
+                    (hashed_address.0, node_hash_to_fixed_size(node_hash)),
+                    node,
+                )
+                .map_err(StoreError::LibmdbxError)?;
+            }
+        }
+        for (code_hash, code) in batch.account_codes {
+            txn.upsert::<AccountCodes>(code_hash.into(), code.into())
+                .map_err(StoreError::LibmdbxError)?;
+        }
+        for staged in batch.blocks {
+            for (transaction_hash, block_number, block_hash, index) in
+                staged.transaction_locations()
+            {
+                txn.upsert::<TransactionLocations>(
+                    transaction_hash.into(),
+                    (block_number, block_hash, index).into(),
+                )
+                .map_err(StoreError::LibmdbxError)?;
+            }
+            txn.upsert::<BlockNumbers>(staged.hash.into(), staged.block.header.number)
+                .map_err(StoreError::LibmdbxError)?;
+            txn.upsert::<BlockTotalDifficulties>(
+                staged.hash.into(),
+                staged.total_difficulty.into(),
+            )
+            .map_err(StoreError::LibmdbxError)?;
+            txn.upsert::<Headers>(staged.hash.into(), staged.block.header.into())
+                .map_err(StoreError::LibmdbxError)?;
+            txn.upsert::<Bodies>(staged.hash.into(), staged.block.body.into())
+                .map_err(StoreError::LibmdbxError)?;
+        }
+        for (block_hash, receipts) in batch.receipts {
+            for (index, receipt) in receipts.into_iter().enumerate() {
+                txn.upsert::<Receipts>((block_hash, index as Index).into(), receipt.into())
+                    .map_err(StoreError::LibmdbxError)?;
+            }
+        }
+        for (number, hash) in batch.canonical_blocks {
+            match hash {
+                Some(hash) => txn
+                    .upsert::<CanonicalBlockHashes>(number, hash.into())
+                    .map_err(StoreError::LibmdbxError)?,
+                None => {
+                    txn.delete::<CanonicalBlockHashes>(number, None)
+                        .map_err(StoreError::LibmdbxError)?;
+                }
+            }
+        }
+        if let Some(block_number) = batch.latest_block_number {
+            txn.upsert::<ChainData>(
+                ChainDataIndex::LatestBlockNumber,
+                block_number.encode_to_vec(),
+            )
+            .map_err(StoreError::LibmdbxError)?;
+        }
+        if let Some(block_number) = batch.finalized_block_number {
+            txn.upsert::<ChainData>(
+                ChainDataIndex::FinalizedBlockNumber,
+                block_number.encode_to_vec(),
+            )
+            .map_err(StoreError::LibmdbxError)?;
+        }
+        if let Some(block_number) = batch.safe_block_number {
+            txn.upsert::<ChainData>(
+                ChainDataIndex::SafeBlockNumber,
+                block_number.encode_to_vec(),
+            )
+            .map_err(StoreError::LibmdbxError)?;
+        }
+        if let Some(total_difficulty) = batch.latest_total_difficulty {
+            txn.upsert::<ChainData>(
+                ChainDataIndex::LatestTotalDifficulty,
+                total_difficulty.encode_to_vec(),
+            )
+            .map_err(StoreError::LibmdbxError)?;
+        }
+        for update in batch.log_index {
+            match update {
+                LogIndexUpdate::Add(index) => {
+                    for key in index.address_keys() {
+                        txn.upsert::<AddressLogIndex>(key, Vec::new())
+                            .map_err(StoreError::LibmdbxError)?;
+                    }
+                    for key in index.topic_keys() {
+                        txn.upsert::<TopicLogIndex>(key, Vec::new())
+                            .map_err(StoreError::LibmdbxError)?;
+                    }
+                }
+                LogIndexUpdate::Remove(index) => {
+                    for key in index.address_keys() {
+                        txn.delete::<AddressLogIndex>(key, None)
+                            .map_err(StoreError::LibmdbxError)?;
+                    }
+                    for key in index.topic_keys() {
+                        txn.delete::<TopicLogIndex>(key, None)
+                            .map_err(StoreError::LibmdbxError)?;
+                    }
+                }
+            }
+        }
+        txn.commit().map_err(StoreError::LibmdbxError)
+    }
+
     fn add_pending_block(&self, block: Block) -> Result<(), StoreError> {
-        self.write::<PendingBlocks>(block.header.compute_block_hash().into(), block.into())
+        let block_hash = block.header.compute_block_hash();
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        txn.upsert::<PendingBlocksByParent>(block.header.parent_hash.into(), block_hash.into())
+            .map_err(StoreError::LibmdbxError)?;
+        txn.upsert::<PendingBlocks>(block_hash.into(), block.into())
+            .map_err(StoreError::LibmdbxError)?;
+        txn.commit().map_err(StoreError::LibmdbxError)
     }
 
     fn get_pending_block(&self, block_hash: BlockHash) -> Result<Option<Block>, StoreError> {
@@ -461,6 +679,62 @@ impl StoreEngine for Store {
             .map(|b| b.to()))
     }
 
+    fn get_pending_blocks_by_parent(
+        &self,
+        parent_hash: BlockHash,
+    ) -> Result<Vec<Block>, StoreError> {
+        let txn = self.db.begin_read().map_err(StoreError::LibmdbxError)?;
+        let cursor = txn
+            .cursor::<PendingBlocksByParent>()
+            .map_err(StoreError::LibmdbxError)?;
+        let children = cursor
+            .walk_key(parent_hash.into(), None)
+            .map(|res| res.map(|hash| hash.to()).map_err(StoreError::LibmdbxError))
+            .collect::<Result<Vec<BlockHash>, _>>()?;
+        let mut blocks = Vec::with_capacity(children.len());
+        for hash in children {
+            if let Some(block) = txn
+                .get::<PendingBlocks>(hash.into())
+                .map_err(StoreError::LibmdbxError)?
+            {
+                blocks.push(block.to());
+            }
+        }
+        Ok(blocks)
+    }
+
+    fn get_pending_blocks(&self) -> Result<Vec<Block>, StoreError> {
+        let txn = self.db.begin_read().map_err(StoreError::LibmdbxError)?;
+        let cursor = txn
+            .cursor::<PendingBlocks>()
+            .map_err(StoreError::LibmdbxError)?;
+        cursor
+            .walk(None)
+            .map(|res| {
+                res.map(|(_, block)| block.to())
+                    .map_err(StoreError::LibmdbxError)
+            })
+            .collect()
+    }
+
+    fn remove_pending_block(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        if let Some(block) = txn
+            .get::<PendingBlocks>(block_hash.into())
+            .map_err(StoreError::LibmdbxError)?
+        {
+            let parent_hash = block.to().header.parent_hash;
+            txn.delete::<PendingBlocksByParent>(parent_hash.into(), Some(block_hash.into()))
+                .map_err(StoreError::LibmdbxError)?;
+            txn.delete::<PendingBlocks>(block_hash.into(), None)
+                .map_err(StoreError::LibmdbxError)?;
+        }
+        txn.commit().map_err(StoreError::LibmdbxError)
+    }
+
     fn add_transaction_locations(
         &self,
         locations: Vec<(H256, BlockNumber, BlockHash, Index)>,
@@ -523,6 +797,487 @@ impl StoreEngine for Store {
     fn update_sync_status(&self, status: bool) -> Result<(), StoreError> {
         self.write::<ChainData>(ChainDataIndex::IsSynced, status.encode_to_vec())
     }
+
+    fn add_batch_status(
+        &self,
+        block_number: BlockNumber,
+        status: BatchStatus,
+    ) -> Result<(), StoreError> {
+        self.write::<BatchStatuses>(block_number, status.into())
+    }
+
+    fn get_batch_status(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Option<BatchStatus>, StoreError> {
+        Ok(self.read::<BatchStatuses>(block_number)?.map(|s| s.to()))
+    }
+
+    fn add_withdrawal_proofs(
+        &self,
+        proofs: Vec<(H256, WithdrawalProof)>,
+    ) -> Result<(), StoreError> {
+        let key_values = proofs
+            .into_iter()
+            .map(|(tx_hash, proof)| (tx_hash.into(), proof.into()));
+        self.write_batch::<WithdrawalProofs>(key_values)
+    }
+
+    fn get_withdrawal_proof(
+        &self,
+        transaction_hash: H256,
+    ) -> Result<Option<WithdrawalProof>, StoreError> {
+        Ok(self
+            .read::<WithdrawalProofs>(transaction_hash.into())?
+            .map(|p| p.to()))
+    }
+
+    fn add_deposit(
+        &self,
+        l1_transaction_hash: H256,
+        deposit: DepositInfo,
+    ) -> Result<(), StoreError> {
+        self.write::<Deposits>(l1_transaction_hash.into(), deposit.into())
+    }
+
+    fn get_deposit(&self, l1_transaction_hash: H256) -> Result<Option<DepositInfo>, StoreError> {
+        Ok(self
+            .read::<Deposits>(l1_transaction_hash.into())?
+            .map(|d| d.to()))
+    }
+
+    fn update_l1_fee_components(&self, components: L1FeeComponents) -> Result<(), StoreError> {
+        self.write::<ChainData>(ChainDataIndex::L1FeeComponents, components.encode_to_vec())
+    }
+
+    fn get_l1_fee_components(&self) -> Result<Option<L1FeeComponents>, StoreError> {
+        match self.read::<ChainData>(ChainDataIndex::L1FeeComponents)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(rlp)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn prune_state_tries(
+        &self,
+        reachable: &ReachableNodes,
+        from: PruneCursor,
+        limit: usize,
+    ) -> Result<(usize, Option<PruneCursor>), StoreError> {
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        let (deleted, next) = match from {
+            PruneCursor::StateNodes(start) => {
+                let keys = txn
+                    .cursor::<StateTrieNodes>()
+                    .map_err(StoreError::LibmdbxError)?
+                    .walk(Some(start))
+                    .take(limit + 1)
+                    .map(|res| res.map(|(key, _)| key))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(StoreError::LibmdbxError)?;
+                let next = match keys.get(limit) {
+                    Some(key) => PruneCursor::StateNodes(key.clone()),
+                    None => PruneCursor::storage_nodes(),
+                };
+                let mut deleted = 0;
+                for key in keys.into_iter().take(limit) {
+                    if !reachable.has_state_node(&key) {
+                        txn.delete::<StateTrieNodes>(key, None)
+                            .map_err(StoreError::LibmdbxError)?;
+                        deleted += 1;
+                    }
+                }
+                (deleted, Some(next))
+            }
+            PruneCursor::StorageNodes(start_address, start_key) => {
+                // Keys are the hashed address followed by the node key, so the sweep can go on
+                // from the middle of an account's nodes
+                let mut start = start_address.as_bytes().to_vec();
+                start.extend(start_key);
+                let entries = txn
+                    .cursor::<UntypedTable<StorageTriesNodes>>()
+                    .map_err(StoreError::LibmdbxError)?
+                    .walk(Some(start))
+                    .take(limit + 1)
+                    .map(|res| {
+                        res.and_then(|(key, node)| {
+                            Ok((UntypedTable::<StorageTriesNodes>::decode_key(&key)?, node))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(StoreError::LibmdbxError)?;
+                let next = entries.get(limit).map(|((hashed_address, node_hash), _)| {
+                    PruneCursor::StorageNodes(H256(*hashed_address), node_hash.to_vec())
+                });
+                let mut deleted = 0;
+                for ((hashed_address, node_hash), node) in entries.into_iter().take(limit) {
+                    // Node hashes are stored with their length as the first byte
+                    if node_hash[0] == 32
+                        && !reachable.has_storage_node(&H256(hashed_address), &node_hash[1..])
+                    {
+                        txn.delete::<StorageTriesNodes>((hashed_address, node_hash), Some(node))
+                            .map_err(StoreError::LibmdbxError)?;
+                        deleted += 1;
+                    }
+                }
+                (deleted, next)
+            }
+        };
+        txn.commit().map_err(StoreError::LibmdbxError)?;
+        Ok((deleted, next))
+    }
+
+    fn update_earliest_state_block_number(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<(), StoreError> {
+        self.write::<ChainData>(
+            ChainDataIndex::EarliestStateBlockNumber,
+            block_number.encode_to_vec(),
+        )
+    }
+
+    fn get_earliest_state_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        match self.read::<ChainData>(ChainDataIndex::EarliestStateBlockNumber)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(rlp)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn remove_frozen_blocks(&self, block_hashes: &[BlockHash]) -> Result<(), StoreError> {
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        for block_hash in block_hashes {
+            txn.delete::<Headers>((*block_hash).into(), None)
+                .map_err(StoreError::LibmdbxError)?;
+            txn.delete::<Bodies>((*block_hash).into(), None)
+                .map_err(StoreError::LibmdbxError)?;
+            // Receipts are indexed from zero, the first missing index is past the last one
+            let mut index = 0;
+            while txn
+                .delete::<Receipts>((*block_hash, index).into(), None)
+                .map_err(StoreError::LibmdbxError)?
+            {
+                index += 1;
+            }
+        }
+        txn.commit().map_err(StoreError::LibmdbxError)
+    }
+
+    fn expire_block_history(
+        &self,
+        block_hashes: &[BlockHash],
+        transaction_locations: &[(H256, BlockNumber, BlockHash, Index)],
+        earliest_block: BlockNumber,
+    ) -> Result<(), StoreError> {
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        for block_hash in block_hashes {
+            txn.delete::<Bodies>((*block_hash).into(), None)
+                .map_err(StoreError::LibmdbxError)?;
+            // Receipts are indexed from zero, the first missing index is past the last one
+            let mut index = 0;
+            while txn
+                .delete::<Receipts>((*block_hash, index).into(), None)
+                .map_err(StoreError::LibmdbxError)?
+            {
+                index += 1;
+            }
+        }
+        for (transaction_hash, block_number, block_hash, index) in transaction_locations {
+            txn.delete::<TransactionLocations>(
+                (*transaction_hash).into(),
+                Some((*block_number, *block_hash, *index).into()),
+            )
+            .map_err(StoreError::LibmdbxError)?;
+        }
+        txn.upsert::<ChainData>(
+            ChainDataIndex::EarliestBlockNumber,
+            earliest_block.encode_to_vec(),
+        )
+        .map_err(StoreError::LibmdbxError)?;
+        txn.commit().map_err(StoreError::LibmdbxError)
+    }
+
+    fn get_snapshot_root(&self) -> Result<Option<H256>, StoreError> {
+        match self.read::<ChainData>(ChainDataIndex::SnapshotRoot)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(rlp)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn get_account_snapshot(
+        &self,
+        hashed_address: H256,
+    ) -> Result<Option<AccountState>, StoreError> {
+        match self.read::<AccountSnapshot>(hashed_address.0.to_vec())? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(rlp)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn get_storage_snapshot(
+        &self,
+        hashed_address: H256,
+        hashed_key: H256,
+    ) -> Result<Option<U256>, StoreError> {
+        Ok(self
+            .read::<StorageSnapshot>([hashed_address.0, hashed_key.0].concat())?
+            .map(U256::from))
+    }
+
+    fn add_snapshot_layer(&self, layer: &SnapshotLayer) -> Result<(), StoreError> {
+        self.write::<SnapshotLayers>(
+            snapshot_layer_key(layer.number, layer.state_root),
+            layer.clone().into(),
+        )
+    }
+
+    fn get_snapshot_layers(&self) -> Result<Vec<SnapshotLayer>, StoreError> {
+        let txn = self.db.begin_read().map_err(StoreError::LibmdbxError)?;
+        let mut cursor = txn
+            .cursor::<SnapshotLayers>()
+            .map_err(StoreError::LibmdbxError)?;
+        cursor
+            .walk(None)
+            .map(|res| res.map(|(_, layer)| layer.to()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(StoreError::LibmdbxError)
+    }
+
+    fn flatten_snapshot_layer(&self, layer: &SnapshotLayer) -> Result<(), StoreError> {
+        self.write_snapshot(&layer.changes, Some(layer))
+    }
+
+    fn write_snapshot_changes(&self, changes: &SnapshotChanges) -> Result<(), StoreError> {
+        self.write_snapshot(changes, None)
+    }
+
+    fn clear_snapshot(&self) -> Result<(), StoreError> {
+        let txn = self
+            .db
+            .begin_readwrite()
+            .map_err(StoreError::LibmdbxError)?;
+        let accounts = txn
+            .cursor::<AccountSnapshot>()
+            .map_err(StoreError::LibmdbxError)?
+            .walk(None)
+            .map(|res| res.map(|(key, _)| key))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(StoreError::LibmdbxError)?;
+        for key in accounts {
+            txn.delete::<AccountSnapshot>(key, None)
+                .map_err(StoreError::LibmdbxError)?;
+        }
+        let storage = txn
+            .cursor::<StorageSnapshot>()
+            .map_err(StoreError::LibmdbxError)?
+            .walk(None)
+            .map(|res| res.map(|(key, _)| key))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(StoreError::LibmdbxError)?;
+        for key in storage {
+            txn.delete::<StorageSnapshot>(key, None)
+                .map_err(StoreError::LibmdbxError)?;
+        }
+        txn.delete::<ChainData>(ChainDataIndex::SnapshotRoot, None)
+            .map_err(StoreError::LibmdbxError)?;
+        txn.commit().map_err(StoreError::LibmdbxError)
+    }
+
+    fn get_address_log_blocks(
+        &self,
+        address: Address,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<Vec<(BlockNumber, BlockHash)>, StoreError> {
+        self.read_log_index::<AddressLogIndex>(address.as_bytes(), from, to)
+    }
+
+    fn get_topic_log_blocks(
+        &self,
+        topic: H256,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<Vec<(BlockNumber, BlockHash)>, StoreError> {
+        self.read_log_index::<TopicLogIndex>(topic.as_bytes(), from, to)
+    }
+
+    fn update_log_index_tail(&self, tail: Option<BlockNumber>) -> Result<(), StoreError> {
+        match tail {
+            Some(tail) => {
+                self.write::<ChainData>(ChainDataIndex::LogIndexTail, tail.encode_to_vec())
+            }
+            None => {
+                let txn = self
+                    .db
+                    .begin_readwrite()
+                    .map_err(StoreError::LibmdbxError)?;
+                txn.delete::<ChainData>(ChainDataIndex::LogIndexTail, None)
+                    .map_err(StoreError::LibmdbxError)?;
+                txn.commit().map_err(StoreError::LibmdbxError)
+            }
+        }
+    }
+
+    fn get_log_index_tail(&self) -> Result<Option<BlockNumber>, StoreError> {
+        match self.read::<ChainData>(ChainDataIndex::LogIndexTail)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(rlp)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn update_schema_version(&self, version: u64) -> Result<(), StoreError> {
+        self.write::<ChainData>(ChainDataIndex::SchemaVersion, version.encode_to_vec())
+    }
+
+    fn get_schema_version(&self) -> Result<Option<u64>, StoreError> {
+        match self.read::<ChainData>(ChainDataIndex::SchemaVersion)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(rlp)
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn table_stats(&self) -> Result<Vec<TableStats>, StoreError> {
+        // Taken from the stats MDBX keeps of each table, all within the same transaction
+        let txn = self
+            .db
+            .begin_ro_txn()
+            .map_err(|error| StoreError::LibmdbxError(error.into()))?;
+        let mut stats = [
+            CanonicalBlockHashes::NAME,
+            BlockNumbers::NAME,
+            BlockTotalDifficulties::NAME,
+            Headers::NAME,
+            Bodies::NAME,
+            AccountCodes::NAME,
+            Receipts::NAME,
+            StorageTriesNodes::NAME,
+            TransactionLocations::NAME,
+            ChainData::NAME,
+            StateTrieNodes::NAME,
+            Payloads::NAME,
+            PendingBlocks::NAME,
+            PendingBlocksByParent::NAME,
+            BatchStatuses::NAME,
+            WithdrawalProofs::NAME,
+            Deposits::NAME,
+            AccountSnapshot::NAME,
+            StorageSnapshot::NAME,
+            SnapshotLayers::NAME,
+            AddressLogIndex::NAME,
+            TopicLogIndex::NAME,
+        ]
+        .into_iter()
+        .map(|name| {
+            let stat = txn
+                .open_table(Some(name))
+                .and_then(|table| txn.table_stat(&table))
+                .map_err(|error| StoreError::LibmdbxError(error.into()))?;
+            Ok(TableStats {
+                name: name.to_string(),
+                entries: stat.entries() as u64,
+                size: stat.total_size(),
+            })
+        })
+        .collect::<Result<Vec<_>, StoreError>>()?;
+        stats.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(stats)
+    }
+
+    fn read_table(
+        &self,
+        table: &str,
+        start: Option<&[u8]>,
+        exact: bool,
+        limit: usize,
+    ) -> Result<Vec<TableEntry>, StoreError> {
+        match table {
+            name if name == CanonicalBlockHashes::NAME => {
+                self.read_table_entries::<CanonicalBlockHashes>(start, exact, limit)
+            }
+            name if name == BlockNumbers::NAME => {
+                self.read_table_entries::<BlockNumbers>(start, exact, limit)
+            }
+            name if name == BlockTotalDifficulties::NAME => {
+                self.read_table_entries::<BlockTotalDifficulties>(start, exact, limit)
+            }
+            name if name == Headers::NAME => {
+                self.read_table_entries::<Headers>(start, exact, limit)
+            }
+            name if name == Bodies::NAME => self.read_table_entries::<Bodies>(start, exact, limit),
+            name if name == AccountCodes::NAME => {
+                self.read_table_entries::<AccountCodes>(start, exact, limit)
+            }
+            name if name == Receipts::NAME => {
+                self.read_table_entries::<Receipts>(start, exact, limit)
+            }
+            name if name == StorageTriesNodes::NAME => {
+                self.read_table_entries::<StorageTriesNodes>(start, exact, limit)
+            }
+            name if name == TransactionLocations::NAME => {
+                self.read_table_entries::<TransactionLocations>(start, exact, limit)
+            }
+            name if name == ChainData::NAME => {
+                self.read_table_entries::<ChainData>(start, exact, limit)
+            }
+            name if name == StateTrieNodes::NAME => {
+                self.read_table_entries::<StateTrieNodes>(start, exact, limit)
+            }
+            name if name == Payloads::NAME => {
+                self.read_table_entries::<Payloads>(start, exact, limit)
+            }
+            name if name == PendingBlocks::NAME => {
+                self.read_table_entries::<PendingBlocks>(start, exact, limit)
+            }
+            name if name == PendingBlocksByParent::NAME => {
+                self.read_table_entries::<PendingBlocksByParent>(start, exact, limit)
+            }
+            name if name == BatchStatuses::NAME => {
+                self.read_table_entries::<BatchStatuses>(start, exact, limit)
+            }
+            name if name == WithdrawalProofs::NAME => {
+                self.read_table_entries::<WithdrawalProofs>(start, exact, limit)
+            }
+            name if name == Deposits::NAME => {
+                self.read_table_entries::<Deposits>(start, exact, limit)
+            }
+            name if name == AccountSnapshot::NAME => {
+                self.read_table_entries::<AccountSnapshot>(start, exact, limit)
+            }
+            name if name == StorageSnapshot::NAME => {
+                self.read_table_entries::<StorageSnapshot>(start, exact, limit)
+            }
+            name if name == SnapshotLayers::NAME => {
+                self.read_table_entries::<SnapshotLayers>(start, exact, limit)
+            }
+            name if name == AddressLogIndex::NAME => {
+                self.read_table_entries::<AddressLogIndex>(start, exact, limit)
+            }
+            name if name == TopicLogIndex::NAME => {
+                self.read_table_entries::<TopicLogIndex>(start, exact, limit)
+            }
+            _ => Err(StoreError::UnknownTable(table.to_string())),
+        }
+    }
 }
 
 impl Debug for Store {
@@ -603,6 +1358,65 @@ table!(
     ( PendingBlocks ) BlockHashRLP => BlockRLP
 );
 
+dupsort!(
+    /// Parent hash to the hashes of its pending children.
+    ( PendingBlocksByParent ) BlockHashRLP => BlockHashRLP
+);
+
+// L2 data
+
+table!(
+    /// L2 block number to the data committed to the L1 for it.
+    ( BatchStatuses ) BlockNumber => Rlp<BatchStatus>
+);
+
+table!(
+    /// Withdrawal transaction hash to its withdrawal proof.
+    ( WithdrawalProofs ) TransactionHashRLP => Rlp<WithdrawalProof>
+);
+
+table!(
+    /// L1 transaction hash to the deposit it initiated.
+    ( Deposits ) TransactionHashRLP => Rlp<DepositInfo>
+);
+
+// Snapshot
+
+table!(
+    /// Snapshot disk layer accounts by hashed address.
+    ( AccountSnapshot ) Vec<u8> => Vec<u8>
+);
+
+table!(
+    /// Snapshot disk layer storage values by hashed address followed by hashed key.
+    ( StorageSnapshot ) Vec<u8> => AccountStorageValueBytes
+);
+
+table!(
+    /// Snapshot diff layers by block number followed by state root, so they are sorted by number.
+    ( SnapshotLayers ) Vec<u8> => Rlp<SnapshotLayer>
+);
+
+table!(
+    /// Log index entries by address followed by block number and hash, with empty values.
+    ( AddressLogIndex ) Vec<u8> => Vec<u8>
+);
+
+table!(
+    /// Log index entries by topic followed by block number and hash, with empty values.
+    ( TopicLogIndex ) Vec<u8> => Vec<u8>
+);
+
+fn snapshot_layer_key(number: BlockNumber, state_root: H256) -> Vec<u8> {
+    [number.to_be_bytes().as_slice(), state_root.as_bytes()].concat()
+}
+
+fn snapshot_layer_number(key: &[u8]) -> BlockNumber {
+    key.get(..8)
+        .and_then(|number| number.try_into().ok())
+        .map_or(BlockNumber::MAX, BlockNumber::from_be_bytes)
+}
+
 // Storage values are stored as bytes instead of using their rlp encoding
 // As they are stored in a dupsort table, they need to have a fixed size, and encoding them doesn't preserve their size
 pub struct AccountStorageKeyBytes(pub [u8; 32]);
@@ -668,6 +1482,23 @@ impl Encodable for ChainDataIndex {
     }
 }
 
+impl Decodable for ChainDataIndex {
+    fn decode(b: &[u8]) -> anyhow::Result<Self> {
+        ChainDataIndex::try_from(u32::decode(b)?).map_err(anyhow::Error::msg)
+    }
+}
+
+impl Inspect for AccountStorageValueBytes {
+    fn inspect(&self) -> String {
+        self.0.inspect()
+    }
+}
+
+// Readable form of a key or value given as stored, or its hex encoding if it can't be decoded
+fn inspect_bytes<T: Decodable + Inspect>(bytes: &[u8]) -> String {
+    T::decode(bytes).map_or_else(|_| bytes.inspect(), |decoded| decoded.inspect())
+}
+
 /// Initializes a new database with the provided path. If the path is `None`, the database
 /// will be temporary.
 pub fn init_db(path: Option<impl AsRef<Path>>) -> Database {
@@ -686,6 +1517,15 @@ pub fn init_db(path: Option<impl AsRef<Path>>) -> Database {
         table_info!(CanonicalBlockHashes),
         table_info!(Payloads),
         table_info!(PendingBlocks),
+        table_info!(PendingBlocksByParent),
+        table_info!(BatchStatuses),
+        table_info!(WithdrawalProofs),
+        table_info!(Deposits),
+        table_info!(AccountSnapshot),
+        table_info!(StorageSnapshot),
+        table_info!(SnapshotLayers),
+        table_info!(AddressLogIndex),
+        table_info!(TopicLogIndex),
     ]
     .into_iter()
     .collect();
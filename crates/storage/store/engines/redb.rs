@@ -1,31 +1,48 @@
-use std::{borrow::Borrow, panic::RefUnwindSafe, sync::Arc};
+use std::{borrow::Borrow, panic::RefUnwindSafe, path::Path, sync::Arc};
 
 use ethrex_core::types::BlockBody;
 use ethrex_core::{
-    types::{BlobsBundle, Block, BlockHash, BlockHeader, BlockNumber, ChainConfig, Index, Receipt},
-    H256, U256,
+    types::{
+        AccountState, BatchStatus, BlobsBundle, Block, BlockHash, BlockHeader, BlockNumber,
+        ChainConfig, DepositInfo, Index, L1FeeComponents, Receipt, WithdrawalProof,
+    },
+    Address, H256, U256,
 };
 use ethrex_rlp::decode::RLPDecode;
 use ethrex_rlp::encode::RLPEncode;
 use ethrex_trie::{
-    db::{redb::RedBTrie, redb_multitable::RedBMultiTableTrieDB},
+    db::{
+        node_hash_to_fixed_size,
+        redb::{RedBTrie, STATE_TRIE_NODES_TABLE},
+        redb_multitable::RedBMultiTableTrieDB,
+    },
     Trie,
 };
-use redb::{AccessGuard, Database, Key, MultimapTableDefinition, TableDefinition, TypeName, Value};
+use redb::{
+    AccessGuard, Database, Key, MultimapTableDefinition, MultimapTableHandle,
+    ReadableMultimapTable, ReadableTable, ReadableTableMetadata, TableDefinition, TableHandle,
+    TypeName, Value,
+};
 
 use crate::rlp::{BlockRLP, BlockTotalDifficultyRLP, Rlp, TransactionHashRLP};
 use crate::{
+    batch::WriteBatch,
     error::StoreError,
+    inspect::{Inspect, TableEntry, TableStats},
+    log_index::{decode_log_index_key, log_index_range, LogIndexUpdate},
+    pruning::{PruneCursor, ReachableNodes},
     rlp::{
         AccountCodeHashRLP, AccountCodeRLP, BlockBodyRLP, BlockHashRLP, BlockHeaderRLP, ReceiptRLP,
         TupleRLP,
     },
+    snapshot::{SnapshotChanges, SnapshotLayer},
 };
 
 use super::{api::StoreEngine, utils::ChainDataIndex};
 
-const STATE_TRIE_NODES_TABLE: TableDefinition<&[u8], &[u8]> =
-    TableDefinition::new("StateTrieNodes");
+/// Name of the database file within the data directory
+const DB_FILE_NAME: &str = "ethrex.redb";
+
 const BLOCK_NUMBERS_TABLE: TableDefinition<BlockHashRLP, BlockNumber> =
     TableDefinition::new("BlockNumbers");
 const BLOCK_TOTAL_DIFFICULTIES_TABLE: TableDefinition<BlockHashRLP, BlockTotalDifficultyRLP> =
@@ -48,10 +65,27 @@ const PAYLOADS_TABLE: TableDefinition<BlockNumber, Rlp<(Block, U256, BlobsBundle
     TableDefinition::new("Payloads");
 const PENDING_BLOCKS_TABLE: TableDefinition<BlockHashRLP, BlockRLP> =
     TableDefinition::new("PendingBlocks");
+const PENDING_BLOCKS_BY_PARENT_TABLE: MultimapTableDefinition<BlockHashRLP, BlockHashRLP> =
+    MultimapTableDefinition::new("PendingBlocksByParent");
 const TRANSACTION_LOCATIONS_TABLE: MultimapTableDefinition<
     TransactionHashRLP,
     Rlp<(BlockNumber, BlockHash, Index)>,
 > = MultimapTableDefinition::new("TransactionLocations");
+const BATCH_STATUSES_TABLE: TableDefinition<BlockNumber, Rlp<BatchStatus>> =
+    TableDefinition::new("BatchStatuses");
+const WITHDRAWAL_PROOFS_TABLE: TableDefinition<TransactionHashRLP, Rlp<WithdrawalProof>> =
+    TableDefinition::new("WithdrawalProofs");
+const DEPOSITS_TABLE: TableDefinition<TransactionHashRLP, Rlp<DepositInfo>> =
+    TableDefinition::new("Deposits");
+const ACCOUNT_SNAPSHOT_TABLE: TableDefinition<[u8; 32], &[u8]> =
+    TableDefinition::new("AccountSnapshot");
+const STORAGE_SNAPSHOT_TABLE: TableDefinition<([u8; 32], [u8; 32]), [u8; 32]> =
+    TableDefinition::new("StorageSnapshot");
+const SNAPSHOT_LAYERS_TABLE: TableDefinition<(BlockNumber, [u8; 32]), Rlp<SnapshotLayer>> =
+    TableDefinition::new("SnapshotLayers");
+// Log index entries by address and by topic, see `log_index_key`
+const ADDRESS_LOG_INDEX_TABLE: TableDefinition<&[u8], ()> = TableDefinition::new("AddressLogIndex");
+const TOPIC_LOG_INDEX_TABLE: TableDefinition<&[u8], ()> = TableDefinition::new("TopicLogIndex");
 
 #[derive(Debug)]
 pub struct RedBStore {
@@ -60,9 +94,9 @@ pub struct RedBStore {
 
 impl RefUnwindSafe for RedBStore {}
 impl RedBStore {
-    pub fn new() -> Result<Self, StoreError> {
+    pub fn new(path: &str) -> Result<Self, StoreError> {
         Ok(Self {
-            db: Arc::new(init_db()?),
+            db: Arc::new(init_db(path)?),
         })
     }
 
@@ -84,6 +118,94 @@ impl RedBStore {
         Ok(())
     }
 
+    // Reads the log index entries for `prefix` within the given block range
+    fn read_log_index(
+        &self,
+        table: TableDefinition<&[u8], ()>,
+        prefix: &[u8],
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<Vec<(BlockNumber, BlockHash)>, StoreError> {
+        let (start, end) = log_index_range(prefix, from, to);
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(table)?;
+        let blocks = table
+            .range(start.as_slice()..=end.as_slice())?
+            .map(|entry| entry.map(|(key, _)| decode_log_index_key(key.value())))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(blocks)
+    }
+
+    // Reads up to `limit` entries of a table from `start` on, as stored and decoded. If `exact`,
+    // just the ones under `start`
+    fn read_table_entries<K, V>(
+        &self,
+        table: TableDefinition<K, V>,
+        start: Option<&[u8]>,
+        exact: bool,
+        limit: usize,
+    ) -> Result<Vec<TableEntry>, StoreError>
+    where
+        K: Key + 'static,
+        V: Value + 'static,
+        for<'a> K::SelfType<'a>: Inspect,
+        for<'a> V::SelfType<'a>: Inspect,
+    {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(table)?;
+        let range = match start {
+            Some(start) => table.range(key_from_bytes::<K>(start)?..)?,
+            None => table.iter()?,
+        };
+        let mut entries = Vec::new();
+        for entry in range.take(limit) {
+            let (key, value) = entry?;
+            let entry = table_entry::<K, V>(key.value(), value.value());
+            if exact && Some(entry.key.as_slice()) != start {
+                break;
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    // Reads up to `limit` entries of a multimap table from `start` on, as stored and decoded,
+    // with an entry for each of the values of a key. If `exact`, just the ones under `start`
+    fn read_multimap_table_entries<K, V>(
+        &self,
+        table: MultimapTableDefinition<K, V>,
+        start: Option<&[u8]>,
+        exact: bool,
+        limit: usize,
+    ) -> Result<Vec<TableEntry>, StoreError>
+    where
+        K: Key + 'static,
+        V: Key + 'static,
+        for<'a> K::SelfType<'a>: Inspect,
+        for<'a> V::SelfType<'a>: Inspect,
+    {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_multimap_table(table)?;
+        let range = match start {
+            Some(start) => table.range(key_from_bytes::<K>(start)?..)?,
+            None => table.iter()?,
+        };
+        let mut entries = Vec::new();
+        for entry in range {
+            let (key, values) = entry?;
+            if exact && Some(K::as_bytes(&key.value()).as_ref()) != start {
+                break;
+            }
+            for value in values {
+                if entries.len() == limit {
+                    return Ok(entries);
+                }
+                entries.push(table_entry::<K, V>(key.value(), value?.value()));
+            }
+        }
+        Ok(entries)
+    }
+
     // Helper method to write into a redb table
     fn write_to_multi<'k, 'v, 'a, K, V>(
         &self,
@@ -190,6 +312,60 @@ impl RedBStore {
     }
 }
 
+impl RedBStore {
+    // Applies changes to the snapshot's disk layer and, if given a diff layer, sets its state
+    // root and deletes the diff layers up to its block number, in a single transaction
+    fn write_snapshot(
+        &self,
+        changes: &SnapshotChanges,
+        flattened: Option<&SnapshotLayer>,
+    ) -> Result<(), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut accounts = write_txn.open_table(ACCOUNT_SNAPSHOT_TABLE)?;
+            let mut storage = write_txn.open_table(STORAGE_SNAPSHOT_TABLE)?;
+            for hashed_address in &changes.removed {
+                accounts.remove(hashed_address.0)?;
+                let keys = storage
+                    .range((hashed_address.0, [0; 32])..=(hashed_address.0, [0xff; 32]))?
+                    .map(|entry| entry.map(|(key, _)| key.value()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                for key in keys {
+                    storage.remove(key)?;
+                }
+            }
+            for (hashed_address, account) in &changes.accounts {
+                accounts.insert(hashed_address.0, account.encode_to_vec().as_slice())?;
+            }
+            for (hashed_address, slots) in &changes.storage {
+                for (hashed_key, value) in slots {
+                    if value.is_zero() {
+                        storage.remove((hashed_address.0, hashed_key.0))?;
+                    } else {
+                        storage.insert((hashed_address.0, hashed_key.0), value.to_big_endian())?;
+                    }
+                }
+            }
+            if let Some(layer) = flattened {
+                write_txn.open_table(CHAIN_DATA_TABLE)?.insert(
+                    ChainDataIndex::SnapshotRoot,
+                    layer.state_root.encode_to_vec(),
+                )?;
+                let mut layers = write_txn.open_table(SNAPSHOT_LAYERS_TABLE)?;
+                let stale_layers = layers
+                    .range(..=(layer.number, [0xff; 32]))?
+                    .map(|entry| entry.map(|(key, _)| key.value()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                for key in stale_layers {
+                    layers.remove(key)?;
+                }
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+}
+
 impl StoreEngine for RedBStore {
     fn add_block_header(
         &self,
@@ -279,11 +455,21 @@ impl StoreEngine for RedBStore {
     }
 
     fn add_pending_block(&self, block: Block) -> Result<(), StoreError> {
-        self.write(
-            PENDING_BLOCKS_TABLE,
-            <H256 as Into<BlockHashRLP>>::into(block.header.compute_block_hash()),
+        let block_hash = block.header.compute_block_hash();
+        let write_txn = self.db.begin_write()?;
+        write_txn
+            .open_multimap_table(PENDING_BLOCKS_BY_PARENT_TABLE)?
+            .insert(
+                <H256 as Into<BlockHashRLP>>::into(block.header.parent_hash),
+                <H256 as Into<BlockHashRLP>>::into(block_hash),
+            )?;
+        write_txn.open_table(PENDING_BLOCKS_TABLE)?.insert(
+            <H256 as Into<BlockHashRLP>>::into(block_hash),
             <Block as Into<BlockRLP>>::into(block),
-        )
+        )?;
+        write_txn.commit()?;
+
+        Ok(())
     }
 
     fn get_pending_block(&self, block_hash: BlockHash) -> Result<Option<Block>, StoreError> {
@@ -295,6 +481,55 @@ impl StoreEngine for RedBStore {
             .map(|b| b.value().to()))
     }
 
+    fn get_pending_blocks_by_parent(
+        &self,
+        parent_hash: BlockHash,
+    ) -> Result<Vec<Block>, StoreError> {
+        let read_txn = self.db.begin_read()?;
+        let children = read_txn
+            .open_multimap_table(PENDING_BLOCKS_BY_PARENT_TABLE)?
+            .get(<H256 as Into<BlockHashRLP>>::into(parent_hash))?
+            .map(|res| res.map(|hash| hash.value().to()))
+            .collect::<Result<Vec<BlockHash>, _>>()?;
+        let pending_blocks = read_txn.open_table(PENDING_BLOCKS_TABLE)?;
+        let mut blocks = Vec::with_capacity(children.len());
+        for hash in children {
+            if let Some(block) = pending_blocks.get(<H256 as Into<BlockHashRLP>>::into(hash))? {
+                blocks.push(block.value().to());
+            }
+        }
+        Ok(blocks)
+    }
+
+    fn get_pending_blocks(&self) -> Result<Vec<Block>, StoreError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(PENDING_BLOCKS_TABLE)?;
+        let blocks = table
+            .iter()?
+            .map(|res| res.map(|(_, block)| block.value().to()))
+            .collect::<Result<Vec<Block>, _>>()?;
+        Ok(blocks)
+    }
+
+    fn remove_pending_block(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        let parent_hash = write_txn
+            .open_table(PENDING_BLOCKS_TABLE)?
+            .remove(<H256 as Into<BlockHashRLP>>::into(block_hash))?
+            .map(|block| block.value().to().header.parent_hash);
+        if let Some(parent_hash) = parent_hash {
+            write_txn
+                .open_multimap_table(PENDING_BLOCKS_BY_PARENT_TABLE)?
+                .remove(
+                    <H256 as Into<BlockHashRLP>>::into(parent_hash),
+                    <H256 as Into<BlockHashRLP>>::into(block_hash),
+                )?;
+        }
+        write_txn.commit()?;
+
+        Ok(())
+    }
+
     fn add_block_number(
         &self,
         block_hash: BlockHash,
@@ -590,6 +825,136 @@ impl StoreEngine for RedBStore {
         self.delete(CANONICAL_BLOCK_HASHES_TABLE, number)
     }
 
+    fn apply_write_batch(&self, batch: WriteBatch) -> Result<(), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut state_trie_nodes = write_txn.open_table(STATE_TRIE_NODES_TABLE)?;
+            for (node_hash, node) in batch.state_trie_nodes.iter() {
+                state_trie_nodes.insert(node_hash.as_slice(), node.as_slice())?;
+            }
+            let mut storage_trie_nodes = write_txn.open_multimap_table(STORAGE_TRIE_NODES_TABLE)?;
+            for (hashed_address, nodes) in batch.storage_trie_nodes {
+                for (node_hash, node) in Arc::unwrap_or_clone(nodes) {
+                    storage_trie_nodes.insert(
+                        (hashed_address.0, node_hash_to_fixed_size(node_hash)),
+                        node.as_slice(),
+                    )?;
+                }
+            }
+            let mut account_codes = write_txn.open_table(ACCOUNT_CODES_TABLE)?;
+            for (code_hash, code) in batch.account_codes {
+                account_codes.insert(
+                    <H256 as Into<AccountCodeHashRLP>>::into(code_hash),
+                    <bytes::Bytes as Into<AccountCodeRLP>>::into(code),
+                )?;
+            }
+            let mut transaction_locations =
+                write_txn.open_multimap_table(TRANSACTION_LOCATIONS_TABLE)?;
+            let mut block_numbers = write_txn.open_table(BLOCK_NUMBERS_TABLE)?;
+            let mut total_difficulties = write_txn.open_table(BLOCK_TOTAL_DIFFICULTIES_TABLE)?;
+            let mut headers = write_txn.open_table(HEADERS_TABLE)?;
+            let mut bodies = write_txn.open_table(BLOCK_BODIES_TABLE)?;
+            for staged in batch.blocks {
+                for (transaction_hash, block_number, block_hash, index) in
+                    staged.transaction_locations()
+                {
+                    transaction_locations.insert(
+                        <H256 as Into<TransactionHashRLP>>::into(transaction_hash),
+                        <(u64, H256, u64) as Into<Rlp<(BlockNumber, BlockHash, Index)>>>::into((
+                            block_number,
+                            block_hash,
+                            index,
+                        )),
+                    )?;
+                }
+                let hash = <H256 as Into<BlockHashRLP>>::into(staged.hash);
+                block_numbers.insert(&hash, staged.block.header.number)?;
+                total_difficulties.insert(
+                    &hash,
+                    <U256 as Into<Rlp<U256>>>::into(staged.total_difficulty),
+                )?;
+                headers.insert(
+                    &hash,
+                    <BlockHeader as Into<BlockHeaderRLP>>::into(staged.block.header),
+                )?;
+                bodies.insert(
+                    &hash,
+                    <BlockBody as Into<BlockBodyRLP>>::into(staged.block.body),
+                )?;
+            }
+            let mut receipts_table = write_txn.open_table(RECEIPTS_TABLE)?;
+            for (block_hash, receipts) in batch.receipts {
+                for (index, receipt) in receipts.into_iter().enumerate() {
+                    receipts_table.insert(
+                        <(H256, u64) as Into<TupleRLP<BlockHash, Index>>>::into((
+                            block_hash,
+                            index as u64,
+                        )),
+                        <Receipt as Into<ReceiptRLP>>::into(receipt),
+                    )?;
+                }
+            }
+            let mut canonical_hashes = write_txn.open_table(CANONICAL_BLOCK_HASHES_TABLE)?;
+            for (number, hash) in batch.canonical_blocks {
+                match hash {
+                    Some(hash) => {
+                        canonical_hashes.insert(number, <H256 as Into<BlockHashRLP>>::into(hash))?
+                    }
+                    None => canonical_hashes.remove(number)?,
+                };
+            }
+            let mut chain_data = write_txn.open_table(CHAIN_DATA_TABLE)?;
+            if let Some(block_number) = batch.latest_block_number {
+                chain_data.insert(
+                    ChainDataIndex::LatestBlockNumber,
+                    block_number.encode_to_vec(),
+                )?;
+            }
+            if let Some(block_number) = batch.finalized_block_number {
+                chain_data.insert(
+                    ChainDataIndex::FinalizedBlockNumber,
+                    block_number.encode_to_vec(),
+                )?;
+            }
+            if let Some(block_number) = batch.safe_block_number {
+                chain_data.insert(
+                    ChainDataIndex::SafeBlockNumber,
+                    block_number.encode_to_vec(),
+                )?;
+            }
+            if let Some(total_difficulty) = batch.latest_total_difficulty {
+                chain_data.insert(
+                    ChainDataIndex::LatestTotalDifficulty,
+                    total_difficulty.encode_to_vec(),
+                )?;
+            }
+            let mut address_log_index = write_txn.open_table(ADDRESS_LOG_INDEX_TABLE)?;
+            let mut topic_log_index = write_txn.open_table(TOPIC_LOG_INDEX_TABLE)?;
+            for update in batch.log_index {
+                match update {
+                    LogIndexUpdate::Add(index) => {
+                        for key in index.address_keys() {
+                            address_log_index.insert(key.as_slice(), ())?;
+                        }
+                        for key in index.topic_keys() {
+                            topic_log_index.insert(key.as_slice(), ())?;
+                        }
+                    }
+                    LogIndexUpdate::Remove(index) => {
+                        for key in index.address_keys() {
+                            address_log_index.remove(key.as_slice())?;
+                        }
+                        for key in index.topic_keys() {
+                            topic_log_index.remove(key.as_slice())?;
+                        }
+                    }
+                }
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
     fn add_payload(&self, payload_id: u64, block: Block) -> Result<(), StoreError> {
         self.write(
             PAYLOADS_TABLE,
@@ -712,6 +1077,474 @@ impl StoreEngine for RedBStore {
             status.encode_to_vec(),
         )
     }
+
+    fn add_batch_status(
+        &self,
+        block_number: BlockNumber,
+        status: BatchStatus,
+    ) -> Result<(), StoreError> {
+        self.write(
+            BATCH_STATUSES_TABLE,
+            block_number,
+            <BatchStatus as Into<Rlp<BatchStatus>>>::into(status),
+        )
+    }
+
+    fn get_batch_status(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Option<BatchStatus>, StoreError> {
+        Ok(self
+            .read(BATCH_STATUSES_TABLE, block_number)?
+            .map(|s| s.value().to()))
+    }
+
+    fn add_withdrawal_proofs(
+        &self,
+        proofs: Vec<(H256, WithdrawalProof)>,
+    ) -> Result<(), StoreError> {
+        let key_values = proofs
+            .into_iter()
+            .map(|(tx_hash, proof)| {
+                (
+                    <H256 as Into<TransactionHashRLP>>::into(tx_hash),
+                    <WithdrawalProof as Into<Rlp<WithdrawalProof>>>::into(proof),
+                )
+            })
+            .collect();
+        self.write_batch(WITHDRAWAL_PROOFS_TABLE, key_values)
+    }
+
+    fn get_withdrawal_proof(
+        &self,
+        transaction_hash: H256,
+    ) -> Result<Option<WithdrawalProof>, StoreError> {
+        Ok(self
+            .read(
+                WITHDRAWAL_PROOFS_TABLE,
+                <H256 as Into<TransactionHashRLP>>::into(transaction_hash),
+            )?
+            .map(|p| p.value().to()))
+    }
+
+    fn add_deposit(
+        &self,
+        l1_transaction_hash: H256,
+        deposit: DepositInfo,
+    ) -> Result<(), StoreError> {
+        self.write(
+            DEPOSITS_TABLE,
+            <H256 as Into<TransactionHashRLP>>::into(l1_transaction_hash),
+            <DepositInfo as Into<Rlp<DepositInfo>>>::into(deposit),
+        )
+    }
+
+    fn get_deposit(&self, l1_transaction_hash: H256) -> Result<Option<DepositInfo>, StoreError> {
+        Ok(self
+            .read(
+                DEPOSITS_TABLE,
+                <H256 as Into<TransactionHashRLP>>::into(l1_transaction_hash),
+            )?
+            .map(|d| d.value().to()))
+    }
+
+    fn update_l1_fee_components(&self, components: L1FeeComponents) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA_TABLE,
+            ChainDataIndex::L1FeeComponents,
+            components.encode_to_vec(),
+        )
+    }
+
+    fn get_l1_fee_components(&self) -> Result<Option<L1FeeComponents>, StoreError> {
+        match self.read(CHAIN_DATA_TABLE, ChainDataIndex::L1FeeComponents)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(&rlp.value())
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn prune_state_tries(
+        &self,
+        reachable: &ReachableNodes,
+        from: PruneCursor,
+        limit: usize,
+    ) -> Result<(usize, Option<PruneCursor>), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        let mut deleted = 0;
+        let next = match from {
+            PruneCursor::StateNodes(start) => {
+                let mut table = write_txn.open_table(STATE_TRIE_NODES_TABLE)?;
+                let mut keys = Vec::new();
+                for entry in table.range(start.as_slice()..)?.take(limit + 1) {
+                    let (key, _) = entry?;
+                    keys.push(key.value().to_vec());
+                }
+                let next = match keys.get(limit) {
+                    Some(key) => PruneCursor::StateNodes(key.clone()),
+                    None => PruneCursor::storage_nodes(),
+                };
+                for key in keys.iter().take(limit) {
+                    if !reachable.has_state_node(key) {
+                        table.remove(key.as_slice())?;
+                        deleted += 1;
+                    }
+                }
+                Some(next)
+            }
+            PruneCursor::StorageNodes(start_address, start_key) => {
+                let mut table = write_txn.open_multimap_table(STORAGE_TRIE_NODES_TABLE)?;
+                let mut start_node = [0; 33];
+                start_node[..start_key.len()].copy_from_slice(&start_key);
+                let mut keys = Vec::new();
+                for entry in table
+                    .range((start_address.0, start_node)..)?
+                    .take(limit + 1)
+                {
+                    let (key, _) = entry?;
+                    keys.push(key.value());
+                }
+                let next = keys.get(limit).map(|(hashed_address, node_hash)| {
+                    PruneCursor::StorageNodes(H256(*hashed_address), node_hash.to_vec())
+                });
+                for (hashed_address, node_hash) in keys.iter().take(limit) {
+                    // Node hashes are stored with their length as the first byte
+                    if node_hash[0] == 32
+                        && !reachable.has_storage_node(&H256(*hashed_address), &node_hash[1..])
+                    {
+                        table.remove_all((*hashed_address, *node_hash))?;
+                        deleted += 1;
+                    }
+                }
+                next
+            }
+        };
+        write_txn.commit()?;
+        Ok((deleted, next))
+    }
+
+    fn update_earliest_state_block_number(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA_TABLE,
+            ChainDataIndex::EarliestStateBlockNumber,
+            block_number.encode_to_vec(),
+        )
+    }
+
+    fn get_earliest_state_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        match self.read(CHAIN_DATA_TABLE, ChainDataIndex::EarliestStateBlockNumber)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(&rlp.value())
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn remove_frozen_blocks(&self, block_hashes: &[BlockHash]) -> Result<(), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut headers = write_txn.open_table(HEADERS_TABLE)?;
+            let mut bodies = write_txn.open_table(BLOCK_BODIES_TABLE)?;
+            let mut receipts = write_txn.open_table(RECEIPTS_TABLE)?;
+            for block_hash in block_hashes {
+                let key: BlockHashRLP = (*block_hash).into();
+                headers.remove(&key)?;
+                bodies.remove(&key)?;
+                // Receipts are indexed from zero, the first missing index is past the last one
+                let mut index = 0;
+                while receipts
+                    .remove(&<(H256, u64) as Into<TupleRLP<BlockHash, Index>>>::into((
+                        *block_hash,
+                        index,
+                    )))?
+                    .is_some()
+                {
+                    index += 1;
+                }
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn expire_block_history(
+        &self,
+        block_hashes: &[BlockHash],
+        transaction_locations: &[(H256, BlockNumber, BlockHash, Index)],
+        earliest_block: BlockNumber,
+    ) -> Result<(), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut bodies = write_txn.open_table(BLOCK_BODIES_TABLE)?;
+            let mut receipts = write_txn.open_table(RECEIPTS_TABLE)?;
+            for block_hash in block_hashes {
+                bodies.remove(&<H256 as Into<BlockHashRLP>>::into(*block_hash))?;
+                // Receipts are indexed from zero, the first missing index is past the last one
+                let mut index = 0;
+                while receipts
+                    .remove(&<(H256, u64) as Into<TupleRLP<BlockHash, Index>>>::into((
+                        *block_hash,
+                        index,
+                    )))?
+                    .is_some()
+                {
+                    index += 1;
+                }
+            }
+            let mut locations = write_txn.open_multimap_table(TRANSACTION_LOCATIONS_TABLE)?;
+            for (transaction_hash, block_number, block_hash, index) in transaction_locations {
+                locations.remove(
+                    <H256 as Into<TransactionHashRLP>>::into(*transaction_hash),
+                    <(u64, H256, u64) as Into<Rlp<(BlockNumber, BlockHash, Index)>>>::into((
+                        *block_number,
+                        *block_hash,
+                        *index,
+                    )),
+                )?;
+            }
+            write_txn.open_table(CHAIN_DATA_TABLE)?.insert(
+                ChainDataIndex::EarliestBlockNumber,
+                earliest_block.encode_to_vec(),
+            )?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_snapshot_root(&self) -> Result<Option<H256>, StoreError> {
+        match self.read(CHAIN_DATA_TABLE, ChainDataIndex::SnapshotRoot)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(&rlp.value())
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn get_account_snapshot(
+        &self,
+        hashed_address: H256,
+    ) -> Result<Option<AccountState>, StoreError> {
+        match self.read(ACCOUNT_SNAPSHOT_TABLE, hashed_address.0)? {
+            None => Ok(None),
+            Some(rlp) => RLPDecode::decode(rlp.value())
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn get_storage_snapshot(
+        &self,
+        hashed_address: H256,
+        hashed_key: H256,
+    ) -> Result<Option<U256>, StoreError> {
+        Ok(self
+            .read(STORAGE_SNAPSHOT_TABLE, (hashed_address.0, hashed_key.0))?
+            .map(|value| U256::from_big_endian(&value.value())))
+    }
+
+    fn add_snapshot_layer(&self, layer: &SnapshotLayer) -> Result<(), StoreError> {
+        self.write(
+            SNAPSHOT_LAYERS_TABLE,
+            (layer.number, layer.state_root.0),
+            <SnapshotLayer as Into<Rlp<SnapshotLayer>>>::into(layer.clone()),
+        )
+    }
+
+    fn get_snapshot_layers(&self) -> Result<Vec<SnapshotLayer>, StoreError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(SNAPSHOT_LAYERS_TABLE)?;
+        let layers = table
+            .iter()?
+            .map(|entry| entry.map(|(_, layer)| layer.value().to()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(layers)
+    }
+
+    fn flatten_snapshot_layer(&self, layer: &SnapshotLayer) -> Result<(), StoreError> {
+        self.write_snapshot(&layer.changes, Some(layer))
+    }
+
+    fn write_snapshot_changes(&self, changes: &SnapshotChanges) -> Result<(), StoreError> {
+        self.write_snapshot(changes, None)
+    }
+
+    fn clear_snapshot(&self) -> Result<(), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        write_txn.delete_table(ACCOUNT_SNAPSHOT_TABLE)?;
+        write_txn.delete_table(STORAGE_SNAPSHOT_TABLE)?;
+        write_txn.open_table(ACCOUNT_SNAPSHOT_TABLE)?;
+        write_txn.open_table(STORAGE_SNAPSHOT_TABLE)?;
+        write_txn
+            .open_table(CHAIN_DATA_TABLE)?
+            .remove(ChainDataIndex::SnapshotRoot)?;
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_address_log_blocks(
+        &self,
+        address: Address,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<Vec<(BlockNumber, BlockHash)>, StoreError> {
+        self.read_log_index(ADDRESS_LOG_INDEX_TABLE, address.as_bytes(), from, to)
+    }
+
+    fn get_topic_log_blocks(
+        &self,
+        topic: H256,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<Vec<(BlockNumber, BlockHash)>, StoreError> {
+        self.read_log_index(TOPIC_LOG_INDEX_TABLE, topic.as_bytes(), from, to)
+    }
+
+    fn update_log_index_tail(&self, tail: Option<BlockNumber>) -> Result<(), StoreError> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut chain_data = write_txn.open_table(CHAIN_DATA_TABLE)?;
+            match tail {
+                Some(tail) => {
+                    chain_data.insert(ChainDataIndex::LogIndexTail, tail.encode_to_vec())?
+                }
+                None => chain_data.remove(ChainDataIndex::LogIndexTail)?,
+            };
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    fn get_log_index_tail(&self) -> Result<Option<BlockNumber>, StoreError> {
+        match self.read(CHAIN_DATA_TABLE, ChainDataIndex::LogIndexTail)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(&rlp.value())
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn update_schema_version(&self, version: u64) -> Result<(), StoreError> {
+        self.write(
+            CHAIN_DATA_TABLE,
+            ChainDataIndex::SchemaVersion,
+            version.encode_to_vec(),
+        )
+    }
+
+    fn get_schema_version(&self) -> Result<Option<u64>, StoreError> {
+        match self.read(CHAIN_DATA_TABLE, ChainDataIndex::SchemaVersion)? {
+            None => Ok(None),
+            Some(ref rlp) => RLPDecode::decode(&rlp.value())
+                .map(Some)
+                .map_err(|_| StoreError::DecodeError),
+        }
+    }
+
+    fn table_stats(&self) -> Result<Vec<TableStats>, StoreError> {
+        let read_txn = self.db.begin_read()?;
+        let mut stats = Vec::new();
+        for handle in read_txn.list_tables()? {
+            let name = handle.name().to_string();
+            let table = read_txn.open_untyped_table(handle)?;
+            stats.push(TableStats {
+                name,
+                entries: table.len()?,
+                size: table.stats()?.stored_bytes(),
+            });
+        }
+        for handle in read_txn.list_multimap_tables()? {
+            let name = handle.name().to_string();
+            let table = read_txn.open_untyped_multimap_table(handle)?;
+            stats.push(TableStats {
+                name,
+                entries: table.len()?,
+                size: table.stats()?.stored_bytes(),
+            });
+        }
+        stats.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(stats)
+    }
+
+    fn read_table(
+        &self,
+        table: &str,
+        start: Option<&[u8]>,
+        exact: bool,
+        limit: usize,
+    ) -> Result<Vec<TableEntry>, StoreError> {
+        match table {
+            name if name == STATE_TRIE_NODES_TABLE.name() => {
+                self.read_table_entries(STATE_TRIE_NODES_TABLE, start, exact, limit)
+            }
+            name if name == BLOCK_NUMBERS_TABLE.name() => {
+                self.read_table_entries(BLOCK_NUMBERS_TABLE, start, exact, limit)
+            }
+            name if name == BLOCK_TOTAL_DIFFICULTIES_TABLE.name() => {
+                self.read_table_entries(BLOCK_TOTAL_DIFFICULTIES_TABLE, start, exact, limit)
+            }
+            name if name == HEADERS_TABLE.name() => {
+                self.read_table_entries(HEADERS_TABLE, start, exact, limit)
+            }
+            name if name == BLOCK_BODIES_TABLE.name() => {
+                self.read_table_entries(BLOCK_BODIES_TABLE, start, exact, limit)
+            }
+            name if name == ACCOUNT_CODES_TABLE.name() => {
+                self.read_table_entries(ACCOUNT_CODES_TABLE, start, exact, limit)
+            }
+            name if name == RECEIPTS_TABLE.name() => {
+                self.read_table_entries(RECEIPTS_TABLE, start, exact, limit)
+            }
+            name if name == CANONICAL_BLOCK_HASHES_TABLE.name() => {
+                self.read_table_entries(CANONICAL_BLOCK_HASHES_TABLE, start, exact, limit)
+            }
+            name if name == STORAGE_TRIE_NODES_TABLE.name() => {
+                self.read_multimap_table_entries(STORAGE_TRIE_NODES_TABLE, start, exact, limit)
+            }
+            name if name == CHAIN_DATA_TABLE.name() => {
+                self.read_table_entries(CHAIN_DATA_TABLE, start, exact, limit)
+            }
+            name if name == PAYLOADS_TABLE.name() => {
+                self.read_table_entries(PAYLOADS_TABLE, start, exact, limit)
+            }
+            name if name == PENDING_BLOCKS_TABLE.name() => {
+                self.read_table_entries(PENDING_BLOCKS_TABLE, start, exact, limit)
+            }
+            name if name == PENDING_BLOCKS_BY_PARENT_TABLE.name() => self
+                .read_multimap_table_entries(PENDING_BLOCKS_BY_PARENT_TABLE, start, exact, limit),
+            name if name == TRANSACTION_LOCATIONS_TABLE.name() => {
+                self.read_multimap_table_entries(TRANSACTION_LOCATIONS_TABLE, start, exact, limit)
+            }
+            name if name == BATCH_STATUSES_TABLE.name() => {
+                self.read_table_entries(BATCH_STATUSES_TABLE, start, exact, limit)
+            }
+            name if name == WITHDRAWAL_PROOFS_TABLE.name() => {
+                self.read_table_entries(WITHDRAWAL_PROOFS_TABLE, start, exact, limit)
+            }
+            name if name == DEPOSITS_TABLE.name() => {
+                self.read_table_entries(DEPOSITS_TABLE, start, exact, limit)
+            }
+            name if name == ACCOUNT_SNAPSHOT_TABLE.name() => {
+                self.read_table_entries(ACCOUNT_SNAPSHOT_TABLE, start, exact, limit)
+            }
+            name if name == STORAGE_SNAPSHOT_TABLE.name() => {
+                self.read_table_entries(STORAGE_SNAPSHOT_TABLE, start, exact, limit)
+            }
+            name if name == SNAPSHOT_LAYERS_TABLE.name() => {
+                self.read_table_entries(SNAPSHOT_LAYERS_TABLE, start, exact, limit)
+            }
+            name if name == ADDRESS_LOG_INDEX_TABLE.name() => {
+                self.read_table_entries(ADDRESS_LOG_INDEX_TABLE, start, exact, limit)
+            }
+            name if name == TOPIC_LOG_INDEX_TABLE.name() => {
+                self.read_table_entries(TOPIC_LOG_INDEX_TABLE, start, exact, limit)
+            }
+            _ => Err(StoreError::UnknownTable(table.to_string())),
+        }
+    }
 }
 
 impl redb::Value for ChainDataIndex {
@@ -755,13 +1588,45 @@ impl redb::Key for ChainDataIndex {
     }
 }
 
-pub fn init_db() -> Result<Database, StoreError> {
-    let db = Database::create("ethrex.redb")?;
+// Decodes a key given as stored, checking its length first, as decoding panics on fixed width
+// keys of a different length
+fn key_from_bytes<K: Key>(bytes: &[u8]) -> Result<K::SelfType<'_>, StoreError> {
+    match K::fixed_width() {
+        Some(width) if width != bytes.len() => Err(StoreError::Custom(format!(
+            "Expected a key of {width} bytes, got {}",
+            bytes.len()
+        ))),
+        _ => Ok(K::from_bytes(bytes)),
+    }
+}
+
+fn table_entry<K: Value, V: Value>(key: K::SelfType<'_>, value: V::SelfType<'_>) -> TableEntry
+where
+    for<'a> K::SelfType<'a>: Inspect,
+    for<'a> V::SelfType<'a>: Inspect,
+{
+    TableEntry {
+        key: K::as_bytes(&key).as_ref().to_vec(),
+        value: V::as_bytes(&value).as_ref().to_vec(),
+        decoded_key: key.inspect(),
+        decoded_value: value.inspect(),
+    }
+}
+
+/// Opens the database file in the given directory, creating both if needed, along with any
+/// missing table, so that reading from any of them doesn't fail
+pub fn init_db(path: &str) -> Result<Database, StoreError> {
+    std::fs::create_dir_all(path).map_err(|error| {
+        StoreError::Custom(format!("Failed to create database directory: {error}"))
+    })?;
+    let db = Database::create(Path::new(path).join(DB_FILE_NAME))?;
 
     let table_creation_txn = db.begin_write()?;
     table_creation_txn.open_table(STATE_TRIE_NODES_TABLE)?;
     table_creation_txn.open_table(BLOCK_NUMBERS_TABLE)?;
     table_creation_txn.open_table(BLOCK_TOTAL_DIFFICULTIES_TABLE)?;
+    table_creation_txn.open_table(HEADERS_TABLE)?;
+    table_creation_txn.open_table(ACCOUNT_CODES_TABLE)?;
     table_creation_txn.open_table(CANONICAL_BLOCK_HASHES_TABLE)?;
     table_creation_txn.open_table(RECEIPTS_TABLE)?;
     table_creation_txn.open_multimap_table(STORAGE_TRIE_NODES_TABLE)?;
@@ -769,7 +1634,16 @@ pub fn init_db() -> Result<Database, StoreError> {
     table_creation_txn.open_table(BLOCK_BODIES_TABLE)?;
     table_creation_txn.open_table(PAYLOADS_TABLE)?;
     table_creation_txn.open_table(PENDING_BLOCKS_TABLE)?;
+    table_creation_txn.open_multimap_table(PENDING_BLOCKS_BY_PARENT_TABLE)?;
     table_creation_txn.open_multimap_table(TRANSACTION_LOCATIONS_TABLE)?;
+    table_creation_txn.open_table(BATCH_STATUSES_TABLE)?;
+    table_creation_txn.open_table(WITHDRAWAL_PROOFS_TABLE)?;
+    table_creation_txn.open_table(DEPOSITS_TABLE)?;
+    table_creation_txn.open_table(ACCOUNT_SNAPSHOT_TABLE)?;
+    table_creation_txn.open_table(STORAGE_SNAPSHOT_TABLE)?;
+    table_creation_txn.open_table(SNAPSHOT_LAYERS_TABLE)?;
+    table_creation_txn.open_table(ADDRESS_LOG_INDEX_TABLE)?;
+    table_creation_txn.open_table(TOPIC_LOG_INDEX_TABLE)?;
     table_creation_txn.commit()?;
 
     Ok(db)
@@ -1,3 +1,5 @@
+use crate::inspect::Inspect;
+
 /// Represents the key for each unique value of the chain data stored in the db
 // (TODO: Remove this comment once full) Will store chain-specific data such as chain id and latest finalized/pending/safe block number
 #[derive(Debug, Copy, Clone)]
@@ -11,28 +13,58 @@ pub enum ChainDataIndex {
     // TODO (#307): Remove TotalDifficulty.
     LatestTotalDifficulty = 6,
     IsSynced = 7,
+    L1FeeComponents = 8,
+    EarliestStateBlockNumber = 9,
+    SnapshotRoot = 10,
+    SchemaVersion = 11,
+    LogIndexTail = 12,
 }
 
 impl From<u8> for ChainDataIndex {
     fn from(value: u8) -> Self {
+        ChainDataIndex::try_from(value as u32).unwrap_or_else(|error| panic!("{error}"))
+    }
+}
+
+impl TryFrom<u32> for ChainDataIndex {
+    type Error = String;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
         match value {
-            x if x == ChainDataIndex::ChainConfig as u8 => ChainDataIndex::ChainConfig,
-            x if x == ChainDataIndex::EarliestBlockNumber as u8 => {
-                ChainDataIndex::EarliestBlockNumber
+            x if x == ChainDataIndex::ChainConfig as u32 => Ok(ChainDataIndex::ChainConfig),
+            x if x == ChainDataIndex::EarliestBlockNumber as u32 => {
+                Ok(ChainDataIndex::EarliestBlockNumber)
             }
-            x if x == ChainDataIndex::FinalizedBlockNumber as u8 => {
-                ChainDataIndex::FinalizedBlockNumber
+            x if x == ChainDataIndex::FinalizedBlockNumber as u32 => {
+                Ok(ChainDataIndex::FinalizedBlockNumber)
             }
-            x if x == ChainDataIndex::SafeBlockNumber as u8 => ChainDataIndex::SafeBlockNumber,
-            x if x == ChainDataIndex::LatestBlockNumber as u8 => ChainDataIndex::LatestBlockNumber,
-            x if x == ChainDataIndex::PendingBlockNumber as u8 => {
-                ChainDataIndex::PendingBlockNumber
+            x if x == ChainDataIndex::SafeBlockNumber as u32 => Ok(ChainDataIndex::SafeBlockNumber),
+            x if x == ChainDataIndex::LatestBlockNumber as u32 => {
+                Ok(ChainDataIndex::LatestBlockNumber)
             }
-            x if x == ChainDataIndex::LatestTotalDifficulty as u8 => {
-                ChainDataIndex::LatestTotalDifficulty
+            x if x == ChainDataIndex::PendingBlockNumber as u32 => {
+                Ok(ChainDataIndex::PendingBlockNumber)
             }
-            x if x == ChainDataIndex::IsSynced as u8 => ChainDataIndex::IsSynced,
-            _ => panic!("Invalid value when casting to ChainDataIndex: {}", value),
+            x if x == ChainDataIndex::LatestTotalDifficulty as u32 => {
+                Ok(ChainDataIndex::LatestTotalDifficulty)
+            }
+            x if x == ChainDataIndex::IsSynced as u32 => Ok(ChainDataIndex::IsSynced),
+            x if x == ChainDataIndex::L1FeeComponents as u32 => Ok(ChainDataIndex::L1FeeComponents),
+            x if x == ChainDataIndex::EarliestStateBlockNumber as u32 => {
+                Ok(ChainDataIndex::EarliestStateBlockNumber)
+            }
+            x if x == ChainDataIndex::SnapshotRoot as u32 => Ok(ChainDataIndex::SnapshotRoot),
+            x if x == ChainDataIndex::SchemaVersion as u32 => Ok(ChainDataIndex::SchemaVersion),
+            x if x == ChainDataIndex::LogIndexTail as u32 => Ok(ChainDataIndex::LogIndexTail),
+            _ => Err(format!(
+                "Invalid value when casting to ChainDataIndex: {value}"
+            )),
         }
     }
 }
+
+impl Inspect for ChainDataIndex {
+    fn inspect(&self) -> String {
+        format!("{self:?}")
+    }
+}
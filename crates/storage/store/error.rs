@@ -40,10 +40,18 @@ pub enum StoreError {
     MissingStore,
     #[error("Could not open DB for reading")]
     ReadError,
+    #[error("Unknown table {0}")]
+    UnknownTable(String),
     #[error("Could not instantiate cursor for table {0}")]
     CursorError(String),
     #[error("Missing latest block number")]
     MissingLatestBlockNumber,
     #[error("Missing earliest block number")]
     MissingEarliestBlockNumber,
+    #[error("Database schema version {found} is outdated, expected {expected}: run `ethrex db migrate` to upgrade it")]
+    OutdatedSchemaVersion { found: u64, expected: u64 },
+    #[error("Freezer I/O error: {0}")]
+    FreezerIo(#[from] std::io::Error),
+    #[error("Database schema version {found} is newer than the supported {supported}: it was written by a newer release")]
+    UnsupportedSchemaVersion { found: u64, supported: u64 },
 }
@@ -0,0 +1,465 @@
+//! Append-only store for the history of finalized blocks, kept out of the database so it
+//! doesn't weigh on its compaction and page cache as the chain grows.
+//!
+//! Each kind of data is kept in its own table, made of data files with the snappy compressed
+//! RLP encoding of each block's item one after the other, and an index file with the offset at
+//! which each item ends within its data file, as a big endian u64. The item of block `n` is the
+//! `n`th one, so tables always start at the genesis block. Empty items stand for data that
+//! wasn't stored, as the bodies and receipts of the blocks skipped by snap sync.
+//!
+//! Data files hold a fixed amount of items each, so the bodies and receipts of expired history
+//! are deleted a whole file at a time.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::ErrorKind,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+};
+
+use ethrex_core::types::{BlockBody, BlockHash, BlockHeader, BlockNumber, Receipt};
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
+
+use crate::error::StoreError;
+
+/// Directory within the data directory holding the freezer tables
+pub const FREEZER_DIR: &str = "ancient";
+
+const INDEX_ENTRY_SIZE: u64 = 8;
+
+/// Amount of items in each data file of a table
+const SEGMENT_ITEMS: u64 = 100_000;
+
+/// A canonical block's data, as moved into the freezer
+#[derive(Debug, Clone)]
+pub(crate) struct FrozenBlock {
+    pub hash: BlockHash,
+    pub header: BlockHeader,
+    pub body: Option<BlockBody>,
+    pub receipts: Option<Vec<Receipt>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Freezer {
+    tables: Mutex<FreezerTables>,
+}
+
+#[derive(Debug)]
+struct FreezerTables {
+    hashes: FreezerTable,
+    headers: FreezerTable,
+    bodies: FreezerTable,
+    receipts: FreezerTable,
+}
+
+impl FreezerTables {
+    fn all(&mut self) -> [&mut FreezerTable; 4] {
+        [
+            &mut self.hashes,
+            &mut self.headers,
+            &mut self.bodies,
+            &mut self.receipts,
+        ]
+    }
+}
+
+impl Freezer {
+    /// Opens the freezer tables in the given directory, creating them if needed. Items appended
+    /// to only some of the tables, by a node stopping halfway, are discarded
+    pub fn open(dir: &Path) -> Result<Self, StoreError> {
+        Self::open_with_segment_items(dir, SEGMENT_ITEMS)
+    }
+
+    fn open_with_segment_items(dir: &Path, segment_items: u64) -> Result<Self, StoreError> {
+        fs::create_dir_all(dir)?;
+        let mut tables = FreezerTables {
+            hashes: FreezerTable::open(dir, "hashes", segment_items)?,
+            headers: FreezerTable::open(dir, "headers", segment_items)?,
+            bodies: FreezerTable::open(dir, "bodies", segment_items)?,
+            receipts: FreezerTable::open(dir, "receipts", segment_items)?,
+        };
+        let items = tables
+            .all()
+            .iter()
+            .map(|table| table.items)
+            .min()
+            .unwrap_or_default();
+        for table in tables.all() {
+            table.truncate(items)?;
+        }
+        Ok(Self {
+            tables: Mutex::new(tables),
+        })
+    }
+
+    /// Returns the amount of blocks in the freezer, which are the ones numbered below it
+    pub fn frozen_blocks(&self) -> Result<u64, StoreError> {
+        Ok(self.tables()?.hashes.items)
+    }
+
+    /// Appends the given blocks, which must follow the last frozen one, and flushes them to disk
+    pub fn append(&self, blocks: &[FrozenBlock]) -> Result<(), StoreError> {
+        let mut tables = self.tables()?;
+        let frozen = tables.hashes.items;
+        let result = Self::append_to(&mut tables, blocks);
+        if result.is_err() {
+            // Don't leave the tables with different lengths
+            for table in tables.all() {
+                table.truncate(frozen)?;
+            }
+        }
+        result
+    }
+
+    fn append_to(tables: &mut FreezerTables, blocks: &[FrozenBlock]) -> Result<(), StoreError> {
+        for block in blocks {
+            if block.header.number != tables.hashes.items {
+                return Err(StoreError::Custom(format!(
+                    "Block {} can't be frozen after block {}",
+                    block.header.number,
+                    tables.hashes.items.wrapping_sub(1)
+                )));
+            }
+            tables.hashes.append(block.hash.as_bytes())?;
+            tables
+                .headers
+                .append(&compress(&block.header.encode_to_vec())?)?;
+            let body = match &block.body {
+                Some(body) => compress(&body.encode_to_vec())?,
+                None => Vec::new(),
+            };
+            tables.bodies.append(&body)?;
+            let receipts = match &block.receipts {
+                Some(receipts) => compress(&receipts.encode_to_vec())?,
+                None => Vec::new(),
+            };
+            tables.receipts.append(&receipts)?;
+        }
+        for table in tables.all() {
+            table.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the bodies and receipts of the blocks below the given one, as far as whole data
+    /// files can be deleted. Returns whether any was
+    pub fn expire(&self, block_number: BlockNumber) -> Result<bool, StoreError> {
+        let mut tables = self.tables()?;
+        let bodies_removed = tables.bodies.remove_segments_below(block_number)?;
+        let receipts_removed = tables.receipts.remove_segments_below(block_number)?;
+        Ok(bodies_removed || receipts_removed)
+    }
+
+    pub fn block_hash(&self, number: BlockNumber) -> Result<Option<BlockHash>, StoreError> {
+        Ok(self
+            .tables()?
+            .hashes
+            .get(number)?
+            .map(|hash| BlockHash::from_slice(&hash)))
+    }
+
+    pub fn header(&self, number: BlockNumber) -> Result<Option<BlockHeader>, StoreError> {
+        let item = self.tables()?.headers.get(number)?;
+        item.map(|item| decode(&item)).transpose()
+    }
+
+    pub fn body(&self, number: BlockNumber) -> Result<Option<BlockBody>, StoreError> {
+        let item = self.tables()?.bodies.get(number)?;
+        item.map(|item| decode(&item)).transpose()
+    }
+
+    pub fn receipts(&self, number: BlockNumber) -> Result<Option<Vec<Receipt>>, StoreError> {
+        let item = self.tables()?.receipts.get(number)?;
+        item.map(|item| decode(&item)).transpose()
+    }
+
+    fn tables(&self) -> Result<MutexGuard<'_, FreezerTables>, StoreError> {
+        self.tables
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))
+    }
+}
+
+fn compress(item: &[u8]) -> Result<Vec<u8>, StoreError> {
+    snap::raw::Encoder::new()
+        .compress_vec(item)
+        .map_err(|error| StoreError::Custom(format!("Failed to compress frozen item: {error}")))
+}
+
+fn decode<T: RLPDecode>(item: &[u8]) -> Result<T, StoreError> {
+    let encoded = snap::raw::Decoder::new()
+        .decompress_vec(item)
+        .map_err(|_| StoreError::DecodeError)?;
+    Ok(T::decode(&encoded)?)
+}
+
+#[derive(Debug)]
+struct FreezerTable {
+    dir: PathBuf,
+    name: &'static str,
+    segment_items: u64,
+    index: File,
+    // Segment the next item is appended to
+    head: File,
+    items: u64,
+    head_len: u64,
+}
+
+impl FreezerTable {
+    fn open(dir: &Path, name: &'static str, segment_items: u64) -> Result<Self, StoreError> {
+        let index = open_file(&dir.join(format!("{name}.idx")))?;
+        let items = index.metadata()?.len() / INDEX_ENTRY_SIZE;
+        let mut table = Self {
+            dir: dir.to_path_buf(),
+            name,
+            segment_items,
+            head: open_file(&dir.join(format!("{name}.{:04}.dat", items / segment_items)))?,
+            index,
+            items,
+            head_len: 0,
+        };
+        // Index entries may have reached the disk before the data they point to
+        while table.items > 0 {
+            let last = table.items - 1;
+            let segment_len = match fs::metadata(table.segment_path(last / segment_items)) {
+                Ok(metadata) => metadata.len(),
+                Err(error) if error.kind() == ErrorKind::NotFound => 0,
+                Err(error) => return Err(error.into()),
+            };
+            if table.end_offset(last)? <= segment_len {
+                break;
+            }
+            table.items = last;
+        }
+        table.truncate(table.items)?;
+        Ok(table)
+    }
+
+    fn segment_path(&self, segment: u64) -> PathBuf {
+        self.dir.join(format!("{}.{segment:04}.dat", self.name))
+    }
+
+    /// Drops the items from the given one onwards, along with any partially written one
+    fn truncate(&mut self, items: u64) -> Result<(), StoreError> {
+        self.items = items.min(self.items);
+        let head_segment = self.items / self.segment_items;
+        self.head_len = match self.items % self.segment_items {
+            0 => 0,
+            _ => self.end_offset(self.items - 1)?,
+        };
+        self.index.set_len(self.items * INDEX_ENTRY_SIZE)?;
+        // Remove the segments past the head one, left by a node stopping halfway
+        let mut segment = head_segment + 1;
+        while self.segment_path(segment).exists() {
+            fs::remove_file(self.segment_path(segment))?;
+            segment += 1;
+        }
+        self.head = open_file(&self.segment_path(head_segment))?;
+        self.head.set_len(self.head_len)?;
+        Ok(())
+    }
+
+    fn append(&mut self, item: &[u8]) -> Result<(), StoreError> {
+        if self.items > 0 && self.items % self.segment_items == 0 {
+            self.head.sync_data()?;
+            self.head = open_file(&self.segment_path(self.items / self.segment_items))?;
+            self.head_len = 0;
+        }
+        self.head.seek(SeekFrom::Start(self.head_len))?;
+        self.head.write_all(item)?;
+        self.head_len += item.len() as u64;
+        self.index
+            .seek(SeekFrom::Start(self.items * INDEX_ENTRY_SIZE))?;
+        self.index.write_all(&self.head_len.to_be_bytes())?;
+        self.items += 1;
+        Ok(())
+    }
+
+    // The data is flushed first, so the index never points past it
+    fn sync(&mut self) -> Result<(), StoreError> {
+        self.head.sync_data()?;
+        self.index.sync_data()?;
+        Ok(())
+    }
+
+    /// Deletes the segments whose items are all below the given one, returning whether any was
+    fn remove_segments_below(&mut self, item: u64) -> Result<bool, StoreError> {
+        let mut removed = false;
+        for segment in 0..item.min(self.items) / self.segment_items {
+            match fs::remove_file(self.segment_path(segment)) {
+                Ok(()) => removed = true,
+                Err(error) if error.kind() == ErrorKind::NotFound => {}
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Returns the given item, or None if it isn't in the table, is empty or its segment was
+    /// removed
+    fn get(&mut self, item: u64) -> Result<Option<Vec<u8>>, StoreError> {
+        if item >= self.items {
+            return Ok(None);
+        }
+        let start = match item % self.segment_items {
+            0 => 0,
+            _ => self.end_offset(item - 1)?,
+        };
+        let end = self.end_offset(item)?;
+        if end <= start {
+            return Ok(None);
+        }
+        let mut buf = vec![0; (end - start) as usize];
+        let segment = item / self.segment_items;
+        if segment == self.items / self.segment_items {
+            self.head.seek(SeekFrom::Start(start))?;
+            self.head.read_exact(&mut buf)?;
+        } else {
+            let mut file = match File::open(self.segment_path(segment)) {
+                Ok(file) => file,
+                Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+                Err(error) => return Err(error.into()),
+            };
+            file.seek(SeekFrom::Start(start))?;
+            file.read_exact(&mut buf)?;
+        }
+        Ok(Some(buf))
+    }
+
+    fn end_offset(&mut self, item: u64) -> Result<u64, StoreError> {
+        let mut buf = [0; INDEX_ENTRY_SIZE as usize];
+        self.index.seek(SeekFrom::Start(item * INDEX_ENTRY_SIZE))?;
+        self.index.read_exact(&mut buf)?;
+        Ok(u64::from_be_bytes(buf))
+    }
+}
+
+fn open_file(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ethereum_types::H256;
+    use ethrex_core::types::TxType;
+
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = PathBuf::from(format!("freezer-test-{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn frozen_block(number: BlockNumber) -> FrozenBlock {
+        let header = BlockHeader {
+            number,
+            ..Default::default()
+        };
+        FrozenBlock {
+            hash: header.compute_block_hash(),
+            header,
+            body: Some(BlockBody::default()),
+            receipts: Some(vec![Receipt::new(TxType::EIP1559, true, 21_000, vec![])]),
+        }
+    }
+
+    #[test]
+    fn frozen_blocks_can_be_read_back() {
+        let dir = test_dir("read");
+        let freezer = Freezer::open(&dir).unwrap();
+        let blocks = (0..3).map(frozen_block).collect::<Vec<_>>();
+        freezer.append(&blocks).unwrap();
+        // Blocks skipped by snap sync lack their body and receipts
+        let mut skipped = frozen_block(3);
+        skipped.body = None;
+        skipped.receipts = None;
+        freezer.append(&[skipped.clone()]).unwrap();
+
+        assert_eq!(freezer.frozen_blocks().unwrap(), 4);
+        for block in blocks {
+            let number = block.header.number;
+            assert_eq!(freezer.block_hash(number).unwrap(), Some(block.hash));
+            assert_eq!(freezer.header(number).unwrap(), Some(block.header));
+            assert_eq!(freezer.body(number).unwrap(), block.body);
+            assert_eq!(freezer.receipts(number).unwrap(), block.receipts);
+        }
+        assert_eq!(freezer.header(3).unwrap(), Some(skipped.header));
+        assert_eq!(freezer.body(3).unwrap(), None);
+        assert_eq!(freezer.receipts(3).unwrap(), None);
+        assert_eq!(freezer.block_hash(4).unwrap(), None);
+
+        // Blocks must follow the last frozen one
+        assert!(freezer.append(&[frozen_block(5)]).is_err());
+
+        drop(freezer);
+        let freezer = Freezer::open(&dir).unwrap();
+        assert_eq!(freezer.frozen_blocks().unwrap(), 4);
+        assert_eq!(freezer.block_hash(2).unwrap(), Some(frozen_block(2).hash));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn partially_appended_blocks_are_discarded() {
+        let dir = test_dir("repair");
+        let freezer = Freezer::open(&dir).unwrap();
+        freezer
+            .append(&(0..2).map(frozen_block).collect::<Vec<_>>())
+            .unwrap();
+        drop(freezer);
+
+        // Simulate a node stopping after writing a block's hash and half of its header
+        let mut hashes = FreezerTable::open(&dir, "hashes", SEGMENT_ITEMS).unwrap();
+        hashes.append(H256::repeat_byte(1).as_bytes()).unwrap();
+        drop(hashes);
+        let mut headers = OpenOptions::new()
+            .append(true)
+            .open(dir.join("headers.0000.dat"))
+            .unwrap();
+        headers.write_all(&[0xff; 10]).unwrap();
+        drop(headers);
+
+        let freezer = Freezer::open(&dir).unwrap();
+        assert_eq!(freezer.frozen_blocks().unwrap(), 2);
+        assert_eq!(freezer.block_hash(2).unwrap(), None);
+        freezer.append(&[frozen_block(2)]).unwrap();
+        assert_eq!(freezer.header(2).unwrap(), Some(frozen_block(2).header));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn expired_segments_are_deleted() {
+        let dir = test_dir("expire");
+        let freezer = Freezer::open_with_segment_items(&dir, 2).unwrap();
+        freezer
+            .append(&(0..5).map(frozen_block).collect::<Vec<_>>())
+            .unwrap();
+        assert_eq!(freezer.body(4).unwrap(), Some(BlockBody::default()));
+
+        // Only whole segments are deleted, along with the bodies and receipts
+        assert!(freezer.expire(3).unwrap());
+        assert!(!freezer.expire(3).unwrap());
+        assert_eq!(freezer.body(1).unwrap(), None);
+        assert_eq!(freezer.receipts(1).unwrap(), None);
+        assert_eq!(freezer.body(2).unwrap(), Some(BlockBody::default()));
+        assert_eq!(freezer.header(1).unwrap(), Some(frozen_block(1).header));
+
+        // Segments are still found after reopening
+        drop(freezer);
+        let freezer = Freezer::open_with_segment_items(&dir, 2).unwrap();
+        assert_eq!(freezer.frozen_blocks().unwrap(), 5);
+        assert_eq!(freezer.body(0).unwrap(), None);
+        freezer.append(&[frozen_block(5)]).unwrap();
+        assert_eq!(freezer.header(5).unwrap(), Some(frozen_block(5).header));
+        assert_eq!(freezer.body(3).unwrap(), Some(BlockBody::default()));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,101 @@
+//! Raw access to the database tables and checks of the stored state, for diagnosing a node's
+//! database from the command line.
+//!
+//! Tables are named as in the engine the database was written with. Each entry is returned as
+//! stored, along with a readable form of its key and value, decoded into the types the engine
+//! stores them as.
+
+use ethereum_types::H256;
+
+/// Amount of entries of a table and the bytes taken by them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableStats {
+    pub name: String,
+    pub entries: u64,
+    pub size: u64,
+}
+
+/// An entry of a table, as stored and decoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableEntry {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    pub decoded_key: String,
+    pub decoded_value: String,
+}
+
+/// Outcome of walking the state trie of a block along with the storage tries of its accounts
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StateVerification {
+    /// Amount of accounts found in the state trie
+    pub accounts: u64,
+    /// Hashes of the state trie nodes missing from the database
+    pub missing_state_nodes: Vec<H256>,
+    /// Hashes of the storage trie nodes missing from the database, by hashed account address
+    pub missing_storage_nodes: Vec<(H256, H256)>,
+}
+
+impl StateVerification {
+    /// Returns whether every node of the state was found
+    pub fn is_complete(&self) -> bool {
+        self.missing_state_nodes.is_empty() && self.missing_storage_nodes.is_empty()
+    }
+}
+
+/// Readable form of the keys and values stored in the database tables
+pub(crate) trait Inspect {
+    fn inspect(&self) -> String;
+}
+
+impl Inspect for u64 {
+    fn inspect(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl Inspect for () {
+    fn inspect(&self) -> String {
+        String::new()
+    }
+}
+
+impl Inspect for Vec<u8> {
+    fn inspect(&self) -> String {
+        format!("0x{}", hex::encode(self))
+    }
+}
+
+impl Inspect for &[u8] {
+    fn inspect(&self) -> String {
+        format!("0x{}", hex::encode(self))
+    }
+}
+
+impl<const N: usize> Inspect for [u8; N] {
+    fn inspect(&self) -> String {
+        format!("0x{}", hex::encode(self))
+    }
+}
+
+impl<A: Inspect, B: Inspect> Inspect for (A, B) {
+    fn inspect(&self) -> String {
+        format!("({}, {})", self.0.inspect(), self.1.inspect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rlp::{BlockHashRLP, TupleRLP};
+
+    #[test]
+    fn rlp_values_are_shown_decoded() {
+        let hash = H256::repeat_byte(0xab);
+        assert_eq!(BlockHashRLP::from(hash).inspect(), format!("{hash:?}"));
+        assert_eq!(
+            TupleRLP::from((hash, 7u64)).inspect(),
+            format!("({hash:?}, 7)")
+        );
+        assert_eq!(([1u8, 2], 3u64).inspect(), "(0x0102, 3)");
+    }
+}
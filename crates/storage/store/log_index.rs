@@ -0,0 +1,102 @@
+use std::collections::BTreeSet;
+
+use ethereum_types::{Address, H256};
+use ethrex_core::types::{BlockHash, BlockNumber, Receipt};
+
+/// Addresses and topics of the logs emitted in a block, as recorded in the log index.
+///
+/// The index maps each (address, block number) and (topic, block number) pair to the hash of the
+/// block the logs were emitted in. Blocks are indexed as their receipts are stored, whether they
+/// end up canonical or not, so lookups keep only the entries of canonical blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BlockLogIndex {
+    pub number: BlockNumber,
+    pub hash: BlockHash,
+    pub addresses: BTreeSet<Address>,
+    pub topics: BTreeSet<H256>,
+}
+
+impl BlockLogIndex {
+    pub fn new(number: BlockNumber, hash: BlockHash, receipts: &[Receipt]) -> Self {
+        let logs = receipts.iter().flat_map(|receipt| &receipt.logs);
+        Self {
+            number,
+            hash,
+            addresses: logs.clone().map(|log| log.address).collect(),
+            topics: logs.flat_map(|log| log.topics.iter().copied()).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty() && self.topics.is_empty()
+    }
+
+    /// Keys of the block's entries in the address index
+    pub fn address_keys(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        self.addresses
+            .iter()
+            .map(|address| log_index_key(address.as_bytes(), self.number, self.hash))
+    }
+
+    /// Keys of the block's entries in the topic index
+    pub fn topic_keys(&self) -> impl Iterator<Item = Vec<u8>> + '_ {
+        self.topics
+            .iter()
+            .map(|topic| log_index_key(topic.as_bytes(), self.number, self.hash))
+    }
+}
+
+/// Change to the log index, applied along with the rest of a write batch
+#[derive(Debug, Clone)]
+pub(crate) enum LogIndexUpdate {
+    Add(BlockLogIndex),
+    Remove(BlockLogIndex),
+}
+
+/// Key of a log index entry, with the block number in big endian so entries are sorted by it
+pub(crate) fn log_index_key(prefix: &[u8], number: BlockNumber, hash: BlockHash) -> Vec<u8> {
+    [prefix, &number.to_be_bytes(), hash.as_bytes()].concat()
+}
+
+/// First and last keys of the entries for `prefix` within the given block range
+pub(crate) fn log_index_range(
+    prefix: &[u8],
+    from: BlockNumber,
+    to: BlockNumber,
+) -> (Vec<u8>, Vec<u8>) {
+    (
+        log_index_key(prefix, from, H256::zero()),
+        log_index_key(prefix, to, H256::repeat_byte(0xff)),
+    )
+}
+
+/// Block number and hash of a log index entry, given its key
+pub(crate) fn decode_log_index_key(key: &[u8]) -> (BlockNumber, BlockHash) {
+    let (number, hash) = key[key.len() - 40..].split_at(8);
+    let mut number_bytes = [0; 8];
+    number_bytes.copy_from_slice(number);
+    (
+        BlockNumber::from_be_bytes(number_bytes),
+        BlockHash::from_slice(hash),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_index_keys_are_sorted_by_block_number() {
+        let address = Address::repeat_byte(1);
+        let hash = H256::repeat_byte(2);
+        let keys: Vec<_> = [256, 1, 255]
+            .into_iter()
+            .map(|number| log_index_key(address.as_bytes(), number, hash))
+            .collect();
+        assert!(keys[1] < keys[2] && keys[2] < keys[0]);
+        assert_eq!(decode_log_index_key(&keys[0]), (256, hash));
+
+        let (start, end) = log_index_range(address.as_bytes(), 1, 255);
+        assert!(start <= keys[1] && keys[2] <= end && keys[0] > end);
+    }
+}
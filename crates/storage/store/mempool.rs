@@ -0,0 +1,342 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
+
+use ethereum_types::{Address, H256};
+use ethrex_core::types::{BlobsBundle, MempoolTransaction};
+use ethrex_rlp::encode::RLPEncode;
+
+/// Rules applied to the transactions entering the mempool
+#[derive(Debug, Clone, Copy)]
+pub struct MempoolConfig {
+    /// Minimum fee increase, in percent, required for a transaction to replace a pooled one
+    /// with the same sender and nonce
+    pub price_bump: u64,
+    /// Maximum amount of pooled transactions
+    pub max_transactions: usize,
+    /// Maximum encoded size, in bytes, of the pooled transactions
+    pub max_size: usize,
+    /// Maximum amount of pooled blob transactions
+    pub max_blob_transactions: usize,
+    /// Maximum encoded size, in bytes, of the pooled blobs bundles
+    pub max_blobs_size: usize,
+    /// Maximum amount of pooled transactions from a single sender
+    pub max_account_slots: usize,
+    /// Maximum amount of queued transactions from a single sender
+    pub max_account_queue: usize,
+    /// Time after which a pooled transaction is dropped
+    pub lifetime: Duration,
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            price_bump: 10,
+            max_transactions: 10_000,
+            max_size: 64 * 1024 * 1024,
+            max_blob_transactions: 1_000,
+            max_blobs_size: 512 * 1024 * 1024,
+            max_account_slots: 64,
+            max_account_queue: 64,
+            lifetime: Duration::from_secs(3 * 60 * 60),
+        }
+    }
+}
+
+/// Amount and encoded size of the pooled transactions and blobs bundles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MempoolStats {
+    pub transactions: usize,
+    /// Amount of pooled transactions that can't be executed yet due to a nonce gap
+    pub queued: usize,
+    pub size: usize,
+    pub blob_transactions: usize,
+    pub blobs_size: usize,
+}
+
+/// Pooled transactions, indexed by hash and by sender and nonce, along with the blobs bundles of
+/// the pooled blob transactions.
+///
+/// Transactions are either pending, when they can be executed on top of the sender's current
+/// nonce, or queued, when there is a nonce gap before them. New transactions are pending until
+/// [`Mempool::reorganize`] is called for their sender.
+#[derive(Debug, Default)]
+pub struct Mempool {
+    transactions: HashMap<H256, MempoolTransaction>,
+    by_sender: HashMap<Address, BTreeMap<u64, H256>>,
+    queued: HashSet<H256>,
+    blobs_bundles: HashMap<H256, BlobsBundle>,
+    stats: MempoolStats,
+}
+
+impl Mempool {
+    /// Adds a transaction, replacing the one with the same hash or the same sender and nonce
+    pub fn insert(&mut self, hash: H256, transaction: MempoolTransaction) {
+        self.remove(&hash);
+        if let Some((replaced_hash, _)) =
+            self.get_by_nonce(transaction.sender(), transaction.nonce())
+        {
+            self.remove(&replaced_hash);
+        }
+        self.stats.transactions += 1;
+        self.stats.size += transaction.encode_canonical_to_vec().len();
+        self.by_sender
+            .entry(transaction.sender())
+            .or_default()
+            .insert(transaction.nonce(), hash);
+        self.transactions.insert(hash, transaction);
+    }
+
+    /// Adds the blobs bundle of a blob transaction
+    pub fn insert_blobs_bundle(&mut self, tx_hash: H256, blobs_bundle: BlobsBundle) {
+        if let Some(replaced) = self.blobs_bundles.remove(&tx_hash) {
+            self.stats.blob_transactions -= 1;
+            self.stats.blobs_size -= replaced.length();
+        }
+        self.stats.blob_transactions += 1;
+        self.stats.blobs_size += blobs_bundle.length();
+        self.blobs_bundles.insert(tx_hash, blobs_bundle);
+    }
+
+    /// Removes a transaction along with its blobs bundle, if any
+    pub fn remove(&mut self, hash: &H256) -> Option<MempoolTransaction> {
+        let transaction = self.transactions.remove(hash)?;
+        self.stats.transactions -= 1;
+        if self.queued.remove(hash) {
+            self.stats.queued -= 1;
+        }
+        self.stats.size -= transaction.encode_canonical_to_vec().len();
+        if let Some(nonces) = self.by_sender.get_mut(&transaction.sender()) {
+            nonces.remove(&transaction.nonce());
+            if nonces.is_empty() {
+                self.by_sender.remove(&transaction.sender());
+            }
+        }
+        if let Some(blobs_bundle) = self.blobs_bundles.remove(hash) {
+            self.stats.blob_transactions -= 1;
+            self.stats.blobs_size -= blobs_bundle.length();
+        }
+        Some(transaction)
+    }
+
+    pub fn get(&self, hash: &H256) -> Option<&MempoolTransaction> {
+        self.transactions.get(hash)
+    }
+
+    pub fn get_blobs_bundle(&self, tx_hash: &H256) -> Option<&BlobsBundle> {
+        self.blobs_bundles.get(tx_hash)
+    }
+
+    /// Returns the transaction sent by `sender` with the given nonce, along with its hash
+    pub fn get_by_nonce(&self, sender: Address, nonce: u64) -> Option<(H256, &MempoolTransaction)> {
+        let hash = *self.by_sender.get(&sender)?.get(&nonce)?;
+        Some((hash, self.transactions.get(&hash)?))
+    }
+
+    pub fn contains(&self, hash: &H256) -> bool {
+        self.transactions.contains_key(hash)
+    }
+
+    /// Amount of pooled transactions sent by `sender`
+    pub fn account_slots(&self, sender: Address) -> usize {
+        self.by_sender.get(&sender).map_or(0, BTreeMap::len)
+    }
+
+    /// Amount of queued transactions sent by `sender`
+    pub fn queued_slots(&self, sender: Address) -> usize {
+        self.by_sender.get(&sender).map_or(0, |nonces| {
+            nonces
+                .values()
+                .filter(|hash| self.queued.contains(hash))
+                .count()
+        })
+    }
+
+    pub fn is_queued(&self, hash: &H256) -> bool {
+        self.queued.contains(hash)
+    }
+
+    /// Returns whether a transaction from `sender` with the given nonce would be pending, that is,
+    /// whether the sender's transactions fill every nonce between `account_nonce` and it
+    pub fn is_executable(&self, sender: Address, nonce: u64, account_nonce: u64) -> bool {
+        if nonce < account_nonce {
+            return false;
+        }
+        let pooled = self
+            .by_sender
+            .get(&sender)
+            .map_or(0, |nonces| nonces.range(account_nonce..nonce).count());
+        pooled as u64 == nonce - account_nonce
+    }
+
+    /// Reclassifies the transactions sent by `sender` given its current nonce. Transactions with
+    /// contiguous nonces starting at `account_nonce` become pending, the ones after a gap become
+    /// queued and the ones below `account_nonce` are removed, as they can no longer be included.
+    /// Returns the hashes of the removed transactions
+    pub fn reorganize(&mut self, sender: Address, account_nonce: u64) -> Vec<H256> {
+        let Some(nonces) = self.by_sender.get(&sender) else {
+            return Vec::new();
+        };
+        let stale: Vec<H256> = nonces
+            .range(..account_nonce)
+            .map(|(_, hash)| *hash)
+            .collect();
+        let mut next_nonce = account_nonce;
+        let mut classified = Vec::new();
+        for (nonce, hash) in nonces.range(account_nonce..) {
+            let pending = *nonce == next_nonce;
+            if pending {
+                next_nonce += 1;
+            }
+            classified.push((*hash, pending));
+        }
+        for hash in &stale {
+            self.remove(hash);
+        }
+        for (hash, pending) in classified {
+            let was_queued = self.queued.contains(&hash);
+            if pending && was_queued {
+                self.queued.remove(&hash);
+                self.stats.queued -= 1;
+            } else if !pending && !was_queued {
+                self.queued.insert(hash);
+                self.stats.queued += 1;
+            }
+        }
+        stale
+    }
+
+    /// Returns the transactions sent by `sender` along with their hashes, sorted by nonce
+    pub fn sender_transactions(&self, sender: Address) -> Vec<(H256, &MempoolTransaction)> {
+        self.by_sender.get(&sender).map_or(Vec::new(), |nonces| {
+            nonces
+                .values()
+                .filter_map(|hash| Some((*hash, self.transactions.get(hash)?)))
+                .collect()
+        })
+    }
+
+    pub fn senders(&self) -> Vec<Address> {
+        self.by_sender.keys().copied().collect()
+    }
+
+    pub fn transactions(&self) -> impl Iterator<Item = (&H256, &MempoolTransaction)> {
+        self.transactions.iter()
+    }
+
+    /// Iterates over the transactions that can be executed on top of their sender's nonce
+    pub fn pending_transactions(&self) -> impl Iterator<Item = (&H256, &MempoolTransaction)> {
+        self.transactions
+            .iter()
+            .filter(|(hash, _)| !self.queued.contains(hash))
+    }
+
+    pub fn stats(&self) -> MempoolStats {
+        self.stats
+    }
+
+    /// Hashes of the transactions that reached the pool before `time`, in unix microseconds
+    pub fn received_before(&self, time: u128) -> Vec<H256> {
+        self.transactions
+            .iter()
+            .filter(|(_, tx)| tx.time() < time)
+            .map(|(hash, _)| *hash)
+            .collect()
+    }
+
+    /// Returns the pooled transactions along with their effective tip under the given base fee,
+    /// from the lowest tip to the highest. Transactions that can't cover the base fee count as
+    /// paying no tip
+    pub fn by_tip(&self, base_fee: Option<u64>) -> Vec<(H256, u64)> {
+        let mut transactions: Vec<(H256, u64)> = self
+            .transactions
+            .iter()
+            .map(|(hash, tx)| (*hash, tx.effective_gas_tip(base_fee).unwrap_or_default()))
+            .collect();
+        transactions.sort_by_key(|(_, tip)| *tip);
+        transactions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethrex_core::types::{EIP1559Transaction, Transaction};
+
+    fn pooled_tx(sender: Address, nonce: u64, max_priority_fee_per_gas: u64) -> MempoolTransaction {
+        let tx = Transaction::EIP1559Transaction(EIP1559Transaction {
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas: max_priority_fee_per_gas + 10,
+            gas_limit: 21_000,
+            ..Default::default()
+        });
+        MempoolTransaction::new(tx, sender)
+    }
+
+    #[test]
+    fn stats_track_inserted_and_removed_transactions() {
+        let mut mempool = Mempool::default();
+        let sender = Address::random();
+        let tx_a = pooled_tx(sender, 0, 1);
+        let hash_a = tx_a.compute_hash();
+        let tx_b = pooled_tx(sender, 1, 2);
+        let hash_b = tx_b.compute_hash();
+
+        mempool.insert(hash_a, tx_a.clone());
+        mempool.insert(hash_b, tx_b);
+        mempool.insert(hash_a, tx_a);
+        assert_eq!(mempool.stats().transactions, 2);
+        assert_eq!(mempool.account_slots(sender), 2);
+        assert_eq!(mempool.by_tip(Some(5)), vec![(hash_a, 1), (hash_b, 2)]);
+
+        // A transaction with the same sender and nonce takes the place of the pooled one
+        let tx_c = pooled_tx(sender, 1, 3);
+        let hash_c = tx_c.compute_hash();
+        mempool.insert(hash_c, tx_c);
+        assert!(!mempool.contains(&hash_b));
+        assert_eq!(
+            mempool.get_by_nonce(sender, 1).map(|(hash, _)| hash),
+            Some(hash_c)
+        );
+
+        mempool.remove(&hash_a);
+        mempool.remove(&hash_c);
+        assert_eq!(mempool.stats(), MempoolStats::default());
+        assert_eq!(mempool.account_slots(sender), 0);
+    }
+
+    #[test]
+    fn reorganize_queues_transactions_after_a_nonce_gap() {
+        let mut mempool = Mempool::default();
+        let sender = Address::random();
+        let hashes: Vec<H256> = [0, 1, 2, 4]
+            .into_iter()
+            .map(|nonce| {
+                let tx = pooled_tx(sender, nonce, 1);
+                let hash = tx.compute_hash();
+                mempool.insert(hash, tx);
+                hash
+            })
+            .collect();
+
+        let removed = mempool.reorganize(sender, 1);
+        assert_eq!(removed, vec![hashes[0]]);
+        assert!(!mempool.is_queued(&hashes[1]) && !mempool.is_queued(&hashes[2]));
+        assert!(mempool.is_queued(&hashes[3]));
+        assert_eq!(mempool.stats().queued, 1);
+        assert_eq!(mempool.pending_transactions().count(), 2);
+        assert!(mempool.is_executable(sender, 3, 1));
+        assert!(!mempool.is_executable(sender, 5, 1));
+
+        // Filling the gap promotes the queued transaction
+        let tx = pooled_tx(sender, 3, 1);
+        mempool.insert(tx.compute_hash(), tx);
+        mempool.reorganize(sender, 1);
+        assert_eq!(mempool.stats().queued, 0);
+        assert_eq!(mempool.queued_slots(sender), 0);
+
+        // Going back to an older nonce, as it happens on reorgs, demotes them all
+        mempool.reorganize(sender, 0);
+        assert_eq!(mempool.queued_slots(sender), 4);
+    }
+}
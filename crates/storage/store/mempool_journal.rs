@@ -0,0 +1,285 @@
+//! On-disk journal of the locally submitted transactions, so they survive restarts.
+//!
+//! Transactions and blobs bundles are appended to it as they are submitted, and it's rewritten
+//! from time to time with just the ones still pooled, so transactions that left the pool don't
+//! pile up. Each record is a kind byte followed by the payload length, as a big endian u32, and the
+//! payload: a transaction's canonical encoding, or a blob transaction's hash followed by the RLP
+//! encoding of its blobs bundle.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use ethereum_types::H256;
+use ethrex_core::types::{BlobsBundle, Transaction};
+use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
+use tracing::warn;
+
+use crate::error::StoreError;
+
+/// Name of the journal file within the data directory
+pub const MEMPOOL_JOURNAL_FILE: &str = "mempool.journal";
+
+const TRANSACTION_RECORD: u8 = 0;
+const BLOBS_BUNDLE_RECORD: u8 = 1;
+
+#[derive(Debug)]
+pub(crate) struct MempoolJournal {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    /// Hashes of the journaled transactions
+    locals: HashSet<H256>,
+}
+
+impl MempoolJournal {
+    /// Opens the journal at `path` to append to it. The given transactions are kept in it when
+    /// it's rewritten, along with the ones appended
+    pub fn open(path: &Path, locals: HashSet<H256>) -> Result<Self, StoreError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            locals,
+        })
+    }
+
+    /// Reads the journaled transactions in the order they were pooled, each one along with its
+    /// blobs bundle, if any. Corrupt records are skipped, and a record cut short, as left by a
+    /// crash while writing it, ends the journal
+    pub fn read(path: &Path) -> Result<Vec<(Transaction, Option<BlobsBundle>)>, StoreError> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error.into()),
+        };
+        let mut reader = BufReader::new(file);
+        let mut transactions = Vec::new();
+        let mut hashes = HashSet::new();
+        let mut blobs_bundles = HashMap::new();
+        while let Some((kind, payload)) = read_record(&mut reader)? {
+            match kind {
+                TRANSACTION_RECORD => match Transaction::decode_canonical(&payload) {
+                    Ok(transaction) => {
+                        if hashes.insert(transaction.compute_hash()) {
+                            transactions.push(transaction);
+                        }
+                    }
+                    Err(error) => warn!("Skipped corrupt mempool journal transaction: {error}"),
+                },
+                BLOBS_BUNDLE_RECORD if payload.len() >= 32 => {
+                    let tx_hash = H256::from_slice(&payload[..32]);
+                    match BlobsBundle::decode(&payload[32..]) {
+                        Ok(blobs_bundle) => {
+                            blobs_bundles.insert(tx_hash, blobs_bundle);
+                        }
+                        Err(error) => {
+                            warn!("Skipped corrupt mempool journal blobs bundle: {error}")
+                        }
+                    }
+                }
+                _ => warn!("Skipped invalid mempool journal record of kind {kind}"),
+            }
+        }
+        Ok(transactions
+            .into_iter()
+            .map(|transaction| {
+                let blobs_bundle = blobs_bundles.remove(&transaction.compute_hash());
+                (transaction, blobs_bundle)
+            })
+            .collect())
+    }
+
+    /// Appends a transaction to the journal. Writes are buffered until [`MempoolJournal::flush`]
+    pub fn append_transaction(&mut self, transaction: &Transaction) -> Result<(), StoreError> {
+        let record = encode_record(TRANSACTION_RECORD, &transaction.encode_canonical_to_vec());
+        self.writer.write_all(&record)?;
+        self.locals.insert(transaction.compute_hash());
+        Ok(())
+    }
+
+    pub fn append_blobs_bundle(
+        &mut self,
+        tx_hash: H256,
+        blobs_bundle: &BlobsBundle,
+    ) -> Result<(), StoreError> {
+        let mut payload = tx_hash.as_bytes().to_vec();
+        blobs_bundle.encode(&mut payload);
+        self.writer
+            .write_all(&encode_record(BLOBS_BUNDLE_RECORD, &payload))?;
+        Ok(())
+    }
+
+    /// Writes the buffered records to the journal file
+    pub fn flush(&mut self) -> Result<(), StoreError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Replaces the journal with one holding just the journaled transactions among the given
+    /// ones. The new journal is written aside and then moved over the old one, so a crash leaves
+    /// either of them whole
+    pub fn rewrite<'a>(
+        &mut self,
+        transactions: impl Iterator<Item = (&'a Transaction, Option<&'a BlobsBundle>)>,
+    ) -> Result<(), StoreError> {
+        let tmp_path = self.path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        let mut locals = HashSet::new();
+        for (transaction, blobs_bundle) in transactions {
+            let hash = transaction.compute_hash();
+            if !self.locals.contains(&hash) {
+                continue;
+            }
+            locals.insert(hash);
+            let tx_bytes = transaction.encode_canonical_to_vec();
+            writer.write_all(&encode_record(TRANSACTION_RECORD, &tx_bytes))?;
+            if let Some(blobs_bundle) = blobs_bundle {
+                let mut payload = hash.as_bytes().to_vec();
+                blobs_bundle.encode(&mut payload);
+                writer.write_all(&encode_record(BLOBS_BUNDLE_RECORD, &payload))?;
+            }
+        }
+        let file = writer.into_inner().map_err(|error| error.into_error())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+        *self = Self::open(&self.path, locals)?;
+        Ok(())
+    }
+}
+
+fn encode_record(kind: u8, payload: &[u8]) -> Vec<u8> {
+    let mut record = Vec::with_capacity(payload.len() + 5);
+    record.push(kind);
+    record.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    record.extend_from_slice(payload);
+    record
+}
+
+/// Reads the next record, returning None once the journal ends
+fn read_record(reader: &mut impl Read) -> Result<Option<(u8, Vec<u8>)>, StoreError> {
+    let mut prefix = [0; 5];
+    if !read_full(reader, &mut prefix)? {
+        return Ok(None);
+    }
+    let len = u32::from_be_bytes([prefix[1], prefix[2], prefix[3], prefix[4]]) as usize;
+    let mut payload = vec![0; len];
+    if !read_full(reader, &mut payload)? {
+        return Ok(None);
+    }
+    Ok(Some((prefix[0], payload)))
+}
+
+/// Fills the buffer, returning false if the reader ran out of data before
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> Result<bool, StoreError> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(error) => Err(error.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethrex_core::types::{EIP1559Transaction, EIP4844Transaction};
+
+    use super::*;
+
+    fn test_path(name: &str) -> PathBuf {
+        let path = PathBuf::from(format!("mempool-journal-test-{name}"));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn transaction(nonce: u64) -> Transaction {
+        Transaction::EIP1559Transaction(EIP1559Transaction {
+            nonce,
+            gas_limit: 21_000,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn journaled_transactions_can_be_read_back() {
+        let path = test_path("read");
+        let blob_tx = Transaction::EIP4844Transaction(EIP4844Transaction::default());
+        let blobs_bundle = BlobsBundle::default();
+        let mut journal = MempoolJournal::open(&path, HashSet::new()).unwrap();
+        journal.append_transaction(&transaction(0)).unwrap();
+        journal.append_transaction(&blob_tx).unwrap();
+        journal
+            .append_blobs_bundle(blob_tx.compute_hash(), &blobs_bundle)
+            .unwrap();
+        journal.append_transaction(&transaction(0)).unwrap();
+        journal.flush().unwrap();
+
+        let journaled = MempoolJournal::read(&path).unwrap();
+        assert_eq!(
+            journaled,
+            vec![(transaction(0), None), (blob_tx, Some(blobs_bundle))]
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rewrite_keeps_just_the_pooled_journaled_transactions() {
+        let path = test_path("rewrite");
+        let mut journal = MempoolJournal::open(&path, HashSet::new()).unwrap();
+        for nonce in 0..3 {
+            journal.append_transaction(&transaction(nonce)).unwrap();
+        }
+        let (kept, not_journaled) = (transaction(1), transaction(4));
+        journal
+            .rewrite([(&kept, None), (&not_journaled, None)].into_iter())
+            .unwrap();
+        journal.append_transaction(&transaction(3)).unwrap();
+        drop(journal);
+
+        let journaled = MempoolJournal::read(&path).unwrap();
+        assert_eq!(journaled, vec![(kept, None), (transaction(3), None)]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn truncated_record_ends_the_journal() {
+        let path = test_path("truncated");
+        let mut journal = MempoolJournal::open(&path, HashSet::new()).unwrap();
+        journal.append_transaction(&transaction(0)).unwrap();
+        journal.append_transaction(&transaction(1)).unwrap();
+        drop(journal);
+        let len = fs::metadata(&path).unwrap().len();
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_len(len - 1)
+            .unwrap();
+
+        let journaled = MempoolJournal::read(&path).unwrap();
+        assert_eq!(journaled, vec![(transaction(0), None)]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn corrupt_records_are_skipped() {
+        let path = test_path("corrupt");
+        let mut journal = MempoolJournal::open(&path, HashSet::new()).unwrap();
+        journal.append_transaction(&transaction(0)).unwrap();
+        journal
+            .writer
+            .write_all(&encode_record(TRANSACTION_RECORD, &[0xff, 0x01]))
+            .unwrap();
+        journal.writer.write_all(&encode_record(7, &[0])).unwrap();
+        journal.append_transaction(&transaction(1)).unwrap();
+        drop(journal);
+
+        let journaled = MempoolJournal::read(&path).unwrap();
+        assert_eq!(
+            journaled,
+            vec![(transaction(0), None), (transaction(1), None)]
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,187 @@
+use std::cmp::Ordering;
+
+use tracing::info;
+
+use crate::{engines::api::StoreEngine, error::StoreError};
+
+/// Version of the database schema written by this build.
+/// It must be bumped, along with a migration from the previous version, whenever the layout or
+/// encoding of a table changes, e.g. when a field is added to the block header.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// Schema version of the databases written before it was recorded
+const UNVERSIONED: u64 = 0;
+
+struct Migration {
+    /// Version upgraded from, to the next one
+    from: u64,
+    description: &'static str,
+    /// Whether it rewrites stored data, which may take long, so it's left to `ethrex db migrate`.
+    /// The other migrations are run when the database is opened
+    rewrites_data: bool,
+    run: fn(&dyn StoreEngine) -> Result<(), StoreError>,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: UNVERSIONED,
+    description: "record the schema version",
+    rewrites_data: false,
+    run: record_schema_version,
+}];
+
+// The layout didn't change, unversioned databases only lack the version
+fn record_schema_version(_engine: &dyn StoreEngine) -> Result<(), StoreError> {
+    Ok(())
+}
+
+/// Returns the schema version of the database. New databases are written with the current one,
+/// which is recorded right away
+fn schema_version(engine: &dyn StoreEngine) -> Result<u64, StoreError> {
+    if let Some(version) = engine.get_schema_version()? {
+        return Ok(version);
+    }
+    // Every database holding a chain has its genesis block
+    if engine.get_canonical_block_hash(0)?.is_some() {
+        return Ok(UNVERSIONED);
+    }
+    engine.update_schema_version(SCHEMA_VERSION)?;
+    Ok(SCHEMA_VERSION)
+}
+
+/// Upgrades the database to the schema of this build when opening it, as long as the migrations
+/// left don't rewrite stored data. Those have to be run with [`migrate`]
+pub(crate) fn upgrade_schema_version(engine: &dyn StoreEngine) -> Result<(), StoreError> {
+    let found = schema_version(engine)?;
+    let version = run_migrations(engine, MIGRATIONS, found, SCHEMA_VERSION, false)?;
+    match version.cmp(&SCHEMA_VERSION) {
+        Ordering::Equal => Ok(()),
+        Ordering::Less => Err(StoreError::OutdatedSchemaVersion {
+            found: version,
+            expected: SCHEMA_VERSION,
+        }),
+        Ordering::Greater => Err(StoreError::UnsupportedSchemaVersion {
+            found: version,
+            supported: SCHEMA_VERSION,
+        }),
+    }
+}
+
+/// Upgrades the database to the schema of this build, one version at a time. The version is
+/// recorded after each migration, so an interrupted upgrade resumes from the last one completed.
+/// Returns the version the database was at
+pub(crate) fn migrate(engine: &dyn StoreEngine) -> Result<u64, StoreError> {
+    let found = schema_version(engine)?;
+    if found > SCHEMA_VERSION {
+        return Err(StoreError::UnsupportedSchemaVersion {
+            found,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    run_migrations(engine, MIGRATIONS, found, SCHEMA_VERSION, true)?;
+    Ok(found)
+}
+
+// Runs the migrations from the found version up to the target one, stopping before the first one
+// rewriting data unless told to run those too. Returns the version reached
+fn run_migrations(
+    engine: &dyn StoreEngine,
+    migrations: &[Migration],
+    found: u64,
+    target: u64,
+    rewrite_data: bool,
+) -> Result<u64, StoreError> {
+    for version in found..target {
+        let migration = migrations
+            .iter()
+            .find(|migration| migration.from == version)
+            .ok_or_else(|| {
+                StoreError::Custom(format!("No migration from schema version {version}"))
+            })?;
+        if migration.rewrites_data && !rewrite_data {
+            return Ok(version);
+        }
+        info!(
+            "Migrating database schema from version {version} to {}: {}",
+            version + 1,
+            migration.description
+        );
+        (migration.run)(engine)?;
+        engine.update_schema_version(version + 1)?;
+    }
+    Ok(found.max(target))
+}
+
+#[cfg(test)]
+mod tests {
+    use ethereum_types::H256;
+
+    use super::*;
+    use crate::engines::in_memory::Store as InMemoryStore;
+
+    #[test]
+    fn migrations_cover_every_version() {
+        for version in UNVERSIONED..SCHEMA_VERSION {
+            assert!(MIGRATIONS.iter().any(|migration| migration.from == version));
+        }
+    }
+
+    #[test]
+    fn new_databases_get_the_current_version() {
+        let engine = InMemoryStore::new();
+        upgrade_schema_version(&engine).unwrap();
+        assert_eq!(engine.get_schema_version().unwrap(), Some(SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn unversioned_databases_are_migrated() {
+        let engine = InMemoryStore::new();
+        engine.set_canonical_block(0, H256::random()).unwrap();
+        assert_eq!(migrate(&engine).unwrap(), UNVERSIONED);
+        upgrade_schema_version(&engine).unwrap();
+        // Migrating an up to date database does nothing
+        assert_eq!(migrate(&engine).unwrap(), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn unversioned_databases_are_upgraded_at_open() {
+        let engine = InMemoryStore::new();
+        engine.set_canonical_block(0, H256::random()).unwrap();
+        upgrade_schema_version(&engine).unwrap();
+        assert_eq!(engine.get_schema_version().unwrap(), Some(SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn migrations_rewriting_data_are_left_to_migrate() {
+        let migration = |from, rewrites_data| Migration {
+            from,
+            description: "test",
+            rewrites_data,
+            run: |_| Ok(()),
+        };
+        let migrations = [
+            migration(UNVERSIONED, false),
+            migration(UNVERSIONED + 1, true),
+            migration(UNVERSIONED + 2, false),
+        ];
+        let engine = InMemoryStore::new();
+        // At open, the upgrade stops before the migration rewriting data
+        let version = run_migrations(&engine, &migrations, UNVERSIONED, 3, false).unwrap();
+        assert_eq!(version, UNVERSIONED + 1);
+        assert_eq!(engine.get_schema_version().unwrap(), Some(UNVERSIONED + 1));
+
+        let version = run_migrations(&engine, &migrations, version, 3, true).unwrap();
+        assert_eq!(version, 3);
+        assert_eq!(engine.get_schema_version().unwrap(), Some(3));
+    }
+
+    #[test]
+    fn newer_databases_are_refused() {
+        let engine = InMemoryStore::new();
+        engine.update_schema_version(SCHEMA_VERSION + 1).unwrap();
+        assert!(matches!(
+            upgrade_schema_version(&engine),
+            Err(StoreError::UnsupportedSchemaVersion { .. })
+        ));
+        assert!(migrate(&engine).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::{HashMap, HashSet};
+
+use ethereum_types::H256;
+
+/// Maximum amount of trie nodes checked by each step of a state pruning sweep
+pub const STATE_PRUNING_BATCH_SIZE: usize = 10_000;
+
+/// Hashes of the trie nodes reachable from the state roots kept by state pruning. Every other
+/// node can be deleted.
+#[derive(Debug, Default)]
+pub struct ReachableNodes {
+    /// State trie nodes
+    pub state: HashSet<H256>,
+    /// Storage trie nodes, by hashed account address
+    pub storage: HashMap<H256, HashSet<H256>>,
+}
+
+impl ReachableNodes {
+    /// Returns whether the state trie node stored under the given key is reachable.
+    /// Keys that aren't node hashes are treated as reachable, so they are never deleted
+    pub fn has_state_node(&self, key: &[u8]) -> bool {
+        key.len() != 32 || self.state.contains(&H256::from_slice(key))
+    }
+
+    /// Returns whether the storage trie node stored under the given key for the account is
+    /// reachable. Keys that aren't node hashes are treated as reachable, so they are never deleted
+    pub fn has_storage_node(&self, hashed_address: &H256, key: &[u8]) -> bool {
+        key.len() != 32
+            || self
+                .storage
+                .get(hashed_address)
+                .is_some_and(|nodes| nodes.contains(&H256::from_slice(key)))
+    }
+}
+
+/// Where a state pruning sweep goes on from: the first key left to check in the state trie nodes
+/// table, or in the storage trie nodes table once every state trie node was checked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PruneCursor {
+    StateNodes(Vec<u8>),
+    /// Hashed account address and node key, as stored
+    StorageNodes(H256, Vec<u8>),
+}
+
+impl Default for PruneCursor {
+    fn default() -> Self {
+        Self::StateNodes(Vec::new())
+    }
+}
+
+impl PruneCursor {
+    /// Start of the sweep of the storage trie nodes table
+    pub fn storage_nodes() -> Self {
+        Self::StorageNodes(H256::zero(), Vec::new())
+    }
+}
@@ -8,6 +8,9 @@ use ethrex_core::{
     H256,
 };
 use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
+
+use crate::inspect::Inspect;
+
 #[cfg(feature = "libmdbx")]
 use libmdbx::orm::{Decodable, Encodable};
 #[cfg(feature = "redb")]
@@ -53,6 +56,15 @@ impl<T: RLPDecode> Rlp<T> {
     }
 }
 
+impl<T: RLPDecode + Debug> Inspect for Rlp<T> {
+    fn inspect(&self) -> String {
+        match T::decode(&self.0) {
+            Ok(value) => format!("{value:?}"),
+            Err(_) => self.0.inspect(),
+        }
+    }
+}
+
 #[cfg(feature = "libmdbx")]
 impl<T: Send + Sync> Decodable for Rlp<T> {
     fn decode(b: &[u8]) -> anyhow::Result<Self> {
@@ -0,0 +1,461 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard},
+    thread::JoinHandle,
+};
+
+use ethereum_types::{H256, U256};
+use ethrex_core::types::{AccountState, BlockNumber, EMPTY_TRIE_HASH};
+use ethrex_rlp::{
+    decode::RLPDecode,
+    encode::RLPEncode,
+    error::RLPDecodeError,
+    structs::{Decoder, Encoder},
+};
+use tracing::{info, warn};
+
+use crate::{engines::api::StoreEngine, error::StoreError};
+
+/// Maximum amount of diff layers stacked over the disk layer. Older layers are merged into it
+pub const MAX_SNAPSHOT_LAYERS: usize = 128;
+
+/// Amount of entries written at once while regenerating the disk layer from the tries
+const SNAPSHOT_GENERATION_CHUNK: usize = 10_000;
+
+/// Changes made to the state, by hashed account address and hashed storage key
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SnapshotChanges {
+    /// Updated accounts
+    pub accounts: HashMap<H256, AccountState>,
+    /// Removed accounts, along with all of their storage
+    pub removed: HashSet<H256>,
+    /// Updated storage slots. Zero values are deleted slots
+    pub storage: HashMap<H256, HashMap<H256, U256>>,
+}
+
+/// State changes of a block, or a segment of blocks imported at once, on top of its parent's
+/// state. Identified by the state root it leads to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotLayer {
+    pub state_root: H256,
+    pub parent_root: H256,
+    pub number: BlockNumber,
+    pub changes: SnapshotChanges,
+}
+
+impl SnapshotChanges {
+    /// Returns the account as left by these changes, or None if they didn't touch it.
+    /// Removed accounts are returned as Some(None)
+    pub fn account(&self, hashed_address: &H256) -> Option<Option<&AccountState>> {
+        match self.accounts.get(hashed_address) {
+            Some(account) => Some(Some(account)),
+            None => self.removed.contains(hashed_address).then_some(None),
+        }
+    }
+
+    /// Returns the storage value as left by these changes, or None if they didn't touch it.
+    /// Deleted slots, and every slot of a removed account, are returned as zero
+    pub fn storage(&self, hashed_address: &H256, hashed_key: &H256) -> Option<U256> {
+        match self
+            .storage
+            .get(hashed_address)
+            .and_then(|storage| storage.get(hashed_key))
+        {
+            Some(value) => Some(*value),
+            None => self
+                .removed
+                .contains(hashed_address)
+                .then_some(U256::zero()),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.accounts.len() + self.storage.values().map(HashMap::len).sum::<usize>()
+    }
+}
+
+impl RLPEncode for SnapshotChanges {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        let accounts: Vec<_> = self.accounts.iter().map(|(k, v)| (*k, v.clone())).collect();
+        let removed: Vec<_> = self.removed.iter().copied().collect();
+        let storage: Vec<(H256, Vec<(H256, U256)>)> = self
+            .storage
+            .iter()
+            .map(|(address, slots)| (*address, slots.iter().map(|(k, v)| (*k, *v)).collect()))
+            .collect();
+        Encoder::new(buf)
+            .encode_field(&accounts)
+            .encode_field(&removed)
+            .encode_field(&storage)
+            .finish();
+    }
+}
+
+impl RLPDecode for SnapshotChanges {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(SnapshotChanges, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (accounts, decoder): (Vec<(H256, AccountState)>, _) =
+            decoder.decode_field("accounts")?;
+        let (removed, decoder): (Vec<H256>, _) = decoder.decode_field("removed")?;
+        let (storage, decoder): (Vec<(H256, Vec<(H256, U256)>)>, _) =
+            decoder.decode_field("storage")?;
+        let changes = SnapshotChanges {
+            accounts: accounts.into_iter().collect(),
+            removed: removed.into_iter().collect(),
+            storage: storage
+                .into_iter()
+                .map(|(address, slots)| (address, slots.into_iter().collect()))
+                .collect(),
+        };
+        Ok((changes, decoder.finish()?))
+    }
+}
+
+impl RLPEncode for SnapshotLayer {
+    fn encode(&self, buf: &mut dyn bytes::BufMut) {
+        Encoder::new(buf)
+            .encode_field(&self.state_root)
+            .encode_field(&self.parent_root)
+            .encode_field(&self.number)
+            .encode_field(&self.changes)
+            .finish();
+    }
+}
+
+impl RLPDecode for SnapshotLayer {
+    fn decode_unfinished(rlp: &[u8]) -> Result<(SnapshotLayer, &[u8]), RLPDecodeError> {
+        let decoder = Decoder::new(rlp)?;
+        let (state_root, decoder) = decoder.decode_field("state_root")?;
+        let (parent_root, decoder) = decoder.decode_field("parent_root")?;
+        let (number, decoder) = decoder.decode_field("number")?;
+        let (changes, decoder) = decoder.decode_field("changes")?;
+        let layer = SnapshotLayer {
+            state_root,
+            parent_root,
+            number,
+            changes,
+        };
+        Ok((layer, decoder.finish()?))
+    }
+}
+
+/// Flat view of the state, made of a disk layer holding the whole state at a given root and the
+/// diff layers of the latest blocks stacked on top of it, so the state of recent blocks on any
+/// branch can be read without walking the tries.
+/// The tries stay authoritative: reads at state roots the snapshot doesn't reach return None.
+#[derive(Debug)]
+pub(crate) struct Snapshot {
+    layers: RwLock<SnapshotLayers>,
+    /// Generation of the disk layer running in the background, if any
+    generator: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[derive(Debug, Default)]
+struct SnapshotLayers {
+    /// State root of the disk layer, None while it is being generated
+    disk_root: Option<H256>,
+    /// Whether the disk layer is being generated. Diff layers are only stacked meanwhile
+    generating: bool,
+    /// Identifies the latest generation of the disk layer, so superseded ones stop
+    generation: u64,
+    diffs: HashMap<H256, SnapshotLayer>,
+}
+
+impl Snapshot {
+    /// Loads the disk layer root and the diff layers stored by the engine
+    pub fn load(engine: &dyn StoreEngine) -> Result<Self, StoreError> {
+        let diffs = engine
+            .get_snapshot_layers()?
+            .into_iter()
+            .map(|layer| (layer.state_root, layer))
+            .collect();
+        Ok(Self {
+            layers: RwLock::new(SnapshotLayers {
+                disk_root: engine.get_snapshot_root()?,
+                diffs,
+                ..Default::default()
+            }),
+            generator: Mutex::new(None),
+        })
+    }
+
+    /// Returns the account at the given state root, or None if the snapshot doesn't reach it.
+    /// Missing accounts are returned as Some(None)
+    pub fn account(
+        &self,
+        engine: &dyn StoreEngine,
+        state_root: H256,
+        hashed_address: H256,
+    ) -> Result<Option<Option<AccountState>>, StoreError> {
+        let layers = self.read()?;
+        let mut root = state_root;
+        // Bounded, in case a state root is repeated along a branch
+        for _ in 0..=layers.diffs.len() {
+            if layers.disk_root == Some(root) {
+                return engine.get_account_snapshot(hashed_address).map(Some);
+            }
+            let Some(layer) = layers.diffs.get(&root) else {
+                break;
+            };
+            if let Some(account) = layer.changes.account(&hashed_address) {
+                return Ok(Some(account.cloned()));
+            }
+            root = layer.parent_root;
+        }
+        Ok(None)
+    }
+
+    /// Returns the storage value at the given state root, or None if the snapshot doesn't reach
+    /// it. Missing slots are returned as Some(None)
+    pub fn storage(
+        &self,
+        engine: &dyn StoreEngine,
+        state_root: H256,
+        hashed_address: H256,
+        hashed_key: H256,
+    ) -> Result<Option<Option<U256>>, StoreError> {
+        let layers = self.read()?;
+        let mut root = state_root;
+        for _ in 0..=layers.diffs.len() {
+            if layers.disk_root == Some(root) {
+                return engine
+                    .get_storage_snapshot(hashed_address, hashed_key)
+                    .map(Some);
+            }
+            let Some(layer) = layers.diffs.get(&root) else {
+                break;
+            };
+            if let Some(value) = layer.changes.storage(&hashed_address, &hashed_key) {
+                return Ok(Some((!value.is_zero()).then_some(value)));
+            }
+            root = layer.parent_root;
+        }
+        Ok(None)
+    }
+
+    /// Stacks a new diff layer. Once a branch has more than [`MAX_SNAPSHOT_LAYERS`] diff layers,
+    /// the oldest ones are merged into the disk layer. If they don't lead to the disk layer, as
+    /// it happens after a state sync, the disk layer is generated again at the new layer's state
+    /// in the background, and reads fall back to the tries until it's done.
+    pub fn add_layer(
+        self: &Arc<Self>,
+        engine: &Arc<dyn StoreEngine>,
+        layer: SnapshotLayer,
+    ) -> Result<(), StoreError> {
+        let mut layers = self.write()?;
+        engine.add_snapshot_layer(&layer)?;
+        let state_root = layer.state_root;
+        let number = layer.number;
+        layers.diffs.insert(state_root, layer);
+        if layers.generating {
+            return Ok(());
+        }
+
+        // Diff layers of the new layer's branch, newest first
+        let mut branch = Vec::new();
+        let mut root = state_root;
+        while let Some(layer) = layers.diffs.get(&root) {
+            if branch.len() > layers.diffs.len() {
+                break;
+            }
+            branch.push(root);
+            if layers.disk_root == Some(layer.parent_root) {
+                break;
+            }
+            root = layer.parent_root;
+        }
+        if branch.len() <= MAX_SNAPSHOT_LAYERS {
+            return Ok(());
+        }
+        let bottom = &layers.diffs[&branch[branch.len() - 1]];
+        if layers.disk_root != Some(bottom.parent_root) {
+            let generation = Self::start_generation(engine.as_ref(), &mut layers)?;
+            drop(layers);
+            let (snapshot, engine) = (self.clone(), engine.clone());
+            let generator = std::thread::spawn(move || {
+                if let Err(error) =
+                    snapshot.build_disk_layer(engine.as_ref(), generation, state_root, number)
+                {
+                    warn!("Failed to generate state snapshot: {error}");
+                }
+            });
+            *self.generator()? = Some(generator);
+            return Ok(());
+        }
+        // Merge the layers past the limit, oldest first
+        for root in branch[MAX_SNAPSHOT_LAYERS..].iter().rev() {
+            let Some(layer) = layers.diffs.get(root) else {
+                continue;
+            };
+            engine.flatten_snapshot_layer(layer)?;
+            let (state_root, number) = (layer.state_root, layer.number);
+            layers.disk_root = Some(state_root);
+            layers.diffs.retain(|_, layer| layer.number > number);
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the disk layer from the tries at the given state root, dropping the diff layers
+    /// up to the given block number. Supersedes any generation running in the background
+    pub fn generate(
+        &self,
+        engine: &dyn StoreEngine,
+        state_root: H256,
+        number: BlockNumber,
+    ) -> Result<(), StoreError> {
+        let generation = Self::start_generation(engine, &mut *self.write()?)?;
+        self.build_disk_layer(engine, generation, state_root, number)
+    }
+
+    /// Waits for the generation of the disk layer running in the background to finish, if any
+    #[cfg(test)]
+    pub fn wait_for_generation(&self) -> Result<(), StoreError> {
+        let generator = self.generator()?.take();
+        if let Some(generator) = generator {
+            generator
+                .join()
+                .map_err(|_| StoreError::Custom("Snapshot generation panicked".to_string()))?;
+        }
+        Ok(())
+    }
+
+    // Drops the disk layer, so reads can't reach it until it is complete, and starts a new
+    // generation of it, returning its identifier
+    fn start_generation(
+        engine: &dyn StoreEngine,
+        layers: &mut SnapshotLayers,
+    ) -> Result<u64, StoreError> {
+        engine.clear_snapshot()?;
+        layers.disk_root = None;
+        layers.generating = true;
+        layers.generation += 1;
+        Ok(layers.generation)
+    }
+
+    // Writes the disk layer at the given state root from the tries, a chunk at a time. Stops
+    // if a newer generation started meanwhile
+    fn build_disk_layer(
+        &self,
+        engine: &dyn StoreEngine,
+        generation: u64,
+        state_root: H256,
+        number: BlockNumber,
+    ) -> Result<(), StoreError> {
+        info!("Generating state snapshot at state root {state_root:#x}");
+        let mut changes = SnapshotChanges::default();
+        for (path, value) in engine.open_state_trie(state_root).into_iter().content() {
+            let hashed_address = H256::from_slice(&path);
+            let account = AccountState::decode(&value)?;
+            if account.storage_root != *EMPTY_TRIE_HASH {
+                let storage_trie = engine.open_storage_trie(hashed_address, account.storage_root);
+                for (path, value) in storage_trie.into_iter().content() {
+                    changes
+                        .storage
+                        .entry(hashed_address)
+                        .or_default()
+                        .insert(H256::from_slice(&path), U256::decode(&value)?);
+                    if changes.len() >= SNAPSHOT_GENERATION_CHUNK
+                        && !self.write_chunk(engine, generation, &mut changes)?
+                    {
+                        return Ok(());
+                    }
+                }
+            }
+            changes.accounts.insert(hashed_address, account);
+            if changes.len() >= SNAPSHOT_GENERATION_CHUNK
+                && !self.write_chunk(engine, generation, &mut changes)?
+            {
+                return Ok(());
+            }
+        }
+
+        let mut layers = self.write()?;
+        if layers.generation != generation {
+            return Ok(());
+        }
+        let layer = SnapshotLayer {
+            state_root,
+            parent_root: state_root,
+            number,
+            changes,
+        };
+        engine.flatten_snapshot_layer(&layer)?;
+        layers.disk_root = Some(state_root);
+        layers.generating = false;
+        layers.diffs.retain(|_, layer| layer.number > number);
+        info!("Generated state snapshot at state root {state_root:#x}");
+        Ok(())
+    }
+
+    // Writes a chunk of the disk layer being generated, returning false without writing it if
+    // the generation was superseded. The read lock keeps a newer one from clearing the disk
+    // layer meanwhile
+    fn write_chunk(
+        &self,
+        engine: &dyn StoreEngine,
+        generation: u64,
+        changes: &mut SnapshotChanges,
+    ) -> Result<bool, StoreError> {
+        let layers = self.read()?;
+        if layers.generation != generation {
+            return Ok(false);
+        }
+        engine.write_snapshot_changes(&std::mem::take(changes))?;
+        Ok(true)
+    }
+
+    fn generator(&self) -> Result<MutexGuard<'_, Option<JoinHandle<()>>>, StoreError> {
+        self.generator
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, SnapshotLayers>, StoreError> {
+        self.layers
+            .read()
+            .map_err(|error| StoreError::Custom(error.to_string()))
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, SnapshotLayers>, StoreError> {
+        self.layers
+            .write()
+            .map_err(|error| StoreError::Custom(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removed_accounts_hide_their_storage() {
+        let (removed, updated) = (H256::repeat_byte(1), H256::repeat_byte(2));
+        let key = H256::repeat_byte(3);
+        let changes = SnapshotChanges {
+            accounts: HashMap::from([(updated, AccountState::default())]),
+            removed: HashSet::from([removed]),
+            storage: HashMap::from([(updated, HashMap::from([(key, U256::from(7))]))]),
+        };
+
+        assert_eq!(changes.account(&removed), Some(None));
+        assert_eq!(changes.storage(&removed, &key), Some(U256::zero()));
+        assert_eq!(
+            changes.account(&updated),
+            Some(Some(&AccountState::default()))
+        );
+        assert_eq!(changes.storage(&updated, &key), Some(U256::from(7)));
+        assert_eq!(changes.storage(&updated, &removed), None);
+        assert_eq!(changes.account(&key), None);
+
+        let layer = SnapshotLayer {
+            state_root: H256::repeat_byte(4),
+            parent_root: H256::repeat_byte(5),
+            number: 6,
+            changes,
+        };
+        assert_eq!(
+            SnapshotLayer::decode(&layer.encode_to_vec()).unwrap(),
+            layer
+        );
+    }
+}
@@ -8,32 +8,92 @@ use engines::api::StoreEngine;
 use engines::redb::RedBStore;
 use ethereum_types::{Address, H256, U256};
 use ethrex_core::types::{
-    code_hash, AccountInfo, AccountState, BlobsBundle, Block, BlockBody, BlockHash, BlockHeader,
-    BlockNumber, ChainConfig, Genesis, GenesisAccount, Index, MempoolTransaction, Receipt,
-    Transaction, TxType, EMPTY_TRIE_HASH,
+    code_hash, AccountInfo, AccountState, BatchStatus, BlobsBundle, Block, BlockBody, BlockHash,
+    BlockHeader, BlockNumber, ChainConfig, DepositInfo, Genesis, GenesisAccount, Index,
+    L1FeeComponents, MempoolTransaction, Receipt, Transaction, WithdrawalProof, EMPTY_TRIE_HASH,
 };
 use ethrex_rlp::decode::RLPDecode;
 use ethrex_rlp::encode::RLPEncode;
 use ethrex_trie::Trie;
 use serde::{Deserialize, Serialize};
 use sha3::{Digest as _, Keccak256};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt::Debug;
-use std::sync::{Arc, Mutex};
-use tracing::info;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+use tracing::{info, warn};
 
+mod batch;
+mod bundle_pool;
 mod engines;
 pub mod error;
+mod freezer;
+mod inspect;
+mod log_index;
+mod mempool;
+mod mempool_journal;
+mod migrations;
+mod pruning;
 mod rlp;
+mod snapshot;
+
+use batch::StagedBlock;
+pub use batch::WriteBatch;
+pub use bundle_pool::{Bundle, BundlePool};
+pub use freezer::FREEZER_DIR;
+use freezer::{Freezer, FrozenBlock};
+pub use inspect::{StateVerification, TableEntry, TableStats};
+use log_index::{BlockLogIndex, LogIndexUpdate};
+pub use mempool::{Mempool, MempoolConfig, MempoolStats};
+use mempool_journal::MempoolJournal;
+pub use mempool_journal::MEMPOOL_JOURNAL_FILE;
+pub use migrations::SCHEMA_VERSION;
+pub use pruning::{PruneCursor, ReachableNodes, STATE_PRUNING_BATCH_SIZE};
+pub use snapshot::MAX_SNAPSHOT_LAYERS;
+use snapshot::{Snapshot, SnapshotChanges, SnapshotLayer};
 
 #[derive(Debug, Clone)]
 pub struct Store {
     // TODO: Check if we can remove this mutex and move it to the in_memory::Store struct
     engine: Arc<dyn StoreEngine>,
-    pub mempool: Arc<Mutex<HashMap<H256, MempoolTransaction>>>,
-    pub blobs_bundle_pool: Arc<Mutex<HashMap<H256, BlobsBundle>>>,
+    pub mempool: Arc<Mutex<Mempool>>,
+    mempool_config: MempoolConfig,
+    // Persists the pooled transactions across restarts, once opened
+    mempool_journal: Arc<Mutex<Option<MempoolJournal>>>,
+    bundle_pool: Arc<Mutex<BundlePool>>,
+    snapshot: Arc<Snapshot>,
+    // Old finalized blocks, kept out of the engine. Only persistent engines have one
+    freezer: Option<Arc<Freezer>>,
+    // Held while blocks are being moved to the freezer or their history expired
+    history_lock: Arc<Mutex<()>>,
+    // Whether the addresses and topics of the stored logs are indexed
+    log_index: bool,
+    // Blocks waiting for their parent by hash, so they can be pruned without reading them
+    pending_block_index: Arc<Mutex<HashMap<BlockHash, PendingBlockInfo>>>,
 }
 
+/// A block stored while waiting for its parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingBlockInfo {
+    pub hash: BlockHash,
+    pub number: BlockNumber,
+    /// When the block was stored. The ones already stored when the database was opened count
+    /// as received then
+    pub received_at: Instant,
+}
+
+/// Amount of blocks moved to the freezer or expired in each step, with their removal from the
+/// database committed in a single transaction
+const HISTORY_BATCH_SIZE: u64 = 1024;
+
+/// Amount of blocks added to the log index in each step of its backfill
+const LOG_INDEX_BATCH_SIZE: u64 = 1024;
+
+/// Most entries returned for a single key of a table, which only tables with many values per key
+/// can reach
+const MAX_TABLE_KEY_ENTRIES: usize = 4096;
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 pub enum EngineType {
@@ -75,31 +135,83 @@ impl AccountUpdate {
 }
 
 impl Store {
+    /// Opens the database, upgrading it to the schema of this build unless that rewrites stored
+    /// data, in which case it has to be upgraded with [`Store::migrate`]. Databases written with
+    /// a newer schema are refused
     pub fn new(path: &str, engine_type: EngineType) -> Result<Self, StoreError> {
         info!("Starting storage engine ({engine_type:?})");
-        let store = match engine_type {
-            #[cfg(feature = "libmdbx")]
-            EngineType::Libmdbx => Self {
-                engine: Arc::new(LibmdbxStore::new(path)?),
-                mempool: Arc::new(Mutex::new(HashMap::new())),
-                blobs_bundle_pool: Arc::new(Mutex::new(HashMap::new())),
-            },
-            EngineType::InMemory => Self {
-                engine: Arc::new(InMemoryStore::new()),
-                mempool: Arc::new(Mutex::new(HashMap::new())),
-                blobs_bundle_pool: Arc::new(Mutex::new(HashMap::new())),
-            },
-            #[cfg(feature = "redb")]
-            EngineType::RedB => Self {
-                engine: Arc::new(RedBStore::new()?),
-                mempool: Arc::new(Mutex::new(HashMap::new())),
-                blobs_bundle_pool: Arc::new(Mutex::new(HashMap::new())),
-            },
+        let engine = open_engine(path, engine_type)?;
+        migrations::upgrade_schema_version(engine.as_ref())?;
+        let snapshot = Arc::new(Snapshot::load(engine.as_ref())?);
+        let freezer = match engine_type {
+            EngineType::InMemory => None,
+            #[allow(unreachable_patterns)]
+            _ => Some(Arc::new(Freezer::open(&Path::new(path).join(FREEZER_DIR))?)),
+        };
+        let received_at = Instant::now();
+        let pending_block_index = engine
+            .get_pending_blocks()?
+            .into_iter()
+            .map(|block| {
+                let hash = block.hash();
+                let info = PendingBlockInfo {
+                    hash,
+                    number: block.header.number,
+                    received_at,
+                };
+                (hash, info)
+            })
+            .collect();
+        let store = Self {
+            engine,
+            mempool: Arc::new(Mutex::new(Mempool::default())),
+            mempool_config: MempoolConfig::default(),
+            mempool_journal: Arc::new(Mutex::new(None)),
+            bundle_pool: Arc::new(Mutex::new(BundlePool::default())),
+            snapshot,
+            freezer,
+            history_lock: Arc::new(Mutex::new(())),
+            log_index: false,
+            pending_block_index: Arc::new(Mutex::new(pending_block_index)),
         };
         info!("Started store engine");
         Ok(store)
     }
 
+    /// Upgrades the database in place to the schema of this build, returning the schema version
+    /// it was written with
+    pub fn migrate(path: &str, engine_type: EngineType) -> Result<u64, StoreError> {
+        let engine = open_engine(path, engine_type)?;
+        migrations::migrate(engine.as_ref())
+    }
+
+    pub fn with_mempool_config(mut self, config: MempoolConfig) -> Self {
+        self.mempool_config = config;
+        self
+    }
+
+    pub fn mempool_config(&self) -> MempoolConfig {
+        self.mempool_config
+    }
+
+    /// Enables or disables the log index. Once enabled, the blocks stored from then on are
+    /// indexed, and the ones stored before are left to [`Store::backfill_log_index`]. Disabling
+    /// it marks the index as unreliable, so it's rebuilt if enabled again
+    pub fn with_log_index(mut self, enabled: bool) -> Result<Self, StoreError> {
+        let tail = self.engine.get_log_index_tail()?;
+        if enabled && tail.is_none() {
+            let next_block = match self.engine.get_latest_block_number()? {
+                Some(latest) => latest + 1,
+                None => 0,
+            };
+            self.engine.update_log_index_tail(Some(next_block))?;
+        } else if !enabled && tail.is_some() {
+            self.engine.update_log_index_tail(None)?;
+        }
+        self.log_index = enabled;
+        Ok(self)
+    }
+
     pub fn get_account_info(
         &self,
         block_number: BlockNumber,
@@ -116,14 +228,9 @@ impl Store {
         block_hash: BlockHash,
         address: Address,
     ) -> Result<Option<AccountInfo>, StoreError> {
-        let Some(state_trie) = self.state_trie(block_hash)? else {
-            return Ok(None);
-        };
-        let hashed_address = hash_address(&address);
-        let Some(encoded_state) = state_trie.get(&hashed_address)? else {
+        let Some(account_state) = self.get_account_state_by_hash(block_hash, address)? else {
             return Ok(None);
         };
-        let account_state = AccountState::decode(&encoded_state)?;
         Ok(Some(AccountInfo {
             code_hash: account_state.code_hash,
             balance: account_state.balance,
@@ -151,21 +258,39 @@ impl Store {
         &self,
         block_number: BlockNumber,
     ) -> Result<Option<BlockHeader>, StoreError> {
-        self.engine.get_block_header(block_number)
+        if let Some(header) = self.engine.get_block_header(block_number)? {
+            return Ok(Some(header));
+        }
+        match &self.freezer {
+            Some(freezer) => freezer.header(block_number),
+            None => Ok(None),
+        }
     }
 
     pub fn get_block_header_by_hash(
         &self,
         block_hash: BlockHash,
     ) -> Result<Option<BlockHeader>, StoreError> {
-        self.engine.get_block_header_by_hash(block_hash)
+        if let Some(header) = self.engine.get_block_header_by_hash(block_hash)? {
+            return Ok(Some(header));
+        }
+        match (&self.freezer, self.frozen_block_number(block_hash)?) {
+            (Some(freezer), Some(block_number)) => freezer.header(block_number),
+            _ => Ok(None),
+        }
     }
 
     pub fn get_block_body_by_hash(
         &self,
         block_hash: BlockHash,
     ) -> Result<Option<BlockBody>, StoreError> {
-        self.engine.get_block_body_by_hash(block_hash)
+        if let Some(body) = self.engine.get_block_body_by_hash(block_hash)? {
+            return Ok(Some(body));
+        }
+        match self.frozen_block_number(block_hash)? {
+            Some(block_number) => self.frozen_body(block_number),
+            None => Ok(None),
+        }
     }
 
     pub fn add_block_body(
@@ -180,15 +305,24 @@ impl Store {
         &self,
         block_number: BlockNumber,
     ) -> Result<Option<BlockBody>, StoreError> {
-        self.engine.get_block_body(block_number)
+        if let Some(body) = self.engine.get_block_body(block_number)? {
+            return Ok(Some(body));
+        }
+        self.frozen_body(block_number)
     }
 
     pub fn add_pending_block(&self, block: Block) -> Result<(), StoreError> {
-        info!(
-            "Adding block to pending: {}",
-            block.header.compute_block_hash()
-        );
-        self.engine.add_pending_block(block)
+        let hash = block.hash();
+        info!("Adding block to pending: {}", hash);
+        let info = PendingBlockInfo {
+            hash,
+            number: block.header.number,
+            received_at: Instant::now(),
+        };
+        self.engine.add_pending_block(block)?;
+        // A block received again keeps its first arrival time
+        self.pending_block_index()?.entry(hash).or_insert(info);
+        Ok(())
     }
 
     pub fn get_pending_block(&self, block_hash: BlockHash) -> Result<Option<Block>, StoreError> {
@@ -196,6 +330,36 @@ impl Store {
         self.engine.get_pending_block(block_hash)
     }
 
+    pub fn get_pending_blocks_by_parent(
+        &self,
+        parent_hash: BlockHash,
+    ) -> Result<Vec<Block>, StoreError> {
+        self.engine.get_pending_blocks_by_parent(parent_hash)
+    }
+
+    pub fn get_pending_blocks(&self) -> Result<Vec<Block>, StoreError> {
+        self.engine.get_pending_blocks()
+    }
+
+    pub fn remove_pending_block(&self, block_hash: BlockHash) -> Result<(), StoreError> {
+        self.engine.remove_pending_block(block_hash)?;
+        self.pending_block_index()?.remove(&block_hash);
+        Ok(())
+    }
+
+    /// Returns the number and arrival time of every pending block, without reading the blocks
+    pub fn get_pending_block_index(&self) -> Result<Vec<PendingBlockInfo>, StoreError> {
+        Ok(self.pending_block_index()?.values().copied().collect())
+    }
+
+    fn pending_block_index(
+        &self,
+    ) -> Result<MutexGuard<'_, HashMap<BlockHash, PendingBlockInfo>>, StoreError> {
+        self.pending_block_index
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))
+    }
+
     pub fn add_block_number(
         &self,
         block_hash: BlockHash,
@@ -273,12 +437,7 @@ impl Store {
         hash: H256,
         transaction: MempoolTransaction,
     ) -> Result<(), StoreError> {
-        let mut mempool = self
-            .mempool
-            .lock()
-            .map_err(|error| StoreError::Custom(error.to_string()))?;
-        mempool.insert(hash, transaction);
-
+        self.mempool()?.insert(hash, transaction);
         Ok(())
     }
 
@@ -288,59 +447,84 @@ impl Store {
         tx_hash: H256,
         blobs_bundle: BlobsBundle,
     ) -> Result<(), StoreError> {
-        self.blobs_bundle_pool
-            .lock()
-            .map_err(|error| StoreError::Custom(error.to_string()))?
-            .insert(tx_hash, blobs_bundle);
+        self.mempool()?.insert_blobs_bundle(tx_hash, blobs_bundle);
         Ok(())
     }
 
+    /// Runs `f` over the mempool, keeping it locked throughout, so that the checks `f` makes on
+    /// the pooled transactions still hold when it changes them
+    pub fn update_mempool<T>(&self, f: impl FnOnce(&mut Mempool) -> T) -> Result<T, StoreError> {
+        Ok(f(&mut *self.mempool()?))
+    }
+
     /// Get a blobs bundle to the pool given its blob transaction hash
     pub fn get_blobs_bundle_from_pool(
         &self,
         tx_hash: H256,
     ) -> Result<Option<BlobsBundle>, StoreError> {
+        Ok(self.mempool()?.get_blobs_bundle(&tx_hash).cloned())
+    }
+
+    /// Get the pooled transaction sent by `sender` with the given nonce, along with its hash
+    pub fn get_pool_transaction_by_nonce(
+        &self,
+        sender: Address,
+        nonce: u64,
+    ) -> Result<Option<(H256, MempoolTransaction)>, StoreError> {
         Ok(self
-            .blobs_bundle_pool
-            .lock()
-            .map_err(|error| StoreError::Custom(error.to_string()))?
-            .get(&tx_hash)
-            .cloned())
+            .mempool()?
+            .get_by_nonce(sender, nonce)
+            .map(|(hash, tx)| (hash, tx.clone())))
     }
 
-    /// Remove a transaction from the pool
-    pub fn remove_transaction_from_pool(&self, hash: &H256) -> Result<(), StoreError> {
-        let mut mempool = self
-            .mempool
-            .lock()
-            .map_err(|error| StoreError::Custom(error.to_string()))?;
-        if let Some(tx) = mempool.get(hash) {
-            if matches!(tx.tx_type(), TxType::EIP4844) {
-                self.blobs_bundle_pool
-                    .lock()
-                    .map_err(|error| StoreError::Custom(error.to_string()))?
-                    .remove(&tx.compute_hash());
-            }
+    /// Reclassifies the pooled transactions sent by `sender` as pending or queued given its
+    /// current nonce, removing the ones below it. Returns the hashes of the removed transactions
+    pub fn reorganize_pool_transactions(
+        &self,
+        sender: Address,
+        account_nonce: u64,
+    ) -> Result<Vec<H256>, StoreError> {
+        Ok(self.mempool()?.reorganize(sender, account_nonce))
+    }
 
-            mempool.remove(hash);
-        };
+    /// Get the pooled transactions sent by `sender` along with their hashes, sorted by nonce
+    pub fn get_pool_transactions_by_sender(
+        &self,
+        sender: Address,
+    ) -> Result<Vec<(H256, MempoolTransaction)>, StoreError> {
+        Ok(self
+            .mempool()?
+            .sender_transactions(sender)
+            .into_iter()
+            .map(|(hash, tx)| (hash, tx.clone()))
+            .collect())
+    }
 
-        Ok(())
+    /// Get the senders of the pooled transactions
+    pub fn get_pool_senders(&self) -> Result<Vec<Address>, StoreError> {
+        Ok(self.mempool()?.senders())
+    }
+
+    /// Get the amount and size of the pooled transactions and blobs bundles
+    pub fn get_pool_stats(&self) -> Result<MempoolStats, StoreError> {
+        Ok(self.mempool()?.stats())
+    }
+
+    /// Remove a transaction from the pool, returns whether it was pooled
+    pub fn remove_transaction_from_pool(&self, hash: &H256) -> Result<bool, StoreError> {
+        Ok(self.mempool()?.remove(hash).is_some())
     }
 
-    /// Applies the filter and returns a set of suitable transactions from the mempool.
+    /// Applies the filter and returns a set of suitable pending transactions from the mempool.
     /// These transactions will be grouped by sender and sorted by nonce
     pub fn filter_pool_transactions(
         &self,
         filter: &dyn Fn(&Transaction) -> bool,
     ) -> Result<HashMap<Address, Vec<MempoolTransaction>>, StoreError> {
         let mut txs_by_sender: HashMap<Address, Vec<MempoolTransaction>> = HashMap::new();
-        let mempool = self
-            .mempool
-            .lock()
-            .map_err(|error| StoreError::Custom(error.to_string()))?;
+        let mempool = self.mempool()?;
 
-        for (_, tx) in mempool.iter() {
+        for (_, tx) in mempool.pending_transactions() {
             if filter(tx) {
                 txs_by_sender
                     .entry(tx.sender())
@@ -358,19 +542,125 @@ impl Store {
         &self,
         possible_hashes: &[H256],
     ) -> Result<Vec<H256>, StoreError> {
-        let mempool = self
-            .mempool
-            .lock()
-            .map_err(|error| StoreError::Custom(error.to_string()))?;
-
-        let tx_set: HashSet<_> = mempool.iter().map(|(hash, _)| hash).collect();
+        let mempool = self.mempool()?;
         Ok(possible_hashes
             .iter()
-            .filter(|hash| !tx_set.contains(hash))
+            .filter(|hash| !mempool.contains(hash))
             .copied()
             .collect())
     }
 
+    fn mempool(&self) -> Result<MutexGuard<'_, Mempool>, StoreError> {
+        self.mempool
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))
+    }
+
+    /// Reads the transactions journaled at `path`, in the order they were pooled, each one along
+    /// with its blobs bundle, if any. They are meant to be added back to the pool before the
+    /// journal is opened with [`Store::open_mempool_journal`]
+    pub fn read_mempool_journal(
+        path: &Path,
+    ) -> Result<Vec<(Transaction, Option<BlobsBundle>)>, StoreError> {
+        MempoolJournal::read(path)
+    }
+
+    /// Starts journaling the locally submitted transactions to the file at `path`, replacing its
+    /// contents with the given pooled transactions, as loaded back from it
+    pub fn open_mempool_journal(
+        &self,
+        path: &Path,
+        locals: impl IntoIterator<Item = H256>,
+    ) -> Result<(), StoreError> {
+        let mut journal = self.mempool_journal()?;
+        *journal = Some(MempoolJournal::open(path, locals.into_iter().collect())?);
+        drop(journal);
+        self.rewrite_mempool_journal()
+    }
+
+    /// Appends a pooled transaction, along with its blobs bundle if any, to the mempool journal,
+    /// if open. Does nothing if the transaction already left the pool.
+    /// The write is buffered until the journal is flushed or rewritten
+    pub fn journal_transaction(&self, hash: H256) -> Result<(), StoreError> {
+        // Locked first, as when rewriting it
+        let mut journal = self.mempool_journal()?;
+        let Some(journal) = journal.as_mut() else {
+            return Ok(());
+        };
+        let mempool = self.mempool()?;
+        let Some(transaction) = mempool.get(&hash) else {
+            return Ok(());
+        };
+        journal.append_transaction(transaction)?;
+        if let Some(blobs_bundle) = mempool.get_blobs_bundle(&hash) {
+            journal.append_blobs_bundle(hash, blobs_bundle)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered records of the mempool journal, if open, to its file
+    pub fn flush_mempool_journal(&self) -> Result<(), StoreError> {
+        match self.mempool_journal()?.as_mut() {
+            Some(journal) => journal.flush(),
+            None => Ok(()),
+        }
+    }
+
+    /// Rewrites the mempool journal, if open, with just the journaled transactions that are
+    /// still pooled
+    pub fn rewrite_mempool_journal(&self) -> Result<(), StoreError> {
+        // The journal is locked first so that transactions pooled while it's rewritten are
+        // appended to the new one
+        let mut journal = self.mempool_journal()?;
+        let Some(journal) = journal.as_mut() else {
+            return Ok(());
+        };
+        let mempool = self.mempool()?;
+        journal.rewrite(
+            mempool
+                .transactions()
+                .map(|(hash, tx)| (&**tx, mempool.get_blobs_bundle(hash))),
+        )
+    }
+
+    fn mempool_journal(&self) -> Result<MutexGuard<'_, Option<MempoolJournal>>, StoreError> {
+        self.mempool_journal
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))
+    }
+
+    /// Add a bundle to the pool, returning its hash
+    pub fn add_bundle_to_pool(&self, bundle: Bundle) -> Result<H256, StoreError> {
+        Ok(self.bundle_pool()?.insert(bundle))
+    }
+
+    /// Returns the amount of pooled bundles targeting the given block
+    pub fn get_pool_block_bundles(&self, block_number: BlockNumber) -> Result<usize, StoreError> {
+        Ok(self.bundle_pool()?.block_bundles(block_number))
+    }
+
+    /// Returns the pooled bundles that can be included in the block with the given number and
+    /// timestamp, in submission order
+    pub fn get_pool_bundles(
+        &self,
+        block_number: BlockNumber,
+        timestamp: u64,
+    ) -> Result<Vec<Bundle>, StoreError> {
+        Ok(self.bundle_pool()?.bundles_for(block_number, timestamp))
+    }
+
+    /// Drops the pooled bundles targeting blocks up to the given number
+    pub fn prune_bundle_pool(&self, block_number: BlockNumber) -> Result<(), StoreError> {
+        self.bundle_pool()?.prune(block_number);
+        Ok(())
+    }
+
+    fn bundle_pool(&self) -> Result<MutexGuard<'_, BundlePool>, StoreError> {
+        self.bundle_pool
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))
+    }
+
     pub fn add_account_code(&self, code_hash: H256, code: Bytes) -> Result<(), StoreError> {
         self.engine.add_account_code(code_hash, code)
     }
@@ -387,14 +677,9 @@ impl Store {
         let Some(block_hash) = self.engine.get_canonical_block_hash(block_number)? else {
             return Ok(None);
         };
-        let Some(state_trie) = self.state_trie(block_hash)? else {
-            return Ok(None);
-        };
-        let hashed_address = hash_address(&address);
-        let Some(encoded_state) = state_trie.get(&hashed_address)? else {
+        let Some(account_state) = self.get_account_state_by_hash(block_hash, address)? else {
             return Ok(None);
         };
-        let account_state = AccountState::decode(&encoded_state)?;
         self.get_account_code(account_state.code_hash)
     }
     pub fn get_nonce_by_account_address(
@@ -405,32 +690,72 @@ impl Store {
         let Some(block_hash) = self.engine.get_canonical_block_hash(block_number)? else {
             return Ok(None);
         };
-        let Some(state_trie) = self.state_trie(block_hash)? else {
-            return Ok(None);
-        };
-        let hashed_address = hash_address(&address);
-        let Some(encoded_state) = state_trie.get(&hashed_address)? else {
+        let Some(account_state) = self.get_account_state_by_hash(block_hash, address)? else {
             return Ok(None);
         };
-        let account_state = AccountState::decode(&encoded_state)?;
         Ok(Some(account_state.nonce))
     }
 
     /// Applies account updates based on the block's latest storage state
     /// and returns the new state root after the updates have been applied.
+    /// The changes are also stacked as a snapshot diff layer on top of the block's state.
     pub fn apply_account_updates(
         &self,
         block_hash: BlockHash,
         account_updates: &[AccountUpdate],
     ) -> Result<Option<H256>, StoreError> {
-        let Some(mut state_trie) = self.state_trie(block_hash)? else {
+        let mut batch = WriteBatch::new();
+        let Some(state_root) =
+            self.stage_account_updates(block_hash, account_updates, &mut batch)?
+        else {
             return Ok(None);
         };
+        self.write_batch(batch)?;
+        Ok(Some(state_root))
+    }
+
+    /// Same as [`Store::apply_account_updates`], but the new trie nodes and code are staged in
+    /// the batch instead of being written right away.
+    /// The block may be one staged in the same batch, along with its state, so the states of a
+    /// run of blocks can be staged one on top of the other and committed at once
+    pub fn stage_account_updates(
+        &self,
+        block_hash: BlockHash,
+        account_updates: &[AccountUpdate],
+        batch: &mut WriteBatch,
+    ) -> Result<Option<H256>, StoreError> {
+        let staged_parent = batch
+            .blocks
+            .iter()
+            .rev()
+            .find(|staged| staged.hash == block_hash)
+            .map(|staged| staged.block.header.clone());
+        let parent_header = match staged_parent {
+            Some(parent_header) => parent_header,
+            None => {
+                let Some(parent_header) = self.get_block_header_by_hash(block_hash)? else {
+                    return Ok(None);
+                };
+                if self.state_trie(block_hash)?.is_none() {
+                    return Ok(None);
+                }
+                parent_header
+            }
+        };
+        let mut state_trie = self
+            .engine
+            .open_state_trie(parent_header.state_root)
+            .with_staged_nodes(batch.state_trie_nodes.clone());
+        let mut changes = SnapshotChanges::default();
         for update in account_updates.iter() {
             let hashed_address = hash_address(&update.address);
+            let snapshot_address = H256::from_slice(&hashed_address);
             if update.removed {
                 // Remove account from trie
                 state_trie.remove(hashed_address)?;
+                changes.accounts.remove(&snapshot_address);
+                changes.storage.remove(&snapshot_address);
+                changes.removed.insert(snapshot_address);
             } else {
                 // Add or update AccountState in the trie
                 // Fetch current state or create a new state to be inserted
@@ -444,7 +769,7 @@ impl Store {
                     account_state.code_hash = info.code_hash;
                     // Store updated code in DB
                     if let Some(code) = &update.code {
-                        self.add_account_code(info.code_hash, code.clone())?;
+                        batch.account_codes.push((info.code_hash, code.clone()));
                     }
                 }
                 // Store the added storage in the account's storage trie and compute its new root
@@ -453,20 +778,88 @@ impl Store {
                         H256::from_slice(&hashed_address),
                         account_state.storage_root,
                     );
+                    if let Some(staged_nodes) = batch.storage_trie_nodes.get(&snapshot_address) {
+                        storage_trie = storage_trie.with_staged_nodes(staged_nodes.clone());
+                    }
+                    let snapshot_storage = changes.storage.entry(snapshot_address).or_default();
                     for (storage_key, storage_value) in &update.added_storage {
                         let hashed_key = hash_key(storage_key);
+                        snapshot_storage.insert(H256::from_slice(&hashed_key), *storage_value);
                         if storage_value.is_zero() {
                             storage_trie.remove(hashed_key)?;
                         } else {
                             storage_trie.insert(hashed_key, storage_value.encode_to_vec())?;
                         }
                     }
-                    account_state.storage_root = storage_trie.hash()?;
+                    let (storage_root, nodes) = storage_trie.collect_changes()?;
+                    // Dropped first so the staged nodes are extended in place rather than copied
+                    drop(storage_trie);
+                    account_state.storage_root = storage_root;
+                    Arc::make_mut(
+                        batch
+                            .storage_trie_nodes
+                            .entry(snapshot_address)
+                            .or_default(),
+                    )
+                    .extend(nodes);
                 }
                 state_trie.insert(hashed_address, account_state.encode_to_vec())?;
+                changes.accounts.insert(snapshot_address, account_state);
             }
         }
-        Ok(Some(state_trie.hash()?))
+        let (state_root, nodes) = state_trie.collect_changes()?;
+        drop(state_trie);
+        Arc::make_mut(&mut batch.state_trie_nodes).extend(nodes);
+        if state_root != parent_header.state_root {
+            batch.snapshot_layers.push(SnapshotLayer {
+                state_root,
+                parent_root: parent_header.state_root,
+                number: parent_header.number + 1,
+                changes,
+            });
+        }
+        Ok(Some(state_root))
+    }
+
+    /// Stages a block to be stored along with its transaction locations and total difficulty
+    pub fn stage_block(&self, batch: &mut WriteBatch, block: Block) -> Result<(), StoreError> {
+        let latest_total_difficulty = match batch.latest_total_difficulty {
+            Some(total_difficulty) => Some(total_difficulty),
+            None => self.get_latest_total_difficulty()?,
+        };
+        let total_difficulty =
+            latest_total_difficulty.unwrap_or(U256::zero()) + block.header.difficulty;
+        batch.blocks.push(StagedBlock {
+            hash: block.header.compute_block_hash(),
+            block,
+            total_difficulty,
+        });
+        batch.latest_total_difficulty = Some(total_difficulty);
+        Ok(())
+    }
+
+    /// Commits all the writes staged in the batch in a single database transaction.
+    /// The snapshot diff layers of the staged state changes are only added once it's committed,
+    /// and only for the blocks staged along with them, so states computed while building
+    /// payloads don't end up in the snapshot
+    pub fn write_batch(&self, mut batch: WriteBatch) -> Result<(), StoreError> {
+        if self.log_index {
+            batch.log_index = self.log_index_updates(&batch)?;
+        }
+        let mut snapshot_layers = std::mem::take(&mut batch.snapshot_layers);
+        snapshot_layers.retain(|layer| {
+            batch
+                .blocks
+                .iter()
+                .any(|staged| staged.block.header.state_root == layer.state_root)
+        });
+        if !batch.is_empty() {
+            self.engine.apply_write_batch(batch)?;
+        }
+        for layer in snapshot_layers {
+            self.snapshot.add_layer(&self.engine, layer)?;
+        }
+        Ok(())
     }
 
     /// Adds all genesis accounts and returns the genesis block's state_root
@@ -517,6 +910,11 @@ impl Store {
         block_hash: BlockHash,
         receipts: Vec<Receipt>,
     ) -> Result<(), StoreError> {
+        if self.log_index {
+            let mut batch = WriteBatch::new();
+            batch.add_receipts(block_hash, receipts);
+            return self.write_batch(batch);
+        }
         self.engine.add_receipts(block_hash, receipts)
     }
 
@@ -525,23 +923,18 @@ impl Store {
         block_number: BlockNumber,
         index: Index,
     ) -> Result<Option<Receipt>, StoreError> {
-        self.engine.get_receipt(block_number, index)
+        if let Some(receipt) = self.engine.get_receipt(block_number, index)? {
+            return Ok(Some(receipt));
+        }
+        Ok(self
+            .frozen_receipts(block_number)?
+            .and_then(|receipts| receipts.into_iter().nth(index as usize)))
     }
 
     pub fn add_block(&self, block: Block) -> Result<(), StoreError> {
-        // TODO Maybe add both in a single tx?
-        let header = block.header;
-        let number = header.number;
-        let latest_total_difficulty = self.get_latest_total_difficulty()?;
-        let block_total_difficulty =
-            latest_total_difficulty.unwrap_or(U256::zero()) + header.difficulty;
-        let hash = header.compute_block_hash();
-        self.add_transaction_locations(&block.body.transactions, number, hash)?;
-        self.add_block_body(hash, block.body)?;
-        self.add_block_header(hash, header)?;
-        self.add_block_number(hash, number)?;
-        self.add_block_total_difficulty(hash, block_total_difficulty)?;
-        self.update_latest_total_difficulty(block_total_difficulty)
+        let mut batch = WriteBatch::new();
+        self.stage_block(&mut batch, block)?;
+        self.write_batch(batch)
     }
 
     pub fn add_initial_state(&self, genesis: Genesis) -> Result<(), StoreError> {
@@ -568,6 +961,11 @@ impl Store {
         // TODO: Should we use this root instead of computing it before the block hash check?
         let genesis_state_root = self.setup_genesis_state_trie(genesis.alloc)?;
         debug_assert_eq!(genesis_state_root, genesis_block.header.state_root);
+        self.snapshot.generate(
+            self.engine.as_ref(),
+            genesis_state_root,
+            genesis_block_number,
+        )?;
 
         // Store genesis block
         info!(
@@ -588,7 +986,12 @@ impl Store {
         &self,
         transaction_hash: H256,
     ) -> Result<Option<Transaction>, StoreError> {
-        self.engine.get_transaction_by_hash(transaction_hash)
+        let Some((_, block_hash, index)) =
+            self.engine.get_transaction_location(transaction_hash)?
+        else {
+            return Ok(None);
+        };
+        self.get_transaction_by_location(block_hash, index)
     }
 
     pub fn get_transaction_by_location(
@@ -596,11 +999,23 @@ impl Store {
         block_hash: BlockHash,
         index: u64,
     ) -> Result<Option<Transaction>, StoreError> {
-        self.engine.get_transaction_by_location(block_hash, index)
+        let Some(body) = self.get_block_body_by_hash(block_hash)? else {
+            return Ok(None);
+        };
+        Ok(index
+            .try_into()
+            .ok()
+            .and_then(|index: usize| body.transactions.get(index).cloned()))
     }
 
     pub fn get_block_by_hash(&self, block_hash: H256) -> Result<Option<Block>, StoreError> {
-        self.engine.get_block_by_hash(block_hash)
+        let Some(header) = self.get_block_header_by_hash(block_hash)? else {
+            return Ok(None);
+        };
+        let Some(body) = self.get_block_body_by_hash(block_hash)? else {
+            return Ok(None);
+        };
+        Ok(Some(Block::new(header, body)))
     }
 
     pub fn get_storage_at(
@@ -621,6 +1036,17 @@ impl Store {
         address: Address,
         storage_key: H256,
     ) -> Result<Option<U256>, StoreError> {
+        let Some(header) = self.get_block_header_by_hash(block_hash)? else {
+            return Ok(None);
+        };
+        if let Some(value) = self.snapshot.storage(
+            self.engine.as_ref(),
+            header.state_root,
+            hash_address_fixed(&address),
+            H256::from_slice(&hash_key(&storage_key)),
+        )? {
+            return Ok(value);
+        }
         let Some(storage_trie) = self.storage_trie(block_hash, address)? else {
             return Ok(None);
         };
@@ -702,6 +1128,11 @@ impl Store {
         number: BlockNumber,
         hash: BlockHash,
     ) -> Result<(), StoreError> {
+        if self.log_index {
+            let mut batch = WriteBatch::new();
+            batch.set_canonical_block(number, hash);
+            return self.write_batch(batch);
+        }
         self.engine.set_canonical_block(number, hash)
     }
 
@@ -716,15 +1147,79 @@ impl Store {
     /// Used for reorgs.
     /// Note: Should we also remove all others up to the head here?
     pub fn unset_canonical_block(&self, number: BlockNumber) -> Result<(), StoreError> {
+        if self.log_index {
+            let mut batch = WriteBatch::new();
+            batch.unset_canonical_block(number);
+            return self.write_batch(batch);
+        }
         self.engine.unset_canonical_block(number)
     }
 
+    /// Makes the canonical block with the given number the head of the chain, removing the blocks
+    /// after it from the canonical chain. Safe and finalized blocks past the new head are moved
+    /// back to it, and the state snapshot is generated again at its state.
+    /// Meant for repairing a node whose latest blocks can't be relied on, so the state of the new
+    /// head must be available, and blocks moved to the freezer can't become the head.
+    pub fn set_head(&self, block_number: BlockNumber) -> Result<BlockHash, StoreError> {
+        let latest = self.get_latest_block_number()?;
+        if block_number > latest {
+            return Err(StoreError::Custom(format!(
+                "Block {block_number} is past the latest block {latest}"
+            )));
+        }
+        if block_number < self.get_frozen_block_count()? {
+            return Err(StoreError::Custom(format!(
+                "Block {block_number} was moved to the freezer"
+            )));
+        }
+        let (Some(block_hash), Some(header)) = (
+            self.get_canonical_block_hash(block_number)?,
+            self.get_block_header(block_number)?,
+        ) else {
+            return Err(StoreError::Custom(format!(
+                "Block {block_number} is not in the canonical chain"
+            )));
+        };
+        if !self.open_state_trie(header.state_root).root_is_stored()? {
+            return Err(StoreError::Custom(format!(
+                "State of block {block_number} is not available"
+            )));
+        }
+
+        let mut batch = WriteBatch::new();
+        for number in (block_number + 1)..=latest {
+            batch.unset_canonical_block(number);
+        }
+        batch.update_latest_block_number(block_number);
+        if self
+            .get_finalized_block_number()?
+            .is_some_and(|number| number > block_number)
+        {
+            batch.update_finalized_block_number(block_number);
+        }
+        if self
+            .get_safe_block_number()?
+            .is_some_and(|number| number > block_number)
+        {
+            batch.update_safe_block_number(block_number);
+        }
+        self.write_batch(batch)?;
+        self.snapshot
+            .generate(self.engine.as_ref(), header.state_root, block_number)?;
+        Ok(block_hash)
+    }
+
     // Obtain the storage trie for the given block
     pub fn state_trie(&self, block_hash: BlockHash) -> Result<Option<Trie>, StoreError> {
         let Some(header) = self.get_block_header_by_hash(block_hash)? else {
             return Ok(None);
         };
-        Ok(Some(self.engine.open_state_trie(header.state_root)))
+        let trie = self.engine.open_state_trie(header.state_root);
+        // Once the state has been pruned, old blocks may no longer have their state available
+        if self.engine.get_earliest_state_block_number()?.is_some() && !trie.root_is_stored()? {
+            return Ok(None);
+        }
+        Ok(Some(trie))
     }
 
     // Obtain the storage trie for the given account on the given block
@@ -758,21 +1253,26 @@ impl Store {
         let Some(block_hash) = self.engine.get_canonical_block_hash(block_number)? else {
             return Ok(None);
         };
-        let Some(state_trie) = self.state_trie(block_hash)? else {
-            return Ok(None);
-        };
-        let hashed_address = hash_address(&address);
-        let Some(encoded_state) = state_trie.get(&hashed_address)? else {
-            return Ok(None);
-        };
-        Ok(Some(AccountState::decode(&encoded_state)?))
+        self.get_account_state_by_hash(block_hash, address)
     }
 
+    /// Obtain the state of the account at the given block, from the snapshot if it reaches the
+    /// block's state or from the state trie otherwise
     pub fn get_account_state_by_hash(
         &self,
         block_hash: BlockHash,
         address: Address,
     ) -> Result<Option<AccountState>, StoreError> {
+        let Some(header) = self.get_block_header_by_hash(block_hash)? else {
+            return Ok(None);
+        };
+        if let Some(account_state) = self.snapshot.account(
+            self.engine.as_ref(),
+            header.state_root,
+            hash_address_fixed(&address),
+        )? {
+            return Ok(account_state);
+        }
         let Some(state_trie) = self.state_trie(block_hash)? else {
             return Ok(None);
         };
@@ -957,44 +1457,414 @@ impl Store {
         &self,
         block_hash: &BlockHash,
     ) -> Result<Vec<Receipt>, StoreError> {
-        self.engine.get_receipts_for_block(block_hash)
+        let receipts = self.engine.get_receipts_for_block(block_hash)?;
+        if !receipts.is_empty() {
+            return Ok(receipts);
+        }
+        match self.frozen_block_number(*block_hash)? {
+            Some(block_number) => Ok(self.frozen_receipts(block_number)?.unwrap_or_default()),
+            None => Ok(receipts),
+        }
     }
 
-    /// Creates a new state trie with an empty state root, for testing purposes only
-    pub fn new_state_trie_for_test(&self) -> Trie {
-        self.engine.open_state_trie(*EMPTY_TRIE_HASH)
+    // The history of expired blocks may still be in the freezer, as it's deleted a whole data
+    // file at a time
+    fn frozen_body(&self, block_number: BlockNumber) -> Result<Option<BlockBody>, StoreError> {
+        match &self.freezer {
+            Some(freezer) if !self.is_history_expired(block_number)? => freezer.body(block_number),
+            _ => Ok(None),
+        }
     }
 
-    /// Methods exclusive for trie management during snap-syncing
+    fn frozen_receipts(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Option<Vec<Receipt>>, StoreError> {
+        match &self.freezer {
+            Some(freezer) if !self.is_history_expired(block_number)? => {
+                freezer.receipts(block_number)
+            }
+            _ => Ok(None),
+        }
+    }
 
-    // Obtain a state trie from the given state root
-    // Doesn't check if the state root is valid
-    pub fn open_state_trie(&self, state_root: H256) -> Trie {
-        self.engine.open_state_trie(state_root)
+    /// Returns the number of the given block if it was moved to the freezer
+    fn frozen_block_number(
+        &self,
+        block_hash: BlockHash,
+    ) -> Result<Option<BlockNumber>, StoreError> {
+        let (Some(freezer), Some(block_number)) =
+            (&self.freezer, self.engine.get_block_number(block_hash)?)
+        else {
+            return Ok(None);
+        };
+        Ok((freezer.block_hash(block_number)? == Some(block_hash)).then_some(block_number))
     }
 
-    // Obtain a storage trie from the given address and storage_root
-    // Doesn't check if the account is stored
-    pub fn open_storage_trie(&self, account_hash: H256, storage_root: H256) -> Trie {
-        self.engine.open_storage_trie(account_hash, storage_root)
+    /// Returns the amount of blocks moved to the freezer, which are the ones numbered below it
+    pub fn get_frozen_block_count(&self) -> Result<u64, StoreError> {
+        match &self.freezer {
+            Some(freezer) => freezer.frozen_blocks(),
+            None => Ok(0),
+        }
     }
 
-    /// Returns true if the given node is part of the state trie's internal storage
-    pub fn contains_state_node(&self, node_hash: H256) -> Result<bool, StoreError> {
-        // Root is irrelevant, we only care about the internal state
-        Ok(self
-            .open_state_trie(*EMPTY_TRIE_HASH)
-            .state()
-            .get_node(node_hash.into())?
-            .is_some())
+    /// Moves the headers, bodies and receipts of the canonical blocks numbered below `limit`
+    /// from the database to the freezer, returning how many blocks were moved. Only finalized
+    /// blocks may be frozen, as the freezer can't be reorged.
+    /// Stops at the first block whose header isn't stored, and does nothing on the in-memory
+    /// engine, which has no freezer.
+    pub fn freeze_blocks(&self, limit: BlockNumber) -> Result<u64, StoreError> {
+        let Some(freezer) = &self.freezer else {
+            return Ok(0);
+        };
+        let _history_guard = self
+            .history_lock
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))?;
+        let earliest_block = self.engine.get_earliest_block_number()?.unwrap_or_default();
+        let first = freezer.frozen_blocks()?;
+        let mut next = first;
+        while next < limit {
+            let end = limit.min(next + HISTORY_BATCH_SIZE);
+            let mut blocks = Vec::new();
+            for number in next..end {
+                let Some(block) = self.frozen_block(number, earliest_block)? else {
+                    break;
+                };
+                blocks.push(block);
+            }
+            if blocks.is_empty() {
+                break;
+            }
+            // The blocks are only removed from the database once they are safely in the freezer.
+            // If the node stops in between, they are just kept in both
+            freezer.append(&blocks)?;
+            let hashes = blocks.iter().map(|block| block.hash).collect::<Vec<_>>();
+            self.engine.remove_frozen_blocks(&hashes)?;
+            next += blocks.len() as u64;
+            if next < end {
+                break;
+            }
+        }
+        Ok(next - first)
     }
 
-    /// Returns true if the given node is part of the given storage trie's internal storage
-    pub fn contains_storage_node(
+    /// Moves the blocks below `limit` to the freezer on a background thread, as in
+    /// [`Store::freeze_blocks`]. Does nothing if blocks are already being moved
+    pub fn freeze_blocks_in_background(&self, limit: BlockNumber) {
+        if self.freezer.is_none() || self.history_lock.try_lock().is_err() {
+            return;
+        }
+        let store = self.clone();
+        std::thread::spawn(move || match store.freeze_blocks(limit) {
+            Ok(0) => {}
+            Ok(frozen) => info!("Moved {frozen} blocks to the freezer, up to block {limit}"),
+            Err(error) => warn!("Failed to move blocks to the freezer: {error}"),
+        });
+    }
+
+    // Reads the data of a canonical block to move it to the freezer. The history of blocks
+    // below the earliest one is expired, so it's left out
+    fn frozen_block(
         &self,
-        hashed_address: H256,
-        node_hash: H256,
-    ) -> Result<bool, StoreError> {
+        number: BlockNumber,
+        earliest_block: BlockNumber,
+    ) -> Result<Option<FrozenBlock>, StoreError> {
+        let Some(hash) = self.engine.get_canonical_block_hash(number)? else {
+            return Ok(None);
+        };
+        let Some(header) = self.engine.get_block_header_by_hash(hash)? else {
+            return Ok(None);
+        };
+        if number < earliest_block {
+            return Ok(Some(FrozenBlock {
+                hash,
+                header,
+                body: None,
+                receipts: None,
+            }));
+        }
+        let body = self.engine.get_block_body_by_hash(hash)?;
+        // Missing receipts can't be told apart from the ones of a block without transactions
+        let receipts = self.engine.get_receipts_for_block(&hash)?;
+        let receipts = (!receipts.is_empty()
+            || body
+                .as_ref()
+                .is_some_and(|body| body.transactions.is_empty()))
+        .then_some(receipts);
+        Ok(Some(FrozenBlock {
+            hash,
+            header,
+            body,
+            receipts,
+        }))
+    }
+
+    /// Returns whether the bodies and receipts of the given block were deleted by history expiry
+    pub fn is_history_expired(&self, block_number: BlockNumber) -> Result<bool, StoreError> {
+        Ok(self
+            .engine
+            .get_earliest_block_number()?
+            .is_some_and(|earliest_block| block_number < earliest_block))
+    }
+
+    /// Deletes the bodies, receipts and transaction locations of the canonical blocks numbered
+    /// below `cutoff`, as in EIP-4444, and records `cutoff` as the earliest block number.
+    /// Returns how many blocks had their history expired. Headers are kept, as are the bodies
+    /// of non-canonical blocks, and those in the freezer are deleted a whole data file at a time.
+    /// Only finalized blocks may have their history expired.
+    pub fn expire_history(&self, cutoff: BlockNumber) -> Result<u64, StoreError> {
+        let _history_guard = self
+            .history_lock
+            .lock()
+            .map_err(|error| StoreError::Custom(error.to_string()))?;
+        let first = self.engine.get_earliest_block_number()?.unwrap_or_default();
+        let mut next = first;
+        while next < cutoff {
+            let end = cutoff.min(next + HISTORY_BATCH_SIZE);
+            let mut block_hashes = Vec::new();
+            let mut transaction_locations = Vec::new();
+            for number in next..end {
+                let Some(hash) = self.engine.get_canonical_block_hash(number)? else {
+                    continue;
+                };
+                let body = match self.engine.get_block_body_by_hash(hash)? {
+                    Some(body) => Some(body),
+                    None => match self.frozen_block_number(hash)? {
+                        Some(number) => self.frozen_body(number)?,
+                        None => None,
+                    },
+                };
+                let transactions = body.map(|body| body.transactions).unwrap_or_default();
+                for (index, transaction) in transactions.iter().enumerate() {
+                    transaction_locations.push((
+                        transaction.compute_hash(),
+                        number,
+                        hash,
+                        index as Index,
+                    ));
+                }
+                block_hashes.push(hash);
+            }
+            self.engine
+                .expire_block_history(&block_hashes, &transaction_locations, end)?;
+            next = end;
+        }
+        // Also retried on every run, in case the node stopped before getting here
+        if let Some(freezer) = &self.freezer {
+            freezer.expire(next)?;
+        }
+        Ok(next - first)
+    }
+
+    /// Expires the history below `cutoff` on a background thread, as in
+    /// [`Store::expire_history`]. Does nothing if blocks are already being moved or expired
+    pub fn expire_history_in_background(&self, cutoff: BlockNumber) {
+        if self.history_lock.try_lock().is_err() {
+            return;
+        }
+        let store = self.clone();
+        std::thread::spawn(move || match store.expire_history(cutoff) {
+            Ok(0) => {}
+            Ok(expired) => info!("Expired the history of {expired} blocks, up to block {cutoff}"),
+            Err(error) => warn!("Failed to expire history: {error}"),
+        });
+    }
+
+    /// Returns the canonical blocks within `from..=to` that may hold logs emitted by one of
+    /// `addresses`, if any, and holding the given topics, where each position lists the accepted
+    /// topics, or none to accept any. Blocks below the indexed ones can't be ruled out, so they
+    /// are all returned. Returns None if the log index is disabled or there's nothing to look up
+    pub fn get_log_blocks(
+        &self,
+        addresses: &[Address],
+        topics: &[Vec<H256>],
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<Option<Vec<BlockNumber>>, StoreError> {
+        if !self.log_index || (addresses.is_empty() && topics.iter().all(Vec::is_empty)) {
+            return Ok(None);
+        }
+        let Some(tail) = self.engine.get_log_index_tail()? else {
+            return Ok(None);
+        };
+        let indexed_from = from.max(tail);
+        let mut blocks: Vec<BlockNumber> = (from..indexed_from.min(to.saturating_add(1))).collect();
+        if indexed_from > to {
+            return Ok(Some(blocks));
+        }
+        // Blocks matching every given filter, each one matched by any of its values
+        let mut candidates: Option<BTreeSet<(BlockNumber, BlockHash)>> = None;
+        if !addresses.is_empty() {
+            let mut matching = BTreeSet::new();
+            for address in addresses {
+                matching.extend(
+                    self.engine
+                        .get_address_log_blocks(*address, indexed_from, to)?,
+                );
+            }
+            candidates = Some(matching);
+        }
+        for position in topics.iter().filter(|position| !position.is_empty()) {
+            let mut matching = BTreeSet::new();
+            for topic in position {
+                matching.extend(self.engine.get_topic_log_blocks(*topic, indexed_from, to)?);
+            }
+            candidates = Some(match candidates {
+                Some(candidates) => candidates.intersection(&matching).copied().collect(),
+                None => matching,
+            });
+        }
+        // Non-canonical blocks are indexed too
+        for (number, hash) in candidates.unwrap_or_default() {
+            if self.engine.get_canonical_block_hash(number)? == Some(hash) {
+                blocks.push(number);
+            }
+        }
+        Ok(Some(blocks))
+    }
+
+    /// Adds the canonical blocks stored before the log index was enabled to it, from the newest
+    /// to the oldest, down to the earliest block with history. Returns how many were added
+    pub fn backfill_log_index(&self) -> Result<u64, StoreError> {
+        let earliest_block = self.engine.get_earliest_block_number()?.unwrap_or(0);
+        let mut indexed = 0;
+        while let Some(tail) = self
+            .engine
+            .get_log_index_tail()?
+            .filter(|tail| *tail > earliest_block)
+        {
+            let start = tail
+                .saturating_sub(LOG_INDEX_BATCH_SIZE)
+                .max(earliest_block);
+            let mut batch = WriteBatch::new();
+            for number in start..tail {
+                let Some(hash) = self.engine.get_canonical_block_hash(number)? else {
+                    continue;
+                };
+                let index = BlockLogIndex::new(number, hash, &self.get_receipts_for_block(&hash)?);
+                if !index.is_empty() {
+                    batch.log_index.push(LogIndexUpdate::Add(index));
+                }
+            }
+            if !batch.is_empty() {
+                self.engine.apply_write_batch(batch)?;
+            }
+            // Blocks are indexed before the tail is moved, so a crash at most indexes them twice
+            self.engine.update_log_index_tail(Some(start))?;
+            indexed += tail - start;
+        }
+        Ok(indexed)
+    }
+
+    /// Runs [`Store::backfill_log_index`] in the background, if the log index is enabled
+    pub fn backfill_log_index_in_background(&self) {
+        if !self.log_index {
+            return;
+        }
+        let store = self.clone();
+        std::thread::spawn(move || match store.backfill_log_index() {
+            Ok(0) => {}
+            Ok(indexed) => info!("Added {indexed} blocks to the log index"),
+            Err(error) => warn!("Failed to backfill the log index: {error}"),
+        });
+    }
+
+    // Changes to the log index that come with committing the batch. Blocks are indexed as their
+    // receipts are stored, removed from the index when they leave the canonical chain, and added
+    // back when they join it, as they may have left it before
+    fn log_index_updates(&self, batch: &WriteBatch) -> Result<Vec<LogIndexUpdate>, StoreError> {
+        let mut updates = Vec::new();
+        for (hash, receipts) in &batch.receipts {
+            let number = match batch.blocks.iter().find(|staged| staged.hash == *hash) {
+                Some(staged) => Some(staged.block.header.number),
+                None => self.engine.get_block_number(*hash)?,
+            };
+            let Some(number) = number else {
+                continue;
+            };
+            let index = BlockLogIndex::new(number, *hash, receipts);
+            if !index.is_empty() {
+                updates.push(LogIndexUpdate::Add(index));
+            }
+        }
+        // Canonical hashes as they are after the changes staged so far
+        let mut canonical_hashes = HashMap::new();
+        for (number, hash) in &batch.canonical_blocks {
+            let previous = match canonical_hashes.get(number) {
+                Some(previous) => *previous,
+                None => self.engine.get_canonical_block_hash(*number)?,
+            };
+            canonical_hashes.insert(*number, *hash);
+            if previous == *hash {
+                continue;
+            }
+            if let Some(previous) = previous {
+                let index = self.block_log_index(*number, previous, batch)?;
+                if !index.is_empty() {
+                    updates.push(LogIndexUpdate::Remove(index));
+                }
+            }
+            if let Some(hash) = hash {
+                let index = self.block_log_index(*number, *hash, batch)?;
+                if !index.is_empty() {
+                    updates.push(LogIndexUpdate::Add(index));
+                }
+            }
+        }
+        Ok(updates)
+    }
+
+    // Log index entries of a block, taking its receipts from the batch if staged there
+    fn block_log_index(
+        &self,
+        number: BlockNumber,
+        hash: BlockHash,
+        batch: &WriteBatch,
+    ) -> Result<BlockLogIndex, StoreError> {
+        let receipts = match batch.receipts.iter().find(|(staged, _)| *staged == hash) {
+            Some((_, receipts)) => receipts.clone(),
+            None => self.get_receipts_for_block(&hash)?,
+        };
+        Ok(BlockLogIndex::new(number, hash, &receipts))
+    }
+
+    /// Creates a new state trie with an empty state root, for testing purposes only
+    pub fn new_state_trie_for_test(&self) -> Trie {
+        self.engine.open_state_trie(*EMPTY_TRIE_HASH)
+    }
+
+    /// Methods exclusive for trie management during snap-syncing
+
+    // Obtain a state trie from the given state root
+    // Doesn't check if the state root is valid
+    pub fn open_state_trie(&self, state_root: H256) -> Trie {
+        self.engine.open_state_trie(state_root)
+    }
+
+    // Obtain a storage trie from the given address and storage_root
+    // Doesn't check if the account is stored
+    pub fn open_storage_trie(&self, account_hash: H256, storage_root: H256) -> Trie {
+        self.engine.open_storage_trie(account_hash, storage_root)
+    }
+
+    /// Returns true if the given node is part of the state trie's internal storage
+    pub fn contains_state_node(&self, node_hash: H256) -> Result<bool, StoreError> {
+        // Root is irrelevant, we only care about the internal state
+        Ok(self
+            .open_state_trie(*EMPTY_TRIE_HASH)
+            .state()
+            .get_node(node_hash.into())?
+            .is_some())
+    }
+
+    /// Returns true if the given node is part of the given storage trie's internal storage
+    pub fn contains_storage_node(
+        &self,
+        hashed_address: H256,
+        node_hash: H256,
+    ) -> Result<bool, StoreError> {
         // Root is irrelevant, we only care about the internal state
         Ok(self
             .open_storage_trie(hashed_address, *EMPTY_TRIE_HASH)
@@ -1003,12 +1873,241 @@ impl Store {
             .is_some())
     }
 
+    /// Deletes every state and storage trie node that is not reachable from the given state roots,
+    /// recording `earliest_state_block` as the earliest block whose state is kept.
+    /// Returns the amount of deleted nodes.
+    /// The caller must make sure no block is being stored concurrently, as the nodes of a block
+    /// whose header hasn't been stored yet are not reachable from any of the retained roots
+    pub fn prune_state(
+        &self,
+        earliest_state_block: BlockNumber,
+        retained_roots: impl IntoIterator<Item = H256>,
+    ) -> Result<usize, StoreError> {
+        let mut reachable = ReachableNodes::default();
+        for state_root in retained_roots {
+            self.mark_reachable_state(&mut reachable, state_root)?;
+        }
+        self.update_earliest_state_block_number(earliest_state_block)?;
+        let mut deleted = 0;
+        let mut cursor = Some(PruneCursor::default());
+        while let Some(from) = cursor {
+            let (batch_deleted, next) =
+                self.prune_unreachable_state(&reachable, from, STATE_PRUNING_BATCH_SIZE)?;
+            deleted += batch_deleted;
+            cursor = next;
+        }
+        Ok(deleted)
+    }
+
+    /// Marks the state and storage trie nodes reachable from the given state root. Subtries that
+    /// were already marked are skipped, so marking the root of a block close to the marked ones
+    /// only walks the nodes it changed
+    pub fn mark_reachable_state(
+        &self,
+        reachable: &mut ReachableNodes,
+        state_root: H256,
+    ) -> Result<(), StoreError> {
+        let state_trie = self.engine.open_state_trie(state_root);
+        state_trie.mark_reachable(&mut reachable.state, |path, encoded_state| {
+            let account_state = AccountState::decode(encoded_state)?;
+            if account_state.storage_root == *EMPTY_TRIE_HASH {
+                return Ok(());
+            }
+            let hashed_address = H256::from_slice(&path);
+            self.engine
+                .open_storage_trie(hashed_address, account_state.storage_root)
+                .mark_reachable(
+                    reachable.storage.entry(hashed_address).or_default(),
+                    |_, _| Ok(()),
+                )
+        })?;
+        Ok(())
+    }
+
+    /// Deletes the state and storage trie nodes that aren't reachable among the next `limit`
+    /// ones from `from`. Returns the amount of deleted nodes and where the sweep goes on from,
+    /// or `None` once every node was checked.
+    /// The caller must make sure no block is being stored concurrently
+    pub fn prune_unreachable_state(
+        &self,
+        reachable: &ReachableNodes,
+        from: PruneCursor,
+        limit: usize,
+    ) -> Result<(usize, Option<PruneCursor>), StoreError> {
+        self.engine.prune_state_tries(reachable, from, limit)
+    }
+
+    /// Records the earliest block whose state is kept. Set before pruning the state of the blocks
+    /// below it, so it's never read while partially deleted
+    pub fn update_earliest_state_block_number(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<(), StoreError> {
+        self.engine.update_earliest_state_block_number(block_number)
+    }
+
+    /// Walks the state trie of the given canonical block, along with the storage tries of its
+    /// accounts, looking for nodes missing from the database
+    pub fn verify_state(&self, block_number: BlockNumber) -> Result<StateVerification, StoreError> {
+        let Some(header) = self.get_block_header(block_number)? else {
+            return Err(StoreError::Custom(format!(
+                "Block {block_number} is not in the canonical chain"
+            )));
+        };
+        let mut verification = StateVerification::default();
+        // Storage roots whose tries were found complete. Storage nodes are stored by account, so
+        // just the root node is looked up for the other accounts sharing one of them
+        let mut complete_storage_roots = HashSet::new();
+        let state_trie = self.engine.open_state_trie(header.state_root);
+        verification.missing_state_nodes = state_trie.missing_nodes(|path, encoded_state| {
+            verification.accounts += 1;
+            let account_state = AccountState::decode(encoded_state)?;
+            if account_state.storage_root == *EMPTY_TRIE_HASH {
+                return Ok(());
+            }
+            let hashed_address = H256::from_slice(&path);
+            let storage_trie = self
+                .engine
+                .open_storage_trie(hashed_address, account_state.storage_root);
+            if complete_storage_roots.contains(&account_state.storage_root) {
+                if !storage_trie.root_is_stored()? {
+                    verification
+                        .missing_storage_nodes
+                        .push((hashed_address, account_state.storage_root));
+                }
+                return Ok(());
+            }
+            let missing_nodes = storage_trie.missing_nodes(|_, _| Ok(()))?;
+            if missing_nodes.is_empty() {
+                complete_storage_roots.insert(account_state.storage_root);
+            }
+            verification.missing_storage_nodes.extend(
+                missing_nodes
+                    .into_iter()
+                    .map(|node_hash| (hashed_address, node_hash)),
+            );
+            Ok(())
+        })?;
+        Ok(verification)
+    }
+
+    /// Returns the amount of entries of each table of the database and the bytes taken by them
+    pub fn table_stats(&self) -> Result<Vec<TableStats>, StoreError> {
+        self.engine.table_stats()
+    }
+
+    /// Returns the entries of the given table stored under the given key. Tables holding many
+    /// values per key return an entry for each of them
+    pub fn get_table_entries(
+        &self,
+        table: &str,
+        key: &[u8],
+    ) -> Result<Vec<TableEntry>, StoreError> {
+        self.engine
+            .read_table(table, Some(key), true, MAX_TABLE_KEY_ENTRIES)
+    }
+
+    /// Returns up to `limit` entries of the given table in key order, starting from the first one
+    /// whose key is not lower than `start`, if given
+    pub fn iter_table(
+        &self,
+        table: &str,
+        start: Option<&[u8]>,
+        limit: usize,
+    ) -> Result<Vec<TableEntry>, StoreError> {
+        self.engine.read_table(table, start, false, limit)
+    }
+
+    /// Returns the earliest block whose state is still available, if the state was ever pruned
+    pub fn get_earliest_state_block_number(&self) -> Result<Option<BlockNumber>, StoreError> {
+        self.engine.get_earliest_state_block_number()
+    }
+
     pub fn is_synced(&self) -> Result<bool, StoreError> {
         self.engine.is_synced()
     }
     pub fn update_sync_status(&self, status: bool) -> Result<(), StoreError> {
         self.engine.update_sync_status(status)
     }
+
+    /// Methods used by the L2 proposer to keep track of its interactions with the L1
+
+    pub fn add_batch_status(
+        &self,
+        block_number: BlockNumber,
+        status: BatchStatus,
+    ) -> Result<(), StoreError> {
+        self.engine.add_batch_status(block_number, status)
+    }
+
+    pub fn get_batch_status(
+        &self,
+        block_number: BlockNumber,
+    ) -> Result<Option<BatchStatus>, StoreError> {
+        self.engine.get_batch_status(block_number)
+    }
+
+    /// Records the hash of the transaction that verified an already committed block
+    pub fn update_batch_verify_tx_hash(
+        &self,
+        block_number: BlockNumber,
+        verify_tx_hash: H256,
+    ) -> Result<(), StoreError> {
+        let Some(mut status) = self.engine.get_batch_status(block_number)? else {
+            return Err(StoreError::Custom(format!(
+                "No commitment found for block {block_number}"
+            )));
+        };
+        status.verify_tx_hash = Some(verify_tx_hash);
+        self.engine.add_batch_status(block_number, status)
+    }
+
+    pub fn add_withdrawal_proofs(
+        &self,
+        proofs: Vec<(H256, WithdrawalProof)>,
+    ) -> Result<(), StoreError> {
+        self.engine.add_withdrawal_proofs(proofs)
+    }
+
+    pub fn get_withdrawal_proof(
+        &self,
+        transaction_hash: H256,
+    ) -> Result<Option<WithdrawalProof>, StoreError> {
+        self.engine.get_withdrawal_proof(transaction_hash)
+    }
+
+    pub fn add_deposit(
+        &self,
+        l1_transaction_hash: H256,
+        deposit: DepositInfo,
+    ) -> Result<(), StoreError> {
+        self.engine.add_deposit(l1_transaction_hash, deposit)
+    }
+
+    pub fn get_deposit(
+        &self,
+        l1_transaction_hash: H256,
+    ) -> Result<Option<DepositInfo>, StoreError> {
+        self.engine.get_deposit(l1_transaction_hash)
+    }
+
+    pub fn update_l1_fee_components(&self, components: L1FeeComponents) -> Result<(), StoreError> {
+        self.engine.update_l1_fee_components(components)
+    }
+
+    pub fn get_l1_fee_components(&self) -> Result<Option<L1FeeComponents>, StoreError> {
+        self.engine.get_l1_fee_components()
+    }
+}
+
+fn open_engine(path: &str, engine_type: EngineType) -> Result<Arc<dyn StoreEngine>, StoreError> {
+    Ok(match engine_type {
+        #[cfg(feature = "libmdbx")]
+        EngineType::Libmdbx => Arc::new(LibmdbxStore::new(path)?),
+        EngineType::InMemory => Arc::new(InMemoryStore::new()),
+        #[cfg(feature = "redb")]
+        EngineType::RedB => Arc::new(RedBStore::new(path)?),
+    })
 }
 
 pub fn hash_address(address: &Address) -> Vec<u8> {
@@ -1037,7 +2136,7 @@ mod tests {
     use bytes::Bytes;
     use ethereum_types::{H256, U256};
     use ethrex_core::{
-        types::{Transaction, TxType, BYTES_PER_BLOB},
+        types::{Log, Transaction, TxType, BYTES_PER_BLOB},
         Bloom,
     };
     use ethrex_rlp::decode::RLPDecode;
@@ -1061,24 +2160,49 @@ mod tests {
         test_store_suite(EngineType::RedB);
     }
 
+    #[cfg(feature = "redb")]
+    #[test]
+    fn test_redb_store_persists_at_path() {
+        let path = "store-test-db-redb-persistence";
+        remove_test_dbs(path);
+        let (block_header, block_body) = create_block_for_testing();
+        let hash = block_header.compute_block_hash();
+        {
+            let store = Store::new(path, EngineType::RedB).unwrap();
+            store.add_block_header(hash, block_header.clone()).unwrap();
+            store.add_block_body(hash, block_body.clone()).unwrap();
+            store.set_canonical_block(1, hash).unwrap();
+        }
+        assert!(std::path::Path::new(path).join("ethrex.redb").exists());
+
+        let store = Store::new(path, EngineType::RedB).unwrap();
+        assert_eq!(store.get_block_header(1).unwrap(), Some(block_header));
+        assert_eq!(store.get_block_body(1).unwrap(), Some(block_body));
+        drop(store);
+        remove_test_dbs(path);
+    }
+
     // Creates an empty store, runs the test and then removes the store (if needed)
     fn run_test(test_func: &dyn Fn(Store), engine_type: EngineType) {
+        // Each engine gets its own directory, as their suites run in parallel
+        let path = format!("store-test-db-{engine_type:?}");
         // Remove preexistent DBs in case of a failed previous test
         if !matches!(engine_type, EngineType::InMemory) {
-            remove_test_dbs("store-test-db");
+            remove_test_dbs(&path);
         };
         // Build a new store
-        let store = Store::new("store-test-db", engine_type).expect("Failed to create test db");
+        let store = Store::new(&path, engine_type).expect("Failed to create test db");
         // Run the test
         test_func(store);
         // Remove store (if needed)
         if !matches!(engine_type, EngineType::InMemory) {
-            remove_test_dbs("store-test-db");
+            remove_test_dbs(&path);
         };
     }
 
     fn test_store_suite(engine_type: EngineType) {
         run_test(&test_store_block, engine_type);
+        run_test(&test_store_pending_blocks, engine_type);
         run_test(&test_store_block_number, engine_type);
         run_test(&test_store_transaction_location, engine_type);
         run_test(&test_store_transaction_location_not_canonical, engine_type);
@@ -1089,6 +2213,17 @@ mod tests {
         run_test(&test_genesis_block, engine_type);
         run_test(&test_filter_mempool_transactions, engine_type);
         run_test(&blobs_bundle_loadtest, engine_type);
+        run_test(&test_store_l2_data, engine_type);
+        run_test(&test_write_batch, engine_type);
+        run_test(&test_snapshot_reads, engine_type);
+        run_test(&test_prune_state, engine_type);
+        run_test(&test_freeze_blocks, engine_type);
+        run_test(&test_expire_history, engine_type);
+        run_test(&test_log_index, engine_type);
+        run_test(&test_verify_state_and_set_head, engine_type);
+        if !matches!(engine_type, EngineType::InMemory) {
+            run_test(&test_inspect_tables, engine_type);
+        }
     }
 
     fn test_genesis_block(store: Store) {
@@ -1111,6 +2246,697 @@ mod tests {
         .expect_err("genesis with a different block should panic");
     }
 
+    fn test_write_batch(store: Store) {
+        const GENESIS_KURTOSIS: &str = include_str!("../../../test_data/genesis-kurtosis.json");
+        let genesis: Genesis =
+            serde_json::from_str(GENESIS_KURTOSIS).expect("deserialize genesis-kurtosis.json");
+        let genesis_hash = genesis.get_block().hash();
+        store.add_initial_state(genesis).unwrap();
+
+        let address = Address::repeat_byte(0x0a);
+        let code = Bytes::from("kiwi");
+        let mut update = AccountUpdate::new(address);
+        update.info = Some(AccountInfo {
+            code_hash: code_hash(&code),
+            nonce: 1,
+            ..Default::default()
+        });
+        update.code = Some(code.clone());
+        update
+            .added_storage
+            .insert(H256::from_low_u64_be(1), U256::from(7));
+
+        let mut batch = WriteBatch::new();
+        let state_root = store
+            .stage_account_updates(genesis_hash, &[update], &mut batch)
+            .unwrap()
+            .unwrap();
+        let block = Block::new(
+            BlockHeader {
+                parent_hash: genesis_hash,
+                number: 1,
+                state_root,
+                ..Default::default()
+            },
+            BlockBody::default(),
+        );
+        let block_hash = block.hash();
+        let receipt = Receipt {
+            tx_type: TxType::EIP2930,
+            succeeded: true,
+            cumulative_gas_used: 1747,
+            bloom: Bloom::random(),
+            logs: vec![],
+        };
+        store.stage_block(&mut batch, block).unwrap();
+        batch.add_receipts(block_hash, vec![receipt.clone()]);
+        batch.set_canonical_block(1, block_hash);
+        batch.update_latest_block_number(1);
+
+        // Nothing is stored until the batch is written
+        assert!(!store
+            .engine
+            .open_state_trie(state_root)
+            .root_is_stored()
+            .unwrap());
+        assert_eq!(store.get_block_header_by_hash(block_hash).unwrap(), None);
+        assert_eq!(store.get_account_code(code_hash(&code)).unwrap(), None);
+        assert_eq!(store.get_canonical_block_hash(1).unwrap(), None);
+
+        store.write_batch(batch).unwrap();
+        assert_eq!(store.get_block_number(block_hash).unwrap(), Some(1));
+        assert_eq!(store.get_canonical_block_hash(1).unwrap(), Some(block_hash));
+        assert_eq!(store.get_latest_block_number().unwrap(), 1);
+        assert_eq!(
+            store.get_receipts_for_block(&block_hash).unwrap(),
+            vec![receipt]
+        );
+        assert_eq!(
+            store.get_account_code(code_hash(&code)).unwrap(),
+            Some(code)
+        );
+        assert_eq!(
+            store
+                .get_storage_at_hash(block_hash, address, H256::from_low_u64_be(1))
+                .unwrap(),
+            Some(U256::from(7))
+        );
+        assert_snapshot_matches_tries(&store, block_hash, &[address], &[H256::from_low_u64_be(1)]);
+    }
+
+    fn test_snapshot_reads(store: Store) {
+        const GENESIS_KURTOSIS: &str = include_str!("../../../test_data/genesis-kurtosis.json");
+        let genesis: Genesis =
+            serde_json::from_str(GENESIS_KURTOSIS).expect("deserialize genesis-kurtosis.json");
+        let genesis_hash = genesis.get_block().hash();
+        store.add_initial_state(genesis).unwrap();
+        assert_eq!(
+            store.engine.get_snapshot_root().unwrap(),
+            Some(store.get_block_header(0).unwrap().unwrap().state_root)
+        );
+
+        let (a, b) = (Address::repeat_byte(0x0a), Address::repeat_byte(0x0b));
+        let keys: Vec<H256> = (1..=4).map(H256::from_low_u64_be).collect();
+        let update = |address: Address, nonce: u64, storage: &[(u64, u64)]| {
+            let mut update = AccountUpdate::new(address);
+            update.info = Some(AccountInfo {
+                nonce,
+                ..Default::default()
+            });
+            for (key, value) in storage {
+                update
+                    .added_storage
+                    .insert(H256::from_low_u64_be(*key), U256::from(*value));
+            }
+            update
+        };
+
+        let block_1 = add_block_with_updates(
+            &store,
+            genesis_hash,
+            &[update(a, 1, &[(1, 1), (2, 2)]), update(b, 1, &[(1, 1)])],
+        );
+        let block_2a = add_block_with_updates(
+            &store,
+            block_1,
+            &[update(a, 2, &[(1, 0), (3, 3)]), AccountUpdate::removed(b)],
+        );
+        let block_2b = add_block_with_updates(&store, block_1, &[update(a, 5, &[(4, 4)])]);
+        for block_hash in [genesis_hash, block_1, block_2a, block_2b] {
+            assert_snapshot_matches_tries(&store, block_hash, &[a, b], &keys);
+        }
+
+        // States not committed along with their block, like the ones of built payloads, add no
+        // layers
+        let payload_root = store
+            .apply_account_updates(block_1, &[update(a, 9, &[])])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            store
+                .snapshot
+                .account(store.engine.as_ref(), payload_root, hash_address_fixed(&a))
+                .unwrap(),
+            None
+        );
+
+        // Layers past the limit are merged into the disk layer
+        let mut head = block_2a;
+        for nonce in 0..MAX_SNAPSHOT_LAYERS as u64 {
+            head = add_block_with_updates(&store, head, &[update(a, nonce + 3, &[(4, nonce)])]);
+        }
+        let disk_root = store.engine.get_snapshot_root().unwrap();
+        assert_eq!(
+            disk_root,
+            Some(
+                store
+                    .get_block_header_by_hash(block_2a)
+                    .unwrap()
+                    .unwrap()
+                    .state_root
+            )
+        );
+        for block_hash in [block_1, block_2a, block_2b, head] {
+            assert_snapshot_matches_tries(&store, block_hash, &[a, b], &keys);
+        }
+
+        // A branch not leading to the disk layer has it regenerated once over the limit
+        let mut state_trie = store.state_trie(head).unwrap().unwrap();
+        let mut account = store
+            .get_account_state_by_hash(head, b)
+            .unwrap()
+            .unwrap_or_default();
+        account.nonce = 100;
+        state_trie
+            .insert(hash_address(&b), account.encode_to_vec())
+            .unwrap();
+        let header = BlockHeader {
+            parent_hash: head,
+            number: store
+                .get_block_header_by_hash(head)
+                .unwrap()
+                .unwrap()
+                .number
+                + 1,
+            state_root: state_trie.hash().unwrap(),
+            ..Default::default()
+        };
+        head = header.compute_block_hash();
+        store.add_block_header(head, header).unwrap();
+        for nonce in 0..MAX_SNAPSHOT_LAYERS as u64 + 1 {
+            head = add_block_with_updates(&store, head, &[update(a, nonce, &[(2, nonce)])]);
+        }
+        // Reads fall back to the tries while it's generated in the background
+        assert_snapshot_matches_tries(&store, head, &[a, b], &keys);
+        store.snapshot.wait_for_generation().unwrap();
+        assert_eq!(
+            store.engine.get_snapshot_root().unwrap(),
+            Some(
+                store
+                    .get_block_header_by_hash(head)
+                    .unwrap()
+                    .unwrap()
+                    .state_root
+            )
+        );
+        assert_snapshot_matches_tries(&store, head, &[a, b], &keys);
+    }
+
+    fn add_block_with_updates(
+        store: &Store,
+        parent_hash: BlockHash,
+        updates: &[AccountUpdate],
+    ) -> BlockHash {
+        let parent = store
+            .get_block_header_by_hash(parent_hash)
+            .unwrap()
+            .unwrap();
+        let mut batch = WriteBatch::new();
+        let header = BlockHeader {
+            parent_hash,
+            number: parent.number + 1,
+            state_root: store
+                .stage_account_updates(parent_hash, updates, &mut batch)
+                .unwrap()
+                .unwrap(),
+            ..Default::default()
+        };
+        let hash = header.compute_block_hash();
+        store
+            .stage_block(&mut batch, Block::new(header, BlockBody::default()))
+            .unwrap();
+        store.write_batch(batch).unwrap();
+        hash
+    }
+
+    fn assert_snapshot_matches_tries(
+        store: &Store,
+        block_hash: BlockHash,
+        addresses: &[Address],
+        keys: &[H256],
+    ) {
+        let state_trie = store.state_trie(block_hash).unwrap().unwrap();
+        for address in addresses {
+            let account = state_trie
+                .get(&hash_address(address))
+                .unwrap()
+                .map(|encoded| AccountState::decode(&encoded).unwrap());
+            assert_eq!(
+                store
+                    .get_account_state_by_hash(block_hash, *address)
+                    .unwrap(),
+                account
+            );
+            for key in keys {
+                let value = account.as_ref().and_then(|account| {
+                    store
+                        .open_storage_trie(hash_address_fixed(address), account.storage_root)
+                        .get(&hash_key(key))
+                        .unwrap()
+                        .map(|encoded| U256::decode(&encoded).unwrap())
+                });
+                assert_eq!(
+                    store
+                        .get_storage_at_hash(block_hash, *address, *key)
+                        .unwrap(),
+                    value
+                );
+            }
+        }
+    }
+
+    fn test_prune_state(store: Store) {
+        const GENESIS_KURTOSIS: &str = include_str!("../../../test_data/genesis-kurtosis.json");
+        let genesis: Genesis =
+            serde_json::from_str(GENESIS_KURTOSIS).expect("deserialize genesis-kurtosis.json");
+        let genesis_hash = genesis.get_block().hash();
+        store.add_initial_state(genesis).unwrap();
+
+        // Each block overwrites the same storage slot, leaving the previous nodes unreachable
+        let address = Address::repeat_byte(0x42);
+        let storage_key = H256::repeat_byte(0x01);
+        let mut parent_hash = genesis_hash;
+        let mut block_hashes = vec![genesis_hash];
+        for number in 1..=3u64 {
+            let mut update = AccountUpdate::new(address);
+            update.info = Some(AccountInfo {
+                nonce: number,
+                ..Default::default()
+            });
+            update.added_storage.insert(storage_key, U256::from(number));
+            let state_root = store
+                .apply_account_updates(parent_hash, &[update])
+                .unwrap()
+                .unwrap();
+            let header = BlockHeader {
+                parent_hash,
+                number,
+                state_root,
+                ..Default::default()
+            };
+            let hash = header.compute_block_hash();
+            store.add_block_header(hash, header).unwrap();
+            store.set_canonical_block(number, hash).unwrap();
+            block_hashes.push(hash);
+            parent_hash = hash;
+        }
+        assert_eq!(store.get_earliest_state_block_number().unwrap(), None);
+
+        // Unreachable nodes are swept a few at a time
+        let retained_root = store.get_block_header(3).unwrap().unwrap().state_root;
+        let mut reachable = ReachableNodes::default();
+        store
+            .mark_reachable_state(&mut reachable, retained_root)
+            .unwrap();
+        store.update_earliest_state_block_number(3).unwrap();
+        let (mut deleted, mut steps) = (0, 0);
+        let mut cursor = Some(PruneCursor::default());
+        while let Some(from) = cursor {
+            let (step_deleted, next) = store.prune_unreachable_state(&reachable, from, 2).unwrap();
+            deleted += step_deleted;
+            cursor = next;
+            steps += 1;
+        }
+        assert!(deleted > 0);
+        assert!(steps > 2);
+        assert_eq!(store.get_earliest_state_block_number().unwrap(), Some(3));
+
+        for hash in &block_hashes[..3] {
+            assert!(store.state_trie(*hash).unwrap().is_none());
+        }
+        assert_eq!(
+            store.get_nonce_by_account_address(3, address).unwrap(),
+            Some(3)
+        );
+        assert_eq!(
+            store.get_storage_at(3, address, storage_key).unwrap(),
+            Some(U256::from(3))
+        );
+
+        // Pruning again with the same retained roots deletes nothing
+        assert_eq!(store.prune_state(3, [retained_root]).unwrap(), 0);
+    }
+
+    fn test_verify_state_and_set_head(store: Store) {
+        const GENESIS_KURTOSIS: &str = include_str!("../../../test_data/genesis-kurtosis.json");
+        let genesis: Genesis =
+            serde_json::from_str(GENESIS_KURTOSIS).expect("deserialize genesis-kurtosis.json");
+        let genesis_hash = genesis.get_block().hash();
+        store.add_initial_state(genesis).unwrap();
+        let store = store.with_log_index(true).unwrap();
+
+        let address = Address::repeat_byte(0x42);
+        let mut parent_hash = genesis_hash;
+        let mut block_hashes = vec![genesis_hash];
+        for number in 1..=3u64 {
+            let mut update = AccountUpdate::new(address);
+            update.info = Some(AccountInfo {
+                nonce: number,
+                ..Default::default()
+            });
+            update
+                .added_storage
+                .insert(H256::from_low_u64_be(number), U256::from(number));
+            let state_root = store
+                .apply_account_updates(parent_hash, &[update])
+                .unwrap()
+                .unwrap();
+            let header = BlockHeader {
+                parent_hash,
+                number,
+                state_root,
+                ..Default::default()
+            };
+            let hash = header.compute_block_hash();
+            store
+                .add_block(Block::new(header, BlockBody::default()))
+                .unwrap();
+            let log = Log {
+                address,
+                topics: vec![],
+                data: Bytes::new(),
+            };
+            store
+                .add_receipts(
+                    hash,
+                    vec![Receipt::new(TxType::EIP1559, true, 21_000, vec![log])],
+                )
+                .unwrap();
+            store.set_canonical_block(number, hash).unwrap();
+            block_hashes.push(hash);
+            parent_hash = hash;
+        }
+        store.update_latest_block_number(3).unwrap();
+        store.update_finalized_block_number(3).unwrap();
+
+        let verification = store.verify_state(3).unwrap();
+        assert!(verification.is_complete());
+        assert!(verification.accounts > 1);
+
+        assert!(store.set_head(4).is_err());
+        assert_eq!(store.set_head(2).unwrap(), block_hashes[2]);
+        assert_eq!(store.get_latest_block_number().unwrap(), 2);
+        assert_eq!(store.get_finalized_block_number().unwrap(), Some(2));
+        assert_eq!(store.get_canonical_block_hash(3).unwrap(), None);
+        assert_eq!(
+            store.get_canonical_block_hash(2).unwrap(),
+            Some(block_hashes[2])
+        );
+        // The rewound blocks leave the log index
+        assert_eq!(
+            store.engine.get_address_log_blocks(address, 0, 3).unwrap(),
+            vec![(1, block_hashes[1]), (2, block_hashes[2])]
+        );
+        // And the snapshot is generated again at the new head's state
+        let head_root = store.get_block_header(2).unwrap().unwrap().state_root;
+        assert_eq!(store.engine.get_snapshot_root().unwrap(), Some(head_root));
+
+        // Blocks whose state was pruned can't become the head
+        let retained_root = store.get_block_header(2).unwrap().unwrap().state_root;
+        store.prune_state(2, [retained_root]).unwrap();
+        let pruned_root = store.get_block_header(1).unwrap().unwrap().state_root;
+        let verification = store.verify_state(1).unwrap();
+        assert_eq!(verification.missing_state_nodes, vec![pruned_root]);
+        assert_eq!(verification.accounts, 0);
+        assert!(store.set_head(1).is_err());
+        assert!(store.verify_state(2).unwrap().is_complete());
+    }
+
+    fn test_inspect_tables(store: Store) {
+        let (header, _) = create_block_for_testing();
+        let hash = header.compute_block_hash();
+        store.add_block_header(hash, header.clone()).unwrap();
+
+        let stats = store.table_stats().unwrap();
+        let headers = stats.iter().find(|table| table.name == "Headers").unwrap();
+        assert_eq!(headers.entries, 1);
+        assert!(headers.size > 0);
+
+        let entries = store.iter_table("Headers", None, 10).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].decoded_key, format!("{hash:?}"));
+        assert_eq!(entries[0].decoded_value, format!("{header:?}"));
+        assert_eq!(
+            store.get_table_entries("Headers", &entries[0].key).unwrap(),
+            entries
+        );
+        assert!(store
+            .get_table_entries("Headers", &[0x12])
+            .unwrap()
+            .is_empty());
+
+        // Transaction locations are stored in a multimap table, with an entry per block including it
+        for (transaction, blocks) in [(H256::from_low_u64_be(1), 3), (H256::from_low_u64_be(2), 2)]
+        {
+            for number in 0..blocks {
+                store
+                    .add_transaction_location(transaction, number, H256::random(), 0)
+                    .unwrap();
+            }
+        }
+        let stats = store.table_stats().unwrap();
+        let locations = stats
+            .iter()
+            .find(|table| table.name == "TransactionLocations")
+            .unwrap();
+        assert_eq!(locations.entries, 5);
+        let entries = store.iter_table("TransactionLocations", None, 10).unwrap();
+        assert_eq!(entries.len(), 5);
+        let first_transaction: Vec<_> = entries
+            .iter()
+            .filter(|entry| entry.key == entries[0].key)
+            .cloned()
+            .collect();
+        assert!(first_transaction.len() > 1 && first_transaction.len() < entries.len());
+        assert_eq!(
+            store
+                .get_table_entries("TransactionLocations", &entries[0].key)
+                .unwrap(),
+            first_transaction
+        );
+
+        store.update_latest_block_number(5).unwrap();
+        let chain_data = store.iter_table("ChainData", None, 10).unwrap();
+        assert!(chain_data
+            .iter()
+            .any(|entry| entry.decoded_key == "LatestBlockNumber"));
+
+        assert!(matches!(
+            store.iter_table("Unknown", None, 1),
+            Err(StoreError::UnknownTable(_))
+        ));
+    }
+
+    fn test_freeze_blocks(store: Store) {
+        let (header, body) = create_block_for_testing();
+        let mut blocks = Vec::new();
+        let mut parent_hash = H256::zero();
+        for number in 0..4 {
+            let block = Block::new(
+                BlockHeader {
+                    number,
+                    parent_hash,
+                    ..header.clone()
+                },
+                body.clone(),
+            );
+            let hash = block.hash();
+            let receipts = vec![
+                Receipt::new(TxType::EIP1559, true, 21_000, vec![]),
+                Receipt::new(TxType::Legacy, false, 42_000, vec![]),
+            ];
+            store.add_block(block.clone()).unwrap();
+            store.add_receipts(hash, receipts.clone()).unwrap();
+            store.set_canonical_block(number, hash).unwrap();
+            blocks.push((block, receipts));
+            parent_hash = hash;
+        }
+
+        let frozen = store.freeze_blocks(2).unwrap();
+        if store.freezer.is_none() {
+            assert_eq!(frozen, 0);
+        } else {
+            assert_eq!(frozen, 2);
+            assert_eq!(store.get_frozen_block_count().unwrap(), 2);
+            assert!(store.engine.get_block_header(0).unwrap().is_none());
+            assert!(store.engine.get_block_header(2).unwrap().is_some());
+        }
+
+        // Frozen blocks are read as any other
+        for (block, receipts) in &blocks {
+            let number = block.header.number;
+            let hash = block.hash();
+            assert_eq!(
+                store.get_block_header(number).unwrap().as_ref(),
+                Some(&block.header)
+            );
+            assert_eq!(
+                store.get_block_header_by_hash(hash).unwrap().as_ref(),
+                Some(&block.header)
+            );
+            assert_eq!(
+                store.get_block_body(number).unwrap().as_ref(),
+                Some(&block.body)
+            );
+            let stored_block = store.get_block_by_hash(hash).unwrap().unwrap();
+            assert_eq!(stored_block.header, block.header);
+            assert_eq!(stored_block.body, block.body);
+            assert_eq!(
+                store.get_receipt(number, 1).unwrap().as_ref(),
+                receipts.get(1)
+            );
+            assert_eq!(&store.get_receipts_for_block(&hash).unwrap(), receipts);
+            assert_eq!(
+                store.get_transaction_by_location(hash, 0).unwrap().as_ref(),
+                block.body.transactions.first()
+            );
+        }
+        assert_eq!(
+            store.get_block_header_by_hash(H256::random()).unwrap(),
+            None
+        );
+
+        // Freezing stops at the first block that isn't stored
+        assert_eq!(store.freeze_blocks(2).unwrap(), 0);
+        if store.freezer.is_some() {
+            assert_eq!(store.freeze_blocks(10).unwrap(), 2);
+            assert_eq!(store.get_frozen_block_count().unwrap(), 4);
+        }
+    }
+
+    fn test_expire_history(store: Store) {
+        let (header, body) = create_block_for_testing();
+        let transaction_hash = body.transactions[0].compute_hash();
+        let mut hashes = Vec::new();
+        let mut parent_hash = H256::zero();
+        for number in 0..4 {
+            let block = Block::new(
+                BlockHeader {
+                    number,
+                    parent_hash,
+                    ..header.clone()
+                },
+                body.clone(),
+            );
+            let hash = block.hash();
+            store.add_block(block).unwrap();
+            store
+                .add_receipts(
+                    hash,
+                    vec![Receipt::new(TxType::EIP1559, true, 21_000, vec![])],
+                )
+                .unwrap();
+            store.set_canonical_block(number, hash).unwrap();
+            hashes.push(hash);
+            parent_hash = hash;
+        }
+        store.update_earliest_block_number(0).unwrap();
+        // Expired history is also deleted from the freezer
+        store.freeze_blocks(3).unwrap();
+
+        assert_eq!(store.expire_history(2).unwrap(), 2);
+        assert_eq!(store.get_earliest_block_number().unwrap(), 2);
+        for number in 0..2 {
+            assert!(store.is_history_expired(number).unwrap());
+            assert!(store.get_block_header(number).unwrap().is_some());
+            assert_eq!(store.get_block_body(number).unwrap(), None);
+            assert_eq!(store.get_receipt(number, 0).unwrap(), None);
+            let hash = hashes[number as usize];
+            assert_eq!(store.get_block_body_by_hash(hash).unwrap(), None);
+            assert!(store.get_receipts_for_block(&hash).unwrap().is_empty());
+            assert_eq!(store.get_block_by_hash(hash).unwrap(), None);
+        }
+        for number in 2..4 {
+            assert!(!store.is_history_expired(number).unwrap());
+            assert_eq!(store.get_block_body(number).unwrap(), Some(body.clone()));
+            assert!(store.get_receipt(number, 0).unwrap().is_some());
+        }
+        // The transaction is only found in the blocks whose history is kept
+        let (number, _, _) = store
+            .get_transaction_location(transaction_hash)
+            .unwrap()
+            .unwrap();
+        assert!(number >= 2);
+
+        // Expiring up to the earliest block again does nothing
+        assert_eq!(store.expire_history(2).unwrap(), 0);
+    }
+
+    fn test_log_index(store: Store) {
+        let address = Address::repeat_byte(1);
+        let topic = H256::repeat_byte(2);
+        let receipts = |logs: Vec<Log>| vec![Receipt::new(TxType::EIP1559, true, 21_000, logs)];
+        let add_block = |store: &Store, number: BlockNumber, extra_data: u8, logs: Vec<Log>| {
+            let block = Block::new(
+                BlockHeader {
+                    number,
+                    extra_data: Bytes::from(vec![extra_data]),
+                    ..Default::default()
+                },
+                BlockBody::default(),
+            );
+            let hash = block.hash();
+            store.add_block(block).unwrap();
+            store.add_receipts(hash, receipts(logs)).unwrap();
+            store.set_canonical_block(number, hash).unwrap();
+            store.update_latest_block_number(number).unwrap();
+            hash
+        };
+        let log = |address: Address, topics: Vec<H256>| Log {
+            address,
+            topics,
+            data: Bytes::new(),
+        };
+
+        // Blocks stored before the index is enabled are only indexed by the backfill
+        add_block(&store, 0, 0, vec![]);
+        add_block(&store, 1, 0, vec![log(address, vec![])]);
+        add_block(&store, 2, 0, vec![]);
+        let store = store.with_log_index(true).unwrap();
+        let block_3 = add_block(&store, 3, 0, vec![log(Address::zero(), vec![topic])]);
+        assert_eq!(
+            store.get_log_blocks(&[address], &[], 0, 3).unwrap(),
+            Some(vec![0, 1, 2])
+        );
+        assert_eq!(store.backfill_log_index().unwrap(), 3);
+        assert_eq!(
+            store.get_log_blocks(&[address], &[], 0, 3).unwrap(),
+            Some(vec![1])
+        );
+        assert_eq!(
+            store.get_log_blocks(&[], &[vec![topic]], 0, 3).unwrap(),
+            Some(vec![3])
+        );
+        assert_eq!(
+            store
+                .get_log_blocks(&[address], &[vec![topic]], 0, 3)
+                .unwrap(),
+            Some(vec![])
+        );
+        assert_eq!(store.get_log_blocks(&[], &[vec![]], 0, 3).unwrap(), None);
+
+        // Blocks leaving the canonical chain leave the index, and come back along with it
+        add_block(&store, 3, 1, vec![]);
+        assert_eq!(
+            store.get_log_blocks(&[], &[vec![topic]], 0, 3).unwrap(),
+            Some(vec![])
+        );
+        store.set_canonical_block(3, block_3).unwrap();
+        assert_eq!(
+            store.get_log_blocks(&[], &[vec![topic]], 0, 3).unwrap(),
+            Some(vec![3])
+        );
+
+        // A disabled index isn't used, and has to be backfilled again once enabled
+        let store = store.with_log_index(false).unwrap();
+        assert_eq!(store.get_log_blocks(&[address], &[], 0, 3).unwrap(), None);
+        let store = store.with_log_index(true).unwrap();
+        assert_eq!(
+            store.get_log_blocks(&[address], &[], 0, 3).unwrap(),
+            Some(vec![0, 1, 2, 3])
+        );
+    }
+
     fn remove_test_dbs(path: &str) {
         // Removes all test databases from filesystem
         if std::path::Path::new(path).exists() {
@@ -1134,6 +2960,54 @@ mod tests {
         assert_eq!(stored_body, block_body);
     }
 
+    fn test_store_pending_blocks(store: Store) {
+        let (mut header, body) = create_block_for_testing();
+        let parent_hash = H256::random();
+        header.parent_hash = parent_hash;
+        let number = header.number;
+        let child_a = Block::new(header.clone(), body.clone());
+        header.timestamp += 1;
+        let child_b = Block::new(header, body);
+        let hash_a = child_a.hash();
+        let hash_b = child_b.hash();
+
+        store.add_pending_block(child_a).unwrap();
+        store.add_pending_block(child_b).unwrap();
+
+        let hashes = |blocks: Vec<Block>| blocks.iter().map(Block::hash).collect::<Vec<_>>();
+        let children = hashes(store.get_pending_blocks_by_parent(parent_hash).unwrap());
+        assert_eq!(children.len(), 2);
+        assert!(children.contains(&hash_a) && children.contains(&hash_b));
+        let pending = hashes(store.get_pending_blocks().unwrap());
+        assert!(pending.contains(&hash_a) && pending.contains(&hash_b));
+        let mut index: Vec<_> = store
+            .get_pending_block_index()
+            .unwrap()
+            .iter()
+            .map(|info| (info.hash, info.number))
+            .collect();
+        index.sort();
+        let mut expected = vec![(hash_a, number), (hash_b, number)];
+        expected.sort();
+        assert_eq!(index, expected);
+
+        store.remove_pending_block(hash_a).unwrap();
+        assert!(store.get_pending_block(hash_a).unwrap().is_none());
+        assert_eq!(
+            hashes(store.get_pending_blocks_by_parent(parent_hash).unwrap()),
+            vec![hash_b]
+        );
+
+        store.remove_pending_block(hash_b).unwrap();
+        // Removing an unknown block is a no-op
+        store.remove_pending_block(hash_b).unwrap();
+        assert!(store
+            .get_pending_blocks_by_parent(parent_hash)
+            .unwrap()
+            .is_empty());
+        assert!(store.get_pending_block_index().unwrap().is_empty());
+    }
+
     fn create_block_for_testing() -> (BlockHeader, BlockBody) {
         let block_header = BlockHeader {
             parent_hash: H256::from_str(
@@ -1375,4 +3249,67 @@ mod tests {
                 .unwrap();
         }
     }
+
+    fn test_store_l2_data(store: Store) {
+        let block_number = 6;
+        let status = BatchStatus {
+            block_number,
+            commit_tx_hash: H256::random(),
+            withdrawals_merkle_root: H256::random(),
+            deposit_logs_hash: H256::random(),
+            verify_tx_hash: None,
+        };
+        store
+            .add_batch_status(block_number, status.clone())
+            .unwrap();
+        assert_eq!(store.get_batch_status(block_number).unwrap(), Some(status));
+
+        let verify_tx_hash = H256::random();
+        store
+            .update_batch_verify_tx_hash(block_number, verify_tx_hash)
+            .unwrap();
+        let stored_status = store.get_batch_status(block_number).unwrap().unwrap();
+        assert_eq!(stored_status.verify_tx_hash, Some(verify_tx_hash));
+        assert!(store
+            .update_batch_verify_tx_hash(block_number + 1, verify_tx_hash)
+            .is_err());
+
+        let withdrawal_tx_hash = H256::random();
+        let proof = WithdrawalProof {
+            block_number,
+            index: 1,
+            withdrawal_hash: H256::random(),
+            merkle_root: stored_status.withdrawals_merkle_root,
+            merkle_proof: vec![H256::random(), H256::random()],
+        };
+        store
+            .add_withdrawal_proofs(vec![(withdrawal_tx_hash, proof.clone())])
+            .unwrap();
+        assert_eq!(
+            store.get_withdrawal_proof(withdrawal_tx_hash).unwrap(),
+            Some(proof)
+        );
+        assert_eq!(store.get_withdrawal_proof(H256::random()).unwrap(), None);
+
+        let l1_tx_hash = H256::random();
+        let deposit = DepositInfo {
+            l1_block_number: 100,
+            l2_tx_hash: H256::random(),
+            recipient: Address::random(),
+            value: U256::from(1000),
+            deposit_id: U256::from(3),
+        };
+        store.add_deposit(l1_tx_hash, deposit.clone()).unwrap();
+        assert_eq!(store.get_deposit(l1_tx_hash).unwrap(), Some(deposit));
+
+        assert_eq!(store.get_l1_fee_components().unwrap(), None);
+        let components = L1FeeComponents {
+            l1_block_number: 100,
+            base_fee_per_gas: 7,
+            blob_base_fee: 1,
+            blob_gas_price: 2,
+        };
+        store.update_l1_fee_components(components).unwrap();
+        assert_eq!(store.get_l1_fee_components().unwrap(), Some(components));
+    }
 }
@@ -9,6 +9,9 @@ pub mod redb;
 pub mod redb_multitable;
 mod utils;
 
+#[cfg(any(feature = "libmdbx", feature = "redb"))]
+pub use utils::node_hash_to_fixed_size;
+
 use crate::error::TrieError;
 
 pub trait TrieDB {
@@ -1,6 +1,6 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
-use crate::error::TrieError;
+use crate::{error::TrieError, NodeRLP, TrieNodes};
 use ethrex_rlp::{decode::RLPDecode, encode::RLPEncode};
 
 use super::db::TrieDB;
@@ -12,6 +12,8 @@ use super::{node::Node, node_hash::NodeHash};
 pub struct TrieState {
     db: Box<dyn TrieDB>,
     cache: HashMap<NodeHash, Node>,
+    /// Encoded nodes keyed by their hash that are not stored in the DB yet, read before it
+    staged: Option<Arc<HashMap<Vec<u8>, NodeRLP>>>,
 }
 
 impl TrieState {
@@ -20,9 +22,15 @@ impl TrieState {
         TrieState {
             db,
             cache: Default::default(),
+            staged: None,
         }
     }
 
+    /// Sets the nodes read before the ones in the DB
+    pub(crate) fn set_staged_nodes(&mut self, staged: Arc<HashMap<Vec<u8>, NodeRLP>>) {
+        self.staged = Some(staged);
+    }
+
     /// Retrieves a node based on its hash
     pub fn get_node(&self, hash: NodeHash) -> Result<Option<Node>, TrieError> {
         // Decode the node if it is inlined
@@ -32,8 +40,12 @@ impl TrieState {
         if let Some(node) = self.cache.get(&hash) {
             return Ok(Some(node.clone()));
         };
+        let key: Vec<u8> = hash.into();
+        if let Some(rlp) = self.staged.as_ref().and_then(|staged| staged.get(&key)) {
+            return Ok(Some(Node::decode(rlp)?));
+        }
         self.db
-            .get(hash.into())?
+            .get(key)?
             .map(|rlp| Node::decode(&rlp).map_err(TrieError::RLPDecode))
             .transpose()
     }
@@ -49,19 +61,18 @@ impl TrieState {
     /// Commits cache changes to DB and clears it
     /// Only writes nodes that follow the root's canonical trie
     pub fn commit(&mut self, root: &NodeHash) -> Result<(), TrieError> {
-        self.commit_node(root)?;
-        self.cache.clear();
+        let to_commit = self.take_changes(root)?;
+        self.db.put_batch(to_commit)?;
         Ok(())
     }
 
-    // Writes a node and its children into the DB
-    fn commit_node(&mut self, node_hash: &NodeHash) -> Result<(), TrieError> {
-        let mut to_commit = vec![];
-        self.commit_node_tail_recursive(node_hash, &mut to_commit)?;
-
-        self.db.put_batch(to_commit)?;
-
-        Ok(())
+    /// Takes the cached nodes that follow the root's canonical trie, encoded and keyed by their
+    /// hash, and clears the cache. The nodes are not written to the DB
+    pub fn take_changes(&mut self, root: &NodeHash) -> Result<TrieNodes, TrieError> {
+        let mut changes = vec![];
+        self.commit_node_tail_recursive(root, &mut changes)?;
+        self.cache.clear();
+        Ok(changes)
     }
 
     // Writes a node and its children into the DB
@@ -13,7 +13,10 @@ use ethereum_types::H256;
 use ethrex_rlp::constants::RLP_NULL;
 use node_hash::NodeHash;
 use sha3::{Digest, Keccak256};
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 #[cfg(feature = "libmdbx")]
 pub use self::db::{libmdbx::LibmdbxTrieDB, libmdbx_dupsort::LibmdbxDupsortTrieDB};
@@ -44,6 +47,8 @@ pub type PathRLP = Vec<u8>;
 pub type ValueRLP = Vec<u8>;
 /// RLP-encoded trie node
 pub type NodeRLP = Vec<u8>;
+/// RLP-encoded trie nodes keyed by their hash, as stored in the DB
+pub type TrieNodes = Vec<(Vec<u8>, NodeRLP)>;
 
 /// Libmdx-based Ethereum Compatible Merkle Patricia Trie
 pub struct Trie {
@@ -71,6 +76,13 @@ impl Trie {
         }
     }
 
+    /// Makes the trie read the given encoded nodes, keyed by their hash, before the ones in its
+    /// DB, so it can be opened on top of nodes that are not stored yet
+    pub fn with_staged_nodes(mut self, nodes: Arc<HashMap<Vec<u8>, NodeRLP>>) -> Self {
+        self.state.set_staged_nodes(nodes);
+        self
+    }
+
     /// Retrieve an RLP-encoded value from the trie given its RLP-encoded path.
     pub fn get(&self, path: &PathRLP) -> Result<Option<ValueRLP>, TrieError> {
         if let Some(root) = &self.root {
@@ -138,6 +150,17 @@ impl Trie {
             .unwrap_or(*EMPTY_TRIE_HASH))
     }
 
+    /// Return the hash of the trie's root node along with the nodes added since the last commit,
+    /// encoded and keyed by their hash, instead of writing them to the DB.
+    /// The nodes are dropped from the trie, so they must be stored before it is opened again
+    pub fn collect_changes(&mut self) -> Result<(H256, TrieNodes), TrieError> {
+        let changes = match &self.root {
+            Some(root) => self.state.take_changes(root)?,
+            None => vec![],
+        };
+        Ok((self.hash_no_commit(), changes))
+    }
+
     /// Return the hash of the trie's root node.
     /// Returns keccak(RLP_NULL) if the trie is empty
     pub fn hash_no_commit(&self) -> H256 {
@@ -320,6 +343,87 @@ impl Trie {
         }
     }
 
+    /// Returns whether the root node can be found in the DB. Empty tries are always stored.
+    /// Only tries whose nodes were pruned, or not downloaded yet, miss their root node
+    pub fn root_is_stored(&self) -> Result<bool, TrieError> {
+        match &self.root {
+            Some(root) => Ok(self.state.get_node(root.clone())?.is_some()),
+            None => Ok(true),
+        }
+    }
+
+    /// Adds the hashes of the stored nodes reachable from the root to `reachable`, calling
+    /// `on_leaf` with the path and value of every leaf found on the way.
+    /// Subtries whose root is already in `reachable` are not walked again, so marking many tries
+    /// that share most of their nodes only visits each node once. Missing nodes are skipped.
+    pub fn mark_reachable(
+        &self,
+        reachable: &mut HashSet<H256>,
+        on_leaf: impl FnMut(PathRLP, &ValueRLP) -> Result<(), TrieError>,
+    ) -> Result<(), TrieError> {
+        self.walk(|hash| reachable.insert(hash), |_| {}, on_leaf)
+    }
+
+    /// Returns the hashes of the nodes reachable from the root that can't be found in the DB,
+    /// calling `on_leaf` with the path and value of every leaf found on the way
+    pub fn missing_nodes(
+        &self,
+        on_leaf: impl FnMut(PathRLP, &ValueRLP) -> Result<(), TrieError>,
+    ) -> Result<Vec<H256>, TrieError> {
+        let mut missing = Vec::new();
+        self.walk(|_| true, |hash| missing.push(hash), on_leaf)?;
+        Ok(missing)
+    }
+
+    // Walks the nodes reachable from the root, skipping the subtries whose root hash `visit`
+    // returns false for and calling `on_missing` with the hash of each node not in the DB
+    fn walk(
+        &self,
+        mut visit: impl FnMut(H256) -> bool,
+        mut on_missing: impl FnMut(H256),
+        mut on_leaf: impl FnMut(PathRLP, &ValueRLP) -> Result<(), TrieError>,
+    ) -> Result<(), TrieError> {
+        let mut stack: Vec<(Nibbles, NodeHash)> = self
+            .root
+            .iter()
+            .map(|root| (Nibbles::default(), root.clone()))
+            .collect();
+        while let Some((mut path, node_hash)) = stack.pop() {
+            if let NodeHash::Hashed(hash) = node_hash {
+                if !visit(hash) {
+                    continue;
+                }
+            }
+            let Some(node) = self.state.get_node(node_hash.clone())? else {
+                if let NodeHash::Hashed(hash) = node_hash {
+                    on_missing(hash);
+                }
+                continue;
+            };
+            match node {
+                Node::Branch(branch_node) => {
+                    for (choice, child) in branch_node.choices.iter().enumerate() {
+                        if child.is_valid() {
+                            stack.push((path.append_new(choice as u8), child.clone()));
+                        }
+                    }
+                    if !branch_node.value.is_empty() {
+                        on_leaf(path.to_bytes(), &branch_node.value)?;
+                    }
+                }
+                Node::Extension(extension_node) => {
+                    path.extend(&extension_node.prefix);
+                    stack.push((path, extension_node.child));
+                }
+                Node::Leaf(leaf_node) => {
+                    path.extend(&leaf_node.partial);
+                    on_leaf(path.to_bytes(), &leaf_node.value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Returns a mutable reference to the trie's internal node state
     /// [WARNING] This will allow directly manipulating the trie's state and
     /// may lead to inconsistent trie structures if not used resposibly
@@ -1143,4 +1247,93 @@ mod test {
         let trie_proof = trie.get_proof(&a).unwrap();
         assert_eq!(cita_proof, trie_proof);
     }
+
+    #[test]
+    fn mark_reachable_covers_committed_nodes() {
+        let map: Arc<std::sync::Mutex<std::collections::HashMap<Vec<u8>, Vec<u8>>>> =
+            Default::default();
+        let mut trie = Trie::new(Box::new(InMemoryTrieDB::new(map.clone())));
+        for i in 0..50u8 {
+            trie.insert(vec![i; 32], vec![i; 40]).unwrap();
+        }
+        let first_root = trie.hash().unwrap();
+        trie.insert(vec![7; 32], vec![0; 40]).unwrap();
+        let second_root = trie.hash().unwrap();
+
+        let mut reachable = HashSet::new();
+        let mut leaves = 0;
+        for root in [first_root, second_root] {
+            let trie = Trie::open(Box::new(InMemoryTrieDB::new(map.clone())), root);
+            trie.mark_reachable(&mut reachable, |_, _| {
+                leaves += 1;
+                Ok(())
+            })
+            .unwrap();
+        }
+        // Only the leaf changed by the second root is visited twice
+        assert_eq!(leaves, 51);
+        let stored: HashSet<H256> = map
+            .lock()
+            .unwrap()
+            .keys()
+            .map(|key| H256::from_slice(key))
+            .collect();
+        assert_eq!(reachable, stored);
+        assert!(trie.root_is_stored().unwrap());
+        assert!(
+            !Trie::open(Box::new(InMemoryTrieDB::new(map)), H256::zero())
+                .root_is_stored()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn missing_nodes_reports_deleted_nodes() {
+        let map: Arc<std::sync::Mutex<std::collections::HashMap<Vec<u8>, Vec<u8>>>> =
+            Default::default();
+        let mut trie = Trie::new(Box::new(InMemoryTrieDB::new(map.clone())));
+        for i in 0..50u8 {
+            trie.insert(vec![i; 32], vec![i; 40]).unwrap();
+        }
+        let root = trie.hash().unwrap();
+        let trie = Trie::open(Box::new(InMemoryTrieDB::new(map.clone())), root);
+        assert!(trie.missing_nodes(|_, _| Ok(())).unwrap().is_empty());
+
+        // Delete a node other than the root, along with the leaves below it
+        let deleted = map
+            .lock()
+            .unwrap()
+            .keys()
+            .map(|key| H256::from_slice(key))
+            .find(|hash| *hash != root)
+            .unwrap();
+        map.lock().unwrap().remove(deleted.as_bytes());
+        let mut leaves = 0;
+        let missing = trie
+            .missing_nodes(|_, _| {
+                leaves += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(missing, vec![deleted]);
+        assert!(leaves < 50);
+    }
+
+    #[test]
+    fn collect_changes_leaves_db_untouched() {
+        let map: Arc<std::sync::Mutex<std::collections::HashMap<Vec<u8>, Vec<u8>>>> =
+            Default::default();
+        let mut trie = Trie::new(Box::new(InMemoryTrieDB::new(map.clone())));
+        for i in 0..50u8 {
+            trie.insert(vec![i; 32], vec![i; 40]).unwrap();
+        }
+        let (root, changes) = trie.collect_changes().unwrap();
+        assert!(map.lock().unwrap().is_empty());
+
+        let db = InMemoryTrieDB::new(map.clone());
+        db.put_batch(changes).unwrap();
+        let mut reopened = Trie::open(Box::new(db), root);
+        assert_eq!(reopened.get(&vec![7; 32]).unwrap(), Some(vec![7; 40]));
+        assert_eq!(reopened.hash().unwrap(), root);
+    }
 }
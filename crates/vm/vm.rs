@@ -71,6 +71,61 @@ impl EvmState {
     }
 }
 
+/// Copy of the changes cached by an [EvmState] at some point, used to undo the transactions
+/// executed after it, for example when a bundle fails halfway through
+pub enum EvmStateSnapshot {
+    Store {
+        cache: revm::db::CacheState,
+        transition_state: Option<revm::db::TransitionState>,
+        bundle_state: revm::db::BundleState,
+    },
+    Execution(Box<revm::db::CacheDB<ExecutionDB>>),
+}
+
+impl EvmState {
+    /// Takes a snapshot of the current state changes
+    pub fn snapshot(&self) -> EvmStateSnapshot {
+        match self {
+            EvmState::Store(db) => EvmStateSnapshot::Store {
+                cache: db.cache.clone(),
+                transition_state: db.transition_state.clone(),
+                bundle_state: db.bundle_state.clone(),
+            },
+            EvmState::Execution(db) => EvmStateSnapshot::Execution(db.clone()),
+        }
+    }
+
+    /// Makes the hash of a block that isn't canonical yet available to the BLOCKHASH opcode, as
+    /// needed when executing several blocks on top of each other before storing them
+    pub fn cache_block_hash(&mut self, number: u64, hash: BlockHash) {
+        if let EvmState::Store(db) = self {
+            db.block_hashes
+                .insert(number, B256::from_slice(hash.as_bytes()));
+        }
+    }
+
+    /// Discards the changes made since the snapshot was taken
+    pub fn restore(&mut self, snapshot: EvmStateSnapshot) {
+        match (self, snapshot) {
+            (
+                EvmState::Store(db),
+                EvmStateSnapshot::Store {
+                    cache,
+                    transition_state,
+                    bundle_state,
+                },
+            ) => {
+                db.cache = cache;
+                db.transition_state = transition_state;
+                db.bundle_state = bundle_state;
+            }
+            (EvmState::Execution(db), EvmStateSnapshot::Execution(snapshot)) => *db = snapshot,
+            // Snapshots are only taken and restored on the same state
+            _ => {}
+        }
+    }
+}
+
 impl From<ExecutionDB> for EvmState {
     fn from(value: ExecutionDB) -> Self {
         EvmState::Execution(Box::new(revm::db::CacheDB::new(value)))
@@ -336,6 +391,24 @@ cfg_if::cfg_if! {
     } else if #[cfg(not(feature = "levm"))] {
         /// Executes all transactions in a block and returns their receipts.
         pub fn execute_block(block: &Block, state: &mut EvmState) -> Result<Vec<Receipt>, EvmError> {
+            let senders: Vec<Address> = block.body.transactions.iter().map(recover_sender).collect();
+            execute_block_with_senders(block, &senders, state)
+        }
+
+        /// Executes all transactions in a block, whose senders were already recovered, and
+        /// returns their receipts.
+        pub fn execute_block_with_senders(
+            block: &Block,
+            senders: &[Address],
+            state: &mut EvmState,
+        ) -> Result<Vec<Receipt>, EvmError> {
+            if senders.len() != block.body.transactions.len() {
+                return Err(EvmError::Custom(format!(
+                    "Expected {} transaction senders, got {}",
+                    block.body.transactions.len(),
+                    senders.len()
+                )));
+            }
             let block_header = &block.header;
             let spec_id = spec_id(&state.chain_config()?, block_header.timestamp);
             //eip 4788: execute beacon_root_contract_call before block transactions
@@ -350,8 +423,8 @@ cfg_if::cfg_if! {
             let mut receipts = Vec::new();
             let mut cumulative_gas_used = 0;
 
-            for transaction in block.body.transactions.iter() {
-                let result = execute_tx(transaction, block_header, state, spec_id)?;
+            for (transaction, sender) in block.body.transactions.iter().zip(senders) {
+                let result = execute_tx_with_sender(transaction, *sender, block_header, state, spec_id)?;
                 cumulative_gas_used += result.gas_used();
                 let receipt = Receipt::new(
                     transaction.tx_type(),
@@ -377,9 +450,20 @@ pub fn execute_tx(
     header: &BlockHeader,
     state: &mut EvmState,
     spec_id: SpecId,
+) -> Result<ExecutionResult, EvmError> {
+    execute_tx_with_sender(tx, recover_sender(tx), header, state, spec_id)
+}
+
+// Executes a single tx whose sender was already recovered, doesn't perform state transitions
+pub fn execute_tx_with_sender(
+    tx: &Transaction,
+    sender: Address,
+    header: &BlockHeader,
+    state: &mut EvmState,
+    spec_id: SpecId,
 ) -> Result<ExecutionResult, EvmError> {
     let block_env = block_env(header);
-    let tx_env = tx_env(tx);
+    let tx_env = tx_env_with_sender(tx, sender);
     run_evm(tx_env, block_env, state, spec_id)
 }
 
@@ -827,17 +911,29 @@ pub fn block_env(header: &BlockHeader) -> BlockEnv {
     }
 }
 
+/// Returns the address the transaction is executed from. Deposits aren't signed, so they are
+/// executed from the zero address and their signature is never checked
+pub fn recover_sender(tx: &Transaction) -> Address {
+    match tx {
+        Transaction::PrivilegedL2Transaction(tx) if tx.tx_type == PrivilegedTxType::Deposit => {
+            Address::zero()
+        }
+        _ => tx.sender(),
+    }
+}
+
 pub fn tx_env(tx: &Transaction) -> TxEnv {
+    tx_env_with_sender(tx, recover_sender(tx))
+}
+
+/// Builds the transaction environment for a transaction whose sender was already recovered
+/// with [recover_sender]
+pub fn tx_env_with_sender(tx: &Transaction, sender: Address) -> TxEnv {
     let max_fee_per_blob_gas = tx
         .max_fee_per_blob_gas()
         .map(|x| RevmU256::from_be_bytes(x.to_big_endian()));
     TxEnv {
-        caller: match tx {
-            Transaction::PrivilegedL2Transaction(tx) if tx.tx_type == PrivilegedTxType::Deposit => {
-                RevmAddress::ZERO
-            }
-            _ => RevmAddress(tx.sender().0.into()),
-        },
+        caller: RevmAddress(sender.0.into()),
         gas_limit: tx.gas_limit(),
         gas_price: RevmU256::from(tx.gas_price()),
         transact_to: match tx {